@@ -0,0 +1,582 @@
+use crate::error::{ArchiveError, ClientError};
+use crate::model::{AggTrade, Kline, Trade};
+use crate::param::Interval;
+use chrono::{Datelike, Duration, NaiveDate};
+use core::pin::Pin;
+use csv::StringRecord;
+use futures::{
+    channel::mpsc::{self, Sender},
+    executor,
+    future::Future,
+    stream::{self, BoxStream, StreamExt, TryStreamExt},
+    task::{Context, Poll},
+    SinkExt, Stream,
+};
+use reqwest::{Client, Response, Url};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Write;
+
+/// Public archive of historical market data.
+pub const DATA_VISION_URL: &str = "https://data.binance.vision";
+
+/// Time stamps at or above this are in microseconds, as in the spot archives since 2025.
+const MICROS_THRESHOLD: i64 = 100_000_000_000_000;
+
+/// Rows parsed ahead of the reader of an archive.
+const ROW_BUFFER: usize = 1024;
+
+/// Period covered by a single archive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ArchivePeriod {
+    Daily(NaiveDate),
+    /// Year and month.
+    Monthly(i32, u32),
+}
+
+impl ArchivePeriod {
+    /// Archives covering the days from start to end, both included;
+    /// monthly archives for whole months, daily archives for the rest.
+    /// # Example
+    ///
+    /// ```
+    /// use tokio_binance::ArchivePeriod;
+    /// use chrono::NaiveDate;
+    ///
+    /// let start = NaiveDate::from_ymd_opt(2022, 1, 30).unwrap();
+    /// let end = NaiveDate::from_ymd_opt(2022, 3, 1).unwrap();
+    ///
+    /// assert_eq!(ArchivePeriod::range(start, end), vec![
+    ///     ArchivePeriod::Daily(NaiveDate::from_ymd_opt(2022, 1, 30).unwrap()),
+    ///     ArchivePeriod::Daily(NaiveDate::from_ymd_opt(2022, 1, 31).unwrap()),
+    ///     ArchivePeriod::Monthly(2022, 2),
+    ///     ArchivePeriod::Daily(NaiveDate::from_ymd_opt(2022, 3, 1).unwrap()),
+    /// ]);
+    /// ```
+    pub fn range(start: NaiveDate, end: NaiveDate) -> Vec<Self> {
+        let mut periods = Vec::new();
+        let mut day = start;
+
+        while day <= end {
+            let (year, month) = (day.year(), day.month());
+            let next_month = match month {
+                12 => NaiveDate::from_ymd_opt(year + 1, 1, 1),
+                _ => NaiveDate::from_ymd_opt(year, month + 1, 1),
+            };
+            let next_month = next_month.expect("valid date");
+
+            if day.day() == 1 && next_month - Duration::days(1) <= end {
+                periods.push(Self::Monthly(year, month));
+                day = next_month;
+            } else {
+                periods.push(Self::Daily(day));
+                day = match day.succ_opt() {
+                    Some(day) => day,
+                    None => break,
+                };
+            }
+        }
+        periods
+    }
+
+    fn dir(&self) -> &'static str {
+        match self {
+            Self::Daily(_) => "daily",
+            Self::Monthly(..) => "monthly",
+        }
+    }
+
+    fn suffix(&self) -> String {
+        match self {
+            Self::Daily(day) => day.format("%Y-%m-%d").to_string(),
+            Self::Monthly(year, month) => format!("{}-{:02}", year, month),
+        }
+    }
+}
+
+/// Rows of one or more archives, in the order of the files.
+///
+/// Rows are parsed from the unzipped archive while they are read, so that an archive
+/// never has to fit in memory; archives of a range are downloaded one at a time, once
+/// the rows of the previous one are read.
+pub struct ArchiveRows<T> {
+    rows: BoxStream<'static, crate::error::Result<T>>,
+}
+
+impl<T: Send + 'static> ArchiveRows<T> {
+    /// Rows of the archives one after the other.
+    fn chain<F, Fut>(periods: Vec<ArchivePeriod>, archive: F) -> Self
+    where
+        F: FnMut(ArchivePeriod) -> Fut + Send + 'static,
+        Fut: Future<Output = crate::error::Result<Self>> + Send + 'static,
+    {
+        let rows = stream::iter(periods).then(archive).try_flatten();
+        Self { rows: rows.boxed() }
+    }
+}
+
+impl<T> Stream for ArchiveRows<T> {
+    type Item = crate::error::Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.rows.poll_next_unpin(cx)
+    }
+}
+
+/// Client for downloading the spot archives of data.binance.vision.
+///
+/// Archives are downloaded to a temporary file, verified against their `.CHECKSUM` file,
+/// then unzipped and parsed as their [`ArchiveRows`] are read.
+#[derive(Clone)]
+pub struct ArchiveClient {
+    url: Url,
+    client: Client,
+}
+
+impl ArchiveClient {
+    /// Creates new client instance
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tokio_binance::{ArchiveClient, DATA_VISION_URL};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let client = ArchiveClient::connect(DATA_VISION_URL)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn connect<U: AsRef<str>>(url: U) -> crate::error::Result<Self> {
+        let mut url = url.as_ref().parse::<Url>()?;
+        // archive paths are joined below the path of the url, e.g. of a mirror
+        if !url.path().ends_with('/') {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        }
+
+        Ok(Self {
+            url,
+            client: Client::new(),
+        })
+    }
+    /// Klines of a single archive, once it is downloaded and verified.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{ArchiveClient, DATA_VISION_URL};
+    /// use tokio_binance::{ArchivePeriod, Interval};
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = ArchiveClient::connect(DATA_VISION_URL)?;
+    /// let mut klines = client
+    ///     .get_klines("BNBUSDT", Interval::OneMinute, ArchivePeriod::Monthly(2022, 1))
+    ///     .await?;
+    ///
+    /// while let Some(kline) = klines.try_next().await? {
+    ///     println!("{:?}", kline);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_klines(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        period: ArchivePeriod,
+    ) -> crate::error::Result<ArchiveRows<Kline>> {
+        let interval = serde_json::to_value(interval)?;
+        let interval = interval.as_str().unwrap_or_default();
+        let path = format!(
+            "data/spot/{}/klines/{}/{}/{}-{}-{}.zip",
+            period.dir(),
+            symbol,
+            interval,
+            symbol,
+            interval,
+            period.suffix()
+        );
+
+        self.download(&path, parse_kline).await
+    }
+    /// Trades of a single archive, once it is downloaded and verified.
+    pub async fn get_trades(
+        &self,
+        symbol: &str,
+        period: ArchivePeriod,
+    ) -> crate::error::Result<ArchiveRows<Trade>> {
+        let path = format!(
+            "data/spot/{}/trades/{}/{}-trades-{}.zip",
+            period.dir(),
+            symbol,
+            symbol,
+            period.suffix()
+        );
+
+        self.download(&path, parse_trade).await
+    }
+    /// Aggregate trades of a single archive, once it is downloaded and verified.
+    pub async fn get_aggregate_trades(
+        &self,
+        symbol: &str,
+        period: ArchivePeriod,
+    ) -> crate::error::Result<ArchiveRows<AggTrade>> {
+        let path = format!(
+            "data/spot/{}/aggTrades/{}/{}-aggTrades-{}.zip",
+            period.dir(),
+            symbol,
+            symbol,
+            period.suffix()
+        );
+
+        self.download(&path, parse_agg_trade).await
+    }
+    /// Klines of all archives from start to end, both days included; errors of
+    /// an archive are yielded in place of its rows.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{ArchiveClient, DATA_VISION_URL};
+    /// use tokio_binance::Interval;
+    /// use chrono::NaiveDate;
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = ArchiveClient::connect(DATA_VISION_URL)?;
+    /// let start = NaiveDate::from_ymd_opt(2021, 11, 15).unwrap();
+    /// let end = NaiveDate::from_ymd_opt(2022, 2, 3).unwrap();
+    ///
+    /// let mut klines = client.get_klines_range("BNBUSDT", Interval::OneHour, start, end);
+    /// while let Some(kline) = klines.try_next().await? {
+    ///     println!("{:?}", kline);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_klines_range(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> ArchiveRows<Kline> {
+        let (client, symbol) = (self.clone(), symbol.to_string());
+        ArchiveRows::chain(ArchivePeriod::range(start, end), move |period| {
+            let (client, symbol) = (client.clone(), symbol.clone());
+            async move { client.get_klines(&symbol, interval, period).await }
+        })
+    }
+    /// Trades of all archives from start to end, both days included; errors of
+    /// an archive are yielded in place of its rows.
+    pub fn get_trades_range(
+        &self,
+        symbol: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> ArchiveRows<Trade> {
+        let (client, symbol) = (self.clone(), symbol.to_string());
+        ArchiveRows::chain(ArchivePeriod::range(start, end), move |period| {
+            let (client, symbol) = (client.clone(), symbol.clone());
+            async move { client.get_trades(&symbol, period).await }
+        })
+    }
+    /// Aggregate trades of all archives from start to end, both days included; errors of
+    /// an archive are yielded in place of its rows.
+    pub fn get_aggregate_trades_range(
+        &self,
+        symbol: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> ArchiveRows<AggTrade> {
+        let (client, symbol) = (self.clone(), symbol.to_string());
+        ArchiveRows::chain(ArchivePeriod::range(start, end), move |period| {
+            let (client, symbol) = (client.clone(), symbol.clone());
+            async move { client.get_aggregate_trades(&symbol, period).await }
+        })
+    }
+
+    async fn download<T, F>(&self, path: &str, parse: F) -> crate::error::Result<ArchiveRows<T>>
+    where
+        T: Send + 'static,
+        F: Fn(&StringRecord) -> Option<T> + Send + 'static,
+    {
+        let file = path.rsplit('/').next().unwrap_or(path).to_string();
+        let checksum = self
+            .get(&format!("{}.CHECKSUM", path))
+            .await?
+            .bytes()
+            .await?;
+        let checksum = String::from_utf8_lossy(&checksum);
+        let expected = checksum.split_whitespace().next().unwrap_or_default();
+
+        let mut res = self.get(path).await?;
+        let mut archive = tempfile::tempfile()?;
+        let mut hasher = Sha256::new();
+        while let Some(chunk) = res.chunk().await? {
+            hasher.update(&chunk);
+            archive.write_all(&chunk)?;
+        }
+
+        let actual = hex::encode(hasher.finalize());
+        if !actual.eq_ignore_ascii_case(expected) {
+            let message = format!("checksum {} does not match {}", actual, expected);
+            return Err(ArchiveError::new(file, message).into());
+        }
+
+        let (mut sender, receiver) = mpsc::channel(ROW_BUFFER);
+        let task = tokio::task::spawn_blocking(move || {
+            if let Err(error) = unzip(&file, archive, parse, &mut sender) {
+                let _ = executor::block_on(sender.send(Err(error)));
+            }
+        });
+        // the task is awaited once the rows end, so that its panic is not taken for the end
+        let rows = stream::unfold((receiver, task), |(mut receiver, task)| async move {
+            match receiver.next().await {
+                Some(row) => Some((row, (receiver, task))),
+                None => match task.await {
+                    Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+                    _ => None,
+                },
+            }
+        });
+        Ok(ArchiveRows { rows: rows.boxed() })
+    }
+
+    async fn get(&self, path: &str) -> crate::error::Result<Response> {
+        let url = self.url.join(path.trim_start_matches('/'))?;
+        let res = self.client.get(url).send().await?;
+        let status = res.status();
+
+        if status.is_client_error() || status.is_server_error() {
+            let reason = status.canonical_reason().unwrap_or("UNKNOWN");
+            return Err(ClientError::new(status.as_u16(), reason, path).into());
+        }
+        Ok(res)
+    }
+}
+
+/// Sends the rows of the archive until they end or the receiver is dropped.
+fn unzip<T, F>(
+    file: &str,
+    archive: File,
+    parse: F,
+    rows: &mut Sender<crate::error::Result<T>>,
+) -> crate::error::Result<()>
+where
+    F: Fn(&StringRecord) -> Option<T>,
+{
+    let mut archive = zip::ZipArchive::new(archive)?;
+
+    for index in 0..archive.len() {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(archive.by_index(index)?);
+
+        for (line, record) in reader.records().enumerate() {
+            let record = record?;
+            match parse(&record) {
+                Some(row) => {
+                    if executor::block_on(rows.send(Ok(row))).is_err() {
+                        return Ok(());
+                    }
+                }
+                // some archives start with a header
+                None if line == 0 => {}
+                None => {
+                    let message = format!("invalid row {}: {:?}", line + 1, record);
+                    return Err(ArchiveError::new(file, message.as_str()).into());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn field<T: std::str::FromStr>(record: &StringRecord, index: usize) -> Option<T> {
+    record.get(index)?.trim().parse().ok()
+}
+
+fn flag(record: &StringRecord, index: usize) -> Option<bool> {
+    match record.get(index)?.trim() {
+        value if value.eq_ignore_ascii_case("true") => Some(true),
+        value if value.eq_ignore_ascii_case("false") => Some(false),
+        _ => None,
+    }
+}
+
+fn millis(record: &StringRecord, index: usize) -> Option<i64> {
+    let time: i64 = field(record, index)?;
+    if time >= MICROS_THRESHOLD {
+        Some(time / 1000)
+    } else {
+        Some(time)
+    }
+}
+
+fn parse_kline(record: &StringRecord) -> Option<Kline> {
+    Some(Kline {
+        open_time: millis(record, 0)?,
+        open: field(record, 1)?,
+        high: field(record, 2)?,
+        low: field(record, 3)?,
+        close: field(record, 4)?,
+        volume: field(record, 5)?,
+        close_time: millis(record, 6)?,
+        quote_volume: field(record, 7)?,
+        trades: field(record, 8)?,
+        taker_buy_volume: field(record, 9)?,
+        taker_buy_quote_volume: field(record, 10)?,
+        is_closed: true,
+    })
+}
+
+fn parse_trade(record: &StringRecord) -> Option<Trade> {
+    Some(Trade {
+        id: field(record, 0)?,
+        price: field(record, 1)?,
+        qty: field(record, 2)?,
+        time: millis(record, 4)?,
+        is_buyer_maker: flag(record, 5)?,
+    })
+}
+
+fn parse_agg_trade(record: &StringRecord) -> Option<AggTrade> {
+    Some(AggTrade {
+        id: field(record, 0)?,
+        price: field(record, 1)?,
+        qty: field(record, 2)?,
+        first_trade_id: field(record, 3)?,
+        last_trade_id: field(record, 4)?,
+        time: millis(record, 5)?,
+        is_buyer_maker: flag(record, 6)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockServer;
+    use hyper::{Body, Response};
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::io::Cursor;
+
+    const KLINES: &str = "open_time,open,high,low,close,volume,close_time,quote_volume,count,taker_buy_volume,taker_buy_quote_volume,ignore
+1643500800000,380.1,381.0,379.5,380.7,12.5,1643500859999,4755.2,42,6.1,2321.3,0
+1643500860000000,380.7,382.2,380.2,381.9,8.25,1643500919999999,3142.8,30,4.0,1524.1,0
+";
+    const PATH: &str = "/mirror/data/spot/daily/klines/BNBUSDT/1m/BNBUSDT-1m-2022-01-30.zip";
+
+    fn zip(name: &str, text: &str) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file(name, zip::write::FileOptions::default())
+            .unwrap();
+        writer.write_all(text.as_bytes()).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// Serves the files at their path; returns the url of the mirror.
+    async fn serve(files: HashMap<String, Vec<u8>>) -> String {
+        let server = MockServer::start().await;
+        for (path, file) in files {
+            server.route(path, move |_| Response::new(Body::from(file.clone())));
+        }
+        format!("{}/mirror", server.url())
+    }
+
+    fn files(archive: Vec<u8>, checksum: &str) -> HashMap<String, Vec<u8>> {
+        let checksum = format!("{}  BNBUSDT-1m-2022-01-30.zip\n", checksum);
+        let mut files = HashMap::new();
+        files.insert(format!("{}.CHECKSUM", PATH), checksum.into_bytes());
+        files.insert(PATH.to_string(), archive);
+        files
+    }
+
+    #[tokio::test]
+    async fn downloads_verified_archives() {
+        let archive = zip("BNBUSDT-1m-2022-01-30.csv", KLINES);
+        let checksum = hex::encode(Sha256::digest(&archive));
+        let client = ArchiveClient::connect(serve(files(archive, &checksum)).await).unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2022, 1, 30).unwrap();
+        let klines: Vec<_> = client
+            .get_klines("BNBUSDT", Interval::OneMinute, ArchivePeriod::Daily(day))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(klines.len(), 2);
+        assert_eq!(klines[0].open_time, 1643500800000);
+        assert_eq!(klines[1].open_time, 1643500860000);
+        assert_eq!(klines[1].close_time, 1643500919999);
+        assert_eq!(klines[1].close, 381.9);
+    }
+
+    #[tokio::test]
+    async fn rows_before_an_invalid_one_are_read() {
+        let text = format!("{}1643500920000,not a kline\n", KLINES);
+        let archive = zip("BNBUSDT-1m-2022-01-30.csv", &text);
+        let checksum = hex::encode(Sha256::digest(&archive));
+        let client = ArchiveClient::connect(serve(files(archive, &checksum)).await).unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2022, 1, 30).unwrap();
+        let mut klines = client
+            .get_klines("BNBUSDT", Interval::OneMinute, ArchivePeriod::Daily(day))
+            .await
+            .unwrap();
+
+        let first = klines.try_next().await.unwrap().unwrap();
+        assert_eq!(first.open_time, 1643500800000);
+        let second = klines.try_next().await.unwrap().unwrap();
+        assert_eq!(second.open_time, 1643500860000);
+        let error = klines.try_next().await.unwrap_err();
+        let error = error.source().unwrap().downcast_ref::<ArchiveError>();
+        assert_eq!(error.unwrap().file(), "BNBUSDT-1m-2022-01-30.zip");
+        assert!(klines.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn ranges_read_the_archives_in_order() {
+        let archive = zip("BNBUSDT-1m-2022-01-30.csv", KLINES);
+        let checksum = hex::encode(Sha256::digest(&archive));
+        // the archive of the 31st is missing
+        let client = ArchiveClient::connect(serve(files(archive, &checksum)).await).unwrap();
+
+        let start = NaiveDate::from_ymd_opt(2022, 1, 30).unwrap();
+        let end = NaiveDate::from_ymd_opt(2022, 1, 31).unwrap();
+        let rows: Vec<_> = client
+            .get_klines_range("BNBUSDT", Interval::OneMinute, start, end)
+            .collect()
+            .await;
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].as_ref().unwrap().open_time, 1643500800000);
+        assert_eq!(rows[1].as_ref().unwrap().open_time, 1643500860000);
+        let error = rows[2].as_ref().unwrap_err();
+        let error = error.source().unwrap().downcast_ref::<ClientError>();
+        assert_eq!(error.unwrap().code(), 404);
+    }
+
+    #[tokio::test]
+    async fn rejects_checksum_mismatch() {
+        let archive = zip("BNBUSDT-1m-2022-01-30.csv", KLINES);
+        let checksum = hex::encode(Sha256::digest(b"another archive"));
+        let client = ArchiveClient::connect(serve(files(archive, &checksum)).await).unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2022, 1, 30).unwrap();
+        let error = client
+            .get_klines("BNBUSDT", Interval::OneMinute, ArchivePeriod::Daily(day))
+            .await
+            .err()
+            .unwrap();
+
+        let error = error.source().unwrap().downcast_ref::<ArchiveError>();
+        assert_eq!(error.unwrap().file(), "BNBUSDT-1m-2022-01-30.zip");
+    }
+}
@@ -0,0 +1,638 @@
+use crate::param::Side;
+use crate::strategy::{Context, MarketEvent, OrderFill, OrderRequest, Strategy};
+use futures::{Stream, TryStreamExt};
+
+/// Quantities below this are treated as zero.
+const EPSILON: f64 = 1e-9;
+
+/// Price impact of market orders.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Slippage {
+    None,
+    /// Fixed amount of the quote asset per unit.
+    Fixed(f64),
+    /// Basis points of the price.
+    Bps(f64),
+}
+
+impl Slippage {
+    fn apply(&self, side: Side, price: f64) -> f64 {
+        let amount = match self {
+            Self::None => 0.0,
+            Self::Fixed(amount) => *amount,
+            Self::Bps(bps) => price * bps / 10_000.0,
+        };
+        match side {
+            Side::Buy => price + amount,
+            Side::Sell => price - amount,
+        }
+    }
+}
+
+/// Result of a backtest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BacktestReport {
+    pub initial_equity: f64,
+    pub final_equity: f64,
+    /// Final minus initial equity.
+    pub pnl: f64,
+    pub realized_pnl: f64,
+    /// Fees paid, in the quote asset.
+    pub fees: f64,
+    /// Largest drop of the equity from a previous peak, as a fraction of the peak.
+    pub max_drawdown: f64,
+    /// Fraction of the time a position was held.
+    pub exposure: f64,
+    /// Equity after every event.
+    pub equity: Vec<(i64, f64)>,
+    /// Trade log.
+    pub trades: Vec<OrderFill>,
+}
+
+impl BacktestReport {
+    /// Pnl as a fraction of the initial equity.
+    pub fn return_pct(&self) -> f64 {
+        match self.initial_equity {
+            equity if equity > 0.0 => self.pnl / equity,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Replays klines or trades through a [`Strategy`].
+///
+/// Market orders fill at the open of the next kline or the price of the next trade,
+/// moved by the slippage. Limit orders fill at their price once a later kline or trade
+/// goes through it. Orders are capped at the cash and position available.
+/// Buys pay the fee in the base asset and sells in the quote asset, as on binance.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{Backtest, MarketDataClient, Environment, Interval, Slippage};
+/// use tokio_binance::{Context, MarketEvent, Strategy};
+/// use chrono::{Duration, Utc};
+///
+/// struct Hold;
+///
+/// impl Strategy for Hold {
+///     fn on_event(&mut self, ctx: &mut Context, event: &MarketEvent) {
+///         if ctx.position() == 0.0 {
+///             ctx.buy(ctx.cash() / event.price());
+///         }
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
+///     let end = Utc::now();
+///     let klines = client.get_candlestick_bars_history(
+///         "BNBUSDT",
+///         Interval::OneHour,
+///         end - Duration::days(30),
+///         end
+///     );
+///
+///     let report = Backtest::new("BNBUSDT", "BNB", "USDT", 10_000.0)
+///         // optional: maker and taker fee; default is 0.001 each.
+///         .with_fees(0.00075, 0.00075)
+///         // optional: price impact of market orders; default is none.
+///         .with_slippage(Slippage::Bps(5.0))
+///         .run_stream(&mut Hold, klines)
+///         .await?;
+///
+///     println!("pnl {} drawdown {}", report.pnl, report.max_drawdown);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Backtest {
+    symbol: String,
+    base: String,
+    quote: String,
+    cash: f64,
+    position: f64,
+    maker_fee: f64,
+    taker_fee: f64,
+    slippage: Slippage,
+}
+
+impl Backtest {
+    /// Starts with the cash in the quote asset and no position.
+    pub fn new<S: Into<String>>(symbol: S, base: S, quote: S, cash: f64) -> Self {
+        Self {
+            symbol: symbol.into(),
+            base: base.into(),
+            quote: quote.into(),
+            cash,
+            position: 0.0,
+            maker_fee: 0.001,
+            taker_fee: 0.001,
+            slippage: Slippage::None,
+        }
+    }
+    /// Starts with a position in the base asset.
+    pub fn with_position(mut self, position: f64) -> Self {
+        self.position = position;
+        self
+    }
+    pub fn with_fees(mut self, maker: f64, taker: f64) -> Self {
+        self.maker_fee = maker;
+        self.taker_fee = taker;
+        self
+    }
+    pub fn with_slippage(mut self, slippage: Slippage) -> Self {
+        self.slippage = slippage;
+        self
+    }
+    /// Runs the strategy over the events, oldest first.
+    /// # Example
+    ///
+    /// ```
+    /// use tokio_binance::{Backtest, Context, MarketEvent, Strategy};
+    /// use tokio_binance::model::Trade;
+    ///
+    /// struct BuyOnce;
+    ///
+    /// impl Strategy for BuyOnce {
+    ///     fn on_event(&mut self, ctx: &mut Context, _event: &MarketEvent) {
+    ///         if ctx.open_orders().is_empty() && ctx.position() == 0.0 {
+    ///             ctx.buy_limit(99.0, 1.0);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let trades = [100.0, 99.5, 98.0, 101.0].iter().enumerate().map(|(id, &price)| Trade {
+    ///     id: id as i64,
+    ///     price,
+    ///     qty: 1.0,
+    ///     time: id as i64 * 1000,
+    ///     is_buyer_maker: false,
+    /// });
+    ///
+    /// let report = Backtest::new("BNBUSDT", "BNB", "USDT", 1000.0)
+    ///     .with_fees(0.0, 0.0)
+    ///     .run(&mut BuyOnce, trades);
+    ///
+    /// assert_eq!(report.trades.len(), 1);
+    /// assert_eq!(report.trades[0].price, 99.0);
+    /// assert_eq!(report.pnl, 2.0);
+    /// ```
+    pub fn run<S, I>(&self, strategy: &mut S, events: I) -> BacktestReport
+    where
+        S: Strategy,
+        I: IntoIterator,
+        I::Item: Into<MarketEvent>,
+    {
+        let mut simulation = Simulation::new(self);
+        for event in events {
+            simulation.step(strategy, event.into());
+        }
+        simulation.report()
+    }
+    /// Runs the strategy over a stream of events, e.g. a history stream of `MarketDataClient`.
+    pub async fn run_stream<S, E, T>(
+        &self,
+        strategy: &mut S,
+        events: E,
+    ) -> crate::error::Result<BacktestReport>
+    where
+        S: Strategy,
+        E: Stream<Item = crate::error::Result<T>>,
+        T: Into<MarketEvent>,
+    {
+        let mut simulation = Simulation::new(self);
+        futures::pin_mut!(events);
+        while let Some(event) = events.try_next().await? {
+            simulation.step(strategy, event.into());
+        }
+        Ok(simulation.report())
+    }
+}
+
+struct Simulation<'a> {
+    config: &'a Backtest,
+    ctx: Context,
+    /// Market orders waiting for the next event.
+    pending: Vec<(String, Side, f64)>,
+    initial_equity: Option<f64>,
+    peak: f64,
+    max_drawdown: f64,
+    last_time: Option<i64>,
+    exposed_time: i64,
+    equity: Vec<(i64, f64)>,
+    trades: Vec<OrderFill>,
+}
+
+impl<'a> Simulation<'a> {
+    fn new(config: &'a Backtest) -> Self {
+        Self {
+            config,
+            ctx: Context::new(
+                &config.symbol,
+                &config.base,
+                &config.quote,
+                config.position,
+                config.cash,
+            ),
+            pending: Vec::new(),
+            initial_equity: None,
+            peak: 0.0,
+            max_drawdown: 0.0,
+            last_time: None,
+            exposed_time: 0,
+            equity: Vec::new(),
+            trades: Vec::new(),
+        }
+    }
+
+    fn step<S: Strategy>(&mut self, strategy: &mut S, event: MarketEvent) {
+        if let Some(last_time) = self.last_time {
+            if self.ctx.position() > EPSILON {
+                self.exposed_time += event.time() - last_time;
+            }
+        }
+        self.last_time = Some(event.time());
+        self.ctx.set_market(&event);
+        if self.initial_equity.is_none() {
+            self.initial_equity = Some(self.ctx.equity());
+        }
+
+        let open = match &event {
+            MarketEvent::Kline(kline) => kline.open,
+            _ => event.price(),
+        };
+        for (client_order_id, side, qty) in std::mem::take(&mut self.pending) {
+            let price = self.config.slippage.apply(side, open);
+            self.execute(strategy, &client_order_id, side, price, qty, false);
+        }
+
+        for order in self.ctx.open_orders().to_vec() {
+            let (through, qty) = match &event {
+                MarketEvent::Kline(kline) => match order.side {
+                    Side::Buy => (kline.low < order.price, order.qty),
+                    Side::Sell => (kline.high > order.price, order.qty),
+                },
+                _ => {
+                    let qty = match &event {
+                        MarketEvent::Trade(trade) => trade.qty,
+                        MarketEvent::AggTrade(trade) => trade.qty,
+                        MarketEvent::Kline(_) => 0.0,
+                    };
+                    match order.side {
+                        Side::Buy => (event.price() < order.price, order.qty.min(qty)),
+                        Side::Sell => (event.price() > order.price, order.qty.min(qty)),
+                    }
+                }
+            };
+            if !through {
+                continue;
+            }
+
+            // a kline that opens beyond the limit fills at the better open
+            let price = match (&event, order.side) {
+                (MarketEvent::Kline(_), Side::Buy) => order.price.min(open),
+                (MarketEvent::Kline(_), Side::Sell) => order.price.max(open),
+                _ => order.price,
+            };
+            let filled = self.execute(
+                strategy,
+                &order.client_order_id,
+                order.side,
+                price,
+                qty,
+                true,
+            );
+            if !filled {
+                // nothing left to pay with
+                self.ctx.remove_order(&order.client_order_id);
+            }
+        }
+
+        strategy.on_event(&mut self.ctx, &event);
+        self.collect();
+
+        let equity = self.ctx.equity();
+        self.peak = self.peak.max(equity);
+        if self.peak > 0.0 {
+            self.max_drawdown = self.max_drawdown.max((self.peak - equity) / self.peak);
+        }
+        self.equity.push((event.time(), equity));
+    }
+
+    /// Fills the order, capped at the cash or position; returns false if nothing was filled.
+    fn execute<S: Strategy>(
+        &mut self,
+        strategy: &mut S,
+        client_order_id: &str,
+        side: Side,
+        price: f64,
+        qty: f64,
+        is_maker: bool,
+    ) -> bool {
+        let qty = match side {
+            Side::Buy => qty.min(self.ctx.cash().max(0.0) / price),
+            Side::Sell => qty.min(self.ctx.position().max(0.0)),
+        };
+        if qty <= EPSILON || price <= 0.0 {
+            return false;
+        }
+
+        let rate = match is_maker {
+            true => self.config.maker_fee,
+            false => self.config.taker_fee,
+        };
+        let (fee, fee_asset) = match side {
+            Side::Buy => (qty * rate, self.ctx.base().to_string()),
+            Side::Sell => (qty * price * rate, self.ctx.quote().to_string()),
+        };
+
+        let time = self.ctx.time();
+        let fill = self.ctx.fill(
+            client_order_id,
+            time,
+            side,
+            price,
+            qty,
+            fee,
+            &fee_asset,
+            is_maker,
+        );
+        strategy.on_fill(&mut self.ctx, &fill);
+        self.trades.push(fill);
+        self.collect();
+        true
+    }
+
+    /// Queues the market orders of the strategy; limit orders and cancels are kept by the context.
+    fn collect(&mut self) {
+        for request in self.ctx.take_requests() {
+            if let OrderRequest::Market(client_order_id, side, qty) = request {
+                self.pending.push((client_order_id, side, qty));
+            }
+        }
+    }
+
+    fn report(self) -> BacktestReport {
+        let initial_equity = self.initial_equity.unwrap_or_else(|| self.ctx.equity());
+        let final_equity = self.ctx.equity();
+        let duration = match (self.equity.first(), self.equity.last()) {
+            (Some(first), Some(last)) => last.0 - first.0,
+            _ => 0,
+        };
+
+        BacktestReport {
+            initial_equity,
+            final_equity,
+            pnl: final_equity - initial_equity,
+            realized_pnl: self.ctx.realized_pnl(),
+            fees: self.ctx.fees(),
+            max_drawdown: self.max_drawdown,
+            exposure: match duration {
+                0 => 0.0,
+                duration => self.exposed_time as f64 / duration as f64,
+            },
+            equity: self.equity,
+            trades: self.trades,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Kline, Trade};
+
+    /// Calls the closure with the index of every event and records the average price on fills.
+    struct Script<F> {
+        step: usize,
+        on_event: F,
+        avg_prices: Vec<f64>,
+    }
+
+    fn script<F: FnMut(usize, &mut Context)>(on_event: F) -> Script<F> {
+        Script {
+            step: 0,
+            on_event,
+            avg_prices: Vec::new(),
+        }
+    }
+
+    impl<F: FnMut(usize, &mut Context)> Strategy for Script<F> {
+        fn on_event(&mut self, ctx: &mut Context, _event: &MarketEvent) {
+            (self.on_event)(self.step, ctx);
+            self.step += 1;
+        }
+
+        fn on_fill(&mut self, ctx: &mut Context, _fill: &OrderFill) {
+            self.avg_prices.push(ctx.avg_price());
+        }
+    }
+
+    /// Trades a second apart with the prices and quantities.
+    fn trades(trades: &[(f64, f64)]) -> Vec<Trade> {
+        trades
+            .iter()
+            .enumerate()
+            .map(|(id, &(price, qty))| Trade {
+                id: id as i64,
+                price,
+                qty,
+                time: id as i64 * 1000,
+                is_buyer_maker: false,
+            })
+            .collect()
+    }
+
+    fn kline(open: f64, high: f64, low: f64, close: f64) -> Kline {
+        Kline {
+            open_time: 0,
+            close_time: 59_999,
+            open,
+            high,
+            low,
+            close,
+            volume: 0.0,
+            quote_volume: 0.0,
+            trades: 0,
+            taker_buy_volume: 0.0,
+            taker_buy_quote_volume: 0.0,
+            is_closed: true,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn buys_pay_the_fee_in_base_and_sells_in_quote() {
+        let mut strategy = script(|step, ctx: &mut Context| match step {
+            0 => {
+                ctx.buy(2.0);
+            }
+            1 => {
+                ctx.sell(ctx.position());
+            }
+            _ => {}
+        });
+        let events = trades(&[(100.0, 1.0), (100.0, 1.0), (110.0, 1.0)]);
+        let report = Backtest::new("BNBUSDT", "BNB", "USDT", 1000.0).run(&mut strategy, events);
+
+        let buy = &report.trades[0];
+        assert_eq!((buy.side, buy.price, buy.qty), (Side::Buy, 100.0, 2.0));
+        assert_close(buy.fee, 0.002);
+        assert_eq!(buy.fee_asset, "BNB");
+        assert!(!buy.is_maker);
+
+        // only what was received after the fee can be sold
+        let sell = &report.trades[1];
+        assert_eq!((sell.side, sell.price), (Side::Sell, 110.0));
+        assert_close(sell.qty, 1.998);
+        assert_close(sell.fee, 1.998 * 110.0 * 0.001);
+        assert_eq!(sell.fee_asset, "USDT");
+
+        assert_close(report.fees, 0.002 * 100.0 + 1.998 * 110.0 * 0.001);
+        assert_close(sell.realized_pnl, 1.998 * 110.0 * 0.999 - 200.0);
+        assert_close(report.realized_pnl, sell.realized_pnl);
+        assert_close(report.final_equity, 800.0 + 1.998 * 110.0 * 0.999);
+        assert_close(report.pnl, report.realized_pnl);
+    }
+
+    #[test]
+    fn sells_realize_against_the_average_price() {
+        let mut strategy = script(|step, ctx: &mut Context| match step {
+            0 | 1 => {
+                ctx.buy(1.0);
+            }
+            2 => {
+                ctx.sell(1.0);
+            }
+            _ => {}
+        });
+        let events = trades(&[(100.0, 1.0), (100.0, 1.0), (110.0, 1.0), (120.0, 1.0)]);
+        let report = Backtest::new("BNBUSDT", "BNB", "USDT", 1000.0)
+            .with_fees(0.0, 0.0)
+            .run(&mut strategy, events);
+
+        let prices: Vec<_> = report.trades.iter().map(|fill| fill.price).collect();
+        assert_eq!(prices, vec![100.0, 110.0, 120.0]);
+        // the average is kept by a partial sell
+        assert_eq!(strategy.avg_prices, vec![100.0, 105.0, 105.0]);
+        assert_eq!(report.trades[0].realized_pnl, 0.0);
+        assert_eq!(report.trades[2].realized_pnl, 15.0);
+        assert_eq!(report.realized_pnl, 15.0);
+        assert_eq!(report.final_equity, 1000.0 - 210.0 + 120.0 + 120.0);
+    }
+
+    #[test]
+    fn trades_fill_limit_orders_up_to_their_quantity() {
+        let mut order = None;
+        let mut strategy = script(|step, ctx: &mut Context| {
+            if step == 0 {
+                order = Some(ctx.buy_limit(99.0, 3.0));
+            }
+        });
+        let events = trades(&[
+            (100.0, 5.0),
+            (98.5, 1.0),
+            (98.0, 1.5),
+            // at the limit is not through it
+            (99.0, 5.0),
+            (97.0, 5.0),
+        ]);
+        let report = Backtest::new("BNBUSDT", "BNB", "USDT", 1000.0)
+            .with_fees(0.0, 0.0)
+            .run(&mut strategy, events);
+
+        let order = order.unwrap();
+        let fills: Vec<_> = report
+            .trades
+            .iter()
+            .map(|fill| {
+                (
+                    fill.client_order_id.as_str(),
+                    fill.time,
+                    fill.price,
+                    fill.qty,
+                )
+            })
+            .collect();
+        assert_eq!(
+            fills,
+            vec![
+                (order.as_str(), 1000, 99.0, 1.0),
+                (order.as_str(), 2000, 99.0, 1.5),
+                (order.as_str(), 4000, 99.0, 0.5),
+            ]
+        );
+        assert!(report.trades.iter().all(|fill| fill.is_maker));
+        assert_eq!(report.final_equity, 1000.0 - 297.0 + 3.0 * 97.0);
+    }
+
+    #[test]
+    fn klines_fill_limit_orders_at_the_better_open() {
+        let mut strategy = script(|step, ctx: &mut Context| {
+            if step == 0 {
+                ctx.buy_limit(99.0, 2.0);
+                ctx.sell_limit(105.0, 1.0);
+            }
+        });
+        let events = vec![
+            kline(100.0, 101.0, 99.5, 100.0),
+            // opens below the buy
+            kline(97.0, 98.0, 96.0, 97.5),
+            // goes through the sell
+            kline(98.0, 106.0, 98.0, 104.0),
+        ];
+        let report = Backtest::new("BNBUSDT", "BNB", "USDT", 1000.0)
+            .with_fees(0.0, 0.0)
+            .run(&mut strategy, events);
+
+        let fills: Vec<_> = report
+            .trades
+            .iter()
+            .map(|fill| (fill.side, fill.price, fill.qty))
+            .collect();
+        assert_eq!(
+            fills,
+            vec![(Side::Buy, 97.0, 2.0), (Side::Sell, 105.0, 1.0)]
+        );
+    }
+
+    #[test]
+    fn drawdown_is_measured_from_the_peak() {
+        let mut strategy = script(|step, ctx: &mut Context| {
+            if step == 0 {
+                ctx.buy(10.0);
+            }
+        });
+        let events = trades(&[
+            (100.0, 1.0),
+            (100.0, 1.0),
+            (120.0, 1.0),
+            (90.0, 1.0),
+            (110.0, 1.0),
+            // a smaller drop from a higher peak
+            (130.0, 1.0),
+            (100.0, 1.0),
+        ]);
+        let report = Backtest::new("BNBUSDT", "BNB", "USDT", 1000.0)
+            .with_fees(0.0, 0.0)
+            .run(&mut strategy, events);
+
+        let equity: Vec<_> = report.equity.iter().map(|&(_, equity)| equity).collect();
+        assert_eq!(
+            equity,
+            vec![1000.0, 1000.0, 1200.0, 900.0, 1100.0, 1300.0, 1000.0]
+        );
+        assert_close(report.max_drawdown, 0.25);
+        assert_eq!(report.pnl, 0.0);
+        assert_close(report.exposure, 5.0 / 6.0);
+    }
+}
@@ -0,0 +1,477 @@
+use crate::client::AccountClient;
+use crate::model::{Account, AccountPosition, Balance, BalanceUpdate};
+use crate::ws_stream::WebSocketStream;
+use log::warn;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Default)]
+struct Asset {
+    free: f64,
+    locked: f64,
+    /// Time of the last account update applied.
+    update_time: i64,
+    /// Number of account updates applied.
+    version: u64,
+}
+
+struct Reservation {
+    asset: String,
+    amount: f64,
+    /// Version of the asset when the amount was reserved.
+    version: u64,
+    /// Kept until the next update of the asset, which includes the order.
+    released: bool,
+}
+
+#[derive(Default)]
+struct State {
+    assets: HashMap<String, Asset>,
+    reservations: HashMap<String, Reservation>,
+    synced: bool,
+}
+
+/// Local copy of the account balances, kept up to date by the user data stream.
+///
+/// Seeded from `get_account` and updated by `outboundAccountPosition` and `balanceUpdate`
+/// events, so the available balance can be checked before every order without the
+/// weight of a REST call. Amounts of orders that are sent but not yet acknowledged can be
+/// reserved; they count against the available balance until the exchange locks them.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{AccountClient, BalanceBook, Channel, Environment, Side};
+/// use tokio_binance::{UserDataClient, WebSocketStream};
+/// use serde_json::Value;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+///     let user_data = UserDataClient::connect("<api-key>", Environment::BinanceUs)?;
+///     let value = user_data.start_stream().json::<Value>().await?;
+///     let channel = Channel::UserData(value["listenKey"].as_str().unwrap());
+///     let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
+///
+///     let book = BalanceBook::new(client.clone());
+///     let tracker = book.clone();
+///     tokio::spawn(async move { tracker.run(&mut stream, Duration::from_secs(300)).await });
+///
+///     if book.reserve("order-1", "USDT", 30.0) {
+///         let response = client
+///             .place_limit_order("BNBUSDT", Side::Buy, 300.0, 0.1, true)
+///             .with_new_client_order_id("order-1")
+///             .json::<Value>()
+///             .await;
+///         book.release("order-1");
+///     }
+///     println!("{}", book.available("USDT"));
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct BalanceBook {
+    client: AccountClient,
+    state: Arc<Mutex<State>>,
+}
+
+impl BalanceBook {
+    /// Empty until the first [`sync`](Self::sync).
+    pub fn new(client: AccountClient) -> Self {
+        Self {
+            client,
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+    /// Replaces the balances with the ones of `get_account`.
+    ///
+    /// Assets that changed on the stream after the snapshot was taken are kept.
+    pub async fn sync(&self) -> crate::error::Result<()> {
+        let account = self.client.get_account().json::<Account>().await?;
+        let mut state = self.state.lock().unwrap();
+
+        for balance in &account.balances {
+            state.set(balance, account.update_time);
+        }
+        let update_time = account.update_time;
+        for (name, asset) in state.assets.iter_mut() {
+            let listed = account
+                .balances
+                .iter()
+                .any(|balance| &balance.asset == name);
+            if !listed && asset.update_time <= update_time {
+                *asset = Asset {
+                    update_time,
+                    version: asset.version + 1,
+                    ..Asset::default()
+                };
+            }
+        }
+        state
+            .reservations
+            .retain(|_, reservation| !reservation.released);
+        state.synced = true;
+        Ok(())
+    }
+    /// Applies the balances of an `outboundAccountPosition` event.
+    pub fn apply_position(&self, position: &AccountPosition) {
+        let mut state = self.state.lock().unwrap();
+        for balance in &position.balances {
+            state.set(balance, position.last_update_time);
+        }
+    }
+    /// Applies a `balanceUpdate` event to the free balance.
+    pub fn apply_balance_update(&self, update: &BalanceUpdate) {
+        let mut state = self.state.lock().unwrap();
+        let asset = state.assets.entry(update.asset.clone()).or_default();
+        if update.clear_time > asset.update_time {
+            asset.free += update.delta;
+            asset.update_time = update.clear_time;
+            asset.version += 1;
+        }
+    }
+    /// Applies the balance events of a user data stream until it ends.
+    pub async fn process(&self, stream: &mut WebSocketStream) -> crate::error::Result<()> {
+        while let Some(event) = stream.stream_event::<Value>().await? {
+            self.apply_event(event.data)?;
+        }
+        Ok(())
+    }
+    /// Syncs, then applies the balance events of a user data stream until it ends,
+    /// syncing again at every interval.
+    ///
+    /// A failed sync is logged and tried again at the next interval; only errors of the
+    /// stream are returned.
+    pub async fn run(
+        &self,
+        stream: &mut WebSocketStream,
+        resync: Duration,
+    ) -> crate::error::Result<()> {
+        let mut interval = tokio::time::interval(resync);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(error) = self.sync().await {
+                        warn!("balances not synced: {}", error);
+                    }
+                }
+                event = stream.stream_event::<Value>() => match event? {
+                    Some(event) => self.apply_event(event.data)?,
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+    /// Whether the balances were seeded by [`sync`](Self::sync).
+    pub fn is_synced(&self) -> bool {
+        self.state.lock().unwrap().synced
+    }
+    /// Free and locked balance of the asset, zero if unknown.
+    pub fn balance(&self, asset: &str) -> Balance {
+        let state = self.state.lock().unwrap();
+        match state.assets.get(asset) {
+            Some(balance) => Balance {
+                asset: asset.to_string(),
+                free: balance.free,
+                locked: balance.locked,
+            },
+            None => Balance {
+                asset: asset.to_string(),
+                ..Balance::default()
+            },
+        }
+    }
+    /// All known balances, sorted by asset.
+    pub fn balances(&self) -> Vec<Balance> {
+        let state = self.state.lock().unwrap();
+        let mut balances: Vec<_> = state
+            .assets
+            .iter()
+            .map(|(name, asset)| Balance {
+                asset: name.clone(),
+                free: asset.free,
+                locked: asset.locked,
+            })
+            .collect();
+        balances.sort_by(|a, b| a.asset.cmp(&b.asset));
+        balances
+    }
+    /// Free balance of the asset minus the reserved amounts.
+    pub fn available(&self, asset: &str) -> f64 {
+        self.state.lock().unwrap().available(asset)
+    }
+    /// Reserves the amount for an order about to be sent, if it is available.
+    ///
+    /// Returns false and reserves nothing otherwise. The id, e.g. the client order id,
+    /// must be unique among the pending reservations.
+    pub fn reserve(&self, id: &str, asset: &str, amount: f64) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.reservations.contains_key(id) || state.available(asset) < amount {
+            return false;
+        }
+        let version = match state.assets.get(asset) {
+            Some(balance) => balance.version,
+            None => 0,
+        };
+        state.reservations.insert(
+            id.to_string(),
+            Reservation {
+                asset: asset.to_string(),
+                amount,
+                version,
+                released: false,
+            },
+        );
+        true
+    }
+    /// Releases the reservation once the order was answered.
+    ///
+    /// The amount stays reserved until the next update of the asset, which includes
+    /// the order, unless the asset was updated since the amount was reserved.
+    pub fn release(&self, id: &str) {
+        let mut state = self.state.lock().unwrap();
+        let version = match state.reservations.get(id) {
+            Some(reservation) => match state.assets.get(&reservation.asset) {
+                Some(balance) => balance.version,
+                None => 0,
+            },
+            None => return,
+        };
+        let reservation = state.reservations.get_mut(id).expect("reservation");
+        match version > reservation.version {
+            true => {
+                state.reservations.remove(id);
+            }
+            false => reservation.released = true,
+        }
+    }
+    /// Drops the reservation at once, e.g. for an order that was never sent.
+    pub fn cancel_reservation(&self, id: &str) {
+        self.state.lock().unwrap().reservations.remove(id);
+    }
+
+    fn apply_event(&self, data: Value) -> crate::error::Result<()> {
+        match data["e"].as_str() {
+            Some("outboundAccountPosition") => {
+                self.apply_position(&serde_json::from_value(data)?);
+            }
+            Some("balanceUpdate") => {
+                self.apply_balance_update(&serde_json::from_value(data)?);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl State {
+    /// Sets the balance unless a newer update was applied already.
+    fn set(&mut self, balance: &Balance, update_time: i64) {
+        let asset = self.assets.entry(balance.asset.clone()).or_default();
+        if update_time < asset.update_time {
+            return;
+        }
+        *asset = Asset {
+            free: balance.free,
+            locked: balance.locked,
+            update_time,
+            version: asset.version + 1,
+        };
+        self.reservations
+            .retain(|_, reservation| !reservation.released || reservation.asset != balance.asset);
+    }
+
+    fn available(&self, asset: &str) -> f64 {
+        let free = match self.assets.get(asset) {
+            Some(balance) => balance.free,
+            None => 0.0,
+        };
+        let reserved: f64 = self
+            .reservations
+            .values()
+            .filter(|reservation| reservation.asset == asset)
+            .map(|reservation| reservation.amount)
+            .sum();
+        free - reserved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "paper")]
+    use crate::{Channel, PaperExchange, Side};
+
+    /// Book whose client is never used.
+    fn book() -> BalanceBook {
+        let client = AccountClient::connect("<api-key>", "<secret-key>", "http://127.0.0.1:1");
+        BalanceBook::new(client.unwrap())
+    }
+
+    fn position(time: i64, balances: &[(&str, f64, f64)]) -> AccountPosition {
+        AccountPosition {
+            event_time: time,
+            last_update_time: time,
+            balances: balances
+                .iter()
+                .map(|&(asset, free, locked)| Balance {
+                    asset: asset.to_string(),
+                    free,
+                    locked,
+                })
+                .collect(),
+        }
+    }
+
+    fn balance_update(time: i64, asset: &str, delta: f64) -> BalanceUpdate {
+        BalanceUpdate {
+            event_time: time,
+            asset: asset.to_string(),
+            delta,
+            clear_time: time,
+        }
+    }
+
+    #[test]
+    fn released_amounts_stay_reserved_until_the_next_update() {
+        let book = book();
+        book.apply_position(&position(100, &[("USDT", 100.0, 0.0), ("BNB", 2.0, 0.0)]));
+
+        assert!(book.reserve("a", "USDT", 60.0));
+        assert_eq!(book.available("USDT"), 40.0);
+        assert!(!book.reserve("b", "USDT", 50.0));
+        assert!(!book.reserve("a", "USDT", 1.0));
+        assert!(book.reserve("c", "BNB", 1.0));
+
+        // answered before the update locking the amount arrived
+        book.release("a");
+        book.release("c");
+        assert_eq!(book.available("USDT"), 40.0);
+
+        book.apply_position(&position(200, &[("USDT", 40.0, 60.0)]));
+        assert_eq!(book.available("USDT"), 40.0);
+        // updates of other assets don't release it
+        assert_eq!(book.available("BNB"), 1.0);
+        book.apply_position(&position(200, &[("BNB", 1.0, 1.0)]));
+        assert_eq!(book.available("BNB"), 1.0);
+    }
+
+    #[test]
+    fn reservations_updated_before_the_release_are_dropped_at_once() {
+        let book = book();
+        book.apply_position(&position(100, &[("USDT", 100.0, 0.0)]));
+        assert!(book.reserve("a", "USDT", 60.0));
+
+        // the update locking the amount arrives before the answer
+        book.apply_position(&position(200, &[("USDT", 40.0, 60.0)]));
+        assert_eq!(book.available("USDT"), -20.0);
+        book.release("a");
+        assert_eq!(book.available("USDT"), 40.0);
+
+        assert!(book.reserve("b", "USDT", 30.0));
+        book.cancel_reservation("b");
+        assert_eq!(book.available("USDT"), 40.0);
+        // unknown ids are ignored
+        book.release("b");
+        assert_eq!(book.available("USDT"), 40.0);
+    }
+
+    #[test]
+    fn updates_apply_in_the_order_of_their_time() {
+        let book = book();
+        book.apply_position(&position(100, &[("USDT", 100.0, 0.0)]));
+
+        book.apply_balance_update(&balance_update(150, "USDT", 10.0));
+        assert_eq!(book.balance("USDT").free, 110.0);
+        // the position of the same time includes the deposit
+        book.apply_position(&position(150, &[("USDT", 110.0, 0.0)]));
+        assert_eq!(book.balance("USDT").free, 110.0);
+        book.apply_balance_update(&balance_update(150, "USDT", 10.0));
+        assert_eq!(book.balance("USDT").free, 110.0);
+
+        // late events are skipped
+        book.apply_position(&position(120, &[("USDT", 50.0, 0.0)]));
+        book.apply_balance_update(&balance_update(140, "USDT", 10.0));
+        assert_eq!(book.balance("USDT").free, 110.0);
+
+        book.apply_position(&position(300, &[("USDT", 205.0, 0.0)]));
+        book.apply_balance_update(&balance_update(300, "USDT", 100.0));
+        assert_eq!(book.balance("USDT").free, 205.0);
+        book.apply_balance_update(&balance_update(400, "USDT", -5.0));
+        assert_eq!(book.balance("USDT").free, 200.0);
+
+        // deposits of new assets need no position
+        book.apply_balance_update(&balance_update(500, "BNB", 1.5));
+        assert_eq!(book.balance("BNB").free, 1.5);
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn sync_keeps_newer_stream_updates() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.deposit("USDT", 100.0);
+        paper.deposit("BNB", 2.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let book = BalanceBook::new(client);
+
+        book.apply_position(&position(i64::MAX, &[("BNB", 5.0, 0.0), ("LTC", 4.0, 0.0)]));
+        book.apply_position(&position(1, &[("ETH", 3.0, 0.0), ("USDT", 1.0, 0.0)]));
+        assert!(!book.is_synced());
+        book.sync().await.unwrap();
+
+        assert!(book.is_synced());
+        assert_eq!(book.balance("USDT").free, 100.0);
+        assert_eq!(book.balance("BNB").free, 5.0);
+        // assets missing from the account are emptied unless they changed since
+        assert_eq!(book.balance("ETH").free, 0.0);
+        assert_eq!(book.balance("LTC").free, 4.0);
+
+        assert!(book.reserve("a", "USDT", 10.0));
+        assert!(book.reserve("b", "USDT", 20.0));
+        book.release("a");
+        book.sync().await.unwrap();
+        assert_eq!(book.available("USDT"), 80.0);
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn failed_syncs_do_not_stop_the_stream() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 100.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let listen_key = client
+            .to_user_data_client()
+            .start_stream()
+            .json::<Value>()
+            .await
+            .unwrap();
+        let channel = Channel::UserData(listen_key["listenKey"].as_str().unwrap());
+        let mut stream = WebSocketStream::connect(channel, &paper).await.unwrap();
+
+        let book = book();
+        let tracker = book.clone();
+        let run =
+            tokio::spawn(async move { tracker.run(&mut stream, Duration::from_millis(10)).await });
+
+        client
+            .place_limit_order("BNBUSDT", Side::Buy, 30.0, 1.0, true)
+            .json::<Value>()
+            .await
+            .unwrap();
+        let locked = async {
+            while book.balance("USDT").locked == 0.0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), locked)
+            .await
+            .unwrap();
+
+        assert_eq!(book.balance("USDT").free, 70.0);
+        assert!(!book.is_synced());
+        run.abort();
+    }
+}
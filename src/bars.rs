@@ -0,0 +1,389 @@
+use crate::error::ValidationError;
+use crate::model::{AggTrade, Trade};
+
+/// Rule that decides when a bar is complete.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BarKind {
+    /// Fixed time intervals in milliseconds, aligned to the unix epoch.
+    Time(i64),
+    /// A fixed number of trades.
+    Tick(usize),
+    /// Closes once the base asset volume reaches the threshold.
+    Volume(f64),
+    /// Closes once the quote asset volume reaches the threshold.
+    Dollar(f64),
+}
+
+/// Single trade as seen by the [`BarBuilder`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tick {
+    pub id: i64,
+    pub time: i64,
+    pub price: f64,
+    pub qty: f64,
+    pub is_buyer_maker: bool,
+}
+
+impl From<&Trade> for Tick {
+    fn from(trade: &Trade) -> Self {
+        Tick {
+            id: trade.id,
+            time: trade.time,
+            price: trade.price,
+            qty: trade.qty,
+            is_buyer_maker: trade.is_buyer_maker,
+        }
+    }
+}
+
+impl From<&AggTrade> for Tick {
+    fn from(trade: &AggTrade) -> Self {
+        Tick {
+            id: trade.id,
+            time: trade.time,
+            price: trade.price,
+            qty: trade.qty,
+            is_buyer_maker: trade.is_buyer_maker,
+        }
+    }
+}
+
+/// OHLCV bar with the volume split by taker side.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bar {
+    pub open_time: i64,
+    /// Last millisecond of the interval for time bars, time of the last trade otherwise.
+    pub close_time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub quote_volume: f64,
+    /// Volume bought by takers.
+    pub buy_volume: f64,
+    /// Volume sold by takers.
+    pub sell_volume: f64,
+    pub trades: usize,
+}
+
+impl Bar {
+    fn new(open_time: i64, close_time: i64, price: f64) -> Self {
+        Bar {
+            open_time,
+            close_time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: 0.0,
+            quote_volume: 0.0,
+            buy_volume: 0.0,
+            sell_volume: 0.0,
+            trades: 0,
+        }
+    }
+    /// Volume weighted average price; the close price when there was no volume.
+    pub fn vwap(&self) -> f64 {
+        if self.volume > 0.0 {
+            self.quote_volume / self.volume
+        } else {
+            self.close
+        }
+    }
+
+    fn apply(&mut self, tick: &Tick) {
+        self.high = self.high.max(tick.price);
+        self.low = self.low.min(tick.price);
+        self.close = tick.price;
+        self.volume += tick.qty;
+        self.quote_volume += tick.qty * tick.price;
+        if tick.is_buyer_maker {
+            self.sell_volume += tick.qty;
+        } else {
+            self.buy_volume += tick.qty;
+        }
+        self.trades += 1;
+    }
+}
+
+/// Builds bars from trades or aggregate trades, live or historical.
+///
+/// Results only depend on the order the trades are pushed in:
+/// - trades that belong to an already completed time bar are dropped and counted as late,
+///   whatever their id, e.g. when replayed after a reconnect,
+/// - other trades with an id at or below the last one seen are dropped and counted as duplicates,
+/// - intervals without trades are skipped, or emitted as flat bars with [`with_empty_bars`](Self::with_empty_bars),
+/// - trades are never split; tick, volume and dollar bars close on the trade that reaches the threshold.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{BarBuilder, BarKind, Environment, MarketDataClient};
+/// use tokio_binance::model::AggTrade;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
+///     let trades = client
+///         .get_aggregate_trades("BNBUSDT")
+///         .with_limit(1000)
+///         .json::<Vec<AggTrade>>()
+///         .await?;
+///
+///     // 10 second bars
+///     let mut builder = BarBuilder::new(BarKind::Time(10_000))?.with_empty_bars(true);
+///     for trade in &trades {
+///         for bar in builder.push(trade) {
+///             println!("{:?} vwap: {}", bar, bar.vwap());
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct BarBuilder {
+    kind: BarKind,
+    empty_bars: bool,
+    current: Option<Bar>,
+    last_id: Option<i64>,
+    last_close: Option<f64>,
+    /// Open time of the next time bar; earlier trades are late.
+    next_open: Option<i64>,
+    late: usize,
+    duplicates: usize,
+}
+
+impl BarBuilder {
+    /// Fails unless the interval or threshold of the bars is positive.
+    pub fn new(kind: BarKind) -> crate::error::Result<Self> {
+        let valid = match kind {
+            BarKind::Time(interval) => interval > 0,
+            BarKind::Tick(count) => count > 0,
+            BarKind::Volume(threshold) | BarKind::Dollar(threshold) => threshold > 0.0,
+        };
+        if !valid {
+            return Err(ValidationError::new(format!("{:?} must be positive", kind)).into());
+        }
+
+        Ok(BarBuilder {
+            kind,
+            empty_bars: false,
+            current: None,
+            last_id: None,
+            last_close: None,
+            next_open: None,
+            late: 0,
+            duplicates: 0,
+        })
+    }
+    /// Emit flat, zero volume bars for time intervals without trades; off by default.
+    pub fn with_empty_bars(mut self, empty_bars: bool) -> Self {
+        self.empty_bars = empty_bars;
+        self
+    }
+    /// Number of trades dropped because their time bar was already completed.
+    pub fn late_trades(&self) -> usize {
+        self.late
+    }
+    /// Number of trades dropped because their id was already seen, outside of completed bars.
+    pub fn duplicate_trades(&self) -> usize {
+        self.duplicates
+    }
+    /// The bar that is still being built.
+    pub fn current(&self) -> Option<&Bar> {
+        self.current.as_ref()
+    }
+    /// Adds a trade and returns the bars it completed.
+    pub fn push<T: Into<Tick>>(&mut self, tick: T) -> Vec<Bar> {
+        let tick = tick.into();
+        let open_time = match self.kind {
+            BarKind::Time(interval) => Some(tick.time - tick.time.rem_euclid(interval)),
+            _ => None,
+        };
+        // trades before the next bar are late unless they belong to the current one
+        let before_next = |open_time| matches!(self.next_open, Some(next) if open_time < next);
+        if let Some(open_time) = open_time.filter(|&open_time| before_next(open_time)) {
+            if !matches!(&self.current, Some(bar) if bar.open_time == open_time) {
+                self.late += 1;
+                return Vec::new();
+            }
+        }
+        if matches!(self.last_id, Some(id) if tick.id <= id) {
+            self.duplicates += 1;
+            return Vec::new();
+        }
+
+        let mut bars = Vec::new();
+        if let (BarKind::Time(interval), Some(open_time)) = (self.kind, open_time) {
+            if !before_next(open_time) {
+                bars = self.advance(open_time);
+                if self.current.is_none() {
+                    self.current = Some(Bar::new(open_time, open_time + interval - 1, tick.price));
+                    self.next_open = Some(open_time + interval);
+                }
+            }
+        }
+
+        self.last_id = Some(tick.id);
+        let bar = self
+            .current
+            .get_or_insert_with(|| Bar::new(tick.time, tick.time, tick.price));
+        bar.apply(&tick);
+
+        let complete = match self.kind {
+            BarKind::Time(_) => false,
+            BarKind::Tick(count) => bar.trades >= count,
+            BarKind::Volume(volume) => bar.volume >= volume,
+            BarKind::Dollar(quote) => bar.quote_volume >= quote,
+        };
+
+        if !matches!(self.kind, BarKind::Time(_)) {
+            bar.close_time = tick.time;
+        }
+
+        if complete {
+            bars.extend(self.close());
+        }
+        bars
+    }
+    /// Completes the time bars that end before `time`, e.g. on a timer when no trades arrive.
+    ///
+    /// Does nothing for other kinds of bars.
+    pub fn advance(&mut self, time: i64) -> Vec<Bar> {
+        let interval = match self.kind {
+            BarKind::Time(interval) => interval,
+            _ => return Vec::new(),
+        };
+
+        let mut bars = Vec::new();
+        if matches!(&self.current, Some(bar) if bar.close_time < time) {
+            bars.extend(self.close());
+        }
+
+        if let (true, Some(mut open_time), Some(close)) =
+            (self.empty_bars, self.next_open, self.last_close)
+        {
+            while open_time + interval <= time {
+                bars.push(Bar::new(open_time, open_time + interval - 1, close));
+                open_time += interval;
+            }
+            self.next_open = Some(open_time);
+        }
+        bars
+    }
+    /// Completes the bar that is being built, e.g. at the end of a history.
+    pub fn close(&mut self) -> Option<Bar> {
+        let bar = self.current.take()?;
+        self.last_close = Some(bar.close);
+        if let BarKind::Time(interval) = self.kind {
+            self.next_open = Some(bar.open_time + interval);
+        }
+        Some(bar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(id: i64, time: i64, price: f64, qty: f64) -> Tick {
+        Tick {
+            id,
+            time,
+            price,
+            qty,
+            is_buyer_maker: id % 2 == 0,
+        }
+    }
+
+    #[test]
+    fn rejects_empty_intervals() {
+        assert!(BarBuilder::new(BarKind::Time(0)).is_err());
+        assert!(BarBuilder::new(BarKind::Time(-1_000)).is_err());
+        assert!(BarBuilder::new(BarKind::Tick(0)).is_err());
+        assert!(BarBuilder::new(BarKind::Volume(0.0)).is_err());
+        assert!(BarBuilder::new(BarKind::Dollar(f64::NAN)).is_err());
+    }
+
+    #[test]
+    fn time_bars_close_at_the_interval() {
+        let mut builder = BarBuilder::new(BarKind::Time(1_000)).unwrap();
+        assert!(builder.push(tick(1, 1_000, 10.0, 1.0)).is_empty());
+        assert!(builder.push(tick(2, 1_999, 12.0, 1.0)).is_empty());
+
+        let bars = builder.push(tick(3, 2_000, 11.0, 2.0));
+        assert_eq!(bars.len(), 1);
+        assert_eq!((bars[0].open_time, bars[0].close_time), (1_000, 1_999));
+        assert_eq!(
+            (bars[0].open, bars[0].high, bars[0].close),
+            (10.0, 12.0, 12.0)
+        );
+        assert_eq!(bars[0].trades, 2);
+
+        // trades of the completed bar replayed after a reconnect are late
+        assert!(builder.push(tick(1, 1_000, 10.0, 1.0)).is_empty());
+        assert!(builder.push(tick(2, 1_999, 12.0, 1.0)).is_empty());
+        assert_eq!(builder.late_trades(), 2);
+        // a replay within the current bar is a duplicate
+        assert!(builder.push(tick(3, 2_000, 11.0, 2.0)).is_empty());
+        assert_eq!(builder.duplicate_trades(), 1);
+        assert_eq!(builder.late_trades(), 2);
+
+        let current = builder.current().unwrap();
+        assert_eq!((current.open_time, current.trades), (2_000, 1));
+    }
+
+    #[test]
+    fn time_bars_fill_empty_intervals() {
+        let mut builder = BarBuilder::new(BarKind::Time(1_000))
+            .unwrap()
+            .with_empty_bars(true);
+        builder.push(tick(1, 1_500, 10.0, 1.0));
+
+        let bars = builder.push(tick(2, 4_200, 11.0, 1.0));
+        let open_times: Vec<_> = bars.iter().map(|bar| bar.open_time).collect();
+        assert_eq!(open_times, vec![1_000, 2_000, 3_000]);
+        assert_eq!((bars[2].close, bars[2].volume), (10.0, 0.0));
+    }
+
+    #[test]
+    fn tick_bars_close_on_the_count() {
+        let mut builder = BarBuilder::new(BarKind::Tick(3)).unwrap();
+        assert!(builder.push(tick(1, 1, 10.0, 1.0)).is_empty());
+        assert!(builder.push(tick(2, 2, 11.0, 1.0)).is_empty());
+        // duplicate
+        assert!(builder.push(tick(2, 2, 11.0, 1.0)).is_empty());
+        assert_eq!(builder.duplicate_trades(), 1);
+
+        let bars = builder.push(tick(3, 3, 9.0, 1.0));
+        assert_eq!(bars.len(), 1);
+        assert_eq!((bars[0].open_time, bars[0].close_time), (1, 3));
+        assert_eq!((bars[0].trades, bars[0].low), (3, 9.0));
+        assert!(builder.current().is_none());
+    }
+
+    #[test]
+    fn volume_bars_close_on_the_trade_reaching_the_threshold() {
+        let mut builder = BarBuilder::new(BarKind::Volume(5.0)).unwrap();
+        assert!(builder.push(tick(1, 1, 10.0, 2.0)).is_empty());
+        assert!(builder.push(tick(2, 2, 10.0, 2.9)).is_empty());
+
+        let bars = builder.push(tick(3, 3, 10.0, 4.0));
+        assert_eq!(bars.len(), 1);
+        assert!((bars[0].volume - 8.9).abs() < 1e-9);
+        assert!((bars[0].buy_volume - 6.0).abs() < 1e-9);
+        assert!((bars[0].sell_volume - 2.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn dollar_bars_close_on_the_quote_volume() {
+        let mut builder = BarBuilder::new(BarKind::Dollar(100.0)).unwrap();
+        assert!(builder.push(tick(1, 1, 10.0, 5.0)).is_empty());
+        assert!(builder.push(tick(2, 2, 20.0, 2.0)).is_empty());
+
+        let bars = builder.push(tick(3, 3, 30.0, 1.0));
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].quote_volume, 120.0);
+        assert_eq!(bars[0].vwap(), 15.0);
+    }
+}
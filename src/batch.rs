@@ -0,0 +1,502 @@
+use crate::client::AccountClient;
+use crate::param::{OrderType, Side, ID};
+use futures::stream::{self, StreamExt};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::time::{self, Duration, Instant};
+
+/// Back off used when a rate limited response has no `Retry-After` header.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(10);
+
+/// Limit or market order of a batch; a limit order is good till canceled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchOrder {
+    pub symbol: String,
+    pub side: Side,
+    order_type: OrderType,
+    /// Only used by limit orders.
+    pub price: f64,
+    pub quantity: f64,
+    pub new_client_order_id: Option<String>,
+}
+
+impl BatchOrder {
+    pub fn limit<S: Into<String>>(symbol: S, side: Side, price: f64, quantity: f64) -> Self {
+        Self {
+            symbol: symbol.into(),
+            side,
+            order_type: OrderType::Limit,
+            price,
+            quantity,
+            new_client_order_id: None,
+        }
+    }
+
+    pub fn market<S: Into<String>>(symbol: S, side: Side, quantity: f64) -> Self {
+        Self {
+            symbol: symbol.into(),
+            side,
+            order_type: OrderType::Market,
+            price: 0.0,
+            quantity,
+            new_client_order_id: None,
+        }
+    }
+
+    pub fn with_new_client_order_id<S: Into<String>>(mut self, new_client_order_id: S) -> Self {
+        self.new_client_order_id = Some(new_client_order_id.into());
+        self
+    }
+    /// Either `Limit` or `Market`.
+    pub fn order_type(&self) -> OrderType {
+        self.order_type
+    }
+}
+
+/// Results of a batch.
+#[derive(Debug)]
+pub struct BatchReport {
+    /// Response or error of every request, in input order.
+    pub results: Vec<crate::error::Result<Value>>,
+    /// Cancels of the placed limit orders after a failure in all-or-nothing mode,
+    /// with the index of the order they undo.
+    pub rollback: Vec<(usize, crate::error::Result<Value>)>,
+}
+
+impl BatchReport {
+    /// Whether every request succeeded.
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|result| result.is_ok())
+    }
+}
+
+/// Places or cancels many orders, with a bounded number of requests in flight.
+///
+/// Orders are sent no faster than the order rate limit, 50 per 10 seconds by default,
+/// and requests refused for the rate limit are sent again after the time binance asks for,
+/// 3 times by default. A ban of the IP, which lasts from minutes to days, is returned at once.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{AccountClient, BatchOrder, Environment, Side, ID};
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+///     let orders: Vec<_> = (0..50)
+///         .map(|i| BatchOrder::limit("BNBUSDT", Side::Buy, 300.0 - i as f64, 0.1))
+///         .collect();
+///
+///     let report = client
+///         .batch()
+///         // optional: requests in flight; default is 10.
+///         .with_concurrency(5)
+///         // optional: orders per interval; default is 50 per 10 seconds.
+///         .with_order_rate_limit(100, Duration::from_secs(10))
+///         // optional: resends of a rate limited request; default is 3.
+///         .with_max_retries(5)
+///         // optional: cancels the placed orders if any of them fails.
+///         .all_or_nothing()
+///         .place_orders(&orders)
+///         .await;
+///
+///     for result in &report.results {
+///         println!("{:?}", result);
+///     }
+///
+///     let report = client
+///         .batch()
+///         .cancel_orders(&[("BNBUSDT", ID::ClientOId("<uuid>")), ("BNBUSDT", ID::OrderId(1))])
+///         .await;
+///     Ok(())
+/// }
+/// ```
+pub struct Batch<'a> {
+    client: &'a AccountClient,
+    concurrency: usize,
+    rate_limit: usize,
+    interval: Duration,
+    max_retries: usize,
+    all_or_nothing: bool,
+    /// Send times of the orders in the current interval.
+    sent: Mutex<VecDeque<Instant>>,
+}
+
+impl<'a> Batch<'a> {
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+    pub fn with_order_rate_limit(mut self, limit: usize, interval: Duration) -> Self {
+        self.rate_limit = limit.max(1);
+        self.interval = interval;
+        self
+    }
+    /// Number of times a request refused for the rate limit is sent again.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+    /// Cancels the orders that were placed if any order of the batch fails.
+    ///
+    /// Only resting orders can be undone: market orders are executed at once and are
+    /// left out of the rollback, and the cancel of a limit order that already filled
+    /// fails. Only applies to [`place_orders`](Self::place_orders).
+    pub fn all_or_nothing(mut self) -> Self {
+        self.all_or_nothing = true;
+        self
+    }
+    /// Places the orders; the results are in the order of the input.
+    pub async fn place_orders(&self, orders: &[BatchOrder]) -> BatchReport {
+        let results = self
+            .fan_out(orders.iter().map(|order| self.place(order)))
+            .await;
+
+        let failed = results.iter().any(|result| result.is_err());
+        let rollback = match self.all_or_nothing && failed {
+            true => self.rollback(orders, &results).await,
+            false => Vec::new(),
+        };
+        BatchReport { results, rollback }
+    }
+    /// Cancels the orders; the results are in the order of the input.
+    pub async fn cancel_orders(&self, orders: &[(&str, ID<'_>)]) -> BatchReport {
+        let results = self
+            .fan_out(orders.iter().map(|&(symbol, id)| self.cancel(symbol, id)))
+            .await;
+
+        BatchReport {
+            results,
+            rollback: Vec::new(),
+        }
+    }
+
+    async fn fan_out<I, F>(&self, requests: I) -> Vec<crate::error::Result<Value>>
+    where
+        I: Iterator<Item = F>,
+        F: std::future::Future<Output = crate::error::Result<Value>>,
+    {
+        stream::iter(requests)
+            .buffered(self.concurrency)
+            .collect()
+            .await
+    }
+
+    async fn place(&self, order: &BatchOrder) -> crate::error::Result<Value> {
+        for retries in 0.. {
+            self.acquire().await;
+            let response = match order.order_type {
+                OrderType::Market => {
+                    let mut builder = self.client.place_market_order(
+                        &order.symbol,
+                        order.side,
+                        order.quantity,
+                        true,
+                    );
+                    if let Some(id) = &order.new_client_order_id {
+                        builder = builder.with_new_client_order_id(id);
+                    }
+                    builder.json::<Value>().await
+                }
+                OrderType::Limit => {
+                    let mut builder = self.client.place_limit_order(
+                        &order.symbol,
+                        order.side,
+                        order.price,
+                        order.quantity,
+                        true,
+                    );
+                    if let Some(id) = &order.new_client_order_id {
+                        builder = builder.with_new_client_order_id(id);
+                    }
+                    builder.json::<Value>().await
+                }
+                _ => unreachable!("batch orders are built as limit or market orders"),
+            };
+
+            let retry_after = response.as_ref().err().and_then(|error| {
+                error.retry_after(retries, self.max_retries, DEFAULT_RETRY_AFTER)
+            });
+            match retry_after {
+                Some(retry_after) => time::sleep(retry_after).await,
+                None => return response,
+            }
+        }
+        unreachable!("retries are bounded")
+    }
+
+    async fn cancel(&self, symbol: &str, id: ID<'_>) -> crate::error::Result<Value> {
+        for retries in 0.. {
+            let response = self.client.cancel_order(symbol, id).json::<Value>().await;
+            let retry_after = response.as_ref().err().and_then(|error| {
+                error.retry_after(retries, self.max_retries, DEFAULT_RETRY_AFTER)
+            });
+            match retry_after {
+                Some(retry_after) => time::sleep(retry_after).await,
+                None => return response,
+            }
+        }
+        unreachable!("retries are bounded")
+    }
+
+    /// Cancels the placed limit orders of a failed batch.
+    async fn rollback(
+        &self,
+        orders: &[BatchOrder],
+        results: &[crate::error::Result<Value>],
+    ) -> Vec<(usize, crate::error::Result<Value>)> {
+        let placed: Vec<_> = results
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| orders[index].order_type == OrderType::Limit)
+            .filter_map(|(index, result)| match result {
+                Ok(response) => Some((index, response["orderId"].as_i64()?)),
+                Err(_) => None,
+            })
+            .collect();
+
+        let cancels = placed.iter().map(|&(index, order_id)| async move {
+            let result = self
+                .cancel(&orders[index].symbol, ID::OrderId(order_id))
+                .await;
+            (index, result)
+        });
+        stream::iter(cancels)
+            .buffered(self.concurrency)
+            .collect()
+            .await
+    }
+
+    /// Waits until another order fits in the order rate limit.
+    async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut sent = self.sent.lock().unwrap();
+                let now = Instant::now();
+                while let Some(&oldest) = sent.front() {
+                    match now.duration_since(oldest) >= self.interval {
+                        true => sent.pop_front(),
+                        false => break,
+                    };
+                }
+                if sent.len() < self.rate_limit {
+                    sent.push_back(now);
+                    return;
+                }
+                self.interval - now.duration_since(sent[0])
+            };
+            time::sleep(wait).await;
+        }
+    }
+}
+
+impl AccountClient {
+    /// Batch of order requests sharing a concurrency and order rate limit.
+    pub fn batch(&self) -> Batch<'_> {
+        Batch {
+            client: self,
+            concurrency: 10,
+            rate_limit: 50,
+            interval: Duration::from_secs(10),
+            max_retries: 3,
+            all_or_nothing: false,
+            sent: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{reply, MockServer};
+    use crate::model::ApiError;
+    #[cfg(feature = "paper")]
+    use crate::model::{OrderBook, PriceLevel};
+    #[cfg(feature = "paper")]
+    use crate::PaperExchange;
+    use hyper::header::HeaderValue;
+    use hyper::StatusCode;
+    use std::collections::HashMap;
+
+    #[cfg(feature = "paper")]
+    async fn paper() -> (PaperExchange, AccountClient) {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 1000.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        (paper, client)
+    }
+
+    #[cfg(feature = "paper")]
+    fn orders() -> Vec<BatchOrder> {
+        (0..6)
+            .map(|i| {
+                BatchOrder::limit("BNBUSDT", Side::Buy, 100.0 - i as f64, 1.0)
+                    .with_new_client_order_id(format!("order-{}", i))
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn results_are_in_input_order() {
+        let (_paper, client) = paper().await;
+        let mut orders = orders();
+        orders[3] = BatchOrder::limit("UNKNOWN", Side::Buy, 100.0, 1.0);
+
+        let report = client
+            .batch()
+            .with_concurrency(3)
+            .place_orders(&orders)
+            .await;
+
+        assert!(!report.is_success());
+        assert!(report.rollback.is_empty());
+        for (i, result) in report.results.iter().enumerate() {
+            match result {
+                Err(error) => {
+                    assert_eq!(i, 3);
+                    assert_eq!(ApiError::from_error(error).unwrap().code, -1121);
+                }
+                Ok(order) => assert_eq!(order["clientOrderId"], format!("order-{}", i)),
+            }
+        }
+
+        let ids: Vec<_> = report.results[..3]
+            .iter()
+            .map(|result| result.as_ref().unwrap()["orderId"].as_i64().unwrap())
+            .collect();
+        let cancels: Vec<_> = ids.iter().map(|&id| ("BNBUSDT", ID::OrderId(id))).collect();
+        let report = client.batch().cancel_orders(&cancels).await;
+        assert!(report.is_success());
+        for (result, id) in report.results.iter().zip(ids) {
+            assert_eq!(result.as_ref().unwrap()["orderId"], id);
+        }
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn all_or_nothing_cancels_placed_orders() {
+        let (paper, client) = paper().await;
+        let mut orders = orders();
+        orders[2] = BatchOrder::market("UNKNOWN", Side::Buy, 1.0);
+
+        let report = client
+            .batch()
+            .with_concurrency(2)
+            .all_or_nothing()
+            .place_orders(&orders)
+            .await;
+
+        assert!(report.results[2].is_err());
+        let undone: Vec<_> = report.rollback.iter().map(|(index, _)| *index).collect();
+        assert_eq!(undone, vec![0, 1, 3, 4, 5]);
+        for (_, result) in &report.rollback {
+            assert_eq!(result.as_ref().unwrap()["status"], "CANCELED");
+        }
+        assert_eq!(paper.balance("USDT"), (1000.0, 0.0));
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn all_or_nothing_leaves_executed_orders() {
+        let (paper, client) = paper().await;
+        let book = OrderBook {
+            last_update_id: 1,
+            bids: Vec::new(),
+            asks: vec![PriceLevel(99.0, 1.0), PriceLevel(100.0, 10.0)],
+        };
+        paper.set_order_book("BNBUSDT", &book);
+        paper.set_fees(0.0, 0.0);
+
+        let orders = vec![
+            BatchOrder::market("BNBUSDT", Side::Buy, 1.0),
+            BatchOrder::limit("BNBUSDT", Side::Buy, 90.0, 1.0),
+            // fills at once
+            BatchOrder::limit("BNBUSDT", Side::Buy, 100.0, 1.0),
+            BatchOrder::limit("UNKNOWN", Side::Buy, 100.0, 1.0),
+        ];
+        let report = client
+            .batch()
+            .with_concurrency(1)
+            .all_or_nothing()
+            .place_orders(&orders)
+            .await;
+
+        assert_eq!(report.results[0].as_ref().unwrap()["status"], "FILLED");
+        let undone: Vec<_> = report.rollback.iter().map(|(index, _)| *index).collect();
+        assert_eq!(undone, vec![1, 2]);
+        assert_eq!(report.rollback[0].1.as_ref().unwrap()["status"], "CANCELED");
+        let error = report.rollback[1].1.as_ref().unwrap_err();
+        assert_eq!(ApiError::from_error(error).unwrap().code, -2011);
+
+        assert_eq!(paper.balance("BNB"), (2.0, 0.0));
+        assert_eq!(paper.balance("USDT"), (801.0, 0.0));
+    }
+
+    /// Answers cancels: BNBUSDT is rate limited twice, BTCUSDT always and ETHUSDT is banned.
+    async fn rate_limited() -> (AccountClient, MockServer) {
+        let server = MockServer::start().await;
+        let mut sent = HashMap::new();
+        server.route("/api/v3/order", move |req| {
+            let symbol = req.param("symbol").unwrap_or_default().to_string();
+            let sent = sent
+                .entry(symbol.clone())
+                .and_modify(|n| *n += 1)
+                .or_insert(1);
+            let mut response = match symbol.as_str() {
+                "ETHUSDT" => reply(
+                    StatusCode::IM_A_TEAPOT,
+                    r#"{"code":-1003,"msg":"Way too many requests; IP banned."}"#,
+                ),
+                "BNBUSDT" if *sent > 2 => reply(StatusCode::OK, r#"{"status":"CANCELED"}"#),
+                _ => reply(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    r#"{"code":-1003,"msg":"Too many requests."}"#,
+                ),
+            };
+            let retry_after = HeaderValue::from_static("1");
+            response.headers_mut().insert("Retry-After", retry_after);
+            response
+        });
+
+        let client = AccountClient::connect("<api-key>", "<secret-key>", server.url()).unwrap();
+        (client, server)
+    }
+
+    #[tokio::test]
+    async fn rate_limited_requests_are_retried_a_few_times() {
+        let (client, server) = rate_limited().await;
+        let orders = [
+            ("BNBUSDT", ID::OrderId(1)),
+            ("BTCUSDT", ID::OrderId(2)),
+            ("ETHUSDT", ID::OrderId(3)),
+        ];
+        time::pause();
+        let report = client
+            .batch()
+            .with_concurrency(1)
+            .with_max_retries(2)
+            .cancel_orders(&orders)
+            .await;
+
+        assert_eq!(report.results[0].as_ref().unwrap()["status"], "CANCELED");
+        let error = report.results[1].as_ref().unwrap_err();
+        assert_eq!(ApiError::from_error(error).unwrap().code, -1003);
+        // a ban is not waited out
+        let error = report.results[2].as_ref().unwrap_err();
+        assert_eq!(ApiError::from_error(error).unwrap().code, -1003);
+
+        let sent = server.requests();
+        let count = |symbol| {
+            sent.iter()
+                .filter(|req| req.param("symbol") == Some(symbol))
+                .count()
+        };
+        assert_eq!(count("BNBUSDT"), 3);
+        assert_eq!(count("BTCUSDT"), 3);
+        assert_eq!(count("ETHUSDT"), 1);
+    }
+}
@@ -1,36 +1,44 @@
 use crate::error::ClientError;
+use crate::model::CancelReplace;
 use crate::param::{self, Parameters};
+use crate::signer::AsyncSigner;
 use crate::types::*;
 use chrono::{DateTime, TimeZone};
 use log::warn;
-use reqwest::{header::CONTENT_TYPE, RequestBuilder, Response};
+use reqwest::{
+    header::{CONTENT_TYPE, RETRY_AFTER},
+    RequestBuilder, Response,
+};
 use serde::de::DeserializeOwned;
 use std::marker::PhantomData;
+use std::time::Duration;
 
-pub struct ParamBuilder<'a, 'b, T> {
+pub struct ParamBuilder<'a, 'b, T, B = RequestBuilder> {
     _marker: PhantomData<T>,
-    params: Parameters<'a>,
-    builder: RequestBuilder,
-    api_key: Option<&'b str>,
-    secret_key: Option<&'b str>,
+    pub(crate) params: Parameters<'a>,
+    pub(crate) builder: B,
+    pub(crate) api_key: Option<&'b str>,
+    pub(crate) signer: Option<&'b dyn AsyncSigner>,
 }
 
-impl<'a, 'b, T> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T, B> ParamBuilder<'a, 'b, T, B> {
     pub fn new(
         params: Parameters<'a>,
-        builder: RequestBuilder,
+        builder: B,
         api_key: Option<&'b str>,
-        secret_key: Option<&'b str>,
+        signer: Option<&'b dyn AsyncSigner>,
     ) -> Self {
         Self {
             _marker: PhantomData,
             params,
             builder,
             api_key,
-            secret_key,
+            signer,
         }
     }
+}
 
+impl<'a, 'b, T> ParamBuilder<'a, 'b, T> {
     pub async fn text(self) -> crate::error::Result<String> {
         let text = self.response().await?.text().await?;
         Ok(text)
@@ -42,15 +50,22 @@ impl<'a, 'b, T> ParamBuilder<'a, 'b, T> {
     }
 
     async fn response(self) -> crate::error::Result<Response> {
-        let res = self.builder()?.send().await?;
+        let res = self.builder().await?.send().await?;
         let status = res.status();
 
         if status.is_success() {
             Ok(res)
         } else if status.is_client_error() {
             let reason = status.canonical_reason().unwrap_or("UNKNOWN");
+            let retry_after = res
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs);
             let message = res.text().await.unwrap_or_else(|_| "".into());
-            let err = ClientError::new(status.as_u16(), reason, &message);
+            let err =
+                ClientError::new(status.as_u16(), reason, &message).with_retry_after(retry_after);
             Err(err.into())
         } else {
             warn!("{}", status);
@@ -58,15 +73,17 @@ impl<'a, 'b, T> ParamBuilder<'a, 'b, T> {
         }
     }
 
-    fn builder(mut self) -> crate::error::Result<RequestBuilder> {
+    async fn builder(mut self) -> crate::error::Result<RequestBuilder> {
+        self.params.validate()?;
+
         let builder = if let Some(api_key) = self.api_key {
             self.builder.header("X-MBX-APIKEY", api_key)
         } else {
             self.builder
         };
 
-        let params = if let Some(secret_key) = self.secret_key {
-            self.params.sign(secret_key)?
+        let params = if let Some(signer) = self.signer {
+            self.params.sign(signer).await?
         } else {
             &self.params
         };
@@ -89,63 +106,97 @@ impl<'a, 'b, T> ParamBuilder<'a, 'b, T> {
     }
 }
 
-impl<'a, 'b, T: Symbol> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b> ParamBuilder<'a, 'b, CancelReplaceParams> {
+    /// Sends the request; partial failures are returned as a response instead of an error.
+    pub async fn outcome(self) -> crate::error::Result<CancelReplace> {
+        match self.json::<CancelReplace>().await {
+            Ok(response) => Ok(response),
+            Err(error) => match CancelReplace::from_error(&error) {
+                Some(response) => Ok(response),
+                None => Err(error),
+            },
+        }
+    }
+}
+
+impl<'a, 'b, T: Symbol, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_symbol(mut self, symbol: &'a str) -> Self {
         self.params.symbol = Some(symbol);
         self
     }
 }
 
-impl<'a, 'b, T: Limit> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: Limit, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_limit(mut self, limit: usize) -> Self {
         self.params.limit = Some(limit);
         self
     }
 }
 
-impl<'a, 'b, T: FromId> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: FromId, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_from_id(mut self, from_id: i64) -> Self {
         self.params.from_id = Some(from_id);
         self
     }
 }
 
-impl<'a, 'b, T: StartTime> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: StartTime, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_start_time<Tz: TimeZone>(mut self, start_time: DateTime<Tz>) -> Self {
         self.params.start_time = Some(start_time.timestamp_millis());
         self
     }
 }
 
-impl<'a, 'b, T: EndTime> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: EndTime, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_end_time<Tz: TimeZone>(mut self, end_time: DateTime<Tz>) -> Self {
         self.params.end_time = Some(end_time.timestamp_millis());
         self
     }
 }
 
-impl<'a, 'b, T: TimeInForce> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: crate::types::TimeZone, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_time_zone(mut self, time_zone: &'a str) -> Self {
+        self.params.time_zone = Some(time_zone);
+        self
+    }
+}
+
+impl<'a, 'b, T: WindowSize, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_window_size(mut self, window_size: param::WindowSize) -> Self {
+        self.params.window_size = Some(window_size);
+        self
+    }
+}
+
+impl<'a, 'b, T: TickerType, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_ticker_type(mut self, ticker_type: param::TickerType) -> Self {
+        self.params.ticker_type = Some(ticker_type);
+        self
+    }
+}
+
+impl<'a, 'b, T: TimeInForce, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_time_in_force(mut self, time_in_force: param::TimeInForce) -> Self {
         self.params.time_in_force = Some(time_in_force);
         self
     }
 }
 
-impl<'a, 'b, T: Price> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: Price, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_price(mut self, price: f64) -> Self {
         self.params.price = Some(price);
         self
     }
 }
 
-impl<'a, 'b, T: NewClientOrderId> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: NewClientOrderId, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_new_client_order_id(mut self, new_client_order_id: &'a str) -> Self {
         self.params.new_client_order_id = Some(new_client_order_id);
         self
     }
 }
 
-impl<'a, 'b, T: MarketOrderStopPrice> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: MarketOrderStopPrice, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_stop_loss(mut self, stop_price: f64) -> Self {
         self.params.order_type = Some(param::OrderType::StopLoss);
         self.params.stop_price = Some(stop_price);
@@ -159,7 +210,7 @@ impl<'a, 'b, T: MarketOrderStopPrice> ParamBuilder<'a, 'b, T> {
     }
 }
 
-impl<'a, 'b, T: LimitOrderStopPrice> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: LimitOrderStopPrice, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_stop_loss_limit(mut self, stop_price: f64) -> Self {
         self.params.order_type = Some(param::OrderType::StopLossLimit);
         self.params.stop_price = Some(stop_price);
@@ -173,8 +224,60 @@ impl<'a, 'b, T: LimitOrderStopPrice> ParamBuilder<'a, 'b, T> {
     }
 }
 
-impl<'a, 'b, T: LimitMaker> ParamBuilder<'a, 'b, T> {
-    pub fn into_limit_maker_order(self) -> ParamBuilder<'a, 'b, LimitMakerOrderParams> {
+impl<'a, 'b, T: QuoteOrderQty, B> ParamBuilder<'a, 'b, T, B> {
+    /// Spends or receives the amount of the quote asset instead of the quantity.
+    pub fn with_quote_order_qty(mut self, quote_order_qty: f64) -> Self {
+        self.params.quantity = None;
+        self.params.quote_order_qty = Some(quote_order_qty);
+        self
+    }
+}
+
+impl<'a, 'b, T: TrailingDelta, B> ParamBuilder<'a, 'b, T, B> {
+    /// Trails the stop by the basis points; the stop price, if set, activates it.
+    pub fn with_trailing_delta(mut self, trailing_delta: u32) -> Self {
+        self.params.trailing_delta = Some(trailing_delta);
+        self
+    }
+}
+
+impl<'a, 'b, T: SelfTradePreventionMode, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_self_trade_prevention_mode(mut self, mode: param::SelfTradePreventionMode) -> Self {
+        self.params.self_trade_prevention_mode = Some(mode);
+        self
+    }
+}
+
+impl<'a, 'b, T: Strategy, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_strategy_id(mut self, strategy_id: i64) -> Self {
+        self.params.strategy_id = Some(strategy_id);
+        self
+    }
+
+    pub fn with_strategy_type(mut self, strategy_type: i64) -> Self {
+        self.params.strategy_type = Some(strategy_type);
+        self
+    }
+}
+
+impl<'a, 'b, T: PegPriceType, B> ParamBuilder<'a, 'b, T, B> {
+    /// Pegs the price to the book; the price of the order is left to binance.
+    pub fn with_peg_price_type(mut self, peg_price_type: param::PegPriceType) -> Self {
+        self.params.price = None;
+        self.params.peg_price_type = Some(peg_price_type);
+        self
+    }
+
+    /// Offsets a pegged price by the number of price levels.
+    pub fn with_peg_offset(mut self, price_levels: u32) -> Self {
+        self.params.peg_offset_value = Some(price_levels);
+        self.params.peg_offset_type = Some("PRICE_LEVEL");
+        self
+    }
+}
+
+impl<'a, 'b, T: LimitMaker, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn into_limit_maker_order(self) -> ParamBuilder<'a, 'b, LimitMakerOrderParams, B> {
         ParamBuilder::new(
             Parameters {
                 symbol: self.params.symbol,
@@ -182,16 +285,24 @@ impl<'a, 'b, T: LimitMaker> ParamBuilder<'a, 'b, T> {
                 order_type: Some(param::OrderType::LimitMaker),
                 price: self.params.price,
                 quantity: self.params.quantity,
+                iceberg_qty: self.params.iceberg_qty,
+                new_client_order_id: self.params.new_client_order_id,
+                self_trade_prevention_mode: self.params.self_trade_prevention_mode,
+                strategy_id: self.params.strategy_id,
+                strategy_type: self.params.strategy_type,
+                peg_price_type: self.params.peg_price_type,
+                peg_offset_value: self.params.peg_offset_value,
+                peg_offset_type: self.params.peg_offset_type,
                 ..Parameters::default()
             },
             self.builder,
             self.api_key,
-            self.secret_key,
+            self.signer,
         )
     }
 }
 
-impl<'a, 'b, T: IcebergQty> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: IcebergQty, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_iceberg_qty(mut self, iceberg_qty: f64) -> Self {
         self.params.time_in_force = Some(param::TimeInForce::Gtc);
         self.params.iceberg_qty = Some(iceberg_qty);
@@ -199,56 +310,56 @@ impl<'a, 'b, T: IcebergQty> ParamBuilder<'a, 'b, T> {
     }
 }
 
-impl<'a, 'b, T: NewOrderRespType> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: NewOrderRespType, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_new_order_resp_type(mut self, new_order_resp_type: param::OrderRespType) -> Self {
         self.params.new_order_resp_type = Some(new_order_resp_type);
         self
     }
 }
 
-impl<'a, 'b, T: OrderId> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: OrderId, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_order_id(mut self, order_id: i64) -> Self {
         self.params.order_id = Some(order_id);
         self
     }
 }
 
-impl<'a, 'b, T: ListClientOrderId> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: ListClientOrderId, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_list_client_order_id(mut self, list_client_order_id: &'a str) -> Self {
         self.params.list_client_order_id = Some(list_client_order_id);
         self
     }
 }
 
-impl<'a, 'b, T: LimitClientOrderId> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: LimitClientOrderId, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_limit_client_order_id(mut self, limit_client_order_id: &'a str) -> Self {
         self.params.limit_client_order_id = Some(limit_client_order_id);
         self
     }
 }
 
-impl<'a, 'b, T: StopClientOrderId> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: StopClientOrderId, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_stop_client_order_id(mut self, stop_client_order_id: &'a str) -> Self {
         self.params.stop_client_order_id = Some(stop_client_order_id);
         self
     }
 }
 
-impl<'a, 'b, T: LimitIcebergQty> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: LimitIcebergQty, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_limit_iceberg_qty(mut self, limit_iceberg_qty: f64) -> Self {
         self.params.limit_iceberg_qty = Some(limit_iceberg_qty);
         self
     }
 }
 
-impl<'a, 'b, T: StopIcebergQty> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: StopIcebergQty, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_stop_iceberg_qty(mut self, stop_iceberg_qty: f64) -> Self {
         self.params.stop_iceberg_qty = Some(stop_iceberg_qty);
         self
     }
 }
 
-impl<'a, 'b, T: StopLimitPrice> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: StopLimitPrice, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_stop_limit_price(
         mut self,
         stop_limit_price: f64,
@@ -260,49 +371,249 @@ impl<'a, 'b, T: StopLimitPrice> ParamBuilder<'a, 'b, T> {
     }
 }
 
-impl<'a, 'b, T: AddressTag> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: AboveLeg, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_above_price(mut self, price: f64) -> Self {
+        self.params.above_price = Some(price);
+        self
+    }
+
+    pub fn with_above_stop_price(mut self, stop_price: f64) -> Self {
+        self.params.above_stop_price = Some(stop_price);
+        self
+    }
+
+    pub fn with_above_client_order_id(mut self, client_order_id: &'a str) -> Self {
+        self.params.above_client_order_id = Some(client_order_id);
+        self
+    }
+
+    pub fn with_above_iceberg_qty(mut self, iceberg_qty: f64) -> Self {
+        self.params.above_iceberg_qty = Some(iceberg_qty);
+        self
+    }
+
+    pub fn with_above_time_in_force(mut self, time_in_force: param::TimeInForce) -> Self {
+        self.params.above_time_in_force = Some(time_in_force);
+        self
+    }
+}
+
+impl<'a, 'b, T: BelowLeg, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_below_price(mut self, price: f64) -> Self {
+        self.params.below_price = Some(price);
+        self
+    }
+
+    pub fn with_below_stop_price(mut self, stop_price: f64) -> Self {
+        self.params.below_stop_price = Some(stop_price);
+        self
+    }
+
+    pub fn with_below_client_order_id(mut self, client_order_id: &'a str) -> Self {
+        self.params.below_client_order_id = Some(client_order_id);
+        self
+    }
+
+    pub fn with_below_iceberg_qty(mut self, iceberg_qty: f64) -> Self {
+        self.params.below_iceberg_qty = Some(iceberg_qty);
+        self
+    }
+
+    pub fn with_below_time_in_force(mut self, time_in_force: param::TimeInForce) -> Self {
+        self.params.below_time_in_force = Some(time_in_force);
+        self
+    }
+}
+
+impl<'a, 'b, T: WorkingLeg, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_working_client_order_id(mut self, client_order_id: &'a str) -> Self {
+        self.params.working_client_order_id = Some(client_order_id);
+        self
+    }
+
+    pub fn with_working_iceberg_qty(mut self, iceberg_qty: f64) -> Self {
+        self.params.working_iceberg_qty = Some(iceberg_qty);
+        self
+    }
+
+    pub fn with_working_time_in_force(mut self, time_in_force: param::TimeInForce) -> Self {
+        self.params.working_time_in_force = Some(time_in_force);
+        self
+    }
+}
+
+impl<'a, 'b, T: PendingLeg, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_pending_price(mut self, price: f64) -> Self {
+        self.params.pending_price = Some(price);
+        self
+    }
+
+    pub fn with_pending_stop_price(mut self, stop_price: f64) -> Self {
+        self.params.pending_stop_price = Some(stop_price);
+        self
+    }
+
+    pub fn with_pending_client_order_id(mut self, client_order_id: &'a str) -> Self {
+        self.params.pending_client_order_id = Some(client_order_id);
+        self
+    }
+
+    pub fn with_pending_iceberg_qty(mut self, iceberg_qty: f64) -> Self {
+        self.params.pending_iceberg_qty = Some(iceberg_qty);
+        self
+    }
+
+    pub fn with_pending_time_in_force(mut self, time_in_force: param::TimeInForce) -> Self {
+        self.params.pending_time_in_force = Some(time_in_force);
+        self
+    }
+}
+
+impl<'a, 'b, T: PendingAboveLeg, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_pending_above_price(mut self, price: f64) -> Self {
+        self.params.pending_above_price = Some(price);
+        self
+    }
+
+    pub fn with_pending_above_stop_price(mut self, stop_price: f64) -> Self {
+        self.params.pending_above_stop_price = Some(stop_price);
+        self
+    }
+
+    pub fn with_pending_above_client_order_id(mut self, client_order_id: &'a str) -> Self {
+        self.params.pending_above_client_order_id = Some(client_order_id);
+        self
+    }
+
+    pub fn with_pending_above_iceberg_qty(mut self, iceberg_qty: f64) -> Self {
+        self.params.pending_above_iceberg_qty = Some(iceberg_qty);
+        self
+    }
+
+    pub fn with_pending_above_time_in_force(mut self, time_in_force: param::TimeInForce) -> Self {
+        self.params.pending_above_time_in_force = Some(time_in_force);
+        self
+    }
+}
+
+impl<'a, 'b, T: PendingBelowLeg, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_pending_below_price(mut self, price: f64) -> Self {
+        self.params.pending_below_price = Some(price);
+        self
+    }
+
+    pub fn with_pending_below_stop_price(mut self, stop_price: f64) -> Self {
+        self.params.pending_below_stop_price = Some(stop_price);
+        self
+    }
+
+    pub fn with_pending_below_client_order_id(mut self, client_order_id: &'a str) -> Self {
+        self.params.pending_below_client_order_id = Some(client_order_id);
+        self
+    }
+
+    pub fn with_pending_below_iceberg_qty(mut self, iceberg_qty: f64) -> Self {
+        self.params.pending_below_iceberg_qty = Some(iceberg_qty);
+        self
+    }
+
+    pub fn with_pending_below_time_in_force(mut self, time_in_force: param::TimeInForce) -> Self {
+        self.params.pending_below_time_in_force = Some(time_in_force);
+        self
+    }
+}
+
+impl<'a, 'b, T: PendingQuantity, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_pending_quantity(mut self, pending_quantity: f64) -> Self {
+        self.params.pending_quantity = Some(pending_quantity);
+        self
+    }
+}
+
+impl<'a, 'b, T: CancelNewClientOrderId, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_cancel_new_client_order_id(mut self, cancel_new_client_order_id: &'a str) -> Self {
+        self.params.cancel_new_client_order_id = Some(cancel_new_client_order_id);
+        self
+    }
+}
+
+impl<'a, 'b, T: CancelRestrictions, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_cancel_restrictions(
+        mut self,
+        cancel_restrictions: param::CancelRestrictions,
+    ) -> Self {
+        self.params.cancel_restrictions = Some(cancel_restrictions);
+        self
+    }
+}
+
+impl<'a, 'b, T: OrderRateLimitExceededMode, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_order_rate_limit_exceeded_mode(
+        mut self,
+        mode: param::OrderRateLimitExceededMode,
+    ) -> Self {
+        self.params.order_rate_limit_exceeded_mode = Some(mode);
+        self
+    }
+}
+
+impl<'a, 'b, T: FromPreventedMatchId, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_from_prevented_match_id(mut self, from_prevented_match_id: i64) -> Self {
+        self.params.from_prevented_match_id = Some(from_prevented_match_id);
+        self
+    }
+}
+
+impl<'a, 'b, T: FromAllocationId, B> ParamBuilder<'a, 'b, T, B> {
+    pub fn with_from_allocation_id(mut self, from_allocation_id: i64) -> Self {
+        self.params.from_allocation_id = Some(from_allocation_id);
+        self
+    }
+}
+
+impl<'a, 'b, T: AddressTag, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_address_tag(mut self, address_tag: &'a str) -> Self {
         self.params.address_tag = Some(address_tag);
         self
     }
 }
 
-impl<'a, 'b, T: Name> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: Name, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_name(mut self, name: &'a str) -> Self {
         self.params.name = Some(name);
         self
     }
 }
 
-impl<'a, 'b, T: Asset> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: Asset, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_asset(mut self, asset: &'a str) -> Self {
         self.params.asset = Some(asset);
         self
     }
 }
 
-impl<'a, 'b, T: Status> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: Status, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_status<J: Into<serde_json::Value>>(mut self, status: J) -> Self {
         self.params.status = Some(status.into());
         self
     }
 }
 
-impl<'a, 'b, T: Email> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: Email, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_email(mut self, email: &'a str) -> Self {
         self.params.email = Some(email);
         self
     }
 }
 
-impl<'a, 'b, T: Page> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: Page, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_page(mut self, page: usize) -> Self {
         self.params.page = Some(page);
         self
     }
 }
 
-impl<'a, 'b, T: RecvWindow> ParamBuilder<'a, 'b, T> {
+impl<'a, 'b, T: RecvWindow, B> ParamBuilder<'a, 'b, T, B> {
     pub fn with_recv_window(mut self, recv_window: usize) -> Self {
         self.params.recv_window = Some(recv_window);
         self
@@ -0,0 +1,523 @@
+use crate::client::MarketDataClient;
+use crate::environment::Endpoint;
+use crate::model::{Kline, KlineEvent};
+use crate::param::Interval;
+use crate::ws_stream::{Channel, WebSocketStream};
+use chrono::{TimeZone, Utc};
+use std::collections::VecDeque;
+
+/// Maximum number of klines binance returns per request.
+const KLINES_PER_REQUEST: usize = 1000;
+
+/// Candles of a symbol, backfilled over REST and kept up to date by the kline stream.
+///
+/// The open candle is replaced on every update and finalized when the stream closes it.
+/// After a reconnect the candles missed in between are refilled over REST,
+/// so the series has no gaps nor duplicates.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{CandleSeries, Environment, Interval};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut series = CandleSeries::connect(
+///         "<api-key>",
+///         Environment::BinanceUs,
+///         "BNBUSDT",
+///         Interval::OneMinute,
+///         // number of candles to keep
+///         2000
+///     ).await?;
+///
+///     while let Some(kline) = series.next().await? {
+///         if kline.is_closed {
+///             let closes: Vec<_> = series.closed().map(|kline| kline.close).collect();
+///             println!("{} closed candles, last {:?}", closes.len(), closes.last());
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct CandleSeries {
+    client: MarketDataClient,
+    stream_url: String,
+    symbol: String,
+    interval: Interval,
+    capacity: usize,
+    candles: VecDeque<Kline>,
+    stream: Option<WebSocketStream>,
+}
+
+impl CandleSeries {
+    /// Attaches to the kline stream and backfills up to `capacity` candles over REST.
+    pub async fn connect<A, U, S>(
+        api_key: A,
+        url: U,
+        symbol: S,
+        interval: Interval,
+        capacity: usize,
+    ) -> crate::error::Result<Self>
+    where
+        A: Into<String>,
+        U: Endpoint,
+        S: Into<String>,
+    {
+        let mut series = Self {
+            client: MarketDataClient::connect(api_key, url.rest_url())?,
+            stream_url: url.stream_url().to_string(),
+            symbol: symbol.into().to_uppercase(),
+            interval,
+            capacity: capacity.max(1),
+            candles: VecDeque::new(),
+            stream: None,
+        };
+
+        // attach first, so that nothing is missed while backfilling
+        series.attach().await?;
+        series.backfill().await?;
+        Ok(series)
+    }
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+    pub fn interval(&self) -> Interval {
+        self.interval
+    }
+    /// All candles from oldest to newest; the last one may still be open.
+    pub fn candles(&self) -> &VecDeque<Kline> {
+        &self.candles
+    }
+    /// Only the closed candles, from oldest to newest.
+    pub fn closed(&self) -> impl Iterator<Item = &Kline> {
+        self.candles.iter().filter(|kline| kline.is_closed)
+    }
+    /// The newest candle.
+    pub fn last(&self) -> Option<&Kline> {
+        self.candles.back()
+    }
+    /// Waits for the next kline update and returns the updated candle.
+    ///
+    /// A connection that ends is reconnected and the gap refilled over REST;
+    /// errors are returned, the next call will try to reconnect again.
+    pub async fn next(&mut self) -> crate::error::Result<Option<Kline>> {
+        loop {
+            if self.stream.is_none() {
+                self.attach().await?;
+                self.refill(None).await?;
+            }
+
+            let stream = self.stream.as_mut().expect("attached");
+            let event = match stream.stream_event::<KlineEvent>().await {
+                Ok(Some(event)) => event,
+                Ok(None) => {
+                    self.stream = None;
+                    continue;
+                }
+                Err(error) => {
+                    self.stream = None;
+                    return Err(error);
+                }
+            };
+
+            let kline = event.data.kline;
+            if let Some(last) = self.candles.back() {
+                // missed the final update of the last candle or whole candles
+                if kline.open_time > last.open_time
+                    && (!last.is_closed || kline.open_time > last.close_time + 1)
+                {
+                    self.refill(Some(kline.open_time - 1)).await?;
+                }
+            }
+
+            if self.insert(kline.clone()) {
+                return Ok(Some(kline));
+            }
+        }
+    }
+    /// Close the kline stream.
+    pub async fn close(&mut self) -> crate::error::Result<()> {
+        if let Some(mut stream) = self.stream.take() {
+            stream.close(None).await?;
+        }
+        Ok(())
+    }
+
+    async fn attach(&mut self) -> crate::error::Result<()> {
+        let channel = Channel::Kline(&self.symbol, self.interval);
+        self.stream = Some(WebSocketStream::connect(channel, self.stream_url.as_str()).await?);
+        Ok(())
+    }
+
+    /// Pages backwards from now until the series is full or the history ends.
+    async fn backfill(&mut self) -> crate::error::Result<()> {
+        let mut end_time = None;
+        while self.candles.len() < self.capacity {
+            let limit = (self.capacity - self.candles.len()).min(KLINES_PER_REQUEST);
+            let mut request = self
+                .client
+                .get_candlestick_bars(&self.symbol, self.interval)
+                .with_limit(limit);
+
+            if let Some(end_time) = end_time {
+                request = request.with_end_time(Utc.timestamp_millis_opt(end_time).unwrap());
+            }
+
+            let klines = request.json::<Vec<Kline>>().await?;
+            let first = match klines.first() {
+                Some(kline) => kline.open_time,
+                None => break,
+            };
+
+            let done = klines.len() < limit;
+            let now = Utc::now().timestamp_millis();
+            for mut kline in klines.into_iter().rev() {
+                kline.is_closed = kline.close_time < now;
+                self.insert(kline);
+            }
+
+            if done {
+                break;
+            }
+            end_time = Some(first - 1);
+        }
+        Ok(())
+    }
+
+    /// Pages forwards from the last candle, which is refetched as it may be open.
+    async fn refill(&mut self, end_time: Option<i64>) -> crate::error::Result<()> {
+        let mut start_time = match self.candles.back() {
+            Some(kline) => kline.open_time,
+            None => return self.backfill().await,
+        };
+
+        loop {
+            let mut request = self
+                .client
+                .get_candlestick_bars(&self.symbol, self.interval)
+                .with_start_time(Utc.timestamp_millis_opt(start_time).unwrap())
+                .with_limit(KLINES_PER_REQUEST);
+
+            if let Some(end_time) = end_time {
+                request = request.with_end_time(Utc.timestamp_millis_opt(end_time).unwrap());
+            }
+
+            let klines = request.json::<Vec<Kline>>().await?;
+            let done = klines.len() < KLINES_PER_REQUEST;
+            let now = Utc::now().timestamp_millis();
+            for mut kline in klines {
+                start_time = kline.open_time + 1;
+                kline.is_closed = kline.close_time < now;
+                self.insert(kline);
+            }
+
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Inserts or replaces the candle with the same open time, keeping the series sorted.
+    ///
+    /// Returns false for candles older than the series or updates of already closed candles.
+    fn insert(&mut self, kline: Kline) -> bool {
+        match self.candles.back() {
+            Some(last) if kline.open_time < last.open_time => {
+                match self
+                    .candles
+                    .binary_search_by_key(&kline.open_time, |candle| candle.open_time)
+                {
+                    Ok(index) if !self.candles[index].is_closed || kline.is_closed => {
+                        self.candles[index] = kline;
+                        true
+                    }
+                    Ok(_) => false,
+                    Err(index) if self.candles.len() < self.capacity => {
+                        self.candles.insert(index, kline);
+                        true
+                    }
+                    Err(_) => false,
+                }
+            }
+            Some(last) if kline.open_time == last.open_time => {
+                if last.is_closed && !kline.is_closed {
+                    return false;
+                }
+                *self.candles.back_mut().expect("not empty") = kline;
+                true
+            }
+            _ => {
+                self.candles.push_back(kline);
+                if self.candles.len() > self.capacity {
+                    self.candles.pop_front();
+                }
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{next_request, send, serve_ws, MockServer, Received, WsServer};
+    use futures::StreamExt;
+    use hyper::{Body, Response};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    const START: i64 = 1_600_000_000_000;
+    const MINUTE: i64 = 60_000;
+
+    fn kline(i: i64, close: f64, is_closed: bool) -> Kline {
+        Kline {
+            open_time: START + i * MINUTE,
+            close_time: START + (i + 1) * MINUTE - 1,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1.0,
+            quote_volume: close,
+            trades: 1,
+            taker_buy_volume: 0.0,
+            taker_buy_quote_volume: 0.0,
+            is_closed,
+        }
+    }
+
+    fn history(range: std::ops::Range<i64>) -> Vec<Kline> {
+        range.map(|i| kline(i, i as f64, true)).collect()
+    }
+
+    fn rest(kline: &Kline) -> Value {
+        json!([
+            kline.open_time,
+            kline.open.to_string(),
+            kline.high.to_string(),
+            kline.low.to_string(),
+            kline.close.to_string(),
+            kline.volume.to_string(),
+            kline.close_time,
+            kline.quote_volume.to_string(),
+            kline.trades,
+            kline.taker_buy_volume.to_string(),
+            kline.taker_buy_quote_volume.to_string(),
+            "0"
+        ])
+    }
+
+    fn event(kline: &Kline) -> Value {
+        let data = json!({
+            "e": "kline", "E": kline.close_time, "s": "BNBUSDT",
+            "k": {
+                "t": kline.open_time, "T": kline.close_time, "s": "BNBUSDT", "i": "1m",
+                "o": kline.open.to_string(), "h": kline.high.to_string(),
+                "l": kline.low.to_string(), "c": kline.close.to_string(),
+                "v": kline.volume.to_string(), "q": kline.quote_volume.to_string(),
+                "n": kline.trades, "V": kline.taker_buy_volume.to_string(),
+                "Q": kline.taker_buy_quote_volume.to_string(), "x": kline.is_closed
+            }
+        });
+        json!({"stream": "bnbusdt@kline_1m", "data": data})
+    }
+
+    /// Kline history served over REST and kline events sent to each new stream connection;
+    /// the connection ends after its events unless it is the last one.
+    struct Market {
+        server: MockServer,
+        stream_url: String,
+        history: Arc<Mutex<Vec<Kline>>>,
+    }
+
+    impl Market {
+        async fn start(history: Vec<Kline>, connections: Vec<Vec<Kline>>) -> Self {
+            let server = MockServer::start().await;
+            let history = Arc::new(Mutex::new(history));
+            let served = history.clone();
+            server.route("/api/v3/klines", move |req| klines(&served, req));
+
+            let connections = Arc::new(Mutex::new(VecDeque::from(connections)));
+            let stream_url = serve_ws(move |ws| {
+                let mut connections = connections.lock().unwrap();
+                let last = connections.len() == 1;
+                let events = match last {
+                    true => connections[0].clone(),
+                    false => connections.pop_front().unwrap_or_default(),
+                };
+                serve_stream(ws, events, last)
+            })
+            .await;
+            Self {
+                server,
+                stream_url,
+                history,
+            }
+        }
+
+        /// Query string of each klines request.
+        fn requests(&self) -> Vec<HashMap<String, i64>> {
+            self.server.requests().iter().map(query).collect()
+        }
+
+        async fn series(&self, capacity: usize) -> CandleSeries {
+            CandleSeries::connect("<api-key>", self, "BNBUSDT", Interval::OneMinute, capacity)
+                .await
+                .unwrap()
+        }
+    }
+
+    impl Endpoint for &Market {
+        fn rest_url(&self) -> &str {
+            self.server.url()
+        }
+
+        fn stream_url(&self) -> &str {
+            &self.stream_url
+        }
+
+        fn ws_api_url(&self) -> &str {
+            &self.stream_url
+        }
+    }
+
+    fn query(req: &Received) -> HashMap<String, i64> {
+        let params = req.params.iter();
+        params
+            .filter_map(|(key, value)| Some((key.clone(), value.parse().ok()?)))
+            .collect()
+    }
+
+    /// Answers like `GET /api/v3/klines`, oldest first.
+    fn klines(history: &Mutex<Vec<Kline>>, req: &Received) -> Response<Body> {
+        let query = query(req);
+        let param = |key: &str| query.get(key).copied();
+        let limit = param("limit").unwrap_or(500) as usize;
+
+        let history = history.lock().unwrap();
+        let start = param("startTime").unwrap_or(i64::MIN);
+        let end = param("endTime").unwrap_or(i64::MAX);
+        let mut klines: Vec<_> = history
+            .iter()
+            .filter(|kline| (start..=end).contains(&kline.open_time))
+            .collect();
+        match param("startTime") {
+            Some(_) => klines.truncate(limit),
+            None => klines = klines.split_off(klines.len().saturating_sub(limit)),
+        }
+
+        let body: Vec<_> = klines.into_iter().map(rest).collect();
+        Response::new(Body::from(Value::from(body).to_string()))
+    }
+
+    async fn serve_stream(mut ws: WsServer, events: Vec<Kline>, last: bool) {
+        // reply to SET_PROPERTY
+        if let Some(request) = next_request(&mut ws).await {
+            send(&mut ws, json!({"result": null, "id": request["id"]})).await;
+        }
+        for kline in &events {
+            send(&mut ws, event(kline)).await;
+        }
+        match last {
+            true => while let Some(Ok(_)) = ws.next().await {},
+            false => {
+                let _ = ws.close(None).await;
+            }
+        }
+    }
+
+    fn open_times(series: &CandleSeries) -> Vec<i64> {
+        let times = series.candles().iter().map(|kline| kline.open_time);
+        times.map(|time| (time - START) / MINUTE).collect()
+    }
+
+    #[tokio::test]
+    async fn backfill_pages_backwards_without_overlap() {
+        let market = Market::start(history(0..3000), vec![vec![]]).await;
+        let series = market.series(2500).await;
+
+        assert_eq!(open_times(&series), (500..3000).collect::<Vec<_>>());
+        assert!(series.candles().iter().all(|kline| kline.is_closed));
+
+        let requests = market.requests();
+        let limits: Vec<_> = requests.iter().map(|query| query["limit"]).collect();
+        assert_eq!(limits, vec![1000, 1000, 500]);
+        let ends: Vec<_> = requests.iter().map(|query| query.get("endTime")).collect();
+        let first = |i: i64| START + i * MINUTE;
+        assert_eq!(
+            ends,
+            vec![None, Some(&(first(2000) - 1)), Some(&(first(1000) - 1))]
+        );
+    }
+
+    #[tokio::test]
+    async fn backfill_stops_at_the_start_of_the_history() {
+        let market = Market::start(history(0..1500), vec![vec![]]).await;
+        let series = market.series(2000).await;
+
+        assert_eq!(open_times(&series), (0..1500).collect::<Vec<_>>());
+        assert_eq!(market.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn updates_replace_the_open_candle_until_it_closes() {
+        let updates = vec![
+            kline(10, 1.0, false),
+            kline(10, 2.0, false),
+            kline(10, 3.0, true),
+            // late update of a closed candle
+            kline(10, 4.0, false),
+            kline(11, 5.0, false),
+        ];
+        let market = Market::start(history(0..10), vec![updates]).await;
+        let mut series = market.series(100).await;
+
+        assert_eq!(series.next().await.unwrap(), Some(kline(10, 1.0, false)));
+        assert_eq!(series.next().await.unwrap(), Some(kline(10, 2.0, false)));
+        assert_eq!(series.candles().len(), 11);
+        assert_eq!(series.closed().count(), 10);
+
+        assert_eq!(series.next().await.unwrap(), Some(kline(10, 3.0, true)));
+        assert_eq!(series.closed().count(), 11);
+
+        assert_eq!(series.next().await.unwrap(), Some(kline(11, 5.0, false)));
+        assert_eq!(series.candles()[10], kline(10, 3.0, true));
+        assert_eq!(open_times(&series), (0..12).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn missed_candles_are_refilled() {
+        let first = vec![kline(10, 10.0, true), kline(13, 13.0, false)];
+        let second = vec![kline(16, 16.0, false)];
+        let market = Market::start(history(0..10), vec![first, second]).await;
+        let mut series = market.series(100).await;
+
+        // 11 and 12 are missed by the stream
+        market.history.lock().unwrap().extend(history(10..13));
+        assert_eq!(series.next().await.unwrap(), Some(kline(10, 10.0, true)));
+        assert_eq!(series.next().await.unwrap(), Some(kline(13, 13.0, false)));
+        assert_eq!(open_times(&series), (0..14).collect::<Vec<_>>());
+
+        // the connection drops, 13 closes and 14 and 15 pass before the reconnect
+        market.history.lock().unwrap().extend(history(13..16));
+        assert!(series.next().await.is_err());
+        assert_eq!(series.next().await.unwrap(), Some(kline(16, 16.0, false)));
+        assert_eq!(open_times(&series), (0..17).collect::<Vec<_>>());
+        assert_eq!(series.closed().count(), 16);
+    }
+
+    #[tokio::test]
+    async fn capacity_drops_the_oldest_candles() {
+        let updates = vec![kline(10, 10.0, false), kline(11, 11.0, false)];
+        let market = Market::start(history(0..10), vec![updates]).await;
+        let mut series = market.series(5).await;
+        assert_eq!(open_times(&series), vec![5, 6, 7, 8, 9]);
+
+        series.next().await.unwrap();
+        series.next().await.unwrap();
+        assert_eq!(open_times(&series), vec![7, 8, 9, 10, 11]);
+        // candles older than the series are not inserted again
+        assert!(!series.insert(kline(2, 2.0, true)));
+    }
+}
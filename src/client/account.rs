@@ -1,14 +1,17 @@
 use crate::builder::ParamBuilder;
 use crate::client::*;
-use crate::param::{OrderType, Parameters, Side, TimeInForce, ID};
+use crate::environment::Endpoint;
+use crate::param::{CancelReplaceMode, OrderType, Parameters, Side, TimeInForce, ID};
+use crate::signer::{AsyncSigner, HmacSigner};
 use crate::types::*;
 use reqwest::{Client, Url};
+use std::sync::Arc;
 
 /// Client for dealing with orders
 #[derive(Clone)]
 pub struct AccountClient {
     api_key: String,
-    secret_key: String,
+    signer: Arc<dyn AsyncSigner>,
     url: Url,
     client: Client,
 }
@@ -18,11 +21,11 @@ impl AccountClient {
     /// # Example
     ///
     /// ```no_run
-    /// use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// use tokio_binance::{AccountClient, Environment};
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ///     let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    ///     let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     ///     Ok(())
     /// }
     /// ```
@@ -30,12 +33,33 @@ impl AccountClient {
     where
         A: Into<String>,
         S: Into<String>,
-        U: Into<String>,
+        U: Endpoint,
+    {
+        Self::connect_with_signer(api_key, HmacSigner::new(secret_key.into())?, url)
+    }
+    /// Creates new client instance that signs requests with the provided signer.
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tokio_binance::{AccountClient, Ed25519Signer, Environment};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let signer = Ed25519Signer::from_pem(&std::fs::read_to_string("private_key.pem")?)?;
+    ///     let client = AccountClient::connect_with_signer("<api-key>", signer, Environment::BinanceUs)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn connect_with_signer<A, S, U>(api_key: A, signer: S, url: U) -> crate::error::Result<Self>
+    where
+        A: Into<String>,
+        S: AsyncSigner + 'static,
+        U: Endpoint,
     {
         Ok(Self {
             api_key: api_key.into(),
-            secret_key: secret_key.into(),
-            url: url.into().parse::<Url>()?,
+            signer: Arc::new(signer),
+            url: url.rest_url().parse::<Url>()?,
             client: Client::new(),
         })
     }
@@ -43,13 +67,14 @@ impl AccountClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use tokio_binance::{Side::Sell, TimeInForce::Fok, OrderRespType::Full};
+    /// use tokio_binance::{PegPriceType::PrimaryPeg, SelfTradePreventionMode::ExpireTaker};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     // false will send as test, true will send as a real order.
     ///     .place_limit_order("BNBUSDT", Sell, 20.00, 5.00, false)
@@ -65,6 +90,15 @@ impl AccountClient {
     ///     .with_stop_loss_limit(21.00)
     ///     // optional: converts Limit to Stop-Limit; triggers when price hits above 21.00.
     ///     .with_take_profit_limit(21.00)
+    ///     // optional: trails the Stop-Limit by 1%; the stop price activates it.
+    ///     .with_trailing_delta(100)
+    ///     // optional: follows the best bid or ask instead of the price.
+    ///     .with_peg_price_type(PrimaryPeg)
+    ///     // optional: what happens when the order would trade against an own order.
+    ///     .with_self_trade_prevention_mode(ExpireTaker)
+    ///     // optional: tags the order; strategy type can't be below 1000000.
+    ///     .with_strategy_id(1)
+    ///     .with_strategy_type(1000000)
     ///     // optional: processing time for request; default is 5000, can't be above 60000.
     ///     .with_recv_window(8000)
     ///     // optional: converts Limit to Limit-Maker; consumes builder and returns a different one.
@@ -85,7 +119,7 @@ impl AccountClient {
     ) -> ParamBuilder<'a, '_, LimitOrderParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -108,20 +142,21 @@ impl AccountClient {
             },
             client.post(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Place a new market order.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use tokio_binance::{Side::Sell, TimeInForce::Fok, OrderRespType::Full};
+    /// use tokio_binance::SelfTradePreventionMode::ExpireTaker;
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     // false will send as test, true will send as a real order.
     ///     .place_market_order("BNBUSDT", Sell, 5.00, false)
@@ -133,6 +168,12 @@ impl AccountClient {
     ///     .with_stop_loss(21.00)
     ///     // optional: converts Market to Stop-Loss; triggers when price hits above 21.00.
     ///     .with_take_profit(21.00)
+    ///     // optional: trails the Stop-Loss by 1%; the stop price activates it.
+    ///     .with_trailing_delta(100)
+    ///     // optional: spends or receives 100.00 of the quote asset instead of the quantity.
+    ///     .with_quote_order_qty(100.00)
+    ///     // optional: what happens when the order would trade against an own order.
+    ///     .with_self_trade_prevention_mode(ExpireTaker)
     ///     // optional: processing time for request; default is 5000, can't be above 60000.
     ///     .with_recv_window(8000)
     ///     //
@@ -150,7 +191,7 @@ impl AccountClient {
     ) -> ParamBuilder<'a, '_, MarketOrderParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -171,20 +212,20 @@ impl AccountClient {
             },
             client.post(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Get order.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use tokio_binance::ID;
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_order("BNBUSDT", ID::ClientOId("<uuid>"))
     ///     // optional: processing time for request; default is 5000, can't be above 60000.
@@ -202,7 +243,7 @@ impl AccountClient {
     ) -> ParamBuilder<'a, '_, OrderStatusParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -230,20 +271,20 @@ impl AccountClient {
             },
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Cancel order.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use tokio_binance::ID;
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .cancel_order("BNBUSDT", ID::ClientOId("<uuid>"))
     ///     // optional: unique id; auto generated by default.
@@ -263,7 +304,7 @@ impl AccountClient {
     ) -> ParamBuilder<'a, '_, CancelOrderParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -291,19 +332,165 @@ impl AccountClient {
             },
             client.delete(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
+        )
+    }
+    /// Cancel an order and place a new limit order in one request.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::{ID, Side::Sell, CancelReplaceMode::StopOnFailure};
+    /// use tokio_binance::{CancelRestrictions::OnlyNew, OrderRateLimitExceededMode::CancelOnly};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let response = client
+    ///     // Cancels the order and sells 5.00 at 21.00; the new order is not placed if the cancel fails.
+    ///     .cancel_replace_order("BNBUSDT", ID::ClientOId("<uuid>"), Sell, 21.00, 5.00, StopOnFailure)
+    ///     // optional: unique id of the new order; auto generated by default.
+    ///     .with_new_client_order_id("<uuid>")
+    ///     // optional: unique id of the cancel; auto generated by default.
+    ///     .with_cancel_new_client_order_id("<uuid>")
+    ///     // optional: only cancels the order if its status is NEW.
+    ///     .with_cancel_restrictions(OnlyNew)
+    ///     // optional: still cancels the order if the order rate limit is exceeded.
+    ///     .with_order_rate_limit_exceeded_mode(CancelOnly)
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     // partial failures are returned as a response too.
+    ///     .outcome()
+    ///     .await?;
+    ///
+    /// if let Some(error) = response.new_order_error() {
+    ///     println!("{}: {}", error.code, error.msg);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cancel_replace_order<'a>(
+        &self,
+        symbol: &'a str,
+        id: ID<'a>,
+        side: Side,
+        price: f64,
+        quantity: f64,
+        mode: CancelReplaceMode,
+    ) -> ParamBuilder<'a, '_, CancelReplaceParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/api/v3/order/cancelReplace").unwrap();
+
+        let cancel_order_id = if let ID::OrderId(id) = id {
+            Some(id)
+        } else {
+            None
+        };
+
+        let cancel_orig_client_order_id = if let ID::ClientOId(id) = id {
+            Some(id)
+        } else {
+            None
+        };
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                side: Some(side),
+                order_type: Some(OrderType::Limit),
+                price: Some(price),
+                quantity: Some(quantity),
+                time_in_force: Some(TimeInForce::Gtc),
+                cancel_replace_mode: Some(mode),
+                cancel_order_id,
+                cancel_orig_client_order_id,
+                ..Parameters::default()
+            },
+            client.post(url),
+            Some(api_key),
+            Some(signer.as_ref()),
+        )
+    }
+    /// Reduce the quantity of an order, keeping its priority in the order book.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::ID;
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let response = client
+    ///     // new quantity must be less than the current one.
+    ///     .amend_order_keep_priority("BNBUSDT", ID::ClientOId("<uuid>"), 2.00)
+    ///     // optional: new unique id of the order; unchanged by default.
+    ///     .with_new_client_order_id("<uuid>")
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn amend_order_keep_priority<'a>(
+        &self,
+        symbol: &'a str,
+        id: ID<'a>,
+        new_qty: f64,
+    ) -> ParamBuilder<'a, '_, AmendOrderParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/api/v3/order/amend/keepPriority").unwrap();
+
+        let order_id = if let ID::OrderId(id) = id {
+            Some(id)
+        } else {
+            None
+        };
+
+        let orig_client_order_id = if let ID::ClientOId(id) = id {
+            Some(id)
+        } else {
+            None
+        };
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                order_id,
+                orig_client_order_id,
+                new_qty: Some(new_qty),
+                ..Parameters::default()
+            },
+            client.put(url),
+            Some(api_key),
+            Some(signer.as_ref()),
         )
     }
     /// Get open orders.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_open_orders()
     ///     // optional: filter by symbol; gets all symbols by default.
@@ -319,7 +506,7 @@ impl AccountClient {
     pub fn get_open_orders(&self) -> ParamBuilder<'_, '_, OpenOrderParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -330,20 +517,20 @@ impl AccountClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Get all orders.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use chrono::{Utc, Duration};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let end = Utc::now();
     /// let start = end - Duration::hours(23);
     ///
@@ -369,7 +556,7 @@ impl AccountClient {
     pub fn get_all_orders<'a>(&self, symbol: &'a str) -> ParamBuilder<'a, '_, AllOrdersParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -383,23 +570,25 @@ impl AccountClient {
             },
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Place a new oco order.
+    ///
+    /// Deprecated by binance in favour of [`place_oco_order_list`](Self::place_oco_order_list).
     /// # Price Restrictions:
     /// - SELL: Limit Price > Last Price > Stop Price
     /// - BUY: Limit Price < Last Price < Stop Price
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use tokio_binance::{Side::Sell, TimeInForce::Gtc, OrderRespType::Full};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     // Limit to sell at 30.00 and Stop-Loss at 20.00; One cancels the other.
     ///     .place_oco_order("BNBUSDT", Sell, 30.00, 20.00, 5.00)
@@ -435,7 +624,7 @@ impl AccountClient {
     ) -> ParamBuilder<'a, '_, OcoParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -453,20 +642,238 @@ impl AccountClient {
             },
             client.post(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
+        )
+    }
+    /// Place a new oco order list; one order above and one below the last price,
+    /// one cancels the other.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::{Side::Sell, OrderType::{LimitMaker, StopLossLimit}};
+    /// use tokio_binance::{TimeInForce::Gtc, OrderRespType::Full};
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let response = client
+    ///     // Take profit at 30.00 and Stop-Limit at 20.00; one cancels the other.
+    ///     .place_oco_order_list("BNBUSDT", Sell, 5.00, LimitMaker, StopLossLimit)
+    ///     .with_above_price(30.00)
+    ///     .with_below_stop_price(20.00)
+    ///     .with_below_price(19.50)
+    ///     // optional: lifetime of the below leg; default is Gtc for limit types.
+    ///     .with_below_time_in_force(Gtc)
+    ///     // optional: A unique Id for the entire orderList; auto generated by default.
+    ///     .with_list_client_order_id("<uuid>")
+    ///     // optional: A unique Id for the above leg; auto generated by default.
+    ///     .with_above_client_order_id("<uuid>")
+    ///     // optional: A unique Id for the below leg; auto generated by default.
+    ///     .with_below_client_order_id("<uuid>")
+    ///     // optional: splits quantity for the above leg.
+    ///     .with_above_iceberg_qty(1.00)
+    ///     // optional: output verbosity; default is Ack.
+    ///     .with_new_order_resp_type(Full)
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn place_oco_order_list<'a>(
+        &self,
+        symbol: &'a str,
+        side: Side,
+        quantity: f64,
+        above_type: OrderType,
+        below_type: OrderType,
+    ) -> ParamBuilder<'a, '_, OcoOrderListParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/api/v3/orderList/oco").unwrap();
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                side: Some(side),
+                quantity: Some(quantity),
+                above_type: Some(above_type),
+                above_time_in_force: default_time_in_force(above_type),
+                below_type: Some(below_type),
+                below_time_in_force: default_time_in_force(below_type),
+                ..Parameters::default()
+            },
+            client.post(url),
+            Some(api_key),
+            Some(signer.as_ref()),
+        )
+    }
+    /// Place a new oto order list; the pending order is placed once the working order fills.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::{Side::{Buy, Sell}, OrderType::{Limit, TakeProfitLimit}};
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let response = client
+    ///     // Buy 5.00 at 20.00, then take profit at 30.00.
+    ///     .place_oto_order_list("BNBUSDT", Limit, Buy, 20.00, 5.00, TakeProfitLimit, Sell, 5.00)
+    ///     .with_pending_stop_price(30.00)
+    ///     .with_pending_price(30.00)
+    ///     // optional: A unique Id for the entire orderList; auto generated by default.
+    ///     .with_list_client_order_id("<uuid>")
+    ///     // optional: A unique Id for the working order; auto generated by default.
+    ///     .with_working_client_order_id("<uuid>")
+    ///     // optional: A unique Id for the pending order; auto generated by default.
+    ///     .with_pending_client_order_id("<uuid>")
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn place_oto_order_list<'a>(
+        &self,
+        symbol: &'a str,
+        working_type: OrderType,
+        working_side: Side,
+        working_price: f64,
+        working_quantity: f64,
+        pending_type: OrderType,
+        pending_side: Side,
+        pending_quantity: f64,
+    ) -> ParamBuilder<'a, '_, OtoOrderListParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/api/v3/orderList/oto").unwrap();
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                working_type: Some(working_type),
+                working_side: Some(working_side),
+                working_price: Some(working_price),
+                working_quantity: Some(working_quantity),
+                working_time_in_force: default_time_in_force(working_type),
+                pending_type: Some(pending_type),
+                pending_side: Some(pending_side),
+                pending_quantity: Some(pending_quantity),
+                pending_time_in_force: default_time_in_force(pending_type),
+                ..Parameters::default()
+            },
+            client.post(url),
+            Some(api_key),
+            Some(signer.as_ref()),
+        )
+    }
+    /// Place a new otoco order list; a bracket of an oco pair placed once the working order fills.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::{Side::{Buy, Sell}, OrderType::{Limit, LimitMaker, StopLoss}};
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let response = client
+    ///     // Buy 5.00 at 20.00, then take profit at 30.00 or stop out at 15.00.
+    ///     .place_otoco_order_list("BNBUSDT", Limit, Buy, 20.00, 5.00, Sell, LimitMaker, StopLoss)
+    ///     .with_pending_above_price(30.00)
+    ///     .with_pending_below_stop_price(15.00)
+    ///     // optional: quantity of the pending legs, e.g. less the fee; default is the working quantity.
+    ///     .with_pending_quantity(4.99)
+    ///     // optional: A unique Id for the entire orderList; auto generated by default.
+    ///     .with_list_client_order_id("<uuid>")
+    ///     // optional: A unique Id for the working order; auto generated by default.
+    ///     .with_working_client_order_id("<uuid>")
+    ///     // optional: A unique Id for the pending above leg; auto generated by default.
+    ///     .with_pending_above_client_order_id("<uuid>")
+    ///     // optional: A unique Id for the pending below leg; auto generated by default.
+    ///     .with_pending_below_client_order_id("<uuid>")
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn place_otoco_order_list<'a>(
+        &self,
+        symbol: &'a str,
+        working_type: OrderType,
+        working_side: Side,
+        working_price: f64,
+        working_quantity: f64,
+        pending_side: Side,
+        pending_above_type: OrderType,
+        pending_below_type: OrderType,
+    ) -> ParamBuilder<'a, '_, OtocoOrderListParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/api/v3/orderList/otoco").unwrap();
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                working_type: Some(working_type),
+                working_side: Some(working_side),
+                working_price: Some(working_price),
+                working_quantity: Some(working_quantity),
+                working_time_in_force: default_time_in_force(working_type),
+                pending_side: Some(pending_side),
+                pending_quantity: Some(working_quantity),
+                pending_above_type: Some(pending_above_type),
+                pending_above_time_in_force: default_time_in_force(pending_above_type),
+                pending_below_type: Some(pending_below_type),
+                pending_below_time_in_force: default_time_in_force(pending_below_type),
+                ..Parameters::default()
+            },
+            client.post(url),
+            Some(api_key),
+            Some(signer.as_ref()),
         )
     }
     /// Cancel oco order.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use tokio_binance::ID;
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .cancel_oco_order("BNBUSDT", ID::ClientOId("<uuid>"))
     ///     // optional: unique id; auto generated by default.
@@ -486,7 +893,7 @@ impl AccountClient {
     ) -> ParamBuilder<'a, '_, CancelOcoParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -514,20 +921,20 @@ impl AccountClient {
             },
             client.delete(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Get oco order.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use tokio_binance::ID;
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_oco_order(ID::ClientOId("<uuid>"))
     ///     // optional: processing time for request; default is 5000, can't be above 60000.
@@ -541,7 +948,7 @@ impl AccountClient {
     pub fn get_oco_order<'a>(&self, id: ID<'a>) -> ParamBuilder<'a, '_, OcoStatusParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -568,20 +975,20 @@ impl AccountClient {
             },
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Get all oco orders.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use chrono::{Utc, Duration};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let end = Utc::now();
     /// let start = end - Duration::hours(23);
     ///
@@ -607,7 +1014,7 @@ impl AccountClient {
     pub fn get_all_oco_orders(&self) -> ParamBuilder<'_, '_, AllOcoParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -618,19 +1025,19 @@ impl AccountClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Get open oco orders.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_open_oco_orders()
     ///     // optional: processing time for request; default is 5000, can't be above 60000.
@@ -644,7 +1051,7 @@ impl AccountClient {
     pub fn get_open_oco_orders(&self) -> ParamBuilder<'_, '_, OpenOcoParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -655,19 +1062,19 @@ impl AccountClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Get current account information.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_account()
     ///     // optional: processing time for request; default is 5000, can't be above 60000.
@@ -681,7 +1088,7 @@ impl AccountClient {
     pub fn get_account(&self) -> ParamBuilder<'_, '_, AccountParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -692,25 +1099,27 @@ impl AccountClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Get trades for a specific account and symbol.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{AccountClient, BINANCE_US_URL};
+    /// # use tokio_binance::{AccountClient, Environment};
     /// use chrono::{Utc, Duration};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let end = Utc::now();
     /// let start = end - Duration::hours(23);
     ///
     /// let response = client
     ///     .get_account_trades("BNBUSDT")
+    ///     // optional: only the trades of an order.
+    ///     .with_order_id(1230494)
     ///     // optional: filter by orders greater than or equal to the provided id.
     ///     // If supplied, neither startTime or endTime can be provided
     ///     .with_from_id(1230494)
@@ -734,7 +1143,7 @@ impl AccountClient {
     ) -> ParamBuilder<'a, '_, AccountTradesParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -748,7 +1157,233 @@ impl AccountClient {
             },
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
+        )
+    }
+
+    /// Get the commission rates of a symbol.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::model::AccountCommission;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let response = client
+    ///     .get_commission("BNBUSDT")
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<AccountCommission>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_commission<'a>(&self, symbol: &'a str) -> ParamBuilder<'a, '_, CommissionParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/api/v3/account/commission").unwrap();
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                ..Parameters::default()
+            },
+            client.get(url),
+            Some(api_key),
+            Some(signer.as_ref()),
+        )
+    }
+    /// Get the order rate limits with the number of orders placed in the current intervals.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::model::RateLimit;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let response = client
+    ///     .get_order_rate_limit()
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Vec<RateLimit>>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_order_rate_limit(&self) -> ParamBuilder<'_, '_, OrderRateLimitParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/api/v3/rateLimit/order").unwrap();
+
+        ParamBuilder::new(
+            Parameters::default(),
+            client.get(url),
+            Some(api_key),
+            Some(signer.as_ref()),
+        )
+    }
+    /// Get the orders of an order that expired for self-trade prevention.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::model::PreventedMatch;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let response = client
+    ///     .get_prevented_matches("BNBUSDT", 1230494)
+    ///     // optional: filter by prevented matches greater than or equal to the provided id.
+    ///     .with_from_prevented_match_id(1)
+    ///     // optional: limit the amount of prevented matches; default 500; max 1000.
+    ///     .with_limit(100)
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Vec<PreventedMatch>>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_prevented_matches<'a>(
+        &self,
+        symbol: &'a str,
+        order_id: i64,
+    ) -> ParamBuilder<'a, '_, PreventedMatchesParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/api/v3/myPreventedMatches").unwrap();
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                order_id: Some(order_id),
+                ..Parameters::default()
+            },
+            client.get(url),
+            Some(api_key),
+            Some(signer.as_ref()),
+        )
+    }
+    /// Get a prevented match by its id.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::model::PreventedMatch;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let response = client
+    ///     .get_prevented_match("BNBUSDT", 1)
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Vec<PreventedMatch>>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_prevented_match<'a>(
+        &self,
+        symbol: &'a str,
+        prevented_match_id: i64,
+    ) -> ParamBuilder<'a, '_, PreventedMatchParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/api/v3/myPreventedMatches").unwrap();
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                prevented_match_id: Some(prevented_match_id),
+                ..Parameters::default()
+            },
+            client.get(url),
+            Some(api_key),
+            Some(signer.as_ref()),
+        )
+    }
+    /// Get the allocations of orders routed by the smart order router.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::model::Allocation;
+    /// use chrono::{Utc, Duration};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let end = Utc::now();
+    /// let start = end - Duration::hours(23);
+    ///
+    /// let response = client
+    ///     .get_allocations("BNBUSDT")
+    ///     // optional: only the allocations of an order.
+    ///     .with_order_id(1230494)
+    ///     // optional: filter by allocations greater than or equal to the provided id.
+    ///     .with_from_allocation_id(1)
+    ///     // optional: get allocations from; pass 24 hours of allocations is the default.
+    ///     .with_start_time(start)
+    ///     // optional: get allocations until; default is now.
+    ///     .with_end_time(end)
+    ///     // optional: limit the amount of allocations; default 500; max 1000.
+    ///     .with_limit(100)
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Vec<Allocation>>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_allocations<'a>(&self, symbol: &'a str) -> ParamBuilder<'a, '_, AllocationsParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/api/v3/myAllocations").unwrap();
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                ..Parameters::default()
+            },
+            client.get(url),
+            Some(api_key),
+            Some(signer.as_ref()),
         )
     }
 
@@ -761,7 +1396,7 @@ impl AccountClient {
     ) -> ParamBuilder<'a, '_, CancelAllOrdersParams> {
         let Self {
             ref api_key,
-            ref secret_key,
+            ref signer,
             url,
             client,
         } = self;
@@ -775,7 +1410,7 @@ impl AccountClient {
             },
             client.delete(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
 
@@ -783,7 +1418,7 @@ impl AccountClient {
     pub fn to_withdraw_client(&self) -> WithdrawalClient {
         WithdrawalClient {
             api_key: self.api_key.clone(),
-            secret_key: self.secret_key.clone(),
+            signer: self.signer.clone(),
             url: self.url.clone(),
             client: self.client.clone(),
         }
@@ -813,3 +1448,13 @@ impl AccountClient {
         }
     }
 }
+
+/// Lifetime binance requires for the limit types of order list legs.
+fn default_time_in_force(order_type: OrderType) -> Option<TimeInForce> {
+    match order_type {
+        OrderType::Limit | OrderType::StopLossLimit | OrderType::TakeProfitLimit => {
+            Some(TimeInForce::Gtc)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,162 @@
+use crate::builder::ParamBuilder;
+use crate::environment::Endpoint;
+use crate::param::Parameters;
+use crate::signer::{AsyncSigner, HmacSigner};
+use crate::types::*;
+use reqwest::{Client, Url};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Client for the USDⓈ-M futures api; connect it to e.g. [`Environment::UsdFutures`].
+///
+/// [`Environment::UsdFutures`]: crate::Environment::UsdFutures
+#[derive(Clone)]
+pub struct UsdFuturesClient {
+    api_key: String,
+    signer: Arc<dyn AsyncSigner>,
+    url: Url,
+    client: Client,
+}
+
+impl UsdFuturesClient {
+    /// Creates new client instance.
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tokio_binance::{UsdFuturesClient, Environment};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let client = UsdFuturesClient::connect("<api-key>", "<secret-key>", Environment::UsdFutures)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn connect<A, S, U>(api_key: A, secret_key: S, url: U) -> crate::error::Result<Self>
+    where
+        A: Into<String>,
+        S: Into<String>,
+        U: Endpoint,
+    {
+        Self::connect_with_signer(api_key, HmacSigner::new(secret_key.into())?, url)
+    }
+    /// Creates new client instance that signs requests with the provided signer.
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tokio_binance::{UsdFuturesClient, Ed25519Signer, Environment};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let signer = Ed25519Signer::from_pem(&std::fs::read_to_string("private_key.pem")?)?;
+    ///     let client = UsdFuturesClient::connect_with_signer("<api-key>", signer, Environment::UsdFutures)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn connect_with_signer<A, S, U>(api_key: A, signer: S, url: U) -> crate::error::Result<Self>
+    where
+        A: Into<String>,
+        S: AsyncSigner + 'static,
+        U: Endpoint,
+    {
+        Ok(Self {
+            api_key: api_key.into(),
+            signer: Arc::new(signer),
+            url: url.rest_url().parse::<Url>()?,
+            client: Client::new(),
+        })
+    }
+    /// Auto-Cancel All Open Orders (TRADE)
+    /// https://binance-docs.github.io/apidocs/futures/en/#auto-cancel-all-open-orders-trade
+    /// Cancels all open orders of the symbol when the countdown ends without being sent again;
+    /// a countdown of 0 stops it.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{UsdFuturesClient, Environment};
+    /// use serde_json::Value;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = UsdFuturesClient::connect("<api-key>", "<secret-key>", Environment::UsdFuturesTestnet)?;
+    /// let response = client
+    ///     .countdown_cancel_all("BTCUSDT", Duration::from_secs(120))
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn countdown_cancel_all<'a>(
+        &self,
+        symbol: &'a str,
+        countdown: Duration,
+    ) -> ParamBuilder<'a, '_, CountdownCancelAllParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/fapi/v1/countdownCancelAll").unwrap();
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                countdown_time: Some(countdown.as_millis() as u64),
+                ..Parameters::default()
+            },
+            client.post(url),
+            Some(api_key),
+            Some(signer.as_ref()),
+        )
+    }
+
+    /// Cancel All Open Orders (TRADE)
+    /// https://binance-docs.github.io/apidocs/futures/en/#cancel-all-open-orders-trade
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{UsdFuturesClient, Environment};
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = UsdFuturesClient::connect("<api-key>", "<secret-key>", Environment::UsdFuturesTestnet)?;
+    /// let response = client
+    ///     .cancel_all_orders("BTCUSDT")
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cancel_all_orders<'a>(
+        &self,
+        symbol: &'a str,
+    ) -> ParamBuilder<'a, '_, CancelAllOrdersParams> {
+        let Self {
+            ref api_key,
+            ref signer,
+            url,
+            client,
+        } = self;
+
+        let url = url.join("/fapi/v1/allOpenOrders").unwrap();
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                ..Parameters::default()
+            },
+            client.delete(url),
+            Some(api_key),
+            Some(signer.as_ref()),
+        )
+    }
+}
@@ -1,4 +1,5 @@
 use crate::builder::ParamBuilder;
+use crate::environment::Endpoint;
 use crate::param::Parameters;
 use crate::types::*;
 use reqwest::{Client, Url};
@@ -15,17 +16,17 @@ impl GeneralClient {
     /// # Example
     ///
     /// ```no_run
-    /// use tokio_binance::{GeneralClient, BINANCE_US_URL};
+    /// use tokio_binance::{GeneralClient, Environment};
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ///     let client = GeneralClient::connect(BINANCE_US_URL)?;
+    ///     let client = GeneralClient::connect(Environment::BinanceUs)?;
     ///     Ok(())
     /// }
     /// ```
-    pub fn connect<U: Into<String>>(url: U) -> crate::error::Result<Self> {
+    pub fn connect<U: Endpoint>(url: U) -> crate::error::Result<Self> {
         Ok(Self {
-            url: url.into().parse::<Url>()?,
+            url: url.rest_url().parse::<Url>()?,
             client: Client::new(),
         })
     }
@@ -33,12 +34,12 @@ impl GeneralClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{GeneralClient, BINANCE_US_URL};
+    /// # use tokio_binance::{GeneralClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = GeneralClient::connect(BINANCE_US_URL)?;
+    /// # let client = GeneralClient::connect(Environment::BinanceUs)?;
     /// let response = client
     ///     .ping()
     ///     .json::<Value>()
@@ -56,12 +57,12 @@ impl GeneralClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{GeneralClient, BINANCE_US_URL};
+    /// # use tokio_binance::{GeneralClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = GeneralClient::connect(BINANCE_US_URL)?;
+    /// # let client = GeneralClient::connect(Environment::BinanceUs)?;
     /// let response = client
     ///     .get_server_time()
     ///     .json::<Value>()
@@ -79,12 +80,12 @@ impl GeneralClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{GeneralClient, BINANCE_US_URL};
+    /// # use tokio_binance::{GeneralClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = GeneralClient::connect(BINANCE_US_URL)?;
+    /// # let client = GeneralClient::connect(Environment::BinanceUs)?;
     /// let response = client
     ///     .get_exchange_info()
     ///     .json::<Value>()
@@ -1,4 +1,5 @@
 use crate::builder::ParamBuilder;
+use crate::environment::Endpoint;
 use crate::param::{Interval, Parameters};
 use crate::types::*;
 use reqwest::{Client, Url};
@@ -16,22 +17,22 @@ impl MarketDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// use tokio_binance::{MarketDataClient, BINANCE_US_URL};
+    /// use tokio_binance::{MarketDataClient, Environment};
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ///     let client = MarketDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    ///     let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
     ///     Ok(())
     /// }
     /// ```
     pub fn connect<A, U>(api_key: A, url: U) -> crate::error::Result<Self>
     where
         A: Into<String>,
-        U: Into<String>,
+        U: Endpoint,
     {
         Ok(Self {
             api_key: api_key.into(),
-            url: url.into().parse::<Url>()?,
+            url: url.rest_url().parse::<Url>()?,
             client: Client::new(),
         })
     }
@@ -39,12 +40,12 @@ impl MarketDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{MarketDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{MarketDataClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = MarketDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_order_book("BNBUSDT")
     ///     // optional: default 100; max 5000.
@@ -77,12 +78,12 @@ impl MarketDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{MarketDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{MarketDataClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = MarketDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_trades("BNBUSDT")
     ///     // optional: default 100; max 5000.
@@ -115,12 +116,12 @@ impl MarketDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{MarketDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{MarketDataClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = MarketDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_historical_trades("BNBUSDT")
     ///     // optional: trade id to fetch from; default gets most recent trades.
@@ -160,13 +161,13 @@ impl MarketDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{MarketDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{MarketDataClient, Environment};
     /// use chrono::{Utc, Duration};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = MarketDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let end = Utc::now();
     /// let start = end - Duration::minutes(59);
     ///
@@ -211,14 +212,14 @@ impl MarketDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{MarketDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{MarketDataClient, Environment};
     /// use tokio_binance::Interval;
     /// use chrono::{Utc, Duration};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = MarketDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let end = Utc::now();
     /// let start = end - Duration::minutes(499);
     ///
@@ -259,16 +260,62 @@ impl MarketDataClient {
             None,
         )
     }
+    /// Kline/candlestick bars optimized for presentation of candlestick charts.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{MarketDataClient, Environment};
+    /// use tokio_binance::Interval;
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
+    /// let response = client
+    ///     .get_ui_klines("BNBUSDT", Interval::OneSecond)
+    ///     // optional: interpret intervals in this time zone; default is UTC.
+    ///     .with_time_zone("+08:00")
+    ///     // optional: limit the amount of klines; default 500; max 1000.
+    ///     .with_limit(100)
+    ///     //
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_ui_klines<'a>(
+        &self,
+        symbol: &'a str,
+        interval: Interval,
+    ) -> ParamBuilder<'a, '_, UiKlinesParams> {
+        let Self {
+            ref api_key,
+            url,
+            client,
+        } = self;
+        let url = url.join("/api/v3/uiKlines").unwrap();
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                interval: Some(interval),
+                ..Parameters::default()
+            },
+            client.get(url),
+            Some(api_key),
+            None,
+        )
+    }
     /// Current average price for a symbol.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{MarketDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{MarketDataClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = MarketDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_average_price("BNBUSDT")
     ///     .json::<Value>()
@@ -297,17 +344,61 @@ impl MarketDataClient {
             None,
         )
     }
+    /// Rolling window price change statistics; the window is 1 day by default.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{MarketDataClient, Environment};
+    /// use tokio_binance::{TickerType, WindowSize};
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
+    /// let response = client
+    ///     .get_rolling_window_ticker("BNBUSDT")
+    ///     // optional: size of the window; default is 1 day.
+    ///     .with_window_size(WindowSize::FourHours)
+    ///     // optional: response verbosity; default is Full.
+    ///     .with_ticker_type(TickerType::Mini)
+    ///     //
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_rolling_window_ticker<'a>(
+        &self,
+        symbol: &'a str,
+    ) -> ParamBuilder<'a, '_, RollingWindowTickerParams> {
+        let Self {
+            ref api_key,
+            url,
+            client,
+        } = self;
+        let url = url.join("/api/v3/ticker").unwrap();
+
+        ParamBuilder::new(
+            Parameters {
+                symbol: Some(symbol),
+                ..Parameters::default()
+            },
+            client.get(url),
+            Some(api_key),
+            None,
+        )
+    }
     /// 24 hour rolling window price change statistics.
     /// Careful when accessing this with no symbol.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{MarketDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{MarketDataClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = MarketDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_24hr_ticker_price()
     ///     // optional: filter by symbol; gets all symbols by default.
@@ -334,12 +425,12 @@ impl MarketDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{MarketDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{MarketDataClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = MarketDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_price_ticker()
     ///     // optional: filter by symbol; gets all symbols by default.
@@ -364,12 +455,12 @@ impl MarketDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{MarketDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{MarketDataClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = MarketDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_order_book_ticker()
     ///     // optional: filter by symbol; gets all symbols by default.
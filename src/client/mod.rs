@@ -1,10 +1,12 @@
 mod account;
+mod futures;
 mod general;
 mod market;
 mod user_data;
 mod withdraw;
 
 pub use account::AccountClient;
+pub use futures::UsdFuturesClient;
 pub use general::GeneralClient;
 pub use market::MarketDataClient;
 pub use user_data::UserDataClient;
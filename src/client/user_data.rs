@@ -1,4 +1,5 @@
 use crate::builder::ParamBuilder;
+use crate::environment::Endpoint;
 use crate::param::Parameters;
 use crate::types::*;
 use reqwest::{Client, Url};
@@ -16,22 +17,22 @@ impl UserDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// use tokio_binance::{UserDataClient, BINANCE_US_URL};
+    /// use tokio_binance::{UserDataClient, Environment};
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ///     let client = UserDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    ///     let client = UserDataClient::connect("<api-key>", Environment::BinanceUs)?;
     ///     Ok(())
     /// }
     /// ```
     pub fn connect<A, U>(api_key: A, url: U) -> crate::error::Result<Self>
     where
         A: Into<String>,
-        U: Into<String>,
+        U: Endpoint,
     {
         Ok(Self {
             api_key: api_key.into(),
-            url: url.into().parse::<Url>()?,
+            url: url.rest_url().parse::<Url>()?,
             client: Client::new(),
         })
     }
@@ -40,12 +41,12 @@ impl UserDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{UserDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{UserDataClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = UserDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = UserDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .start_stream()
     ///     .json::<Value>()
@@ -69,12 +70,12 @@ impl UserDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{UserDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{UserDataClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = UserDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = UserDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .keep_alive("<listen-key>")
     ///     .json::<Value>()
@@ -107,12 +108,12 @@ impl UserDataClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{UserDataClient, BINANCE_US_URL};
+    /// # use tokio_binance::{UserDataClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = UserDataClient::connect("<api-key>", BINANCE_US_URL)?;
+    /// # let client = UserDataClient::connect("<api-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .close_stream("<listen-key>")
     ///     .json::<Value>()
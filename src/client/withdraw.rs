@@ -1,13 +1,16 @@
 use crate::builder::ParamBuilder;
+use crate::environment::Endpoint;
 use crate::param::Parameters;
+use crate::signer::{AsyncSigner, HmacSigner};
 use crate::types::*;
 use reqwest::{Client, Url};
+use std::sync::Arc;
 
 /// Client for dealing with withdrawals and sub accounts.
 #[derive(Clone)]
 pub struct WithdrawalClient {
     pub(super) api_key: String,
-    pub(super) secret_key: String,
+    pub(super) signer: Arc<dyn AsyncSigner>,
     pub(super) url: Url,
     pub(super) client: Client,
 }
@@ -17,11 +20,11 @@ impl WithdrawalClient {
     /// # Example
     ///
     /// ```no_run
-    /// use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// use tokio_binance::{WithdrawalClient, Environment};
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ///     let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    ///     let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     ///     Ok(())
     /// }
     /// ```
@@ -29,12 +32,33 @@ impl WithdrawalClient {
     where
         A: Into<String>,
         S: Into<String>,
-        U: Into<String>,
+        U: Endpoint,
+    {
+        Self::connect_with_signer(api_key, HmacSigner::new(secret_key.into())?, url)
+    }
+    /// Creates new client instance that signs requests with the provided signer.
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tokio_binance::{WithdrawalClient, Ed25519Signer, Environment};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let signer = Ed25519Signer::from_pem(&std::fs::read_to_string("private_key.pem")?)?;
+    ///     let client = WithdrawalClient::connect_with_signer("<api-key>", signer, Environment::BinanceUs)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn connect_with_signer<A, S, U>(api_key: A, signer: S, url: U) -> crate::error::Result<Self>
+    where
+        A: Into<String>,
+        S: AsyncSigner + 'static,
+        U: Endpoint,
     {
         Ok(Self {
             api_key: api_key.into(),
-            secret_key: secret_key.into(),
-            url: url.into().parse::<Url>()?,
+            signer: Arc::new(signer),
+            url: url.rest_url().parse::<Url>()?,
             client: Client::new(),
         })
     }
@@ -42,12 +66,12 @@ impl WithdrawalClient {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .withdraw("BNB", "<public-address>", 5.00)
     ///     //optional: Secondary address identifier for coins like XRP,XMR etc.
@@ -70,7 +94,7 @@ impl WithdrawalClient {
     ) -> ParamBuilder<'a, '_, WithdrawParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -85,20 +109,20 @@ impl WithdrawalClient {
             },
             client.post(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch deposit history.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use chrono::{Utc, Duration};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let end = Utc::now();
     /// let start = end - Duration::hours(23);
     ///
@@ -123,7 +147,7 @@ impl WithdrawalClient {
     pub fn get_deposit_history(&self) -> ParamBuilder<'_, '_, DepositHistoryParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -133,20 +157,20 @@ impl WithdrawalClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch withdraw history.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use chrono::{Utc, Duration};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let end = Utc::now();
     /// let start = end - Duration::hours(23);
     ///
@@ -171,7 +195,7 @@ impl WithdrawalClient {
     pub fn get_withdraw_history(&self) -> ParamBuilder<'_, '_, WithdrawHistoryParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -181,19 +205,19 @@ impl WithdrawalClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch deposit address.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_deposit_address("BNB")
     ///     // optional: Boolean.
@@ -212,7 +236,7 @@ impl WithdrawalClient {
     ) -> ParamBuilder<'a, '_, DepositAddressParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -225,19 +249,19 @@ impl WithdrawalClient {
             },
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch account status detail.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_account_status()
     ///     // optional: processing time for request; default is 5000, can't be above 60000.
@@ -251,7 +275,7 @@ impl WithdrawalClient {
     pub fn get_account_status(&self) -> ParamBuilder<'_, '_, AccountStatusParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -261,19 +285,19 @@ impl WithdrawalClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch system status.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_system_status()
     ///     .json::<Value>()
@@ -284,7 +308,7 @@ impl WithdrawalClient {
     pub fn get_system_status(&self) -> ParamBuilder<'_, '_, SystemStatusParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -294,19 +318,19 @@ impl WithdrawalClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch account api trading status detail.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_api_status()
     ///     // optional: processing time for request; default is 5000, can't be above 60000.
@@ -320,7 +344,7 @@ impl WithdrawalClient {
     pub fn get_api_status(&self) -> ParamBuilder<'_, '_, ApiStatusParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -330,19 +354,19 @@ impl WithdrawalClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch small amounts of assets exchanged BNB records.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_dustlog()
     ///     // optional: processing time for request; default is 5000, can't be above 60000.
@@ -356,7 +380,7 @@ impl WithdrawalClient {
     pub fn get_dustlog(&self) -> ParamBuilder<'_, '_, DustlogParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -366,19 +390,19 @@ impl WithdrawalClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch trade fee.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_trade_fee()
     ///     // optional: filter by symbol; gets all symbols by default.
@@ -394,7 +418,7 @@ impl WithdrawalClient {
     pub fn get_trade_fee(&self) -> ParamBuilder<'_, '_, TradeFeeParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -404,19 +428,19 @@ impl WithdrawalClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch asset detail.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_asset_detail()
     ///     // optional: processing time for request; default is 5000, can't be above 60000.
@@ -430,7 +454,7 @@ impl WithdrawalClient {
     pub fn get_asset_detail(&self) -> ParamBuilder<'_, '_, AssetDetailParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -440,19 +464,19 @@ impl WithdrawalClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch sub account list.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_sub_accounts()
     ///     // optional: Sub-account email.
@@ -474,7 +498,7 @@ impl WithdrawalClient {
     pub fn get_sub_accounts(&self) -> ParamBuilder<'_, '_, SubAccountParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -484,20 +508,20 @@ impl WithdrawalClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch transfer history list
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use chrono::{Utc, Duration};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let end = Utc::now();
     /// let start = end - Duration::days(99);
     ///
@@ -525,7 +549,7 @@ impl WithdrawalClient {
     ) -> ParamBuilder<'a, '_, SubAccountTranferParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -540,19 +564,19 @@ impl WithdrawalClient {
             },
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Execute sub-account transfer.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .tranfer_sub_account("<from_email>", "<to_email>", "BNB", 5.00)
     ///     // optional: processing time for request; default is 5000, can't be above 60000.
@@ -572,7 +596,7 @@ impl WithdrawalClient {
     ) -> ParamBuilder<'a, '_, TransferSubAccountParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -588,19 +612,19 @@ impl WithdrawalClient {
             },
             client.post(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Fetch sub-account assets.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     .get_sub_account_assets("<email>")
     ///     // optional: filter by symbol; gets all symbols by default.
@@ -619,7 +643,7 @@ impl WithdrawalClient {
     ) -> ParamBuilder<'a, '_, SubAccountAssetParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -632,19 +656,19 @@ impl WithdrawalClient {
             },
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Convert dust assets to BNB.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let response = client
     ///     // restricted to one asset at a time.
     ///     .dust_transfer("ETH")
@@ -659,7 +683,7 @@ impl WithdrawalClient {
     pub fn dust_transfer<'a>(&self, asset: &'a str) -> ParamBuilder<'a, '_, DustTransferParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -672,20 +696,20 @@ impl WithdrawalClient {
             },
             client.post(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
     /// Query asset dividend record.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WithdrawalClient, BINANCE_US_URL};
+    /// # use tokio_binance::{WithdrawalClient, Environment};
     /// use chrono::{Utc, Duration};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+    /// # let client = WithdrawalClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
     /// let end = Utc::now();
     /// let start = end - Duration::days(99);
     ///
@@ -708,7 +732,7 @@ impl WithdrawalClient {
     pub fn get_asset_dividends(&self) -> ParamBuilder<'_, '_, AssetDividendParams> {
         let Self {
             api_key,
-            secret_key,
+            signer,
             url,
             client,
         } = self;
@@ -718,7 +742,7 @@ impl WithdrawalClient {
             Parameters::default(),
             client.get(url),
             Some(api_key),
-            Some(secret_key),
+            Some(signer.as_ref()),
         )
     }
 }
@@ -0,0 +1,107 @@
+/// Binance deployments with their matching REST, websocket stream and websocket api urls.
+/// # Example
+///
+/// ```
+/// use tokio_binance::{Endpoint, Environment};
+///
+/// let env = Environment::SpotTestnet;
+/// assert_eq!(env.rest_url(), "https://testnet.binance.vision");
+/// assert_eq!(env.stream_url(), "wss://stream.testnet.binance.vision");
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Environment {
+    /// Production binance.com
+    Binance,
+    /// Production binance.us
+    BinanceUs,
+    /// Spot test network; https://testnet.binance.vision
+    SpotTestnet,
+    /// Production USDⓈ-M futures fapi.binance.com
+    UsdFutures,
+    /// USDⓈ-M futures test network
+    UsdFuturesTestnet,
+    /// COIN-M futures test network
+    CoinFuturesTestnet,
+    /// Alternative binance.com host api1.binance.com
+    Api1,
+    /// Alternative binance.com host api2.binance.com
+    Api2,
+    /// Alternative binance.com host api3.binance.com
+    Api3,
+    /// Alternative binance.com host api4.binance.com
+    Api4,
+    /// Market data only host; public endpoints and streams only.
+    DataApi,
+}
+
+/// Resolves the base url of each Binance api.
+///
+/// Implemented for [`Environment`] and for plain strings, in which case
+/// the same url is returned for every api.
+pub trait Endpoint {
+    /// Base url of the REST api.
+    fn rest_url(&self) -> &str;
+    /// Base url of the websocket market and user data streams.
+    fn stream_url(&self) -> &str;
+    /// Full url of the websocket api.
+    fn ws_api_url(&self) -> &str;
+}
+
+impl Endpoint for Environment {
+    fn rest_url(&self) -> &str {
+        match self {
+            Self::Binance => "https://api.binance.com",
+            Self::BinanceUs => "https://api.binance.us",
+            Self::SpotTestnet => "https://testnet.binance.vision",
+            Self::UsdFutures => "https://fapi.binance.com",
+            Self::UsdFuturesTestnet => "https://testnet.binancefuture.com",
+            Self::CoinFuturesTestnet => "https://testnet.binancefuture.com",
+            Self::Api1 => "https://api1.binance.com",
+            Self::Api2 => "https://api2.binance.com",
+            Self::Api3 => "https://api3.binance.com",
+            Self::Api4 => "https://api4.binance.com",
+            Self::DataApi => "https://data-api.binance.vision",
+        }
+    }
+
+    fn stream_url(&self) -> &str {
+        match self {
+            Self::Binance | Self::Api1 | Self::Api2 | Self::Api3 | Self::Api4 => {
+                "wss://stream.binance.com:9443"
+            }
+            Self::BinanceUs => "wss://stream.binance.us:9443",
+            Self::SpotTestnet => "wss://stream.testnet.binance.vision",
+            Self::UsdFutures => "wss://fstream.binance.com",
+            Self::UsdFuturesTestnet => "wss://fstream.binancefuture.com",
+            Self::CoinFuturesTestnet => "wss://dstream.binancefuture.com",
+            Self::DataApi => "wss://data-stream.binance.vision",
+        }
+    }
+
+    fn ws_api_url(&self) -> &str {
+        match self {
+            Self::Binance | Self::Api1 | Self::Api2 | Self::Api3 | Self::Api4 | Self::DataApi => {
+                "wss://ws-api.binance.com:443/ws-api/v3"
+            }
+            Self::BinanceUs => "wss://ws-api.binance.us:443/ws-api/v3",
+            Self::SpotTestnet => "wss://ws-api.testnet.binance.vision/ws-api/v3",
+            Self::UsdFutures => "wss://ws-fapi.binance.com/ws-fapi/v1",
+            Self::UsdFuturesTestnet => "wss://testnet.binancefuture.com/ws-fapi/v1",
+            Self::CoinFuturesTestnet => "wss://testnet.binancefuture.com/ws-dapi/v1",
+        }
+    }
+}
+
+impl<T: AsRef<str>> Endpoint for T {
+    fn rest_url(&self) -> &str {
+        self.as_ref()
+    }
+
+    fn stream_url(&self) -> &str {
+        self.as_ref()
+    }
+
+    fn ws_api_url(&self) -> &str {
+        self.as_ref()
+    }
+}
@@ -1,11 +1,13 @@
 use async_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use std::error;
 use std::fmt;
+use std::time::Duration;
 
 pub type Result<T> = std::result::Result<T, Error>;
 pub(crate) type BoxError = Box<dyn error::Error + Send + Sync>;
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Kind {
     Binance,
     SerdeUrlEncoded,
@@ -13,7 +15,18 @@ pub enum Kind {
     Tungstenite,
     SerdeJson,
     Hmac,
+    Signer,
+    #[cfg(feature = "archive")]
+    Archive,
+    #[cfg(feature = "archive")]
+    Zip,
+    Csv,
+    Io,
+    #[cfg(feature = "parquet")]
+    Parquet,
     Url,
+    Validation,
+    Risk,
 }
 
 #[derive(Debug)]
@@ -43,11 +56,74 @@ impl error::Error for WsCloseError {
     }
 }
 
+/// Error reply to a websocket request such as `SUBSCRIBE`.
+#[derive(Debug)]
+pub struct WsRequestError {
+    id: u64,
+    code: i64,
+    message: String,
+}
+
+impl WsRequestError {
+    pub fn new<T: Into<String>>(id: u64, code: i64, message: T) -> Self {
+        WsRequestError {
+            id,
+            code,
+            message: message.into(),
+        }
+    }
+    /// Id of the request that failed.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    /// Binance error code.
+    pub fn code(&self) -> i64 {
+        self.code
+    }
+}
+
+impl fmt::Display for WsRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request {}: {}. {}", self.id, self.code, self.message)
+    }
+}
+
+impl error::Error for WsRequestError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+/// Stream name that does not match any [`ChannelBuf`](crate::ChannelBuf).
+#[derive(Debug)]
+pub struct ParseChannelError {
+    name: String,
+}
+
+impl ParseChannelError {
+    pub fn new<T: Into<String>>(name: T) -> Self {
+        ParseChannelError { name: name.into() }
+    }
+}
+
+impl fmt::Display for ParseChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown channel: {}", self.name)
+    }
+}
+
+impl error::Error for ParseChannelError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
 #[derive(Debug)]
 pub struct ClientError {
     code: u16,
     reason: String,
     message: String,
+    retry_after: Option<Duration>,
 }
 
 impl ClientError {
@@ -56,8 +132,33 @@ impl ClientError {
             code,
             reason: reason.into(),
             message: message.into(),
+            retry_after: None,
         }
     }
+    pub(crate) fn with_retry_after(mut self, retry_after: Option<Duration>) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+    /// Http status code.
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+    /// Body of the response, usually the binance error as json.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+    /// Whether the request was rejected for breaking a rate limit; 429, or 418 once banned.
+    pub fn is_rate_limited(&self) -> bool {
+        self.code == 429 || self.is_banned()
+    }
+    /// Whether the IP is banned for breaking the rate limit; a ban lasts from minutes to days.
+    pub fn is_banned(&self) -> bool {
+        self.code == 418
+    }
+    /// How long to back off, as asked by binance for rate limited requests.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
 }
 
 impl fmt::Display for ClientError {
@@ -72,6 +173,157 @@ impl error::Error for ClientError {
     }
 }
 
+/// Order options that binance would refuse, caught before sending.
+#[derive(Debug)]
+pub struct ValidationError {
+    message: String,
+}
+
+impl ValidationError {
+    pub fn new<T: Into<String>>(message: T) -> Self {
+        ValidationError {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl error::Error for ValidationError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+/// Order refused by the pre-trade risk checks of a [`RiskGuard`](crate::RiskGuard).
+#[derive(Clone, Debug, PartialEq)]
+pub enum RiskError {
+    /// The kill switch is engaged.
+    KillSwitch,
+    /// The base and quote asset of the symbol are not known.
+    UnknownSymbol(String),
+    /// No last price or best bid and ask of the symbol to check against.
+    NoMarketData(String),
+    MaxNotional {
+        notional: f64,
+        limit: f64,
+    },
+    /// Holding of the asset once the open orders and this one fill.
+    MaxPosition {
+        asset: String,
+        position: f64,
+        limit: f64,
+    },
+    MaxOpenOrders {
+        symbol: String,
+        open: usize,
+        limit: usize,
+    },
+    /// Price too far from the last trade or mark price; the band is a fraction of it.
+    PriceBand {
+        price: f64,
+        reference: f64,
+        band: f64,
+    },
+    /// Price too far through the best bid or ask; the limit is a fraction of it.
+    FatFinger {
+        price: f64,
+        best: f64,
+        limit: f64,
+    },
+}
+
+impl fmt::Display for RiskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KillSwitch => write!(f, "Kill switch is engaged"),
+            Self::UnknownSymbol(symbol) => write!(f, "Unknown symbol {}", symbol),
+            Self::NoMarketData(symbol) => write!(f, "No market data for {}", symbol),
+            Self::MaxNotional { notional, limit } => {
+                write!(f, "Notional {} above the limit of {}", notional, limit)
+            }
+            Self::MaxPosition {
+                asset,
+                position,
+                limit,
+            } => write!(
+                f,
+                "Position of {} {} above the limit of {}",
+                position, asset, limit
+            ),
+            Self::MaxOpenOrders {
+                symbol,
+                open,
+                limit,
+            } => write!(
+                f,
+                "{} open orders on {}, the limit is {}",
+                open, symbol, limit
+            ),
+            Self::PriceBand {
+                price,
+                reference,
+                band,
+            } => write!(
+                f,
+                "Price {} outside the band of {} around {}",
+                price, band, reference
+            ),
+            Self::FatFinger { price, best, limit } => write!(
+                f,
+                "Price {} more than {} through the best price {}",
+                price, limit, best
+            ),
+        }
+    }
+}
+
+impl error::Error for RiskError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+/// Archive from data.binance.vision that failed the checksum or could not be parsed.
+#[cfg(feature = "archive")]
+#[derive(Debug)]
+pub struct ArchiveError {
+    file: String,
+    message: String,
+}
+
+#[cfg(feature = "archive")]
+impl ArchiveError {
+    pub fn new<T: Into<String>>(file: T, message: T) -> Self {
+        ArchiveError {
+            file: file.into(),
+            message: message.into(),
+        }
+    }
+    /// Name of the archive.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+}
+
+#[cfg(feature = "archive")]
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.file, self.message)
+    }
+}
+
+#[cfg(feature = "archive")]
+impl error::Error for ArchiveError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
 pub struct Error {
     kind: Kind,
     source: Option<BoxError>,
@@ -87,6 +339,21 @@ impl Error {
             source: source.map(Into::into),
         }
     }
+    /// How long to wait before sending a request refused for the rate limit again, after
+    /// `retries` resends; none for other errors, a ban of the IP or once the retries are
+    /// used up. `default` is used when binance does not say.
+    pub(crate) fn retry_after(
+        &self,
+        retries: usize,
+        max_retries: usize,
+        default: Duration,
+    ) -> Option<Duration> {
+        let error = error::Error::source(self)?.downcast_ref::<ClientError>()?;
+        match error.is_rate_limited() && !error.is_banned() && retries < max_retries {
+            true => Some(error.retry_after().unwrap_or(default)),
+            false => None,
+        }
+    }
 }
 
 impl fmt::Debug for Error {
@@ -131,6 +398,31 @@ impl From<WsCloseError> for Error {
     }
 }
 
+impl From<WsRequestError> for Error {
+    fn from(error: WsRequestError) -> Self {
+        Error::new(Kind::Binance, Some(error))
+    }
+}
+
+impl From<ValidationError> for Error {
+    fn from(error: ValidationError) -> Self {
+        Error::new(Kind::Validation, Some(error))
+    }
+}
+
+impl From<RiskError> for Error {
+    fn from(error: RiskError) -> Self {
+        Error::new(Kind::Risk, Some(error))
+    }
+}
+
+#[cfg(feature = "archive")]
+impl From<ArchiveError> for Error {
+    fn from(error: ArchiveError) -> Self {
+        Error::new(Kind::Archive, Some(error))
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(error: reqwest::Error) -> Self {
         Error::new(Kind::Reqwest, Some(error))
@@ -166,3 +458,29 @@ impl From<hmac::digest::InvalidLength> for Error {
         Error::new(Kind::Hmac, Some(error))
     }
 }
+
+#[cfg(feature = "archive")]
+impl From<zip::result::ZipError> for Error {
+    fn from(error: zip::result::ZipError) -> Self {
+        Error::new(Kind::Zip, Some(error))
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(error: csv::Error) -> Self {
+        Error::new(Kind::Csv, Some(error))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::new(Kind::Io, Some(error))
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(error: parquet::errors::ParquetError) -> Self {
+        Error::new(Kind::Parquet, Some(error))
+    }
+}
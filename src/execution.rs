@@ -0,0 +1,829 @@
+use crate::client::{AccountClient, MarketDataClient};
+use crate::model::{AggTrade, ApiError, BookTicker, Kline};
+use crate::param::{Interval, OrderRespType, Side, TimeInForce, ID};
+use chrono::{TimeZone, Utc};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::time::{self, Duration, Instant};
+
+/// Quantities below this are treated as zero.
+const EPSILON: f64 = 1e-9;
+
+/// Maximum number of rows binance returns per request.
+const MAX_LIMIT: usize = 1000;
+
+const DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// Message of the -2010 refusal of a limit maker order that would take.
+const WOULD_TAKE: &str = "Order would immediately match and take.";
+
+/// Share of the volume traded over a window, used to schedule a VWAP execution.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VolumeProfile {
+    /// Volume by start and end offset from the start of the window, in milliseconds.
+    buckets: BTreeMap<i64, (i64, f64)>,
+}
+
+impl VolumeProfile {
+    /// Profile of the same window on each of the previous days, from `get_candlestick_bars`.
+    ///
+    /// At most 1000 klines of the interval are used per day.
+    pub async fn fetch(
+        client: &MarketDataClient,
+        symbol: &str,
+        interval: Interval,
+        duration: Duration,
+        days: u32,
+    ) -> crate::error::Result<Self> {
+        let now = Utc::now().timestamp_millis();
+        let mut profile = Self::default();
+        for day in 1..=days as i64 {
+            let start = now - day * DAY;
+            let end = start + duration.as_millis() as i64 - 1;
+            let klines = client
+                .get_candlestick_bars(symbol, interval)
+                .with_start_time(Utc.timestamp_millis_opt(start).unwrap())
+                .with_end_time(Utc.timestamp_millis_opt(end).unwrap())
+                .with_limit(MAX_LIMIT)
+                .json::<Vec<Kline>>()
+                .await?;
+            profile.add_klines(&klines, start);
+        }
+        Ok(profile)
+    }
+    /// Adds the volume of klines of a window that started at `start`, unix time in ms.
+    pub fn add_klines(&mut self, klines: &[Kline], start: i64) {
+        for kline in klines {
+            let offset = kline.open_time - start;
+            let bucket = self
+                .buckets
+                .entry(offset)
+                .or_insert((kline.close_time + 1 - start, 0.0));
+            bucket.1 += kline.volume;
+        }
+    }
+    /// Share of the volume traded after `elapsed` of the window, between 0 and 1.
+    ///
+    /// Without volume the share grows evenly over the duration.
+    pub fn fraction(&self, elapsed: Duration, duration: Duration) -> f64 {
+        let elapsed = elapsed.as_millis() as i64;
+        let total: f64 = self.buckets.values().map(|(_, volume)| volume).sum::<f64>();
+        if total <= 0.0 {
+            let duration = duration.as_millis().max(1) as f64;
+            return (elapsed as f64 / duration).min(1.0);
+        }
+
+        let traded: f64 = self
+            .buckets
+            .iter()
+            .map(|(&start, &(end, volume))| {
+                if elapsed >= end {
+                    volume
+                } else if elapsed > start {
+                    volume * (elapsed - start) as f64 / (end - start) as f64
+                } else {
+                    0.0
+                }
+            })
+            .sum::<f64>();
+        (traded / total).min(1.0)
+    }
+}
+
+/// How the quantity of a parent order is spread over time.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Algorithm {
+    /// Even quantities over the duration.
+    Twap,
+    /// Quantities following the volume profile over the duration.
+    Vwap(VolumeProfile),
+    /// A share of the volume traded since the start, e.g. 0.1 for 10%; runs until filled.
+    Pov(f64),
+}
+
+/// Type of the child orders.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ChildOrder {
+    /// Rests at the best price of the own side, posted again every slice.
+    LimitMaker,
+    /// Takes the best price of the other side; what is not filled at once expires.
+    Ioc,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    Pending,
+    Running,
+    Paused,
+    Canceled,
+    Completed,
+}
+
+/// Progress of an execution.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionSummary {
+    pub status: ExecutionStatus,
+    pub quantity: f64,
+    pub filled_qty: f64,
+    /// Quote asset spent or received, before fees.
+    pub quote_qty: f64,
+    /// Zero without fills.
+    pub avg_price: f64,
+    /// Middle of the best bid and ask when the execution started.
+    pub arrival_price: f64,
+    /// Cost of the average price against the arrival price in basis points;
+    /// positive when worse.
+    pub slippage_bps: f64,
+    pub child_orders: usize,
+}
+
+/// Order worked by an [`Execution`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParentOrder {
+    symbol: String,
+    side: Side,
+    quantity: f64,
+    duration: Duration,
+    algorithm: Algorithm,
+    child_order: ChildOrder,
+    slice_interval: Duration,
+    limit_price: Option<f64>,
+    iceberg_qty: Option<f64>,
+    step_size: f64,
+    min_qty: f64,
+}
+
+impl ParentOrder {
+    pub fn twap<S: Into<String>>(symbol: S, side: Side, quantity: f64, duration: Duration) -> Self {
+        Self::new(symbol.into(), side, quantity, duration, Algorithm::Twap)
+    }
+
+    pub fn vwap<S: Into<String>>(
+        symbol: S,
+        side: Side,
+        quantity: f64,
+        duration: Duration,
+        profile: VolumeProfile,
+    ) -> Self {
+        Self::new(
+            symbol.into(),
+            side,
+            quantity,
+            duration,
+            Algorithm::Vwap(profile),
+        )
+    }
+
+    pub fn pov<S: Into<String>>(symbol: S, side: Side, quantity: f64, rate: f64) -> Self {
+        Self::new(
+            symbol.into(),
+            side,
+            quantity,
+            Duration::ZERO,
+            Algorithm::Pov(rate),
+        )
+    }
+
+    fn new(
+        symbol: String,
+        side: Side,
+        quantity: f64,
+        duration: Duration,
+        algorithm: Algorithm,
+    ) -> Self {
+        Self {
+            symbol,
+            side,
+            quantity,
+            duration,
+            algorithm,
+            child_order: ChildOrder::LimitMaker,
+            slice_interval: Duration::from_secs(10),
+            limit_price: None,
+            iceberg_qty: None,
+            step_size: 0.0,
+            min_qty: 0.0,
+        }
+    }
+
+    pub fn with_child_order(mut self, child_order: ChildOrder) -> Self {
+        self.child_order = child_order;
+        self
+    }
+
+    pub fn with_slice_interval(mut self, slice_interval: Duration) -> Self {
+        self.slice_interval = slice_interval;
+        self
+    }
+
+    /// Worst price of the child orders.
+    pub fn with_limit_price(mut self, limit_price: f64) -> Self {
+        self.limit_price = Some(limit_price);
+        self
+    }
+
+    /// Shows only this quantity of the limit maker child orders in the book; children of
+    /// at most this quantity are sent whole. IOC children are never icebergs.
+    pub fn with_iceberg_qty(mut self, iceberg_qty: f64) -> Self {
+        self.iceberg_qty = Some(iceberg_qty);
+        self
+    }
+
+    /// Child quantities are rounded down to the step size and skipped below the minimum,
+    /// as in the `LOT_SIZE` filter of the symbol.
+    pub fn with_lot_size(mut self, step_size: f64, min_qty: f64) -> Self {
+        self.step_size = step_size;
+        self.min_qty = min_qty;
+        self
+    }
+
+    /// Quantity due after `elapsed` of the schedule.
+    fn target(&self, elapsed: Duration, market_volume: f64) -> f64 {
+        let fraction = match &self.algorithm {
+            Algorithm::Pov(rate) => return (rate * market_volume).min(self.quantity),
+            _ if elapsed >= self.duration => 1.0,
+            Algorithm::Twap => elapsed.as_secs_f64() / self.duration.as_secs_f64(),
+            Algorithm::Vwap(profile) => profile.fraction(elapsed, self.duration),
+        };
+        self.quantity * fraction.min(1.0)
+    }
+
+    /// Rounds down to the step size and to the 8 decimals binance accepts.
+    fn round(&self, qty: f64) -> f64 {
+        let qty = match self.step_size > 0.0 {
+            true => ((qty + EPSILON) / self.step_size).floor() * self.step_size,
+            false => qty,
+        };
+        ((qty + EPSILON) * 1e8).floor() / 1e8
+    }
+}
+
+struct State {
+    status: ExecutionStatus,
+    filled_qty: f64,
+    quote_qty: f64,
+    arrival_price: f64,
+    child_orders: usize,
+    market_volume: f64,
+    last_trade_id: Option<i64>,
+    /// Limit maker order of the current slice.
+    child: Option<i64>,
+    /// Time and unix time in ms of the first run.
+    start: Option<(Instant, i64)>,
+    /// Time spent paused, which does not count for the schedule.
+    paused: Duration,
+    paused_at: Option<Instant>,
+}
+
+/// Works a parent order as a TWAP, VWAP or POV execution of child orders.
+///
+/// Every slice the child order of the previous slice is canceled and a new one is sent
+/// for the quantity that is due by the end of the slice and not filled yet, at the best
+/// price of the book. Once the schedule ends the remaining quantity is worked every slice
+/// until it is filled. Pausing and canceling take effect at the next slice; a paused
+/// execution has no open child order and the pause does not count for the schedule.
+/// Limit maker children can be sent as icebergs to hide the size of each slice.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{AccountClient, ChildOrder, Environment, Execution, Interval};
+/// use tokio_binance::{MarketDataClient, ParentOrder, Side, VolumeProfile};
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let account = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+///     let market = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
+///     let duration = Duration::from_secs(3600);
+///
+///     // volume of the same hour on the last 5 days
+///     let profile = VolumeProfile::fetch(&market, "BNBUSDT", Interval::FiveMinutes, duration, 5).await?;
+///     let order = ParentOrder::vwap("BNBUSDT", Side::Buy, 10.0, duration, profile)
+///         // optional: default is LimitMaker.
+///         .with_child_order(ChildOrder::Ioc)
+///         // optional: default is 10 seconds.
+///         .with_slice_interval(Duration::from_secs(30))
+///         // optional: worst price of the child orders.
+///         .with_limit_price(320.0)
+///         // optional: visible quantity of limit maker child orders.
+///         .with_iceberg_qty(0.5)
+///         // optional: step size and minimum quantity of the symbol.
+///         .with_lot_size(0.001, 0.001);
+///
+///     let execution = Execution::new(account, market, order);
+///     let worker = execution.clone();
+///     let handle = tokio::spawn(async move { worker.run().await });
+///
+///     tokio::time::sleep(Duration::from_secs(60)).await;
+///     execution.pause();
+///     execution.resume();
+///
+///     let summary = handle.await??;
+///     println!("{} at {}, {} bps", summary.filled_qty, summary.avg_price, summary.slippage_bps);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Execution {
+    account: AccountClient,
+    market: MarketDataClient,
+    order: Arc<ParentOrder>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Execution {
+    pub fn new(account: AccountClient, market: MarketDataClient, order: ParentOrder) -> Self {
+        Self {
+            account,
+            market,
+            order: Arc::new(order),
+            state: Arc::new(Mutex::new(State {
+                status: ExecutionStatus::Pending,
+                filled_qty: 0.0,
+                quote_qty: 0.0,
+                arrival_price: 0.0,
+                child_orders: 0,
+                market_volume: 0.0,
+                last_trade_id: None,
+                child: None,
+                start: None,
+                paused: Duration::ZERO,
+                paused_at: None,
+            })),
+        }
+    }
+    /// Works the order until it is filled or canceled.
+    ///
+    /// On an error the open child order is canceled before the error is returned;
+    /// running again continues the execution.
+    pub async fn run(&self) -> crate::error::Result<ExecutionSummary> {
+        let result = self.work().await;
+        if result.is_err() {
+            let child = self.state.lock().unwrap().child;
+            if let Some(order_id) = child {
+                // a cancel that fails as well is sent again by the next run
+                if self.settle(order_id).await.is_ok() {
+                    self.state.lock().unwrap().child = None;
+                }
+            }
+        }
+        result
+    }
+    /// Stops sending child orders until resumed.
+    pub fn pause(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let ExecutionStatus::Pending | ExecutionStatus::Running = state.status {
+            state.status = ExecutionStatus::Paused;
+            state.paused_at = Some(Instant::now());
+        }
+    }
+    pub fn resume(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(paused_at) = state.paused_at.take() {
+            state.paused += paused_at.elapsed();
+            if state.status == ExecutionStatus::Paused {
+                state.status = ExecutionStatus::Running;
+            }
+        }
+    }
+    /// Cancels the open child order and stops the execution.
+    pub fn cancel(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.status != ExecutionStatus::Completed {
+            state.status = ExecutionStatus::Canceled;
+        }
+    }
+    pub fn summary(&self) -> ExecutionSummary {
+        let state = self.state.lock().unwrap();
+        let avg_price = match state.filled_qty > EPSILON {
+            true => state.quote_qty / state.filled_qty,
+            false => 0.0,
+        };
+        let slippage_bps = match avg_price > 0.0 && state.arrival_price > 0.0 {
+            true => {
+                let slippage = (avg_price - state.arrival_price) / state.arrival_price * 1e4;
+                match self.order.side {
+                    Side::Buy => slippage,
+                    Side::Sell => -slippage,
+                }
+            }
+            false => 0.0,
+        };
+        ExecutionSummary {
+            status: state.status,
+            quantity: self.order.quantity,
+            filled_qty: state.filled_qty,
+            quote_qty: state.quote_qty,
+            avg_price,
+            arrival_price: state.arrival_price,
+            slippage_bps,
+            child_orders: state.child_orders,
+        }
+    }
+
+    async fn work(&self) -> crate::error::Result<ExecutionSummary> {
+        let order = &*self.order;
+        let ticker = self.book_ticker().await?;
+        let start = {
+            let mut state = self.state.lock().unwrap();
+            if state.status == ExecutionStatus::Pending {
+                state.status = ExecutionStatus::Running;
+            }
+            match state.start {
+                Some((start, _)) => start,
+                None => {
+                    let start = Instant::now();
+                    state.start = Some((start, Utc::now().timestamp_millis()));
+                    state.arrival_price = (ticker.bid_price + ticker.ask_price) / 2.0;
+                    // a pause before the start does not count
+                    if state.paused_at.is_some() {
+                        state.paused_at = Some(start);
+                    }
+                    start
+                }
+            }
+        };
+
+        let mut interval = time::interval(order.slice_interval);
+        loop {
+            interval.tick().await;
+            let child = self.state.lock().unwrap().child;
+            if let Some(order_id) = child {
+                self.settle(order_id).await?;
+                self.state.lock().unwrap().child = None;
+            }
+
+            let (status, elapsed, filled) = {
+                let state = self.state.lock().unwrap();
+                let paused = match state.paused_at {
+                    Some(paused_at) => state.paused + paused_at.elapsed(),
+                    None => state.paused,
+                };
+                let elapsed = start.elapsed().saturating_sub(paused);
+                (state.status, elapsed, state.filled_qty)
+            };
+            match status {
+                ExecutionStatus::Canceled => break,
+                ExecutionStatus::Paused => continue,
+                _ => {}
+            }
+            if order.quantity - filled < order.min_qty.max(EPSILON) {
+                self.state.lock().unwrap().status = ExecutionStatus::Completed;
+                break;
+            }
+
+            if let Algorithm::Pov(_) = order.algorithm {
+                self.update_volume().await?;
+            }
+            let market_volume = self.state.lock().unwrap().market_volume;
+            let target = order.target(elapsed + order.slice_interval, market_volume);
+            let qty = order.round(target - filled);
+            if qty < order.min_qty.max(EPSILON) {
+                continue;
+            }
+            let ticker = self.book_ticker().await?;
+            let child = self.place(qty, &ticker).await?;
+            self.state.lock().unwrap().child = child;
+        }
+        Ok(self.summary())
+    }
+
+    async fn book_ticker(&self) -> crate::error::Result<BookTicker> {
+        self.market
+            .get_order_book_ticker()
+            .with_symbol(&self.order.symbol)
+            .json::<BookTicker>()
+            .await
+    }
+
+    /// Sends a child order; returns the id of a limit maker order left open.
+    async fn place(&self, qty: f64, ticker: &BookTicker) -> crate::error::Result<Option<i64>> {
+        let order = &*self.order;
+        let (own, other) = match order.side {
+            Side::Buy => (ticker.bid_price, ticker.ask_price),
+            Side::Sell => (ticker.ask_price, ticker.bid_price),
+        };
+        let within_limit = |price: f64| match (order.limit_price, order.side) {
+            (Some(limit), Side::Buy) => price <= limit,
+            (Some(limit), Side::Sell) => price >= limit,
+            (None, _) => true,
+        };
+
+        let request = match order.child_order {
+            ChildOrder::LimitMaker => {
+                let price = match within_limit(own) {
+                    true => own,
+                    false => order.limit_price.unwrap_or(own),
+                };
+                if price <= 0.0 {
+                    return Ok(None);
+                }
+                let request =
+                    self.account
+                        .place_limit_order(&order.symbol, order.side, price, qty, true);
+                let request = match order.iceberg_qty {
+                    Some(iceberg_qty) if iceberg_qty < qty => request.with_iceberg_qty(iceberg_qty),
+                    _ => request,
+                };
+                request
+                    .into_limit_maker_order()
+                    .with_new_order_resp_type(OrderRespType::Result)
+                    .json::<Value>()
+                    .await
+            }
+            ChildOrder::Ioc => {
+                if other <= 0.0 || !within_limit(other) {
+                    return Ok(None);
+                }
+                self.account
+                    .place_limit_order(&order.symbol, order.side, other, qty, true)
+                    .with_time_in_force(TimeInForce::Ioc)
+                    .with_new_order_resp_type(OrderRespType::Result)
+                    .json::<Value>()
+                    .await
+            }
+        };
+
+        let response = match request {
+            Ok(response) => response,
+            Err(error) => match ApiError::from_error(&error) {
+                // the book moved and the limit maker order would take
+                Some(refusal) if refusal.code == -2010 && refusal.msg == WOULD_TAKE => {
+                    return Ok(None)
+                }
+                _ => return Err(error),
+            },
+        };
+        self.state.lock().unwrap().child_orders += 1;
+        match response["status"].as_str() {
+            Some("NEW") | Some("PARTIALLY_FILLED") => Ok(response["orderId"].as_i64()),
+            _ => {
+                self.record(&response);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Cancels a child order and records what it filled.
+    async fn settle(&self, order_id: i64) -> crate::error::Result<()> {
+        let symbol = &self.order.symbol;
+        let response = match self
+            .account
+            .cancel_order(symbol, ID::OrderId(order_id))
+            .json::<Value>()
+            .await
+        {
+            Ok(response) => response,
+            // filled in the meantime
+            Err(error) if ApiError::has_code(&error, -2011) => {
+                self.account
+                    .get_order(symbol, ID::OrderId(order_id))
+                    .json::<Value>()
+                    .await?
+            }
+            Err(error) => return Err(error),
+        };
+        self.record(&response);
+        Ok(())
+    }
+
+    fn record(&self, response: &Value) {
+        let decimal = |key: &str| match response[key].as_str() {
+            Some(value) => value.parse::<f64>().unwrap_or_default(),
+            None => 0.0,
+        };
+        let mut state = self.state.lock().unwrap();
+        state.filled_qty += decimal("executedQty");
+        state.quote_qty += decimal("cummulativeQuoteQty");
+    }
+
+    /// Adds the volume traded since the last call, from `get_aggregate_trades`.
+    async fn update_volume(&self) -> crate::error::Result<()> {
+        loop {
+            let (last_trade_id, start_time) = {
+                let state = self.state.lock().unwrap();
+                let start_time = state.start.map(|(_, time)| time).unwrap_or_default();
+                (state.last_trade_id, start_time)
+            };
+            let request = self
+                .market
+                .get_aggregate_trades(&self.order.symbol)
+                .with_limit(MAX_LIMIT);
+            let request = match last_trade_id {
+                Some(id) => request.with_from_id(id + 1),
+                None => request.with_start_time(Utc.timestamp_millis_opt(start_time).unwrap()),
+            };
+            let trades = request.json::<Vec<AggTrade>>().await?;
+
+            let mut state = self.state.lock().unwrap();
+            state.market_volume += trades.iter().map(|trade| trade.qty).sum::<f64>();
+            if let Some(trade) = trades.last() {
+                state.last_trade_id = Some(trade.id);
+            }
+            if trades.len() < MAX_LIMIT {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "paper")]
+    use crate::model::{OrderBook, PriceLevel};
+    #[cfg(feature = "paper")]
+    use crate::PaperExchange;
+
+    const MINUTE: i64 = 60_000;
+
+    fn kline(open_time: i64, volume: f64) -> Kline {
+        Kline {
+            open_time,
+            close_time: open_time + MINUTE - 1,
+            open: 1.0,
+            high: 1.0,
+            low: 1.0,
+            close: 1.0,
+            volume,
+            quote_volume: volume,
+            trades: 1,
+            taker_buy_volume: 0.0,
+            taker_buy_quote_volume: 0.0,
+            is_closed: true,
+        }
+    }
+
+    fn minutes(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn twap_spreads_evenly() {
+        let order = ParentOrder::twap("BNBUSDT", Side::Buy, 10.0, minutes(10));
+        assert_near(order.target(Duration::ZERO, 0.0), 0.0);
+        assert_near(order.target(minutes(3), 0.0), 3.0);
+        assert_near(order.target(minutes(10), 0.0), 10.0);
+        assert_near(order.target(minutes(20), 0.0), 10.0);
+    }
+
+    #[test]
+    fn vwap_follows_the_profile() {
+        let start = 1_000 * DAY;
+        let mut profile = VolumeProfile::default();
+        profile.add_klines(&[kline(start, 1.0), kline(start + MINUTE, 3.0)], start);
+        // the same window a day later adds up
+        let start = start + DAY;
+        profile.add_klines(&[kline(start, 1.0), kline(start + MINUTE, 3.0)], start);
+
+        assert_near(profile.fraction(Duration::ZERO, minutes(2)), 0.0);
+        assert_near(profile.fraction(Duration::from_secs(30), minutes(2)), 0.125);
+        assert_near(profile.fraction(minutes(1), minutes(2)), 0.25);
+        assert_near(profile.fraction(minutes(2), minutes(2)), 1.0);
+
+        let order = ParentOrder::vwap("BNBUSDT", Side::Sell, 8.0, minutes(2), profile);
+        assert_near(order.target(minutes(1), 0.0), 2.0);
+        assert_near(order.target(minutes(3), 0.0), 8.0);
+
+        // without volume the share grows evenly
+        let empty = VolumeProfile::default();
+        assert_near(empty.fraction(minutes(1), minutes(4)), 0.25);
+    }
+
+    #[test]
+    fn pov_is_capped_at_the_quantity() {
+        let order = ParentOrder::pov("BNBUSDT", Side::Buy, 5.0, 0.1);
+        assert_near(order.target(minutes(1), 20.0), 2.0);
+        assert_near(order.target(minutes(1), 100.0), 5.0);
+    }
+
+    #[test]
+    fn rounds_down_to_the_lot_size() {
+        let order = ParentOrder::twap("BNBUSDT", Side::Buy, 10.0, minutes(10));
+        assert_near(order.round(1.123456785), 1.12345678);
+
+        let order = order.with_lot_size(0.01, 0.05);
+        assert_near(order.round(1.239), 1.23);
+        // float noise below the step is not rounded away
+        assert_near(order.round(0.3 - 0.1 - 0.1), 0.1);
+    }
+
+    #[test]
+    fn slippage_is_positive_when_worse() {
+        let account = AccountClient::connect("<api-key>", "<secret-key>", "http://127.0.0.1:1");
+        let market = MarketDataClient::connect("<api-key>", "http://127.0.0.1:1");
+        let (account, market) = (account.unwrap(), market.unwrap());
+
+        for (side, avg_price, slippage_bps) in [
+            (Side::Buy, 101.0, 100.0),
+            (Side::Buy, 99.0, -100.0),
+            (Side::Sell, 99.0, 100.0),
+            (Side::Sell, 101.0, -100.0),
+        ] {
+            let order = ParentOrder::twap("BNBUSDT", side, 2.0, minutes(10));
+            let execution = Execution::new(account.clone(), market.clone(), order);
+            {
+                let mut state = execution.state.lock().unwrap();
+                state.arrival_price = 100.0;
+                state.filled_qty = 2.0;
+                state.quote_qty = 2.0 * avg_price;
+            }
+
+            let summary = execution.summary();
+            assert_near(summary.avg_price, avg_price);
+            assert_near(summary.slippage_bps, slippage_bps);
+        }
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn only_taking_limit_makers_are_skipped() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 100.0);
+        let book = OrderBook {
+            last_update_id: 1,
+            bids: vec![PriceLevel(9.0, 10.0)],
+            asks: vec![PriceLevel(10.0, 10.0)],
+        };
+        paper.set_order_book("BNBUSDT", &book);
+        let account = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let market = MarketDataClient::connect("<api-key>", &paper).unwrap();
+
+        let order = ParentOrder::twap("BNBUSDT", Side::Buy, 20.0, minutes(10));
+        let execution = Execution::new(account, market, order);
+        let ticker = |bid_price: f64| BookTicker {
+            update_id: None,
+            symbol: "BNBUSDT".into(),
+            bid_price,
+            bid_qty: 1.0,
+            ask_price: bid_price + 1.0,
+            ask_qty: 1.0,
+        };
+
+        // the bid of a stale ticker is at the ask of the book by now
+        assert_eq!(execution.place(1.0, &ticker(10.0)).await.unwrap(), None);
+        assert_eq!(execution.summary().child_orders, 0);
+
+        let order_id = execution.place(1.0, &ticker(9.0)).await.unwrap();
+        assert!(order_id.is_some());
+        assert_eq!(execution.summary().child_orders, 1);
+
+        // other refusals end the execution
+        let error = execution.place(20.0, &ticker(9.0)).await.unwrap_err();
+        let refusal = ApiError::from_error(&error).unwrap();
+        assert_eq!(refusal.code, -2010);
+        assert_ne!(refusal.msg, WOULD_TAKE);
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn limit_makers_are_sent_as_icebergs() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 100.0);
+        let account = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let market = MarketDataClient::connect("<api-key>", &paper).unwrap();
+        let ticker = BookTicker {
+            update_id: None,
+            symbol: "BNBUSDT".into(),
+            bid_price: 9.0,
+            bid_qty: 1.0,
+            ask_price: 10.0,
+            ask_qty: 1.0,
+        };
+
+        /// Visible quantity of the child order placed for the quantity.
+        async fn iceberg_qty(execution: &Execution, ticker: &BookTicker, qty: f64) -> f64 {
+            let order_id = execution.place(qty, ticker).await.unwrap().unwrap();
+            let order = execution
+                .account
+                .get_order("BNBUSDT", ID::OrderId(order_id))
+                .json::<Value>()
+                .await
+                .unwrap();
+            assert_eq!(order["type"], "LIMIT_MAKER");
+            order["icebergQty"].as_str().unwrap().parse().unwrap()
+        }
+
+        let order = ParentOrder::twap("BNBUSDT", Side::Buy, 5.0, minutes(10)).with_iceberg_qty(0.5);
+        let execution = Execution::new(account.clone(), market.clone(), order);
+        assert_near(iceberg_qty(&execution, &ticker, 2.0).await, 0.5);
+        // a slice that is not larger than the visible quantity is sent whole
+        assert_near(iceberg_qty(&execution, &ticker, 0.5).await, 0.0);
+
+        let order = ParentOrder::twap("BNBUSDT", Side::Buy, 5.0, minutes(10));
+        let execution = Execution::new(account, market, order);
+        assert_near(iceberg_qty(&execution, &ticker, 2.0).await, 0.0);
+    }
+}
@@ -0,0 +1,879 @@
+use crate::model::{AggTrade, BookTicker, DepthUpdate, Kline, KlineEvent, StreamEvent, Trade};
+use chrono::{NaiveDate, TimeZone, Utc};
+use flate2::{write::GzEncoder, Compression};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// Type of a column; every record type has a fixed list of columns.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    Int,
+    Float,
+    Bool,
+    Text,
+}
+
+/// Value of a single cell.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Field {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+    Null,
+}
+
+impl<T: Into<Field>> From<Option<T>> for Field {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Field::Null, Into::into)
+    }
+}
+
+impl From<i64> for Field {
+    fn from(value: i64) -> Self {
+        Field::Int(value)
+    }
+}
+
+impl From<u64> for Field {
+    fn from(value: u64) -> Self {
+        Field::Int(value as i64)
+    }
+}
+
+impl From<f64> for Field {
+    fn from(value: f64) -> Self {
+        Field::Float(value)
+    }
+}
+
+impl From<bool> for Field {
+    fn from(value: bool) -> Self {
+        Field::Bool(value)
+    }
+}
+
+impl From<&str> for Field {
+    fn from(value: &str) -> Self {
+        Field::Text(value.into())
+    }
+}
+
+/// Data that can be written by an [`ExportSink`].
+pub trait Record {
+    /// Name of the data set; the first level of the partitions.
+    const NAME: &'static str;
+    /// Names and types of the columns.
+    const COLUMNS: &'static [(&'static str, ColumnType)];
+
+    /// Time of the event in milliseconds; none when the event does not carry one.
+    fn time(&self) -> Option<i64>;
+    /// Symbol, for records that carry one.
+    fn symbol(&self) -> Option<&str> {
+        None
+    }
+    /// One or more rows matching the columns; `time` is the event time or the time it was written.
+    fn rows(&self, time: i64) -> Vec<Vec<Field>>;
+}
+
+impl Record for Kline {
+    const NAME: &'static str = "klines";
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("open_time", ColumnType::Int),
+        ("close_time", ColumnType::Int),
+        ("open", ColumnType::Float),
+        ("high", ColumnType::Float),
+        ("low", ColumnType::Float),
+        ("close", ColumnType::Float),
+        ("volume", ColumnType::Float),
+        ("quote_volume", ColumnType::Float),
+        ("trades", ColumnType::Int),
+        ("taker_buy_volume", ColumnType::Float),
+        ("taker_buy_quote_volume", ColumnType::Float),
+        ("is_closed", ColumnType::Bool),
+    ];
+
+    fn time(&self) -> Option<i64> {
+        Some(self.open_time)
+    }
+
+    fn rows(&self, _: i64) -> Vec<Vec<Field>> {
+        vec![vec![
+            self.open_time.into(),
+            self.close_time.into(),
+            self.open.into(),
+            self.high.into(),
+            self.low.into(),
+            self.close.into(),
+            self.volume.into(),
+            self.quote_volume.into(),
+            self.trades.into(),
+            self.taker_buy_volume.into(),
+            self.taker_buy_quote_volume.into(),
+            self.is_closed.into(),
+        ]]
+    }
+}
+
+/// Kline stream payload; written as its kline, partitioned by its open time like a [`Kline`].
+impl Record for KlineEvent {
+    const NAME: &'static str = Kline::NAME;
+    const COLUMNS: &'static [(&'static str, ColumnType)] = Kline::COLUMNS;
+
+    fn time(&self) -> Option<i64> {
+        self.kline.time()
+    }
+
+    fn symbol(&self) -> Option<&str> {
+        Some(&self.symbol)
+    }
+
+    fn rows(&self, time: i64) -> Vec<Vec<Field>> {
+        self.kline.rows(time)
+    }
+}
+
+impl Record for Trade {
+    const NAME: &'static str = "trades";
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("id", ColumnType::Int),
+        ("time", ColumnType::Int),
+        ("price", ColumnType::Float),
+        ("qty", ColumnType::Float),
+        ("is_buyer_maker", ColumnType::Bool),
+    ];
+
+    fn time(&self) -> Option<i64> {
+        Some(self.time)
+    }
+
+    fn rows(&self, _: i64) -> Vec<Vec<Field>> {
+        vec![vec![
+            self.id.into(),
+            self.time.into(),
+            self.price.into(),
+            self.qty.into(),
+            self.is_buyer_maker.into(),
+        ]]
+    }
+}
+
+impl Record for AggTrade {
+    const NAME: &'static str = "aggTrades";
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("id", ColumnType::Int),
+        ("time", ColumnType::Int),
+        ("price", ColumnType::Float),
+        ("qty", ColumnType::Float),
+        ("first_trade_id", ColumnType::Int),
+        ("last_trade_id", ColumnType::Int),
+        ("is_buyer_maker", ColumnType::Bool),
+    ];
+
+    fn time(&self) -> Option<i64> {
+        Some(self.time)
+    }
+
+    fn rows(&self, _: i64) -> Vec<Vec<Field>> {
+        vec![vec![
+            self.id.into(),
+            self.time.into(),
+            self.price.into(),
+            self.qty.into(),
+            self.first_trade_id.into(),
+            self.last_trade_id.into(),
+            self.is_buyer_maker.into(),
+        ]]
+    }
+}
+
+impl Record for BookTicker {
+    const NAME: &'static str = "bookTicker";
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("time", ColumnType::Int),
+        ("update_id", ColumnType::Int),
+        ("bid_price", ColumnType::Float),
+        ("bid_qty", ColumnType::Float),
+        ("ask_price", ColumnType::Float),
+        ("ask_qty", ColumnType::Float),
+    ];
+
+    fn time(&self) -> Option<i64> {
+        None
+    }
+
+    fn symbol(&self) -> Option<&str> {
+        Some(&self.symbol)
+    }
+
+    fn rows(&self, time: i64) -> Vec<Vec<Field>> {
+        vec![vec![
+            time.into(),
+            self.update_id.into(),
+            self.bid_price.into(),
+            self.bid_qty.into(),
+            self.ask_price.into(),
+            self.ask_qty.into(),
+        ]]
+    }
+}
+
+impl Record for DepthUpdate {
+    const NAME: &'static str = "depth";
+    const COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("event_time", ColumnType::Int),
+        ("first_update_id", ColumnType::Int),
+        ("final_update_id", ColumnType::Int),
+        ("side", ColumnType::Text),
+        ("price", ColumnType::Float),
+        ("qty", ColumnType::Float),
+    ];
+
+    fn time(&self) -> Option<i64> {
+        Some(self.event_time)
+    }
+
+    fn symbol(&self) -> Option<&str> {
+        Some(&self.symbol)
+    }
+
+    /// A row per changed level.
+    fn rows(&self, _: i64) -> Vec<Vec<Field>> {
+        let bids = self.bids.iter().map(|level| ("bid", level));
+        let asks = self.asks.iter().map(|level| ("ask", level));
+
+        bids.chain(asks)
+            .map(|(side, level)| {
+                vec![
+                    self.event_time.into(),
+                    self.first_update_id.into(),
+                    self.final_update_id.into(),
+                    side.into(),
+                    level.0.into(),
+                    level.1.into(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// File format of an [`ExportSink`]; the parquet formats need the `parquet` feature.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExportFormat {
+    Csv,
+    /// Gzip compressed csv.
+    CsvGzip,
+    /// Snappy compressed parquet.
+    #[cfg(feature = "parquet")]
+    Parquet,
+    /// Zstd compressed parquet.
+    #[cfg(feature = "parquet")]
+    ParquetZstd,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::CsvGzip => "csv.gz",
+            #[cfg(feature = "parquet")]
+            Self::Parquet | Self::ParquetZstd => "parquet",
+        }
+    }
+}
+
+/// Writes records to files partitioned by symbol and date.
+///
+/// Files are laid out as `<dir>/<name>/symbol=<SYMBOL>/date=<YYYY-MM-DD>/part-<n>.<ext>`,
+/// e.g. `data/klines/symbol=BNBUSDT/date=2022-01-01/part-0000.csv`.
+/// A new part is started for another date, once a file reaches the maximum rows,
+/// and for every new sink, so existing files are never overwritten.
+///
+/// Writes are blocking and buffered; call [`close`](Self::close) to finish the files,
+/// parquet files are only readable once closed. Dropping the sink closes them as well, ignoring errors.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{Channel, Environment, ExportFormat, ExportSink, WebSocketStream};
+/// use tokio_binance::model::{AggTrade, StreamEvent};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let channel = Channel::AggTrade("BNBUSDT");
+///     let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
+///
+///     let mut sink = ExportSink::<AggTrade>::new("data", ExportFormat::CsvGzip)
+///         // optional: rotate files after this many rows; default 1 million.
+///         .with_max_rows(100_000);
+///
+///     while let Some(event) = stream.json::<StreamEvent<AggTrade>>().await? {
+///         sink.write_event(&event)?;
+///     }
+///     sink.close()?;
+///     Ok(())
+/// }
+/// ```
+pub struct ExportSink<R: Record> {
+    dir: PathBuf,
+    format: ExportFormat,
+    max_rows: usize,
+    /// Open file per symbol.
+    files: HashMap<String, Partition>,
+    _marker: PhantomData<R>,
+}
+
+struct Partition {
+    date: NaiveDate,
+    rows: usize,
+    writer: Writer,
+}
+
+impl<R: Record> ExportSink<R> {
+    /// Creates a sink writing to the directory; directories are created as needed.
+    pub fn new<P: AsRef<Path>>(dir: P, format: ExportFormat) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            format,
+            max_rows: 1_000_000,
+            files: HashMap::new(),
+            _marker: PhantomData,
+        }
+    }
+    /// Rotate files after this many rows; default 1 million.
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows.max(1);
+        self
+    }
+    /// Write a record of the symbol, e.g. from the REST history.
+    pub fn write(&mut self, symbol: &str, record: &R) -> crate::error::Result<()> {
+        let time = record
+            .time()
+            .unwrap_or_else(|| Utc::now().timestamp_millis());
+        let date = Utc.timestamp_millis_opt(time).unwrap().naive_utc().date();
+        let symbol = symbol.to_uppercase();
+
+        let rotate = match self.files.get(&symbol) {
+            Some(file) => file.date != date || file.rows >= self.max_rows,
+            None => true,
+        };
+
+        if rotate {
+            if let Some(file) = self.files.remove(&symbol) {
+                file.writer.close()?;
+            }
+            let writer = self.open(&symbol, date)?;
+            let file = Partition {
+                date,
+                rows: 0,
+                writer,
+            };
+            self.files.insert(symbol.clone(), file);
+        }
+
+        let file = self.files.get_mut(&symbol).expect("opened");
+        for row in record.rows(time) {
+            file.writer.write(row)?;
+            file.rows += 1;
+        }
+        Ok(())
+    }
+    /// Write the records of the symbol.
+    pub fn write_all<'r, I>(&mut self, symbol: &str, records: I) -> crate::error::Result<()>
+    where
+        I: IntoIterator<Item = &'r R>,
+        R: 'r,
+    {
+        for record in records {
+            self.write(symbol, record)?;
+        }
+        Ok(())
+    }
+    /// Write a stream event; the symbol is taken from the event or else its channel.
+    pub fn write_event(&mut self, event: &StreamEvent<R>) -> crate::error::Result<()> {
+        let symbol = event.data.symbol().or_else(|| event.stream.symbol());
+        match symbol {
+            Some(symbol) => {
+                let symbol = symbol.to_string();
+                self.write(&symbol, &event.data)
+            }
+            None => {
+                let message = format!("No symbol for {}", event.stream);
+                Err(io::Error::new(io::ErrorKind::InvalidInput, message).into())
+            }
+        }
+    }
+    /// Flush the csv files; parquet rows are only written in row groups or on close.
+    pub fn flush(&mut self) -> crate::error::Result<()> {
+        for file in self.files.values_mut() {
+            file.writer.flush()?;
+        }
+        Ok(())
+    }
+    /// Finish and close all files.
+    pub fn close(&mut self) -> crate::error::Result<()> {
+        for (_, file) in self.files.drain() {
+            file.writer.close()?;
+        }
+        Ok(())
+    }
+
+    fn open(&self, symbol: &str, date: NaiveDate) -> crate::error::Result<Writer> {
+        let dir = self
+            .dir
+            .join(R::NAME)
+            .join(format!("symbol={}", symbol))
+            .join(format!("date={}", date.format("%Y-%m-%d")));
+        fs::create_dir_all(&dir)?;
+
+        let extension = self.format.extension();
+        let path = (0..)
+            .map(|part| dir.join(format!("part-{:04}.{}", part, extension)))
+            .find(|path| !path.exists())
+            .expect("free part");
+
+        let file = BufWriter::new(File::create(path)?);
+        let names = R::COLUMNS.iter().map(|(name, _)| *name);
+
+        Ok(match self.format {
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(file);
+                writer.write_record(names)?;
+                Writer::Csv(writer)
+            }
+            ExportFormat::CsvGzip => {
+                let encoder = GzEncoder::new(file, Compression::default());
+                let mut writer = csv::Writer::from_writer(encoder);
+                writer.write_record(names)?;
+                Writer::CsvGzip(writer)
+            }
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => Writer::Parquet(parquet_file::ParquetFile::new(
+                file,
+                R::COLUMNS,
+                parquet::basic::Compression::SNAPPY,
+            )?),
+            #[cfg(feature = "parquet")]
+            ExportFormat::ParquetZstd => Writer::Parquet(parquet_file::ParquetFile::new(
+                file,
+                R::COLUMNS,
+                parquet::basic::Compression::ZSTD(Default::default()),
+            )?),
+        })
+    }
+}
+
+impl<R: Record> Drop for ExportSink<R> {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+enum Writer {
+    Csv(csv::Writer<BufWriter<File>>),
+    CsvGzip(csv::Writer<GzEncoder<BufWriter<File>>>),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet_file::ParquetFile),
+}
+
+impl Writer {
+    fn write(&mut self, row: Vec<Field>) -> crate::error::Result<()> {
+        match self {
+            Self::Csv(writer) => writer.write_record(row.iter().map(csv_field))?,
+            Self::CsvGzip(writer) => writer.write_record(row.iter().map(csv_field))?,
+            #[cfg(feature = "parquet")]
+            Self::Parquet(writer) => writer.write(row)?,
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> crate::error::Result<()> {
+        match self {
+            Self::Csv(writer) => writer.flush()?,
+            Self::CsvGzip(writer) => writer.flush()?,
+            #[cfg(feature = "parquet")]
+            Self::Parquet(_) => {}
+        }
+        Ok(())
+    }
+
+    fn close(self) -> crate::error::Result<()> {
+        match self {
+            Self::Csv(writer) => {
+                let mut file = writer.into_inner().map_err(|error| error.into_error())?;
+                file.flush()?;
+            }
+            Self::CsvGzip(writer) => {
+                let encoder = writer.into_inner().map_err(|error| error.into_error())?;
+                encoder.finish()?.flush()?;
+            }
+            #[cfg(feature = "parquet")]
+            Self::Parquet(writer) => writer.close()?,
+        }
+        Ok(())
+    }
+}
+
+fn csv_field(field: &Field) -> String {
+    match field {
+        Field::Int(value) => value.to_string(),
+        Field::Float(value) => value.to_string(),
+        Field::Bool(value) => value.to_string(),
+        Field::Text(value) => value.clone(),
+        Field::Null => String::new(),
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_file {
+    use super::{ColumnType, Field};
+    use parquet::basic::Compression;
+    use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type};
+    use parquet::errors::Result;
+    use parquet::file::{properties::WriterProperties, writer::SerializedFileWriter};
+    use parquet::schema::parser::parse_message_type;
+    use std::fs::File;
+    use std::io::BufWriter;
+    use std::sync::Arc;
+
+    /// Rows buffered before they are written as a row group.
+    const ROW_GROUP_SIZE: usize = 100_000;
+
+    pub(super) struct ParquetFile {
+        writer: SerializedFileWriter<BufWriter<File>>,
+        columns: &'static [(&'static str, ColumnType)],
+        rows: Vec<Vec<Field>>,
+    }
+
+    impl ParquetFile {
+        pub(super) fn new(
+            file: BufWriter<File>,
+            columns: &'static [(&'static str, ColumnType)],
+            compression: Compression,
+        ) -> Result<Self> {
+            let fields: String = columns
+                .iter()
+                .map(|(name, column)| match column {
+                    ColumnType::Int => format!("OPTIONAL INT64 {};", name),
+                    ColumnType::Float => format!("OPTIONAL DOUBLE {};", name),
+                    ColumnType::Bool => format!("OPTIONAL BOOLEAN {};", name),
+                    ColumnType::Text => format!("OPTIONAL BYTE_ARRAY {} (UTF8);", name),
+                })
+                .collect();
+
+            let schema = parse_message_type(&format!("message schema {{ {} }}", fields))?;
+            let properties = WriterProperties::builder()
+                .set_compression(compression)
+                .build();
+            let writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties))?;
+
+            Ok(Self {
+                writer,
+                columns,
+                rows: Vec::new(),
+            })
+        }
+
+        pub(super) fn write(&mut self, row: Vec<Field>) -> Result<()> {
+            self.rows.push(row);
+            if self.rows.len() >= ROW_GROUP_SIZE {
+                self.write_row_group()?;
+            }
+            Ok(())
+        }
+
+        pub(super) fn close(mut self) -> Result<()> {
+            self.write_row_group()?;
+            self.writer.close()?;
+            Ok(())
+        }
+
+        fn write_row_group(&mut self) -> Result<()> {
+            if self.rows.is_empty() {
+                return Ok(());
+            }
+
+            let mut row_group = self.writer.next_row_group()?;
+            let mut index = 0;
+            while let Some(mut column) = row_group.next_column()? {
+                let cells = self.rows.iter().map(|row| &row[index]);
+                let levels: Vec<i16> = cells
+                    .clone()
+                    .map(|cell| (*cell != Field::Null) as i16)
+                    .collect();
+
+                match self.columns[index].1 {
+                    ColumnType::Int => {
+                        let values: Vec<_> = cells
+                            .filter_map(|cell| match cell {
+                                Field::Int(value) => Some(*value),
+                                _ => None,
+                            })
+                            .collect();
+                        column
+                            .typed::<Int64Type>()
+                            .write_batch(&values, Some(&levels), None)?;
+                    }
+                    ColumnType::Float => {
+                        let values: Vec<_> = cells
+                            .filter_map(|cell| match cell {
+                                Field::Float(value) => Some(*value),
+                                _ => None,
+                            })
+                            .collect();
+                        column
+                            .typed::<DoubleType>()
+                            .write_batch(&values, Some(&levels), None)?;
+                    }
+                    ColumnType::Bool => {
+                        let values: Vec<_> = cells
+                            .filter_map(|cell| match cell {
+                                Field::Bool(value) => Some(*value),
+                                _ => None,
+                            })
+                            .collect();
+                        column
+                            .typed::<BoolType>()
+                            .write_batch(&values, Some(&levels), None)?;
+                    }
+                    ColumnType::Text => {
+                        let values: Vec<_> = cells
+                            .filter_map(|cell| match cell {
+                                Field::Text(value) => Some(ByteArray::from(value.as_str())),
+                                _ => None,
+                            })
+                            .collect();
+                        column.typed::<ByteArrayType>().write_batch(
+                            &values,
+                            Some(&levels),
+                            None,
+                        )?;
+                    }
+                }
+                column.close()?;
+                index += 1;
+            }
+            row_group.close()?;
+            self.rows.clear();
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use serde_json::json;
+    use std::io::Read;
+
+    /// 2022-01-01T00:00:00Z
+    const DAY: i64 = 1_640_995_200_000;
+    const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+    fn trade(id: i64, time: i64) -> Trade {
+        let value = json!({
+            "id": id, "price": "300.5", "qty": "2", "quoteQty": "601",
+            "time": time, "isBuyerMaker": true, "isBestMatch": true
+        });
+        serde_json::from_value(value).unwrap()
+    }
+
+    /// Parts of the data set as `symbol=<SYMBOL>/date=<DATE>/<part>`, sorted.
+    fn parts(dir: &Path, name: &str) -> Vec<String> {
+        let root = dir.join(name);
+        let mut parts = Vec::new();
+        for symbol in fs::read_dir(&root).unwrap() {
+            for date in fs::read_dir(symbol.unwrap().path()).unwrap() {
+                for part in fs::read_dir(date.unwrap().path()).unwrap() {
+                    let path = part.unwrap().path();
+                    let relative = path.strip_prefix(&root).unwrap();
+                    parts.push(relative.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
+        parts.sort();
+        parts
+    }
+
+    fn lines(path: PathBuf) -> Vec<String> {
+        let text = fs::read_to_string(path).unwrap();
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn records_are_partitioned_by_symbol_and_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sink = ExportSink::<Trade>::new(dir.path(), ExportFormat::Csv);
+        sink.write_all("bnbusdt", &[trade(1, DAY), trade(2, DAY + 1)])
+            .unwrap();
+        sink.write("BTCUSDT", &trade(3, DAY + 2)).unwrap();
+        sink.write("BNBUSDT", &trade(4, DAY + DAY_MILLIS)).unwrap();
+        sink.close().unwrap();
+
+        let expected = vec![
+            "symbol=BNBUSDT/date=2022-01-01/part-0000.csv",
+            "symbol=BNBUSDT/date=2022-01-02/part-0000.csv",
+            "symbol=BTCUSDT/date=2022-01-01/part-0000.csv",
+        ];
+        assert_eq!(parts(dir.path(), "trades"), expected);
+
+        let path = dir
+            .path()
+            .join("trades/symbol=BNBUSDT/date=2022-01-01/part-0000.csv");
+        let expected = vec![
+            "id,time,price,qty,is_buyer_maker".to_string(),
+            format!("1,{},300.5,2,true", DAY),
+            format!("2,{},300.5,2,true", DAY + 1),
+        ];
+        assert_eq!(lines(path), expected);
+    }
+
+    #[test]
+    fn full_files_and_new_sinks_start_new_parts() {
+        let dir = tempfile::tempdir().unwrap();
+        let trades: Vec<_> = (0..5).map(|id| trade(id, DAY + id)).collect();
+
+        let mut sink = ExportSink::<Trade>::new(dir.path(), ExportFormat::Csv).with_max_rows(2);
+        sink.write_all("BNBUSDT", &trades).unwrap();
+        sink.close().unwrap();
+        let mut sink = ExportSink::<Trade>::new(dir.path(), ExportFormat::Csv);
+        sink.write("BNBUSDT", &trades[0]).unwrap();
+        drop(sink);
+
+        let parts = parts(dir.path(), "trades");
+        let names: Vec<_> = parts.iter().map(|part| &part[31..]).collect();
+        let expected = [
+            "part-0000.csv",
+            "part-0001.csv",
+            "part-0002.csv",
+            "part-0003.csv",
+        ];
+        assert_eq!(names, expected);
+
+        let date = dir.path().join("trades/symbol=BNBUSDT/date=2022-01-01");
+        let rows: Vec<_> = ["part-0000.csv", "part-0002.csv", "part-0003.csv"]
+            .iter()
+            .map(|part| lines(date.join(part)).len() - 1)
+            .collect();
+        assert_eq!(rows, vec![2, 1, 1]);
+    }
+
+    #[test]
+    fn kline_events_are_written_as_klines() {
+        // the last candle of the day, closed by an event after midnight
+        let open_time = DAY + DAY_MILLIS - 60_000;
+        let value = json!({
+            "stream": "bnbusdt@kline_1m",
+            "data": {
+                "e": "kline", "E": open_time + 60_001, "s": "BNBUSDT",
+                "k": {
+                    "t": open_time, "T": open_time + 59_999, "s": "BNBUSDT", "i": "1m",
+                    "o": "300", "h": "301", "l": "299", "c": "300.5", "v": "10",
+                    "q": "3005", "n": 7, "V": "4", "Q": "1202", "x": true
+                }
+            }
+        });
+        let event: StreamEvent<KlineEvent> = serde_json::from_value(value).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut sink = ExportSink::<KlineEvent>::new(dir.path(), ExportFormat::Csv);
+        sink.write_event(&event).unwrap();
+        sink.close().unwrap();
+
+        let expected = vec!["symbol=BNBUSDT/date=2022-01-01/part-0000.csv"];
+        assert_eq!(parts(dir.path(), "klines"), expected);
+        let path = dir.path().join("klines").join(expected[0]);
+        let header = Kline::COLUMNS.iter().map(|(name, _)| *name);
+        let expected = vec![
+            header.collect::<Vec<_>>().join(","),
+            format!(
+                "{},{},300,301,299,300.5,10,3005,7,4,1202,true",
+                open_time,
+                open_time + 59_999
+            ),
+        ];
+        assert_eq!(lines(path), expected);
+    }
+
+    #[test]
+    fn depth_updates_write_a_row_per_level() {
+        let value = json!({
+            "stream": "bnbusdt@depth",
+            "data": {
+                "e": "depthUpdate", "E": DAY, "s": "BNBUSDT", "U": 10, "u": 12,
+                "b": [["300", "1"], ["299", "0"]], "a": [["301", "2"]]
+            }
+        });
+        let event: StreamEvent<DepthUpdate> = serde_json::from_value(value).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut sink = ExportSink::<DepthUpdate>::new(dir.path(), ExportFormat::CsvGzip);
+        sink.write_event(&event).unwrap();
+        sink.close().unwrap();
+
+        let expected = vec!["symbol=BNBUSDT/date=2022-01-01/part-0000.csv.gz"];
+        assert_eq!(parts(dir.path(), "depth"), expected);
+        let file = File::open(dir.path().join("depth").join(expected[0])).unwrap();
+        let mut text = String::new();
+        GzDecoder::new(file).read_to_string(&mut text).unwrap();
+        let expected = format!(
+            "event_time,first_update_id,final_update_id,side,price,qty\n\
+             {0},10,12,bid,300,1\n{0},10,12,bid,299,0\n{0},10,12,ask,301,2\n",
+            DAY
+        );
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn events_without_a_time_are_written_when_received() {
+        let value = json!({
+            "stream": "bnbusdt@bookTicker",
+            "data": {"u": 1, "s": "BNBUSDT", "b": "300", "B": "1", "a": "301", "A": "2"}
+        });
+        let event: StreamEvent<BookTicker> = serde_json::from_value(value).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let mut sink = ExportSink::<BookTicker>::new(dir.path(), ExportFormat::Csv);
+        sink.write_event(&event).unwrap();
+        sink.close().unwrap();
+
+        let today = Utc::now().format("%Y-%m-%d");
+        let expected = vec![format!("symbol=BNBUSDT/date={}/part-0000.csv", today)];
+        assert_eq!(parts(dir.path(), "bookTicker"), expected);
+    }
+
+    #[test]
+    fn events_without_a_symbol_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sink = ExportSink::<Trade>::new(dir.path(), ExportFormat::Csv);
+        let event = StreamEvent {
+            stream: "!miniTicker@arr".parse().unwrap(),
+            data: trade(1, DAY),
+        };
+        assert!(sink.write_event(&event).is_err());
+        assert!(!dir.path().join("trades").exists());
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_files_are_readable_once_closed() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let trades: Vec<_> = (0..3).map(|id| trade(id, DAY + id)).collect();
+        let mut sink = ExportSink::<Trade>::new(dir.path(), ExportFormat::ParquetZstd);
+        sink.write_all("BNBUSDT", &trades).unwrap();
+        sink.close().unwrap();
+
+        let expected = vec!["symbol=BNBUSDT/date=2022-01-01/part-0000.parquet"];
+        assert_eq!(parts(dir.path(), "trades"), expected);
+        let file = File::open(dir.path().join("trades").join(expected[0])).unwrap();
+        let reader = SerializedFileReader::new(file).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 3);
+        assert_eq!(metadata.schema_descr().num_columns(), Trade::COLUMNS.len());
+    }
+}
@@ -0,0 +1,677 @@
+use crate::client::{AccountClient, MarketDataClient};
+use crate::model::{AccountTrade, AggTrade, Kline, Order, Trade};
+use crate::param::Interval;
+use chrono::{DateTime, TimeZone, Utc};
+use core::pin::Pin;
+use futures::{
+    future::{BoxFuture, FutureExt},
+    task::{Context, Poll},
+    Future, Stream,
+};
+use std::collections::VecDeque;
+use tokio::time::{self, Duration, Sleep};
+
+/// Maximum number of rows binance returns per request.
+const MAX_LIMIT: usize = 1000;
+
+/// Back off used when a rate limited response has no `Retry-After` header.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// aggTrades accepts less than an hour between start and end time.
+const AGG_TRADES_WINDOW: i64 = 60 * 60 * 1000;
+
+/// allOrders and myTrades accept at most 24 hours between start and end time.
+const ACCOUNT_WINDOW: i64 = 24 * 60 * 60 * 1000;
+
+/// Range walked by a [`History`], both ends included.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HistoryRange {
+    /// From the first id up to the last id, or up to the most recent row.
+    Ids(i64, Option<i64>),
+    /// Unix time in milliseconds.
+    Time(i64, i64),
+}
+
+impl HistoryRange {
+    pub fn ids(from: i64, to: Option<i64>) -> Self {
+        Self::Ids(from, to)
+    }
+
+    pub fn time<Tz: TimeZone>(start: DateTime<Tz>, end: DateTime<Tz>) -> Self {
+        Self::Time(start.timestamp_millis(), end.timestamp_millis())
+    }
+
+    fn contains(&self, id: i64, time: i64) -> bool {
+        match *self {
+            Self::Ids(from, Some(to)) => id >= from && id <= to,
+            Self::Ids(from, None) => id >= from,
+            Self::Time(start, end) => time >= start && time <= end,
+        }
+    }
+
+    fn is_past(&self, id: i64, time: i64) -> bool {
+        match *self {
+            Self::Ids(_, Some(to)) => id > to,
+            Self::Ids(_, None) => false,
+            Self::Time(_, end) => time > end,
+        }
+    }
+}
+
+/// Id and time a history is paged by.
+pub trait Cursor {
+    fn id(&self) -> i64;
+    fn time(&self) -> i64;
+}
+
+impl Cursor for Trade {
+    fn id(&self) -> i64 {
+        self.id
+    }
+    fn time(&self) -> i64 {
+        self.time
+    }
+}
+
+impl Cursor for AggTrade {
+    fn id(&self) -> i64 {
+        self.id
+    }
+    fn time(&self) -> i64 {
+        self.time
+    }
+}
+
+impl Cursor for Kline {
+    fn id(&self) -> i64 {
+        self.open_time
+    }
+    fn time(&self) -> i64 {
+        self.open_time
+    }
+}
+
+impl Cursor for Order {
+    fn id(&self) -> i64 {
+        self.order_id
+    }
+    fn time(&self) -> i64 {
+        self.time
+    }
+}
+
+impl Cursor for AccountTrade {
+    fn id(&self) -> i64 {
+        self.id
+    }
+    fn time(&self) -> i64 {
+        self.time
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Page {
+    /// Start and end time of a window.
+    Time(i64, i64),
+    /// Rows from this id onwards.
+    Id(i64),
+}
+
+type Fetch<'a, T> =
+    Box<dyn Fn(Page, usize) -> BoxFuture<'a, crate::error::Result<Vec<T>>> + Send + Sync + 'a>;
+
+/// Stream of rows walking a range page by page.
+///
+/// Requests are spaced by a delay and rate limited requests are retried after the
+/// time binance asks for, 3 times by default. A ban of the IP, used up retries and other
+/// errors are yielded; polling again retries the failed page.
+pub struct History<'a, T> {
+    fetch: Fetch<'a, T>,
+    range: HistoryRange,
+    /// Widest time window the endpoint accepts.
+    window: Option<i64>,
+    /// Whether the endpoint can continue from an id.
+    by_id: bool,
+    limit: usize,
+    delay: Duration,
+    max_retries: usize,
+    /// Resends of the current page.
+    retries: usize,
+    next: Option<Page>,
+    rows: VecDeque<T>,
+    pending: Option<BoxFuture<'a, crate::error::Result<Vec<T>>>>,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<'a, T: Cursor> History<'a, T> {
+    fn new(fetch: Fetch<'a, T>, range: HistoryRange, window: Option<i64>, by_id: bool) -> Self {
+        let mut history = Self {
+            fetch,
+            range,
+            window,
+            by_id,
+            limit: MAX_LIMIT,
+            delay: Duration::from_millis(250),
+            max_retries: 3,
+            retries: 0,
+            next: None,
+            rows: VecDeque::new(),
+            pending: None,
+            sleep: None,
+        };
+
+        history.next = match range {
+            HistoryRange::Ids(from, _) => Some(Page::Id(from)),
+            HistoryRange::Time(start, _) => Some(history.window_from(start)),
+        };
+        history
+    }
+    /// Rows per request; default and max 1000.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit.clamp(1, MAX_LIMIT);
+        self
+    }
+    /// Delay between requests; default 250ms.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+    /// Number of times a page refused for the rate limit is requested again; default 3.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    fn window_from(&self, start: i64) -> Page {
+        let end = match self.range {
+            HistoryRange::Time(_, end) => end,
+            HistoryRange::Ids(..) => i64::MAX,
+        };
+        let window_end = self
+            .window
+            .map_or(end, |window| start.saturating_add(window - 1).min(end));
+
+        Page::Time(start, window_end)
+    }
+
+    /// Keeps the rows in range and picks the page after them.
+    fn advance(&mut self, page: Page, rows: Vec<T>) -> Option<Page> {
+        let full = rows.len() >= self.limit;
+        let last = rows.last().map(|row| (row.id(), row.time()));
+        let range = self.range;
+        let past = rows.iter().any(|row| range.is_past(row.id(), row.time()));
+
+        self.rows.extend(
+            rows.into_iter()
+                .filter(|row| range.contains(row.id(), row.time())),
+        );
+
+        if past {
+            return None;
+        }
+
+        match (page, last) {
+            (Page::Id(_), Some((id, _))) if full => Some(Page::Id(id + 1)),
+            (Page::Id(_), _) => None,
+            (Page::Time(_, end), Some((id, time))) if full => {
+                if self.by_id {
+                    Some(Page::Id(id + 1))
+                } else {
+                    Some(Page::Time(time + 1, end))
+                }
+            }
+            (Page::Time(_, end), _) => match range {
+                HistoryRange::Time(_, until) if end < until => Some(self.window_from(end + 1)),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl<'a, T: Cursor + Unpin> Stream for History<'a, T> {
+    type Item = crate::error::Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(row) = this.rows.pop_front() {
+                return Poll::Ready(Some(Ok(row)));
+            }
+
+            if let Some(sleep) = this.sleep.as_mut() {
+                futures::ready!(sleep.as_mut().poll(cx));
+                this.sleep = None;
+            }
+
+            let page = match this.next {
+                Some(page) => page,
+                None => return Poll::Ready(None),
+            };
+
+            let pending = match this.pending.as_mut() {
+                Some(pending) => pending,
+                None => this.pending.insert((this.fetch)(page, this.limit)),
+            };
+
+            let result = futures::ready!(pending.as_mut().poll(cx));
+            this.pending = None;
+
+            match result {
+                Ok(rows) => {
+                    this.retries = 0;
+                    this.next = this.advance(page, rows);
+                    if this.next.is_some() {
+                        this.sleep = Some(Box::pin(time::sleep(this.delay)));
+                    }
+                }
+                Err(error) => {
+                    let retry_after =
+                        error.retry_after(this.retries, this.max_retries, DEFAULT_RETRY_AFTER);
+                    match retry_after {
+                        Some(retry_after) => {
+                            this.retries += 1;
+                            this.sleep = Some(Box::pin(time::sleep(retry_after)));
+                        }
+                        None => {
+                            this.retries = 0;
+                            return Poll::Ready(Some(Err(error)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn millis(time: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(time).unwrap()
+}
+
+impl MarketDataClient {
+    /// Walks older trades by id; the api key is required.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{MarketDataClient, Environment};
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
+    /// let mut trades = client.get_historical_trades_history("BNBUSDT", 28457, Some(100_000));
+    ///
+    /// while let Some(trade) = trades.try_next().await? {
+    ///     println!("{:?}", trade);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_historical_trades_history<'a>(
+        &'a self,
+        symbol: &'a str,
+        from_id: i64,
+        to_id: Option<i64>,
+    ) -> History<'a, Trade> {
+        let fetch = move |page, limit| {
+            let request = self.get_historical_trades(symbol).with_limit(limit);
+            let request = match page {
+                Page::Id(id) => request.with_from_id(id),
+                Page::Time(..) => request,
+            };
+            request.json::<Vec<Trade>>().boxed()
+        };
+
+        History::new(
+            Box::new(fetch),
+            HistoryRange::Ids(from_id, to_id),
+            None,
+            true,
+        )
+    }
+    /// Walks aggregate trades by id or time, in windows of less than an hour.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{MarketDataClient, Environment};
+    /// use tokio_binance::HistoryRange;
+    /// use chrono::{Utc, Duration};
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
+    /// let end = Utc::now();
+    /// let start = end - Duration::days(3);
+    ///
+    /// let trades: Vec<_> = client
+    ///     .get_aggregate_trades_history("BNBUSDT", HistoryRange::time(start, end))
+    ///     // optional: delay between requests; default 250ms.
+    ///     .with_delay(std::time::Duration::from_millis(100))
+    ///     .try_collect()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_aggregate_trades_history<'a>(
+        &'a self,
+        symbol: &'a str,
+        range: HistoryRange,
+    ) -> History<'a, AggTrade> {
+        let fetch = move |page, limit| {
+            let request = self.get_aggregate_trades(symbol).with_limit(limit);
+            let request = match page {
+                Page::Id(id) => request.with_from_id(id),
+                Page::Time(start, end) => request
+                    .with_start_time(millis(start))
+                    .with_end_time(millis(end)),
+            };
+            request.json::<Vec<AggTrade>>().boxed()
+        };
+
+        History::new(Box::new(fetch), range, Some(AGG_TRADES_WINDOW), true)
+    }
+    /// Walks klines by open time.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{MarketDataClient, Environment};
+    /// use tokio_binance::Interval;
+    /// use chrono::{Utc, Duration};
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = MarketDataClient::connect("<api-key>", Environment::BinanceUs)?;
+    /// let end = Utc::now();
+    /// let start = end - Duration::days(30);
+    ///
+    /// let klines: Vec<_> = client
+    ///     .get_candlestick_bars_history("BNBUSDT", Interval::OneMinute, start, end)
+    ///     .try_collect()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_candlestick_bars_history<'a, Tz: TimeZone>(
+        &'a self,
+        symbol: &'a str,
+        interval: Interval,
+        start: DateTime<Tz>,
+        end: DateTime<Tz>,
+    ) -> History<'a, Kline> {
+        let fetch = move |page, limit| {
+            let request = self
+                .get_candlestick_bars(symbol, interval)
+                .with_limit(limit);
+            let request = match page {
+                Page::Time(start, end) => request
+                    .with_start_time(millis(start))
+                    .with_end_time(millis(end)),
+                Page::Id(open_time) => request.with_start_time(millis(open_time)),
+            };
+            request.json::<Vec<Kline>>().boxed()
+        };
+
+        History::new(Box::new(fetch), HistoryRange::time(start, end), None, false)
+    }
+}
+
+impl AccountClient {
+    /// Walks all orders by order id or time, in windows of 24 hours.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::HistoryRange;
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let mut orders = client.get_all_orders_history("BNBUSDT", HistoryRange::ids(0, None));
+    ///
+    /// while let Some(order) = orders.try_next().await? {
+    ///     println!("{:?}", order);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_all_orders_history<'a>(
+        &'a self,
+        symbol: &'a str,
+        range: HistoryRange,
+    ) -> History<'a, Order> {
+        let fetch = move |page, limit| {
+            let request = self.get_all_orders(symbol).with_limit(limit);
+            let request = match page {
+                Page::Id(id) => request.with_order_id(id),
+                Page::Time(start, end) => request
+                    .with_start_time(millis(start))
+                    .with_end_time(millis(end)),
+            };
+            request.json::<Vec<Order>>().boxed()
+        };
+
+        History::new(Box::new(fetch), range, Some(ACCOUNT_WINDOW), true)
+    }
+    /// Walks the account trades by trade id or time, in windows of 24 hours.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{AccountClient, Environment};
+    /// use tokio_binance::HistoryRange;
+    /// use chrono::{Utc, Duration};
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+    /// let end = Utc::now();
+    /// let start = end - Duration::days(90);
+    ///
+    /// let trades: Vec<_> = client
+    ///     .get_account_trades_history("BNBUSDT", HistoryRange::time(start, end))
+    ///     .try_collect()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_account_trades_history<'a>(
+        &'a self,
+        symbol: &'a str,
+        range: HistoryRange,
+    ) -> History<'a, AccountTrade> {
+        let fetch = move |page, limit| {
+            let request = self.get_account_trades(symbol).with_limit(limit);
+            let request = match page {
+                Page::Id(id) => request.with_from_id(id),
+                Page::Time(start, end) => request
+                    .with_start_time(millis(start))
+                    .with_end_time(millis(end)),
+            };
+            request.json::<Vec<AccountTrade>>().boxed()
+        };
+
+        History::new(Box::new(fetch), range, Some(ACCOUNT_WINDOW), true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ClientError;
+    use futures::{future, TryStreamExt};
+    use std::sync::{Arc, Mutex};
+
+    /// Row with an id and a time.
+    #[derive(Debug)]
+    struct Row(i64, i64);
+
+    impl Cursor for Row {
+        fn id(&self) -> i64 {
+            self.0
+        }
+        fn time(&self) -> i64 {
+            self.1
+        }
+    }
+
+    /// Walks the rows like an endpoint returning them oldest first;
+    /// returns the ids walked and the pages requested.
+    async fn walk(
+        rows: &[(i64, i64)],
+        range: HistoryRange,
+        window: Option<i64>,
+        by_id: bool,
+        limit: usize,
+    ) -> (Vec<i64>, Vec<Page>) {
+        let pages = Arc::new(Mutex::new(Vec::new()));
+        let requested = pages.clone();
+        let fetch = move |page, limit| {
+            requested.lock().unwrap().push(page);
+            let page: Vec<_> = rows
+                .iter()
+                .filter(|&&(id, time)| match page {
+                    Page::Id(from) => id >= from,
+                    Page::Time(start, end) => time >= start && time <= end,
+                })
+                .take(limit)
+                .map(|&(id, time)| Row(id, time))
+                .collect();
+            future::ready(Ok(page)).boxed()
+        };
+
+        let history = History::new(Box::new(fetch), range, window, by_id)
+            .with_limit(limit)
+            .with_delay(Duration::from_millis(0));
+        let walked: Vec<_> = history.try_collect().await.unwrap();
+        let ids = walked.iter().map(|row| row.0).collect();
+        let pages = pages.lock().unwrap().clone();
+        (ids, pages)
+    }
+
+    /// One row every 10ms.
+    fn rows(ids: std::ops::Range<i64>) -> Vec<(i64, i64)> {
+        ids.map(|id| (id, id * 10)).collect()
+    }
+
+    #[tokio::test]
+    async fn ids_continue_after_the_last_row() {
+        let range = HistoryRange::ids(3, Some(20));
+        let (ids, pages) = walk(&rows(0..25), range, None, true, 5).await;
+
+        assert_eq!(ids, (3..=20).collect::<Vec<_>>());
+        // the page reaching past the range ends the walk
+        let expected = vec![Page::Id(3), Page::Id(8), Page::Id(13), Page::Id(18)];
+        assert_eq!(pages, expected);
+    }
+
+    #[tokio::test]
+    async fn a_short_page_ends_an_open_range() {
+        let range = HistoryRange::ids(0, None);
+        let (ids, pages) = walk(&rows(0..12), range, None, true, 5).await;
+
+        assert_eq!(ids, (0..12).collect::<Vec<_>>());
+        assert_eq!(pages, vec![Page::Id(0), Page::Id(5), Page::Id(10)]);
+    }
+
+    #[tokio::test]
+    async fn time_ranges_continue_by_id_when_the_endpoint_can() {
+        let range = HistoryRange::Time(25, 125);
+        let (ids, pages) = walk(&rows(0..40), range, Some(100), true, 5).await;
+
+        assert_eq!(ids, (3..=12).collect::<Vec<_>>());
+        let expected = vec![Page::Time(25, 124), Page::Id(8), Page::Id(13)];
+        assert_eq!(pages, expected);
+    }
+
+    #[tokio::test]
+    async fn time_windows_move_on_past_empty_windows() {
+        // nothing between 100 and 199
+        let mut table = rows(0..10);
+        table.extend(rows(20..30));
+        let range = HistoryRange::Time(0, 299);
+        let (ids, pages) = walk(&table, range, Some(100), false, 5).await;
+
+        let mut expected: Vec<_> = (0..10).collect();
+        expected.extend(20..30);
+        assert_eq!(ids, expected);
+        let expected = vec![
+            Page::Time(0, 99),
+            Page::Time(41, 99),
+            Page::Time(91, 99),
+            Page::Time(100, 199),
+            Page::Time(200, 299),
+            Page::Time(241, 299),
+            Page::Time(291, 299),
+        ];
+        assert_eq!(pages, expected);
+    }
+
+    #[tokio::test]
+    async fn the_last_window_ends_at_the_range() {
+        let range = HistoryRange::Time(0, 149);
+        let (ids, pages) = walk(&rows(0..20), range, Some(100), false, 20).await;
+
+        assert_eq!(ids, (0..15).collect::<Vec<_>>());
+        assert_eq!(pages, vec![Page::Time(0, 99), Page::Time(100, 149)]);
+    }
+
+    /// Refuses every page with the http status the first `failures` times, then returns a
+    /// single row; returns the fetch and the number of requests.
+    fn refusing(code: u16, failures: usize) -> (Fetch<'static, Row>, Arc<Mutex<usize>>) {
+        let sent = Arc::new(Mutex::new(0));
+        let requests = sent.clone();
+        let fetch = move |_, _| {
+            let mut requests = requests.lock().unwrap();
+            *requests += 1;
+            let response = match *requests <= failures {
+                true => {
+                    let message = r#"{"code":-1003,"msg":"Too many requests."}"#;
+                    let error = ClientError::new(code, "Too Many Requests", message)
+                        .with_retry_after(Some(Duration::from_secs(1)));
+                    Err(error.into())
+                }
+                false => Ok(vec![Row(0, 0)]),
+            };
+            future::ready(response).boxed()
+        };
+        (Box::new(fetch), sent)
+    }
+
+    fn code(error: &crate::error::Error) -> u16 {
+        let error = std::error::Error::source(error).unwrap();
+        error.downcast_ref::<ClientError>().unwrap().code()
+    }
+
+    #[tokio::test]
+    async fn rate_limited_pages_are_retried_a_few_times() {
+        time::pause();
+        let range = HistoryRange::ids(0, None);
+        let (fetch, sent) = refusing(429, 2);
+        let history = History::new(fetch, range, None, true);
+        let rows: Vec<_> = history.try_collect().await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(*sent.lock().unwrap(), 3);
+
+        let (fetch, sent) = refusing(429, 5);
+        let mut history = History::new(fetch, range, None, true).with_max_retries(2);
+        let error = history.try_next().await.unwrap_err();
+        assert_eq!(code(&error), 429);
+        assert_eq!(*sent.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn bans_are_yielded_at_once() {
+        time::pause();
+        let (fetch, sent) = refusing(418, 1);
+        let mut history = History::new(fetch, HistoryRange::ids(0, None), None, true);
+        let error = history.try_next().await.unwrap_err();
+        assert_eq!(code(&error), 418);
+        assert_eq!(*sent.lock().unwrap(), 1);
+
+        // polling again requests the page again
+        assert_eq!(history.try_next().await.unwrap().unwrap().0, 0);
+        assert_eq!(*sent.lock().unwrap(), 2);
+    }
+}
@@ -5,12 +5,12 @@
 //!
 //! ### Client
 //! ```no_run
-//! use tokio_binance::{AccountClient, BINANCE_US_URL, ID};
+//! use tokio_binance::{AccountClient, Environment, ID};
 //! use serde_json::Value;
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
-//!     let client = AccountClient::connect("<api-key>", "<secret-key>", BINANCE_US_URL)?;
+//!     let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
 //!     let response = client
 //!         .get_order("BNBUSDT", ID::ClientOId("<uuid>"))
 //!         // optional: processing time for request; default is 5000, can't be above 60000.
@@ -29,7 +29,7 @@
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
-//!     let client = UserDataClient::connect("<api-key>", BINANCE_US_URL)?;
+//!     let client = UserDataClient::connect("<api-key>", Environment::BinanceUs)?;
 //!     let value = client.start_stream().json::<Value>().await?;
 //!
 //!     let listen_key = value["listenKey"].as_str().unwrap();
@@ -46,7 +46,7 @@
 //!     });
 //!
 //!     let channel = Channel::UserData(listen_key);
-//!     let mut stream = WebSocketStream::connect(channel, BINANCE_US_WSS_URL).await?;
+//!     let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
 //!
 //!     while let Some(value) = stream.json::<Value>().await? {
 //!         if channel == value["stream"] {
@@ -57,13 +57,56 @@
 //! }
 //! ```
 
+#[cfg(feature = "archive")]
+mod archive;
+mod backtest;
+mod balance_book;
+mod bars;
+mod batch;
 pub mod builder;
+mod candle_series;
 mod client;
+mod environment;
 pub mod error;
+mod execution;
+mod export;
+mod history;
+#[cfg(test)]
+mod mock;
+pub mod model;
+mod order_manager;
+#[cfg(feature = "paper")]
+mod paper;
 mod param;
+mod risk;
+mod signer;
+mod strategy;
+mod stream_pool;
 pub mod types;
+mod watchdog;
+mod ws_api;
 mod ws_stream;
 
+#[cfg(feature = "archive")]
+pub use archive::*;
+pub use backtest::*;
+pub use balance_book::*;
+pub use bars::*;
+pub use batch::*;
+pub use candle_series::*;
 pub use client::*;
+pub use environment::*;
+pub use execution::*;
+pub use export::*;
+pub use history::*;
+pub use order_manager::*;
+#[cfg(feature = "paper")]
+pub use paper::*;
 pub use param::*;
+pub use risk::*;
+pub use signer::*;
+pub use strategy::*;
+pub use stream_pool::*;
+pub use watchdog::*;
+pub use ws_api::*;
 pub use ws_stream::*;
@@ -0,0 +1,145 @@
+//! Local servers standing in for binance in tests.
+use async_tungstenite::tokio::TokioAdapter;
+use async_tungstenite::tungstenite::Message;
+use futures::{Future, SinkExt, StreamExt};
+use hyper::service::service_fn;
+use hyper::{Body, HeaderMap, Request, Response, StatusCode};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+
+type Handler = Box<dyn FnMut(&Received) -> Response<Body> + Send>;
+
+/// A request received by the [`MockServer`].
+#[derive(Clone, Debug)]
+pub(crate) struct Received {
+    pub path: String,
+    /// Parameters of the query string and of the form body.
+    pub params: HashMap<String, String>,
+    pub headers: HeaderMap,
+}
+
+impl Received {
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params.get(key).map(String::as_str)
+    }
+}
+
+/// Http server answering each path with its handler and recording every request;
+/// other paths get a 404.
+pub(crate) struct MockServer {
+    url: String,
+    routes: Arc<Mutex<HashMap<String, Handler>>>,
+    requests: Arc<Mutex<Vec<Received>>>,
+}
+
+impl MockServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = Self {
+            url: format!("http://{}", listener.local_addr().unwrap()),
+            routes: Arc::default(),
+            requests: Arc::default(),
+        };
+
+        let (routes, requests) = (server.routes.clone(), server.requests.clone());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (routes, requests) = (routes.clone(), requests.clone());
+                let service = service_fn(move |req| {
+                    let (routes, requests) = (routes.clone(), requests.clone());
+                    async move {
+                        let received = received(req).await;
+                        requests.lock().unwrap().push(received.clone());
+                        let response = match routes.lock().unwrap().get_mut(&received.path) {
+                            Some(handler) => handler(&received),
+                            None => reply(StatusCode::NOT_FOUND, ""),
+                        };
+                        Ok::<_, Infallible>(response)
+                    }
+                });
+                tokio::spawn(hyper::server::conn::Http::new().serve_connection(stream, service));
+            }
+        });
+        server
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Answers requests to the path with the handler.
+    pub fn route<F>(&self, path: impl Into<String>, handler: F)
+    where
+        F: FnMut(&Received) -> Response<Body> + Send + 'static,
+    {
+        let handler = Box::new(handler);
+        self.routes.lock().unwrap().insert(path.into(), handler);
+    }
+
+    /// Requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<Received> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn received(req: Request<Body>) -> Received {
+    let (parts, body) = req.into_parts();
+    let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+    let query = parts.uri.query().unwrap_or_default();
+    let mut params: HashMap<String, String> = serde_urlencoded::from_str(query).unwrap_or_default();
+    if let Ok(form) = serde_urlencoded::from_bytes::<Vec<(String, String)>>(&body) {
+        params.extend(form);
+    }
+    Received {
+        path: parts.uri.path().to_string(),
+        params,
+        headers: parts.headers,
+    }
+}
+
+pub(crate) fn reply(status: StatusCode, body: impl Into<Body>) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(body.into())
+        .unwrap()
+}
+
+/// Server side of a websocket connection.
+pub(crate) type WsServer = async_tungstenite::WebSocketStream<TokioAdapter<TcpStream>>;
+
+/// Serves websocket connections, running the script on each; returns the url.
+pub(crate) async fn serve_ws<F, Fut>(script: F) -> String
+where
+    F: Fn(WsServer) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let script = Arc::new(script);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let script = script.clone();
+            tokio::spawn(async move {
+                if let Ok(ws) = async_tungstenite::tokio::accept_async(stream).await {
+                    script(ws).await;
+                }
+            });
+        }
+    });
+    format!("ws://{}", addr)
+}
+
+/// Next text message as json; none once the connection ends.
+pub(crate) async fn next_request(ws: &mut WsServer) -> Option<Value> {
+    match ws.next().await? {
+        Ok(Message::Text(text)) => Some(serde_json::from_str(&text).unwrap()),
+        _ => None,
+    }
+}
+
+pub(crate) async fn send(ws: &mut WsServer, value: Value) {
+    ws.send(Message::Text(value.to_string())).await.unwrap();
+}
@@ -0,0 +1,753 @@
+//! Typed models for market data; each deserializes from both the REST response and the stream event.
+use crate::error::ClientError;
+use crate::param::{OrderType, SelfTradePreventionMode, Side, TimeInForce};
+use crate::ws_stream::ChannelBuf;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Message of a combined stream.
+/// # Example
+///
+/// ```
+/// use tokio_binance::model::{AggTrade, StreamEvent};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let text = r#"{"stream":"bnbusdt@aggTrade","data":{"e":"aggTrade","E":1672515782136,"s":"BNBUSDT",
+///     "a":12345,"p":"0.001","q":"100","f":100,"l":105,"T":1672515782136,"m":true,"M":true}}"#;
+/// let event: StreamEvent<AggTrade> = serde_json::from_str(text)?;
+/// assert_eq!(event.stream, "bnbusdt@aggTrade");
+/// assert_eq!(event.data.price, 0.001);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+pub struct StreamEvent<T> {
+    pub stream: ChannelBuf,
+    pub data: T,
+}
+
+/// Trade from `get_trades`/`get_historical_trades` or the trade stream.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Trade {
+    #[serde(alias = "t")]
+    pub id: i64,
+    #[serde(alias = "p", deserialize_with = "from_str")]
+    pub price: f64,
+    #[serde(alias = "q", deserialize_with = "from_str")]
+    pub qty: f64,
+    #[serde(alias = "T")]
+    pub time: i64,
+    #[serde(rename = "isBuyerMaker", alias = "m")]
+    pub is_buyer_maker: bool,
+}
+
+/// Aggregate trade from `get_aggregate_trades` or the aggTrade stream.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct AggTrade {
+    #[serde(rename = "a")]
+    pub id: i64,
+    #[serde(rename = "p", deserialize_with = "from_str")]
+    pub price: f64,
+    #[serde(rename = "q", deserialize_with = "from_str")]
+    pub qty: f64,
+    #[serde(rename = "f")]
+    pub first_trade_id: i64,
+    #[serde(rename = "l")]
+    pub last_trade_id: i64,
+    #[serde(rename = "T")]
+    pub time: i64,
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+}
+
+/// Binance sends decimals as strings to keep their precision.
+pub(crate) fn from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let text = String::deserialize(deserializer)?;
+    text.parse().map_err(de::Error::custom)
+}
+
+/// Kline/candlestick from `get_candlestick_bars` or the kline stream.
+///
+/// Klines from the REST api are never marked as closed;
+/// the stream marks the last update of a kline as closed.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "RawKline")]
+pub struct Kline {
+    pub open_time: i64,
+    pub close_time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub quote_volume: f64,
+    pub trades: u64,
+    pub taker_buy_volume: f64,
+    pub taker_buy_quote_volume: f64,
+    pub is_closed: bool,
+}
+
+/// Payload of the kline stream.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct KlineEvent {
+    #[serde(rename = "E")]
+    pub event_time: i64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "k")]
+    pub kline: Kline,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawKline {
+    Rest(
+        i64,
+        String,
+        String,
+        String,
+        String,
+        String,
+        i64,
+        String,
+        u64,
+        String,
+        String,
+        de::IgnoredAny,
+    ),
+    Stream {
+        t: i64,
+        #[serde(rename = "T")]
+        close_time: i64,
+        o: String,
+        h: String,
+        l: String,
+        c: String,
+        v: String,
+        q: String,
+        n: u64,
+        #[serde(rename = "V")]
+        taker_buy_volume: String,
+        #[serde(rename = "Q")]
+        taker_buy_quote_volume: String,
+        x: bool,
+    },
+}
+
+impl TryFrom<RawKline> for Kline {
+    type Error = std::num::ParseFloatError;
+
+    fn try_from(raw: RawKline) -> Result<Self, Self::Error> {
+        Ok(match raw {
+            RawKline::Rest(t, o, h, l, c, v, close_time, q, n, buy_v, buy_q, _) => Kline {
+                open_time: t,
+                close_time,
+                open: o.parse()?,
+                high: h.parse()?,
+                low: l.parse()?,
+                close: c.parse()?,
+                volume: v.parse()?,
+                quote_volume: q.parse()?,
+                trades: n,
+                taker_buy_volume: buy_v.parse()?,
+                taker_buy_quote_volume: buy_q.parse()?,
+                is_closed: false,
+            },
+            RawKline::Stream {
+                t,
+                close_time,
+                o,
+                h,
+                l,
+                c,
+                v,
+                q,
+                n,
+                taker_buy_volume,
+                taker_buy_quote_volume,
+                x,
+            } => Kline {
+                open_time: t,
+                close_time,
+                open: o.parse()?,
+                high: h.parse()?,
+                low: l.parse()?,
+                close: c.parse()?,
+                volume: v.parse()?,
+                quote_volume: q.parse()?,
+                trades: n,
+                taker_buy_volume: taker_buy_volume.parse()?,
+                taker_buy_quote_volume: taker_buy_quote_volume.parse()?,
+                is_closed: x,
+            },
+        })
+    }
+}
+
+/// Status of an order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderStatus {
+    New,
+    PendingNew,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    PendingCancel,
+    Rejected,
+    Expired,
+    ExpiredInMatch,
+}
+
+/// Order from `get_order`, `get_open_orders` or `get_all_orders`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub symbol: String,
+    pub order_id: i64,
+    pub order_list_id: i64,
+    pub client_order_id: String,
+    #[serde(deserialize_with = "from_str")]
+    pub price: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub orig_qty: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub executed_qty: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub cummulative_quote_qty: f64,
+    pub status: OrderStatus,
+    pub time_in_force: TimeInForce,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    pub side: Side,
+    #[serde(deserialize_with = "from_str")]
+    pub stop_price: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub iceberg_qty: f64,
+    pub time: i64,
+    pub update_time: i64,
+    pub is_working: bool,
+}
+
+/// Trade of the account from `get_account_trades`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountTrade {
+    pub symbol: String,
+    pub id: i64,
+    pub order_id: i64,
+    pub order_list_id: i64,
+    #[serde(deserialize_with = "from_str")]
+    pub price: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub qty: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub quote_qty: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub commission: f64,
+    pub commission_asset: String,
+    pub time: i64,
+    pub is_buyer: bool,
+    pub is_maker: bool,
+}
+
+/// Best bid and ask from `get_order_book_ticker` or the bookTicker stream.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct BookTicker {
+    /// Only sent by the stream.
+    #[serde(rename = "u", default)]
+    pub update_id: Option<i64>,
+    #[serde(alias = "s")]
+    pub symbol: String,
+    #[serde(rename = "bidPrice", alias = "b", deserialize_with = "from_str")]
+    pub bid_price: f64,
+    #[serde(rename = "bidQty", alias = "B", deserialize_with = "from_str")]
+    pub bid_qty: f64,
+    #[serde(rename = "askPrice", alias = "a", deserialize_with = "from_str")]
+    pub ask_price: f64,
+    #[serde(rename = "askQty", alias = "A", deserialize_with = "from_str")]
+    pub ask_qty: f64,
+}
+
+/// Price and quantity of an order book level.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+pub struct PriceLevel(
+    #[serde(deserialize_with = "from_str")] pub f64,
+    #[serde(deserialize_with = "from_str")] pub f64,
+);
+
+/// Order book changes from the depth stream.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct DepthUpdate {
+    #[serde(rename = "E")]
+    pub event_time: i64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "U")]
+    pub first_update_id: i64,
+    #[serde(rename = "u")]
+    pub final_update_id: i64,
+    /// A quantity of zero removes the level.
+    #[serde(rename = "b")]
+    pub bids: Vec<PriceLevel>,
+    #[serde(rename = "a")]
+    pub asks: Vec<PriceLevel>,
+}
+
+/// Order book from `get_order_book` or the partial depth stream.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderBook {
+    pub last_update_id: i64,
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+}
+
+/// What happened to an order in an [`ExecutionReport`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ExecutionType {
+    New,
+    Canceled,
+    Replaced,
+    Rejected,
+    Trade,
+    Expired,
+    TradePrevention,
+}
+
+/// Order update from the user data stream.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ExecutionReport {
+    #[serde(rename = "E")]
+    pub event_time: i64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "c")]
+    pub client_order_id: String,
+    #[serde(rename = "S")]
+    pub side: Side,
+    #[serde(rename = "o")]
+    pub order_type: OrderType,
+    #[serde(rename = "f")]
+    pub time_in_force: TimeInForce,
+    #[serde(rename = "q", deserialize_with = "from_str")]
+    pub qty: f64,
+    #[serde(rename = "p", deserialize_with = "from_str")]
+    pub price: f64,
+    #[serde(rename = "P", deserialize_with = "from_str")]
+    pub stop_price: f64,
+    #[serde(rename = "g")]
+    pub order_list_id: i64,
+    /// Id of the canceled order for cancels, empty otherwise.
+    #[serde(rename = "C", default)]
+    pub orig_client_order_id: String,
+    #[serde(rename = "x")]
+    pub execution_type: ExecutionType,
+    #[serde(rename = "X")]
+    pub status: OrderStatus,
+    #[serde(rename = "r")]
+    pub reject_reason: String,
+    #[serde(rename = "i")]
+    pub order_id: i64,
+    #[serde(rename = "l", deserialize_with = "from_str")]
+    pub last_qty: f64,
+    #[serde(rename = "z", deserialize_with = "from_str")]
+    pub cumulative_qty: f64,
+    #[serde(rename = "L", deserialize_with = "from_str")]
+    pub last_price: f64,
+    #[serde(rename = "n", deserialize_with = "from_str")]
+    pub commission: f64,
+    #[serde(rename = "N")]
+    pub commission_asset: Option<String>,
+    #[serde(rename = "T")]
+    pub transaction_time: i64,
+    /// Id of the trade, -1 unless the report is a trade.
+    #[serde(rename = "t")]
+    pub trade_id: i64,
+    #[serde(rename = "w")]
+    pub is_working: bool,
+    #[serde(rename = "m")]
+    pub is_maker: bool,
+    #[serde(rename = "O")]
+    pub creation_time: i64,
+    #[serde(rename = "Z", deserialize_with = "from_str")]
+    pub cumulative_quote_qty: f64,
+    #[serde(rename = "Y", deserialize_with = "from_str")]
+    pub last_quote_qty: f64,
+}
+
+impl ExecutionReport {
+    /// Client id of the order the report is about; cancels carry it separately.
+    pub fn order_client_id(&self) -> &str {
+        match self.orig_client_order_id.as_str() {
+            "" => &self.client_order_id,
+            id => id,
+        }
+    }
+}
+
+/// Balance of an asset from `get_account` or an [`AccountPosition`] event.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct Balance {
+    #[serde(alias = "a")]
+    pub asset: String,
+    #[serde(alias = "f", deserialize_with = "from_str")]
+    pub free: f64,
+    #[serde(alias = "l", deserialize_with = "from_str")]
+    pub locked: f64,
+}
+
+/// Account from `get_account`, without the commission and permission details.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub can_trade: bool,
+    pub update_time: i64,
+    pub balances: Vec<Balance>,
+}
+
+/// Changed balances from the user data stream; `outboundAccountPosition`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct AccountPosition {
+    #[serde(rename = "E")]
+    pub event_time: i64,
+    #[serde(rename = "u")]
+    pub last_update_time: i64,
+    #[serde(rename = "B")]
+    pub balances: Vec<Balance>,
+}
+
+/// Deposit, withdrawal or transfer from the user data stream; `balanceUpdate`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct BalanceUpdate {
+    #[serde(rename = "E")]
+    pub event_time: i64,
+    #[serde(rename = "a")]
+    pub asset: String,
+    #[serde(rename = "d", deserialize_with = "from_str")]
+    pub delta: f64,
+    #[serde(rename = "T")]
+    pub clear_time: i64,
+}
+
+/// Error of a binance request, e.g. `{"code": -2010, "msg": "Account has insufficient balance."}`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ApiError {
+    pub code: i64,
+    pub msg: String,
+}
+
+impl ApiError {
+    /// The binance error of a failed request, if it was refused with one.
+    pub fn from_error(error: &crate::error::Error) -> Option<Self> {
+        let error = std::error::Error::source(error)?.downcast_ref::<ClientError>()?;
+        serde_json::from_str(error.message()).ok()
+    }
+    /// Whether the request was refused with the binance error code.
+    pub(crate) fn has_code(error: &crate::error::Error, code: i64) -> bool {
+        match Self::from_error(error) {
+            Some(error) => error.code == code,
+            None => false,
+        }
+    }
+}
+
+/// Outcome of one half of a cancel-replace.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelReplaceResult {
+    Success,
+    Failure,
+    NotAttempted,
+}
+
+/// Response of `cancel_replace_order`, including the partial failures binance reports as errors.
+/// # Example
+///
+/// ```
+/// use tokio_binance::model::{CancelReplace, CancelReplaceResult};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let text = r#"{"cancelResult":"SUCCESS","newOrderResult":"FAILURE",
+///     "cancelResponse":{"symbol":"BNBUSDT","orderId":12,"status":"CANCELED"},
+///     "newOrderResponse":{"code":-2010,"msg":"Order would immediately match and take."}}"#;
+/// let response: CancelReplace = serde_json::from_str(text)?;
+/// assert_eq!(response.new_order_result, CancelReplaceResult::Failure);
+/// assert_eq!(response.new_order_error().unwrap().code, -2010);
+/// assert!(response.cancel_error().is_none());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelReplace {
+    pub cancel_result: CancelReplaceResult,
+    pub new_order_result: CancelReplaceResult,
+    /// Canceled order, or the error of the cancel.
+    pub cancel_response: Value,
+    /// New order, the error of the new order, or null if not attempted.
+    #[serde(default)]
+    pub new_order_response: Value,
+}
+
+impl CancelReplace {
+    /// Whether both the cancel and the new order succeeded.
+    pub fn is_success(&self) -> bool {
+        self.cancel_result == CancelReplaceResult::Success
+            && self.new_order_result == CancelReplaceResult::Success
+    }
+    pub fn cancel_error(&self) -> Option<ApiError> {
+        match self.cancel_result {
+            CancelReplaceResult::Failure => {
+                serde_json::from_value(self.cancel_response.clone()).ok()
+            }
+            _ => None,
+        }
+    }
+    pub fn new_order_error(&self) -> Option<ApiError> {
+        match self.new_order_result {
+            CancelReplaceResult::Failure => {
+                serde_json::from_value(self.new_order_response.clone()).ok()
+            }
+            _ => None,
+        }
+    }
+    /// The partial failure carried by an error of `cancel_replace_order`, if any.
+    pub fn from_error(error: &crate::error::Error) -> Option<Self> {
+        let error = std::error::Error::source(error)?.downcast_ref::<ClientError>()?;
+        let value: Value = serde_json::from_str(error.message()).ok()?;
+        serde_json::from_value(value["data"].clone()).ok()
+    }
+}
+
+/// Commission rates as fractions, e.g. 0.001 for 0.1%.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct CommissionRates {
+    #[serde(deserialize_with = "from_str")]
+    pub maker: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub taker: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub buyer: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub seller: f64,
+}
+
+/// Discount on the standard commission when it is paid in the discount asset.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommissionDiscount {
+    pub enabled_for_account: bool,
+    pub enabled_for_symbol: bool,
+    pub discount_asset: String,
+    /// Fraction of the standard commission that is paid, e.g. 0.75.
+    #[serde(deserialize_with = "from_str")]
+    pub discount: f64,
+}
+
+/// Commission rates of a symbol from `get_commission`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountCommission {
+    pub symbol: String,
+    pub standard_commission: CommissionRates,
+    pub tax_commission: CommissionRates,
+    pub discount: CommissionDiscount,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RateLimitType {
+    RequestWeight,
+    Orders,
+    RawRequests,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RateLimitInterval {
+    Second,
+    Minute,
+    Hour,
+    Day,
+}
+
+/// Rate limit from `get_order_rate_limit`, with the usage of the current interval.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    pub rate_limit_type: RateLimitType,
+    pub interval: RateLimitInterval,
+    pub interval_num: i64,
+    pub limit: i64,
+    /// Only sent for the usage of the account.
+    #[serde(default)]
+    pub count: Option<i64>,
+}
+
+/// Order that expired for self-trade prevention, from `get_prevented_matches`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreventedMatch {
+    pub symbol: String,
+    pub prevented_match_id: i64,
+    pub taker_order_id: i64,
+    pub maker_order_id: i64,
+    pub trade_group_id: i64,
+    pub self_trade_prevention_mode: SelfTradePreventionMode,
+    #[serde(deserialize_with = "from_str")]
+    pub price: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub maker_prevented_quantity: f64,
+    pub transact_time: i64,
+}
+
+/// Fill of an order routed by the smart order router, from `get_allocations`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Allocation {
+    pub symbol: String,
+    pub allocation_id: i64,
+    pub allocation_type: String,
+    pub order_id: i64,
+    pub order_list_id: i64,
+    #[serde(deserialize_with = "from_str")]
+    pub price: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub qty: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub quote_qty: f64,
+    #[serde(deserialize_with = "from_str")]
+    pub commission: f64,
+    pub commission_asset: String,
+    pub time: i64,
+    pub is_buyer: bool,
+    pub is_maker: bool,
+    pub is_allocator: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // samples from the binance spot api documentation
+
+    #[test]
+    fn account_commission_deserializes() {
+        let text = r#"{
+            "symbol": "BTCUSDT",
+            "standardCommission": {
+                "maker": "0.00000010", "taker": "0.00000020",
+                "buyer": "0.00000030", "seller": "0.00000040"
+            },
+            "taxCommission": {
+                "maker": "0.00000112", "taker": "0.00000114",
+                "buyer": "0.00000118", "seller": "0.00000116"
+            },
+            "discount": {
+                "enabledForAccount": true, "enabledForSymbol": true,
+                "discountAsset": "BNB", "discount": "0.75000000"
+            }
+        }"#;
+        let commission: AccountCommission = serde_json::from_str(text).unwrap();
+        assert_eq!(commission.symbol, "BTCUSDT");
+        assert_eq!(commission.standard_commission.maker, 0.0000001);
+        assert_eq!(commission.standard_commission.seller, 0.0000004);
+        assert_eq!(commission.tax_commission.buyer, 0.00000118);
+        assert!(commission.discount.enabled_for_account);
+        assert_eq!(commission.discount.discount_asset, "BNB");
+        assert_eq!(commission.discount.discount, 0.75);
+    }
+
+    #[test]
+    fn rate_limits_deserialize() {
+        let text = r#"[
+            {"rateLimitType": "ORDERS", "interval": "SECOND", "intervalNum": 10,
+                "limit": 50, "count": 0},
+            {"rateLimitType": "ORDERS", "interval": "DAY", "intervalNum": 1,
+                "limit": 160000, "count": 0},
+            {"rateLimitType": "REQUEST_WEIGHT", "interval": "MINUTE", "intervalNum": 1,
+                "limit": 6000}
+        ]"#;
+        let limits: Vec<RateLimit> = serde_json::from_str(text).unwrap();
+        assert_eq!(
+            limits[0],
+            RateLimit {
+                rate_limit_type: RateLimitType::Orders,
+                interval: RateLimitInterval::Second,
+                interval_num: 10,
+                limit: 50,
+                count: Some(0),
+            }
+        );
+        assert_eq!(limits[1].interval, RateLimitInterval::Day);
+        assert_eq!(limits[1].limit, 160000);
+        // exchange info has no usage
+        assert_eq!(limits[2].rate_limit_type, RateLimitType::RequestWeight);
+        assert_eq!(limits[2].count, None);
+    }
+
+    #[test]
+    fn prevented_match_deserializes() {
+        let text = r#"{
+            "symbol": "BTCUSDT", "preventedMatchId": 1, "takerOrderId": 5,
+            "makerSymbol": "BTCUSDT", "makerOrderId": 3, "tradeGroupId": 1,
+            "selfTradePreventionMode": "EXPIRE_MAKER", "price": "1.100000",
+            "makerPreventedQuantity": "1.300000", "transactTime": 1669101687094
+        }"#;
+        let prevented: PreventedMatch = serde_json::from_str(text).unwrap();
+        assert_eq!(
+            prevented,
+            PreventedMatch {
+                symbol: "BTCUSDT".to_string(),
+                prevented_match_id: 1,
+                taker_order_id: 5,
+                maker_order_id: 3,
+                trade_group_id: 1,
+                self_trade_prevention_mode: SelfTradePreventionMode::ExpireMaker,
+                price: 1.1,
+                maker_prevented_quantity: 1.3,
+                transact_time: 1669101687094,
+            }
+        );
+    }
+
+    #[test]
+    fn allocation_deserializes() {
+        let text = r#"{
+            "symbol": "BTCUSDT", "allocationId": 0, "allocationType": "SOR",
+            "orderId": 1, "orderListId": -1, "price": "1.00000000", "qty": "5.00000000",
+            "quoteQty": "5.00000000", "commission": "0.00000000", "commissionAsset": "BTC",
+            "time": 1687506878118, "isBuyer": true, "isMaker": false, "isAllocator": false
+        }"#;
+        let allocation: Allocation = serde_json::from_str(text).unwrap();
+        assert_eq!(
+            allocation,
+            Allocation {
+                symbol: "BTCUSDT".to_string(),
+                allocation_id: 0,
+                allocation_type: "SOR".to_string(),
+                order_id: 1,
+                order_list_id: -1,
+                price: 1.0,
+                qty: 5.0,
+                quote_qty: 5.0,
+                commission: 0.0,
+                commission_asset: "BTC".to_string(),
+                time: 1687506878118,
+                is_buyer: true,
+                is_maker: false,
+                is_allocator: false,
+            }
+        );
+    }
+}
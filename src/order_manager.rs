@@ -0,0 +1,907 @@
+use crate::client::AccountClient;
+use crate::error::ClientError;
+use crate::model::{AccountTrade, ApiError, ExecutionReport, ExecutionType, Order, OrderStatus};
+use crate::param::{OrderRespType, OrderType, Side, ID};
+use crate::ws_stream::WebSocketStream;
+use chrono::Utc;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::error::Error as _;
+use std::sync::{Arc, Mutex};
+
+/// Quantities below this are treated as zero.
+const EPSILON: f64 = 1e-9;
+
+/// Binance error code of an unknown order.
+const NO_SUCH_ORDER: i64 = -2013;
+
+/// Order tracked by an [`OrderManager`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackedOrder {
+    pub symbol: String,
+    pub client_order_id: String,
+    /// Known once the exchange acknowledged the order.
+    pub order_id: Option<i64>,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub price: f64,
+    pub orig_qty: f64,
+    pub executed_qty: f64,
+    pub cummulative_quote_qty: f64,
+    /// `PendingNew` until the exchange acknowledged the order.
+    pub status: OrderStatus,
+    pub reject_reason: Option<String>,
+    pub update_time: i64,
+}
+
+impl TrackedOrder {
+    /// Whether the order can still change.
+    pub fn is_open(&self) -> bool {
+        rank(self.status) < rank(OrderStatus::Filled)
+    }
+}
+
+/// Trade of a tracked order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackedFill {
+    pub symbol: String,
+    pub client_order_id: String,
+    pub order_id: i64,
+    pub trade_id: i64,
+    pub price: f64,
+    pub qty: f64,
+    pub commission: f64,
+    pub commission_asset: String,
+    pub time: i64,
+    pub is_maker: bool,
+}
+
+/// Change of a tracked order, with the fill that caused it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderUpdate {
+    pub order: TrackedOrder,
+    pub fill: Option<TrackedFill>,
+}
+
+#[derive(Default)]
+struct State {
+    orders: HashMap<String, TrackedOrder>,
+    /// Symbol and trade id of every fill seen.
+    trades: HashSet<(String, i64)>,
+    fills: Vec<TrackedFill>,
+    /// Orders whose placement request has not been answered yet.
+    placing: HashSet<String>,
+    subscribers: Vec<UnboundedSender<OrderUpdate>>,
+    next_id: u64,
+}
+
+/// Keeps the state of the orders it places from REST responses and `executionReport`s.
+///
+/// Every order gets a client order id from the manager and moves only forward through
+/// `PENDING_NEW → NEW → PARTIALLY_FILLED → FILLED/CANCELED/EXPIRED/REJECTED`, so a late
+/// REST response can't undo a newer report. Fills are deduplicated by trade id.
+/// After a reconnect of the user data stream, [`reconcile`](Self::reconcile) catches up
+/// with what was missed.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{AccountClient, Channel, Environment, OrderManager, Side};
+/// use tokio_binance::{UserDataClient, WebSocketStream};
+/// use futures::StreamExt;
+/// use serde_json::Value;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+///     let manager = OrderManager::new(client);
+///     let mut updates = manager.updates();
+///
+///     let tracker = manager.clone();
+///     tokio::spawn(async move {
+///         let user_data = UserDataClient::connect("<api-key>", Environment::BinanceUs)?;
+///         loop {
+///             let value = user_data.start_stream().json::<Value>().await?;
+///             let channel = Channel::UserData(value["listenKey"].as_str().unwrap());
+///             let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
+///
+///             tracker.reconcile().await?;
+///             if let Err(error) = tracker.process(&mut stream).await {
+///                 eprintln!("{}", error);
+///             }
+///         }
+///         #[allow(unreachable_code)]
+///         Ok::<_, tokio_binance::error::Error>(())
+///     });
+///
+///     let order = manager.place_limit_order("BNBUSDT", Side::Buy, 300.0, 0.1).await?;
+///
+///     while let Some(update) = updates.next().await {
+///         println!("{:?} {:?}", update.order.status, update.fill);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct OrderManager {
+    client: AccountClient,
+    prefix: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl OrderManager {
+    pub fn new(client: AccountClient) -> Self {
+        Self {
+            client,
+            prefix: format!("om{}-", Utc::now().timestamp_millis()),
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+    /// Places a limit order, GTC; tracked from before the request is sent.
+    pub async fn place_limit_order(
+        &self,
+        symbol: &str,
+        side: Side,
+        price: f64,
+        quantity: f64,
+    ) -> crate::error::Result<TrackedOrder> {
+        let client_order_id = self.track(symbol, side, OrderType::Limit, price, quantity);
+        let response = self
+            .client
+            .place_limit_order(symbol, side, price, quantity, true)
+            .with_new_client_order_id(&client_order_id)
+            .with_new_order_resp_type(OrderRespType::Full)
+            .json::<Value>()
+            .await;
+
+        self.placed(&client_order_id, response)
+    }
+    /// Places a market order; tracked from before the request is sent.
+    pub async fn place_market_order(
+        &self,
+        symbol: &str,
+        side: Side,
+        quantity: f64,
+    ) -> crate::error::Result<TrackedOrder> {
+        let client_order_id = self.track(symbol, side, OrderType::Market, 0.0, quantity);
+        let response = self
+            .client
+            .place_market_order(symbol, side, quantity, true)
+            .with_new_client_order_id(&client_order_id)
+            .with_new_order_resp_type(OrderRespType::Full)
+            .json::<Value>()
+            .await;
+
+        self.placed(&client_order_id, response)
+    }
+    /// Cancels a tracked order; `None` if the manager does not know the id.
+    pub async fn cancel_order(
+        &self,
+        client_order_id: &str,
+    ) -> crate::error::Result<Option<TrackedOrder>> {
+        let symbol = match self.order(client_order_id) {
+            Some(order) => order.symbol,
+            None => return Ok(None),
+        };
+
+        let response = self
+            .client
+            .cancel_order(&symbol, ID::ClientOId(client_order_id))
+            .json::<Value>()
+            .await?;
+
+        self.apply_response(&response);
+        Ok(self.order(client_order_id))
+    }
+    /// Applies an execution report; returns false for orders of others and duplicates.
+    pub fn apply_report(&self, report: &ExecutionReport) -> bool {
+        let fill = match report.execution_type {
+            ExecutionType::Trade => Some(TrackedFill {
+                symbol: report.symbol.clone(),
+                client_order_id: report.order_client_id().to_string(),
+                order_id: report.order_id,
+                trade_id: report.trade_id,
+                price: report.last_price,
+                qty: report.last_qty,
+                commission: report.commission,
+                commission_asset: report.commission_asset.clone().unwrap_or_default(),
+                time: report.transaction_time,
+                is_maker: report.is_maker,
+            }),
+            _ => None,
+        };
+        let reject_reason = match report.status {
+            OrderStatus::Rejected => Some(report.reject_reason.clone()),
+            _ => None,
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let changed = state.apply(
+            report.order_client_id(),
+            Some(report.order_id),
+            report.status,
+            report.cumulative_qty,
+            report.cumulative_quote_qty,
+            report.transaction_time,
+            reject_reason,
+        );
+        let fill = fill.and_then(|fill| state.add_fill(fill));
+        state.notify(report.order_client_id(), changed, fill)
+    }
+    /// Applies the execution reports of a user data stream until it ends.
+    pub async fn process(&self, stream: &mut WebSocketStream) -> crate::error::Result<()> {
+        while let Some(event) = stream.stream_event::<Value>().await? {
+            if event.data["e"] == "executionReport" {
+                let report: ExecutionReport = serde_json::from_value(event.data)?;
+                self.apply_report(&report);
+            }
+        }
+        Ok(())
+    }
+    /// Catches up with the exchange, e.g. after the user data stream reconnected.
+    ///
+    /// Open orders come from `get_open_orders`, the ones that closed meanwhile from
+    /// `get_order` and their missed fills from `get_account_trades`. Orders the exchange
+    /// never received are marked rejected; orders whose placement is still in flight are
+    /// left to its response.
+    pub async fn reconcile(&self) -> crate::error::Result<()> {
+        let open = self.client.get_open_orders().json::<Vec<Order>>().await?;
+        for order in &open {
+            self.apply_order(order);
+        }
+
+        for tracked in self.open_orders() {
+            if self.placing(&tracked.client_order_id)
+                || open
+                    .iter()
+                    .any(|order| order.client_order_id == tracked.client_order_id)
+            {
+                continue;
+            }
+
+            let response = self
+                .client
+                .get_order(&tracked.symbol, ID::ClientOId(&tracked.client_order_id))
+                .json::<Order>()
+                .await;
+
+            match response {
+                Ok(order) => self.apply_order(&order),
+                Err(error) if ApiError::has_code(&error, NO_SUCH_ORDER) => {
+                    let mut state = self.state.lock().unwrap();
+                    let changed = state.apply(
+                        &tracked.client_order_id,
+                        None,
+                        OrderStatus::Rejected,
+                        tracked.executed_qty,
+                        tracked.cummulative_quote_qty,
+                        Utc::now().timestamp_millis(),
+                        Some("Order does not exist.".into()),
+                    );
+                    state.notify(&tracked.client_order_id, changed, None);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        for (order, filled) in self.unfilled() {
+            let order_id = match order.order_id {
+                Some(order_id) if order.executed_qty > filled + EPSILON => order_id,
+                _ => continue,
+            };
+
+            let trades = self
+                .client
+                .get_account_trades(&order.symbol)
+                .with_order_id(order_id)
+                .json::<Vec<AccountTrade>>()
+                .await?;
+
+            let mut state = self.state.lock().unwrap();
+            for trade in trades {
+                let fill = state.add_fill(TrackedFill {
+                    symbol: trade.symbol,
+                    client_order_id: order.client_order_id.clone(),
+                    order_id,
+                    trade_id: trade.id,
+                    price: trade.price,
+                    qty: trade.qty,
+                    commission: trade.commission,
+                    commission_asset: trade.commission_asset,
+                    time: trade.time,
+                    is_maker: trade.is_maker,
+                });
+                state.notify(&order.client_order_id, false, fill);
+            }
+        }
+        Ok(())
+    }
+    pub fn order(&self, client_order_id: &str) -> Option<TrackedOrder> {
+        self.state
+            .lock()
+            .unwrap()
+            .orders
+            .get(client_order_id)
+            .cloned()
+    }
+    /// Orders that can still change, oldest first.
+    pub fn open_orders(&self) -> Vec<TrackedOrder> {
+        let state = self.state.lock().unwrap();
+        let mut orders: Vec<_> = state
+            .orders
+            .values()
+            .filter(|order| order.is_open())
+            .cloned()
+            .collect();
+        orders.sort_by_key(|order| order.update_time);
+        orders
+    }
+    /// All fills in the order they were seen.
+    pub fn fills(&self) -> Vec<TrackedFill> {
+        self.state.lock().unwrap().fills.clone()
+    }
+    /// Fills of a tracked order.
+    pub fn fills_of(&self, client_order_id: &str) -> Vec<TrackedFill> {
+        let state = self.state.lock().unwrap();
+        state
+            .fills
+            .iter()
+            .filter(|fill| fill.client_order_id == client_order_id)
+            .cloned()
+            .collect()
+    }
+    /// Every change of a tracked order from now on.
+    pub fn updates(&self) -> UnboundedReceiver<OrderUpdate> {
+        let (tx, rx) = mpsc::unbounded();
+        self.state.lock().unwrap().subscribers.push(tx);
+        rx
+    }
+
+    fn track(
+        &self,
+        symbol: &str,
+        side: Side,
+        order_type: OrderType,
+        price: f64,
+        quantity: f64,
+    ) -> String {
+        let mut state = self.state.lock().unwrap();
+        state.next_id += 1;
+        let client_order_id = format!("{}{}", self.prefix, state.next_id);
+
+        state.orders.insert(
+            client_order_id.clone(),
+            TrackedOrder {
+                symbol: symbol.to_uppercase(),
+                client_order_id: client_order_id.clone(),
+                order_id: None,
+                side,
+                order_type,
+                price,
+                orig_qty: quantity,
+                executed_qty: 0.0,
+                cummulative_quote_qty: 0.0,
+                status: OrderStatus::PendingNew,
+                reject_reason: None,
+                update_time: Utc::now().timestamp_millis(),
+            },
+        );
+        state.placing.insert(client_order_id.clone());
+        state.notify(&client_order_id, true, None);
+        client_order_id
+    }
+
+    /// Applies the response of a new order; orders the exchange refused are rejected,
+    /// the outcome of other failures is left to [`reconcile`](Self::reconcile).
+    fn placed(
+        &self,
+        client_order_id: &str,
+        response: crate::error::Result<Value>,
+    ) -> crate::error::Result<TrackedOrder> {
+        self.state.lock().unwrap().placing.remove(client_order_id);
+        match response {
+            Ok(response) => {
+                self.apply_response(&response);
+                Ok(self.order(client_order_id).expect("tracked order"))
+            }
+            Err(error) => {
+                let refused = error
+                    .source()
+                    .and_then(|source| source.downcast_ref::<ClientError>())
+                    .filter(|error| error.code() < 500);
+
+                if let Some(refused) = refused {
+                    let reason = match serde_json::from_str::<Value>(refused.message()) {
+                        Ok(value) => value["msg"].as_str().unwrap_or_default().to_string(),
+                        Err(_) => refused.message().to_string(),
+                    };
+                    let mut state = self.state.lock().unwrap();
+                    let changed = state.apply(
+                        client_order_id,
+                        None,
+                        OrderStatus::Rejected,
+                        0.0,
+                        0.0,
+                        Utc::now().timestamp_millis(),
+                        Some(reason),
+                    );
+                    state.notify(client_order_id, changed, None);
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Applies a new order or cancel response, with the fills of a `FULL` response.
+    fn apply_response(&self, response: &Value) {
+        let client_order_id = match response["origClientOrderId"].as_str() {
+            Some(id) => id,
+            None => response["clientOrderId"].as_str().unwrap_or_default(),
+        };
+        let status = match serde_json::from_value::<OrderStatus>(response["status"].clone()) {
+            Ok(status) => status,
+            Err(_) => OrderStatus::New,
+        };
+        let decimal = |value: &Value| -> f64 {
+            value
+                .as_str()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default()
+        };
+        let order_id = response["orderId"].as_i64();
+        let time = response["transactTime"]
+            .as_i64()
+            .unwrap_or_else(|| Utc::now().timestamp_millis());
+
+        let mut state = self.state.lock().unwrap();
+        let symbol = match state.orders.get(client_order_id) {
+            Some(order) => order.symbol.clone(),
+            None => return,
+        };
+        let mut changed = state.apply(
+            client_order_id,
+            order_id,
+            status,
+            decimal(&response["executedQty"]),
+            decimal(&response["cummulativeQuoteQty"]),
+            time,
+            None,
+        );
+
+        let fills = response["fills"].as_array().cloned().unwrap_or_default();
+        for fill in fills {
+            let fill = state.add_fill(TrackedFill {
+                symbol: symbol.clone(),
+                client_order_id: client_order_id.to_string(),
+                order_id: order_id.unwrap_or_default(),
+                trade_id: fill["tradeId"].as_i64().unwrap_or_default(),
+                price: decimal(&fill["price"]),
+                qty: decimal(&fill["qty"]),
+                commission: decimal(&fill["commission"]),
+                commission_asset: fill["commissionAsset"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                time,
+                // fills of a new order took liquidity
+                is_maker: false,
+            });
+            if fill.is_some() {
+                state.notify(client_order_id, changed, fill);
+                changed = false;
+            }
+        }
+        state.notify(client_order_id, changed, None);
+    }
+
+    fn apply_order(&self, order: &Order) {
+        let mut state = self.state.lock().unwrap();
+        let changed = state.apply(
+            &order.client_order_id,
+            Some(order.order_id),
+            order.status,
+            order.executed_qty,
+            order.cummulative_quote_qty,
+            order.update_time,
+            None,
+        );
+        state.notify(&order.client_order_id, changed, None);
+    }
+
+    fn placing(&self, client_order_id: &str) -> bool {
+        self.state.lock().unwrap().placing.contains(client_order_id)
+    }
+
+    /// Tracked orders with the quantity of their known fills.
+    fn unfilled(&self) -> Vec<(TrackedOrder, f64)> {
+        let state = self.state.lock().unwrap();
+        state
+            .orders
+            .values()
+            .map(|order| {
+                let filled = state
+                    .fills
+                    .iter()
+                    .filter(|fill| fill.client_order_id == order.client_order_id)
+                    .map(|fill| fill.qty)
+                    .sum::<f64>();
+                (order.clone(), filled)
+            })
+            .filter(|(order, filled)| order.executed_qty > filled + EPSILON)
+            .collect()
+    }
+}
+
+impl State {
+    /// Moves the order forward; returns whether it changed.
+    #[allow(clippy::too_many_arguments)]
+    fn apply(
+        &mut self,
+        client_order_id: &str,
+        order_id: Option<i64>,
+        status: OrderStatus,
+        executed_qty: f64,
+        cummulative_quote_qty: f64,
+        time: i64,
+        reject_reason: Option<String>,
+    ) -> bool {
+        let order = match self.orders.get_mut(client_order_id) {
+            Some(order) => order,
+            None => return false,
+        };
+
+        let mut changed = false;
+        if order.order_id.is_none() && order_id.is_some() {
+            order.order_id = order_id;
+            changed = true;
+        }
+        if executed_qty > order.executed_qty + EPSILON {
+            order.executed_qty = executed_qty;
+            order.cummulative_quote_qty = cummulative_quote_qty;
+            changed = true;
+        }
+        if rank(status) > rank(order.status) {
+            order.status = status;
+            if status == OrderStatus::Rejected {
+                order.reject_reason = reject_reason;
+            }
+            changed = true;
+        }
+        if changed {
+            order.update_time = order.update_time.max(time);
+        }
+        changed
+    }
+
+    /// Records the fill unless it was seen before.
+    fn add_fill(&mut self, fill: TrackedFill) -> Option<TrackedFill> {
+        if !self.orders.contains_key(&fill.client_order_id)
+            || !self.trades.insert((fill.symbol.clone(), fill.trade_id))
+        {
+            return None;
+        }
+        self.fills.push(fill.clone());
+        Some(fill)
+    }
+
+    /// Sends the update if anything changed; returns whether it did.
+    fn notify(&mut self, client_order_id: &str, changed: bool, fill: Option<TrackedFill>) -> bool {
+        if !changed && fill.is_none() {
+            return false;
+        }
+        let order = match self.orders.get(client_order_id) {
+            Some(order) => order.clone(),
+            None => return false,
+        };
+
+        let update = OrderUpdate { order, fill };
+        self.subscribers
+            .retain(|tx| tx.unbounded_send(update.clone()).is_ok());
+        true
+    }
+}
+
+/// Position of the status in the lifecycle; orders never move back.
+fn rank(status: OrderStatus) -> u8 {
+    match status {
+        OrderStatus::PendingNew => 0,
+        OrderStatus::New => 1,
+        OrderStatus::PartiallyFilled | OrderStatus::PendingCancel => 2,
+        OrderStatus::Filled
+        | OrderStatus::Canceled
+        | OrderStatus::Rejected
+        | OrderStatus::Expired
+        | OrderStatus::ExpiredInMatch => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "paper")]
+    use crate::model::Trade;
+    #[cfg(feature = "paper")]
+    use crate::PaperExchange;
+    use serde_json::json;
+
+    /// Manager whose client is never used.
+    fn manager() -> OrderManager {
+        let client = AccountClient::connect("<api-key>", "<secret-key>", "http://127.0.0.1:1");
+        OrderManager::new(client.unwrap())
+    }
+
+    /// Report of a limit buy of 3 at 10; trades fill `last_qty` with the trade id.
+    fn report(
+        client_order_id: &str,
+        execution_type: &str,
+        status: &str,
+        cumulative_qty: f64,
+        trade: Option<(i64, f64)>,
+    ) -> ExecutionReport {
+        let (trade_id, last_qty) = trade.unwrap_or((-1, 0.0));
+        serde_json::from_value(json!({
+            "e": "executionReport",
+            "E": 1000,
+            "s": "BNBUSDT",
+            "c": client_order_id,
+            "S": "BUY",
+            "o": "LIMIT",
+            "f": "GTC",
+            "q": "3",
+            "p": "10",
+            "P": "0",
+            "g": -1,
+            "C": "",
+            "x": execution_type,
+            "X": status,
+            "r": "NONE",
+            "i": 7,
+            "l": last_qty.to_string(),
+            "z": cumulative_qty.to_string(),
+            "L": "10",
+            "n": "0",
+            "N": null,
+            "T": 1000,
+            "t": trade_id,
+            "w": true,
+            "m": true,
+            "O": 900,
+            "Z": (cumulative_qty * 10.0).to_string(),
+            "Y": (last_qty * 10.0).to_string(),
+        }))
+        .unwrap()
+    }
+
+    /// FULL response of the new order with the trade ids, a unit each.
+    fn response(client_order_id: &str, status: &str, executed_qty: f64, trades: &[i64]) -> Value {
+        let fills: Vec<_> = trades
+            .iter()
+            .map(|trade_id| {
+                json!({
+                    "price": "10",
+                    "qty": "1",
+                    "commission": "0",
+                    "commissionAsset": "BNB",
+                    "tradeId": trade_id,
+                })
+            })
+            .collect();
+        json!({
+            "symbol": "BNBUSDT",
+            "orderId": 7,
+            "clientOrderId": client_order_id,
+            "transactTime": 900,
+            "executedQty": executed_qty.to_string(),
+            "cummulativeQuoteQty": (executed_qty * 10.0).to_string(),
+            "status": status,
+            "fills": fills,
+        })
+    }
+
+    fn track(manager: &OrderManager) -> String {
+        manager.track("BNBUSDT", Side::Buy, OrderType::Limit, 10.0, 3.0)
+    }
+
+    #[test]
+    fn orders_only_move_forward() {
+        let manager = manager();
+        let id = track(&manager);
+        let mut updates = manager.updates();
+
+        assert!(manager.apply_report(&report(
+            &id,
+            "TRADE",
+            "PARTIALLY_FILLED",
+            1.0,
+            Some((1, 1.0))
+        )));
+        // the NEW report arrives after the first trade
+        assert!(!manager.apply_report(&report(&id, "NEW", "NEW", 0.0, None)));
+        let order = manager.order(&id).unwrap();
+        assert_eq!(order.status, OrderStatus::PartiallyFilled);
+        assert_eq!(order.executed_qty, 1.0);
+
+        assert!(manager.apply_report(&report(&id, "TRADE", "FILLED", 3.0, Some((2, 2.0)))));
+        assert!(!manager.apply_report(&report(&id, "CANCELED", "CANCELED", 1.0, None)));
+        let order = manager.order(&id).unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.executed_qty, 3.0);
+        assert_eq!(order.cummulative_quote_qty, 30.0);
+        assert!(manager.open_orders().is_empty());
+
+        let statuses: Vec<_> = std::iter::from_fn(|| updates.try_next().ok().flatten())
+            .map(|update| update.order.status)
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![OrderStatus::PartiallyFilled, OrderStatus::Filled]
+        );
+    }
+
+    #[test]
+    fn fills_are_kept_once_per_trade_id() {
+        let manager = manager();
+        let id = track(&manager);
+
+        let trade = report(&id, "TRADE", "PARTIALLY_FILLED", 1.0, Some((1, 1.0)));
+        assert!(manager.apply_report(&trade));
+        assert!(!manager.apply_report(&trade));
+
+        // the response lists the trade seen before and a new one
+        manager.apply_response(&response(&id, "PARTIALLY_FILLED", 2.0, &[1, 2]));
+        let trades: Vec<_> = manager
+            .fills_of(&id)
+            .iter()
+            .map(|fill| fill.trade_id)
+            .collect();
+        assert_eq!(trades, vec![1, 2]);
+        assert_eq!(manager.order(&id).unwrap().executed_qty, 2.0);
+
+        // reports of unknown orders are ignored
+        assert!(!manager.apply_report(&report("other", "TRADE", "FILLED", 1.0, Some((3, 1.0)))));
+        assert_eq!(manager.fills().len(), 2);
+    }
+
+    #[test]
+    fn late_responses_keep_the_reported_state() {
+        let manager = manager();
+        let id = track(&manager);
+        manager.apply_report(&report(&id, "NEW", "NEW", 0.0, None));
+        manager.apply_report(&report(&id, "TRADE", "FILLED", 3.0, Some((1, 3.0))));
+        let reported = manager.order(&id).unwrap();
+        let mut updates = manager.updates();
+
+        // an ACK-like response of the same order arrives last
+        manager.apply_response(&response(&id, "NEW", 0.0, &[]));
+        manager.apply_response(&response(&id, "FILLED", 3.0, &[1]));
+        assert_eq!(manager.order(&id).unwrap(), reported);
+        assert_eq!(manager.fills().len(), 1);
+        assert!(updates.try_next().is_err());
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn refused_orders_are_rejected() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 10.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let refusing = OrderManager::new(client);
+        let mut updates = refusing.updates();
+
+        let error = refusing
+            .place_limit_order("BNBUSDT", Side::Buy, 10.0, 3.0)
+            .await
+            .unwrap_err();
+        assert_eq!(ApiError::from_error(&error).unwrap().code, -2010);
+        let pending = updates.try_next().unwrap().unwrap().order;
+        assert_eq!(pending.status, OrderStatus::PendingNew);
+        let order = refusing.order(&pending.client_order_id).unwrap();
+        assert_eq!(order.status, OrderStatus::Rejected);
+        assert_eq!(
+            order.reject_reason.as_deref(),
+            Some("Account has insufficient balance for requested action.")
+        );
+        assert!(refusing.open_orders().is_empty());
+
+        // failures without an answer leave the order to reconcile
+        let unreachable = manager();
+        assert!(unreachable
+            .place_limit_order("BNBUSDT", Side::Buy, 10.0, 3.0)
+            .await
+            .is_err());
+        let pending = unreachable.open_orders();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].status, OrderStatus::PendingNew);
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn reconcile_catches_up_with_the_exchange() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 1000.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let manager = OrderManager::new(client);
+
+        let filled = manager
+            .place_limit_order("BNBUSDT", Side::Buy, 10.0, 2.0)
+            .await
+            .unwrap();
+        let resting = manager
+            .place_limit_order("BNBUSDT", Side::Buy, 5.0, 1.0)
+            .await
+            .unwrap();
+        // tracked, but the request failed without reaching the exchange
+        let lost = track(&manager);
+        manager.state.lock().unwrap().placing.remove(&lost);
+
+        // the fill is missed by the manager
+        let trade = Trade {
+            id: 1,
+            price: 9.0,
+            qty: 5.0,
+            time: 0,
+            is_buyer_maker: true,
+        };
+        paper.push_trade("BNBUSDT", &trade);
+        assert_eq!(
+            manager.order(&filled.client_order_id).unwrap().status,
+            OrderStatus::New
+        );
+
+        manager.reconcile().await.unwrap();
+        let order = manager.order(&filled.client_order_id).unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.executed_qty, 2.0);
+        let fills = manager.fills_of(&filled.client_order_id);
+        assert_eq!(fills.iter().map(|fill| fill.qty).sum::<f64>(), 2.0);
+
+        assert_eq!(
+            manager.order(&resting.client_order_id).unwrap().status,
+            OrderStatus::New
+        );
+        let order = manager.order(&lost).unwrap();
+        assert_eq!(order.status, OrderStatus::Rejected);
+        assert_eq!(
+            order.reject_reason.as_deref(),
+            Some("Order does not exist.")
+        );
+
+        // nothing is applied twice
+        let fills = manager.fills().len();
+        manager.reconcile().await.unwrap();
+        assert_eq!(manager.fills().len(), fills);
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn reconcile_leaves_placements_in_flight_alone() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 1000.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let manager = OrderManager::new(client);
+
+        // the request is sent, its response has not arrived
+        let pending = track(&manager);
+        manager.reconcile().await.unwrap();
+        assert_eq!(
+            manager.order(&pending).unwrap().status,
+            OrderStatus::PendingNew
+        );
+        manager.apply_response(&response(&pending, "NEW", 0.0, &[]));
+        assert_eq!(manager.order(&pending).unwrap().status, OrderStatus::New);
+
+        for _ in 0..20 {
+            let (placed, reconciled) = tokio::join!(
+                manager.place_limit_order("BNBUSDT", Side::Buy, 5.0, 1.0),
+                manager.reconcile(),
+            );
+            reconciled.unwrap();
+            let order = manager.order(&placed.unwrap().client_order_id).unwrap();
+            assert_eq!(order.status, OrderStatus::New);
+            assert!(order.is_open());
+        }
+    }
+}
@@ -0,0 +1,2016 @@
+use crate::model::{OrderStatus, PriceLevel};
+use crate::param::{OrderRespType, OrderType, Side, TimeInForce};
+use chrono::Utc;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Quantities below this are treated as zero.
+const EPSILON: f64 = 1e-9;
+
+/// Error reply, with the code and message binance uses for the same case.
+#[derive(Debug)]
+pub(super) struct Reject {
+    pub(super) code: i64,
+    pub(super) msg: String,
+    /// Details of partial failures, e.g. of a cancel-replace.
+    pub(super) data: Option<Value>,
+}
+
+impl Reject {
+    pub(super) fn new<T: Into<String>>(code: i64, msg: T) -> Self {
+        Self {
+            code,
+            msg: msg.into(),
+            data: None,
+        }
+    }
+
+    pub(super) fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    fn insufficient_balance() -> Self {
+        Self::new(
+            -2010,
+            "Account has insufficient balance for requested action.",
+        )
+    }
+
+    fn unsupported_legs() -> Self {
+        Self::new(
+            -1000,
+            "Only a LIMIT_MAKER and a STOP_LOSS or STOP_LOSS_LIMIT leg are supported by the paper exchange.",
+        )
+    }
+
+    fn mandatory(param: &str) -> Self {
+        Self::new(
+            -1102,
+            format!(
+                "Mandatory parameter '{}' was not sent, was empty/null, or malformed.",
+                param
+            ),
+        )
+    }
+}
+
+/// Parameters of `POST /api/v3/order`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct OrderParams {
+    pub(super) symbol: String,
+    pub(super) side: Side,
+    #[serde(rename = "type")]
+    pub(super) order_type: OrderType,
+    pub(super) time_in_force: Option<TimeInForce>,
+    pub(super) quantity: Option<f64>,
+    /// Amount of the quote asset of market orders sent without a quantity.
+    pub(super) quote_order_qty: Option<f64>,
+    pub(super) price: Option<f64>,
+    pub(super) stop_price: Option<f64>,
+    /// Visible quantity of an iceberg order; the whole quantity matches as usual.
+    pub(super) iceberg_qty: Option<f64>,
+    pub(super) new_client_order_id: Option<String>,
+    pub(super) new_order_resp_type: Option<OrderRespType>,
+}
+
+/// Parameters of `POST /api/v3/order/oco`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct OcoParams {
+    pub(super) symbol: String,
+    pub(super) side: Side,
+    pub(super) quantity: f64,
+    pub(super) price: f64,
+    pub(super) stop_price: f64,
+    pub(super) stop_limit_price: Option<f64>,
+    pub(super) stop_limit_time_in_force: Option<TimeInForce>,
+    pub(super) list_client_order_id: Option<String>,
+    pub(super) limit_client_order_id: Option<String>,
+    pub(super) stop_client_order_id: Option<String>,
+}
+
+struct Market {
+    base: String,
+    quote: String,
+    bids: Vec<PriceLevel>,
+    asks: Vec<PriceLevel>,
+    last_price: Option<f64>,
+    traded: bool,
+    /// Trades fed to the exchange, served as aggregate trades.
+    tape: Vec<MarketTrade>,
+}
+
+struct MarketTrade {
+    id: i64,
+    price: f64,
+    qty: f64,
+    time: i64,
+}
+
+#[derive(Default)]
+struct Balance {
+    free: f64,
+    locked: f64,
+}
+
+struct PaperOrder {
+    symbol: String,
+    order_id: i64,
+    order_list_id: i64,
+    client_order_id: String,
+    price: f64,
+    orig_qty: f64,
+    executed_qty: f64,
+    cummulative_quote_qty: f64,
+    status: OrderStatus,
+    time_in_force: TimeInForce,
+    order_type: OrderType,
+    side: Side,
+    stop_price: f64,
+    iceberg_qty: f64,
+    time: i64,
+    update_time: i64,
+    is_working: bool,
+    /// Funds still reserved for the order, in the asset it pays with.
+    locked: f64,
+}
+
+impl PaperOrder {
+    fn is_open(&self) -> bool {
+        matches!(self.status, OrderStatus::New | OrderStatus::PartiallyFilled)
+    }
+
+    /// Whether the order ended; pending orders of an OTO list have not started yet.
+    fn is_done(&self) -> bool {
+        !self.is_open() && self.status != OrderStatus::PendingNew
+    }
+
+    fn remaining(&self) -> f64 {
+        self.orig_qty - self.executed_qty
+    }
+
+    fn is_market(&self) -> bool {
+        matches!(
+            self.order_type,
+            OrderType::Market | OrderType::StopLoss | OrderType::TakeProfit
+        )
+    }
+}
+
+struct PaperList {
+    order_list_id: i64,
+    list_client_order_id: String,
+    symbol: String,
+    /// `OCO`, or `OTO` for lists with a working order and pending orders.
+    contingency_type: &'static str,
+    /// The working order of an OTO list comes first.
+    orders: Vec<i64>,
+    time: i64,
+    done: bool,
+}
+
+impl PaperList {
+    /// The orders of the list where one cancels the other.
+    fn legs(&self) -> &[i64] {
+        match (self.contingency_type, self.orders.len()) {
+            ("OCO", _) => &self.orders,
+            ("OTO", 3) => &self.orders[1..],
+            _ => &[],
+        }
+    }
+}
+
+struct PaperTrade {
+    symbol: String,
+    id: i64,
+    order_id: i64,
+    order_list_id: i64,
+    price: f64,
+    qty: f64,
+    commission: f64,
+    commission_asset: String,
+    time: i64,
+    is_buyer: bool,
+    is_maker: bool,
+}
+
+/// Balances, orders and order books of the paper exchange.
+pub(super) struct Engine {
+    markets: HashMap<String, Market>,
+    balances: BTreeMap<String, Balance>,
+    maker_fee: f64,
+    taker_fee: f64,
+    orders: BTreeMap<i64, PaperOrder>,
+    lists: BTreeMap<i64, PaperList>,
+    trades: Vec<PaperTrade>,
+    events: Vec<UnboundedSender<Value>>,
+    next_order_id: i64,
+    next_list_id: i64,
+    next_trade_id: i64,
+}
+
+impl Engine {
+    pub(super) fn new() -> Self {
+        Self {
+            markets: HashMap::new(),
+            balances: BTreeMap::new(),
+            maker_fee: 0.001,
+            taker_fee: 0.001,
+            orders: BTreeMap::new(),
+            lists: BTreeMap::new(),
+            trades: Vec::new(),
+            events: Vec::new(),
+            next_order_id: 1,
+            next_list_id: 1,
+            next_trade_id: 1,
+        }
+    }
+
+    pub(super) fn add_symbol(&mut self, symbol: &str, base: &str, quote: &str) {
+        self.markets.insert(
+            symbol.to_uppercase(),
+            Market {
+                base: base.to_uppercase(),
+                quote: quote.to_uppercase(),
+                bids: Vec::new(),
+                asks: Vec::new(),
+                last_price: None,
+                traded: false,
+                tape: Vec::new(),
+            },
+        );
+    }
+
+    pub(super) fn set_fees(&mut self, maker: f64, taker: f64) {
+        self.maker_fee = maker;
+        self.taker_fee = taker;
+    }
+
+    pub(super) fn deposit(&mut self, asset: &str, amount: f64) {
+        let asset = asset.to_uppercase();
+        self.balances.entry(asset.clone()).or_default().free += amount;
+        self.position(&[asset.as_str()]);
+    }
+
+    pub(super) fn balance(&self, asset: &str) -> (f64, f64) {
+        match self.balances.get(&asset.to_uppercase()) {
+            Some(balance) => (balance.free, balance.locked),
+            None => (0.0, 0.0),
+        }
+    }
+
+    pub(super) fn subscribe(&mut self) -> UnboundedReceiver<Value> {
+        let (tx, rx) = mpsc::unbounded();
+        self.events.push(tx);
+        rx
+    }
+
+    /// Replaces the book of the symbol.
+    pub(super) fn set_book(&mut self, symbol: &str, bids: &[PriceLevel], asks: &[PriceLevel]) {
+        let symbol = symbol.to_uppercase();
+        if let Some(market) = self.markets.get_mut(&symbol) {
+            market.bids = bids.to_vec();
+            market.asks = asks.to_vec();
+            market.bids.sort_by(|a, b| b.0.total_cmp(&a.0));
+            market.asks.sort_by(|a, b| a.0.total_cmp(&b.0));
+            market.update_reference();
+            self.on_market(&symbol, None);
+        }
+    }
+
+    /// Applies a diff of the book of the symbol; levels with zero quantity are removed.
+    pub(super) fn update_book(&mut self, symbol: &str, bids: &[PriceLevel], asks: &[PriceLevel]) {
+        let symbol = symbol.to_uppercase();
+        if let Some(market) = self.markets.get_mut(&symbol) {
+            for level in bids {
+                apply_level(&mut market.bids, *level, |a, b| b.total_cmp(&a));
+            }
+            for level in asks {
+                apply_level(&mut market.asks, *level, |a, b| a.total_cmp(&b));
+            }
+            market.update_reference();
+            self.on_market(&symbol, None);
+        }
+    }
+
+    pub(super) fn trade(&mut self, symbol: &str, price: f64, qty: f64) {
+        let symbol = symbol.to_uppercase();
+        if let Some(market) = self.markets.get_mut(&symbol) {
+            market.last_price = Some(price);
+            market.traded = true;
+            market.tape.push(MarketTrade {
+                id: market.tape.len() as i64,
+                price,
+                qty,
+                time: now(),
+            });
+            self.on_market(&symbol, Some((price, qty)));
+        }
+    }
+
+    /// Checks the order and returns the asset and amount to lock for it, and its quantity.
+    fn validate(&self, params: &OrderParams) -> Result<(String, f64, f64), Reject> {
+        let market = self.market(&params.symbol)?;
+        let quantity = match (params.quantity, params.quote_order_qty) {
+            (Some(quantity), None) => quantity,
+            (None, Some(quote)) if params.order_type == OrderType::Market => {
+                market.quantity_for(params.side, quote)
+            }
+            (None, Some(_)) => {
+                return Err(Reject::new(
+                    -1106,
+                    "Parameter 'quoteOrderQty' sent when not required.",
+                ))
+            }
+            (Some(_), Some(_)) => {
+                return Err(Reject::new(
+                    -1102,
+                    "Param 'quantity' or 'quoteOrderQty' must be sent, but both were provided.",
+                ))
+            }
+            (None, None) => return Err(Reject::mandatory("quantity")),
+        };
+        if quantity <= 0.0 {
+            return Err(Reject::new(-1013, "Invalid quantity."));
+        }
+
+        let needs_price = matches!(
+            params.order_type,
+            OrderType::Limit
+                | OrderType::StopLossLimit
+                | OrderType::TakeProfitLimit
+                | OrderType::LimitMaker
+        );
+        let price = match params.price {
+            Some(price) if price > 0.0 => price,
+            _ if needs_price => return Err(Reject::mandatory("price")),
+            _ => 0.0,
+        };
+
+        let stop_price = match params.stop_price {
+            Some(stop_price) if stop_price > 0.0 => stop_price,
+            _ if is_stop(params.order_type) => return Err(Reject::mandatory("stopPrice")),
+            _ => 0.0,
+        };
+
+        if is_stop(params.order_type) && market.triggers(params.order_type, params.side, stop_price)
+        {
+            return Err(Reject::new(-2010, "Stop price would trigger immediately."));
+        }
+
+        if params.order_type == OrderType::LimitMaker && market.crosses(params.side, price) {
+            return Err(Reject::new(
+                -2010,
+                "Order would immediately match and take.",
+            ));
+        }
+
+        let (asset, amount) = match params.side {
+            Side::Sell => (market.base.clone(), quantity),
+            Side::Buy if needs_price => (market.quote.clone(), quantity * price),
+            Side::Buy => (market.quote.clone(), 0.0),
+        };
+
+        let free = self.balance(&asset).0;
+        let cost = match (params.side, params.order_type) {
+            (Side::Buy, OrderType::Market) => market.cost(quantity),
+            _ => amount,
+        };
+        if cost > free + EPSILON {
+            return Err(Reject::insufficient_balance());
+        }
+        Ok((asset, amount, quantity))
+    }
+
+    /// Validates the order without placing it, as `POST /api/v3/order/test`.
+    pub(super) fn test(&self, params: &OrderParams) -> Result<(), Reject> {
+        self.validate(params).map(|_| ())
+    }
+
+    /// Places the order and matches it against the book; returns the order id.
+    pub(super) fn place(&mut self, params: &OrderParams) -> Result<i64, Reject> {
+        let (asset, amount, quantity) = self.validate(params)?;
+        let id = self.insert(
+            &params.symbol,
+            -1,
+            params.new_client_order_id.clone(),
+            params.side,
+            params.order_type,
+            params.time_in_force.unwrap_or(TimeInForce::Gtc),
+            quantity,
+            params.price.unwrap_or_default(),
+            params.stop_price.unwrap_or_default(),
+        );
+        if let Some(order) = self.orders.get_mut(&id) {
+            order.iceberg_qty = params.iceberg_qty.unwrap_or_default();
+        }
+
+        self.lock(id, &asset, amount);
+        self.report(id, "NEW", None);
+        self.position(&[asset.as_str()]);
+
+        if self.orders[&id].is_working {
+            self.execute(id);
+        }
+        Ok(id)
+    }
+
+    /// Places both legs of the oco order; returns the order list id.
+    pub(super) fn place_oco(&mut self, params: &OcoParams) -> Result<i64, Reject> {
+        let market = self.market(&params.symbol)?;
+        if params.quantity <= 0.0 {
+            return Err(Reject::new(-1013, "Invalid quantity."));
+        }
+
+        let reference = market.last_price.unwrap_or(params.stop_price);
+        let valid = match params.side {
+            Side::Sell => params.price > reference && reference >= params.stop_price,
+            Side::Buy => params.price < reference && reference <= params.stop_price,
+        };
+        if !valid || market.triggers(OrderType::StopLoss, params.side, params.stop_price) {
+            return Err(Reject::new(
+                -2010,
+                "The relationship of the prices for the orders is not correct.",
+            ));
+        }
+
+        let (asset, amount) = match params.side {
+            Side::Sell => (market.base.clone(), params.quantity),
+            Side::Buy => {
+                let stop = params.stop_limit_price.unwrap_or(params.stop_price);
+                (
+                    market.quote.clone(),
+                    params.quantity * params.price.max(stop),
+                )
+            }
+        };
+        if amount > self.balance(&asset).0 + EPSILON {
+            return Err(Reject::insufficient_balance());
+        }
+
+        let symbol = params.symbol.to_uppercase();
+        let list_id = self.next_list_id;
+        self.next_list_id += 1;
+
+        let stop_type = match params.stop_limit_price {
+            Some(_) => OrderType::StopLossLimit,
+            None => OrderType::StopLoss,
+        };
+        let stop = self.insert(
+            &symbol,
+            list_id,
+            params.stop_client_order_id.clone(),
+            params.side,
+            stop_type,
+            params.stop_limit_time_in_force.unwrap_or(TimeInForce::Gtc),
+            params.quantity,
+            params.stop_limit_price.unwrap_or_default(),
+            params.stop_price,
+        );
+        let limit = self.insert(
+            &symbol,
+            list_id,
+            params.limit_client_order_id.clone(),
+            params.side,
+            OrderType::LimitMaker,
+            TimeInForce::Gtc,
+            params.quantity,
+            params.price,
+            0.0,
+        );
+
+        let list_client_order_id = params
+            .list_client_order_id
+            .clone()
+            .unwrap_or_else(|| format!("paper-list-{}", list_id));
+        self.lists.insert(
+            list_id,
+            PaperList {
+                order_list_id: list_id,
+                list_client_order_id,
+                symbol,
+                contingency_type: "OCO",
+                orders: vec![stop, limit],
+                time: now(),
+                done: false,
+            },
+        );
+
+        // the limit leg holds the funds of the list, they move to the stop leg when it triggers
+        self.lock(limit, &asset, amount);
+        self.emit(self.list_status(list_id));
+        self.report(stop, "NEW", None);
+        self.report(limit, "NEW", None);
+        self.position(&[asset.as_str()]);
+        Ok(list_id)
+    }
+
+    /// Places an oco list given by its above and below leg, as `POST /api/v3/orderList/oco`;
+    /// returns the order list id. One leg must be a limit maker and the other a stop loss.
+    pub(super) fn place_oco_list(
+        &mut self,
+        above: &OrderParams,
+        below: &OrderParams,
+        list_client_order_id: Option<String>,
+    ) -> Result<i64, Reject> {
+        let (limit, stop) = match (above.order_type, below.order_type) {
+            (OrderType::LimitMaker, _) => (above, below),
+            (_, OrderType::LimitMaker) => (below, above),
+            _ => return Err(Reject::unsupported_legs()),
+        };
+        let stop_limit_price = match stop.order_type {
+            OrderType::StopLoss => None,
+            OrderType::StopLossLimit => Some(stop.price.ok_or_else(|| Reject::mandatory("price"))?),
+            _ => return Err(Reject::unsupported_legs()),
+        };
+
+        self.place_oco(&OcoParams {
+            symbol: limit.symbol.clone(),
+            side: limit.side,
+            quantity: limit
+                .quantity
+                .ok_or_else(|| Reject::mandatory("quantity"))?,
+            price: limit.price.ok_or_else(|| Reject::mandatory("price"))?,
+            stop_price: stop
+                .stop_price
+                .ok_or_else(|| Reject::mandatory("stopPrice"))?,
+            stop_limit_price,
+            stop_limit_time_in_force: stop.time_in_force,
+            list_client_order_id,
+            limit_client_order_id: limit.new_client_order_id.clone(),
+            stop_client_order_id: stop.new_client_order_id.clone(),
+        })
+    }
+
+    /// Places the working order of an oto list and keeps the pending orders, one or an
+    /// oco pair, until it is filled; returns the order list id.
+    pub(super) fn place_oto(
+        &mut self,
+        working: &OrderParams,
+        pending: &[OrderParams],
+        list_client_order_id: Option<String>,
+    ) -> Result<i64, Reject> {
+        if !matches!(working.order_type, OrderType::Limit | OrderType::LimitMaker) {
+            return Err(Reject::new(-1116, "Invalid orderType."));
+        }
+        let (asset, amount, quantity) = self.validate(working)?;
+        for leg in pending {
+            self.check_pending(leg)?;
+        }
+
+        let symbol = working.symbol.to_uppercase();
+        let list_id = self.next_list_id;
+        self.next_list_id += 1;
+
+        let working_id = self.insert(
+            &symbol,
+            list_id,
+            working.new_client_order_id.clone(),
+            working.side,
+            working.order_type,
+            working.time_in_force.unwrap_or(TimeInForce::Gtc),
+            quantity,
+            working.price.unwrap_or_default(),
+            0.0,
+        );
+        let mut orders = vec![working_id];
+        for leg in pending {
+            let id = self.insert(
+                &symbol,
+                list_id,
+                leg.new_client_order_id.clone(),
+                leg.side,
+                leg.order_type,
+                leg.time_in_force.unwrap_or(TimeInForce::Gtc),
+                leg.quantity.unwrap_or_default(),
+                leg.price.unwrap_or_default(),
+                leg.stop_price.unwrap_or_default(),
+            );
+            self.orders.get_mut(&id).expect("known order").status = OrderStatus::PendingNew;
+            orders.push(id);
+        }
+
+        let list_client_order_id =
+            list_client_order_id.unwrap_or_else(|| format!("paper-list-{}", list_id));
+        self.lists.insert(
+            list_id,
+            PaperList {
+                order_list_id: list_id,
+                list_client_order_id,
+                symbol,
+                contingency_type: "OTO",
+                orders: orders.clone(),
+                time: now(),
+                done: false,
+            },
+        );
+
+        self.lock(working_id, &asset, amount);
+        self.emit(self.list_status(list_id));
+        for id in orders {
+            self.report(id, "NEW", None);
+        }
+        self.position(&[asset.as_str()]);
+
+        if self.orders[&working_id].is_working {
+            self.execute(working_id);
+        }
+        Ok(list_id)
+    }
+
+    /// Checks a pending order of an oto list; its funds are only needed once it is placed.
+    fn check_pending(&self, params: &OrderParams) -> Result<(), Reject> {
+        self.market(&params.symbol)?;
+        match params.quantity {
+            Some(quantity) if quantity > 0.0 => {}
+            Some(_) => return Err(Reject::new(-1013, "Invalid quantity.")),
+            None => return Err(Reject::mandatory("quantity")),
+        }
+
+        let needs_price = matches!(
+            params.order_type,
+            OrderType::Limit
+                | OrderType::StopLossLimit
+                | OrderType::TakeProfitLimit
+                | OrderType::LimitMaker
+        );
+        if needs_price && params.price.filter(|&price| price > 0.0).is_none() {
+            return Err(Reject::mandatory("price"));
+        }
+        if is_stop(params.order_type) && params.stop_price.filter(|&stop| stop > 0.0).is_none() {
+            return Err(Reject::mandatory("stopPrice"));
+        }
+        Ok(())
+    }
+
+    pub(super) fn cancel(
+        &mut self,
+        symbol: &str,
+        order_id: Option<i64>,
+        client_order_id: Option<&str>,
+    ) -> Result<i64, Reject> {
+        let id = self.find(symbol, order_id, client_order_id)?;
+        if !self.orders[&id].is_open() {
+            return Err(Reject::new(-2011, "Unknown order sent."));
+        }
+
+        match self.orders[&id].order_list_id {
+            -1 => self.finish(id, OrderStatus::Canceled),
+            list_id => self.cancel_list_orders(list_id),
+        }
+        Ok(id)
+    }
+
+    /// Reduces the quantity of an open order, keeping its place in the book.
+    pub(super) fn amend(
+        &mut self,
+        symbol: &str,
+        order_id: Option<i64>,
+        client_order_id: Option<&str>,
+        new_qty: f64,
+        new_client_order_id: Option<String>,
+    ) -> Result<i64, Reject> {
+        let id = self.find(symbol, order_id, client_order_id)?;
+        let order = &self.orders[&id];
+        if !order.is_open() {
+            return Err(Reject::new(-2011, "Unknown order sent."));
+        }
+        if order.order_list_id != -1 {
+            return Err(Reject::new(-1000, "Not supported by the paper exchange."));
+        }
+        if new_qty <= EPSILON || new_qty >= order.orig_qty - EPSILON {
+            return Err(Reject::new(
+                -2038,
+                "Order amend (quantity increase) is not supported.",
+            ));
+        }
+
+        let order = self.orders.get_mut(&id).expect("known order");
+        let market = &self.markets[&order.symbol];
+        let (base, quote) = (market.base.clone(), market.quote.clone());
+        let remaining = order.remaining();
+
+        order.orig_qty = new_qty.max(order.executed_qty);
+        order.update_time = now();
+        if let Some(client_order_id) = new_client_order_id {
+            order.client_order_id = client_order_id;
+        }
+        if order.remaining() <= EPSILON {
+            // amended down to the executed quantity
+            order.status = OrderStatus::Filled;
+            release(&mut self.balances, order, &base, &quote);
+        } else {
+            let unlocked = order.locked * (1.0 - order.remaining() / remaining);
+            let asset = match order.side {
+                Side::Buy => &quote,
+                Side::Sell => &base,
+            };
+            let balance = self.balances.entry(asset.clone()).or_default();
+            balance.locked -= unlocked;
+            balance.free += unlocked;
+            order.locked -= unlocked;
+        }
+
+        self.report(id, "REPLACED", None);
+        self.position(&[base.as_str(), quote.as_str()]);
+        Ok(id)
+    }
+
+    /// Cancels all open orders of the symbol; returns the order and order list ids.
+    pub(super) fn cancel_all(&mut self, symbol: &str) -> Result<Vec<(i64, i64)>, Reject> {
+        let symbol = self.market_symbol(symbol)?;
+        let open: Vec<_> = self
+            .orders
+            .values()
+            .filter(|order| order.symbol == symbol && order.is_open())
+            .map(|order| (order.order_id, order.order_list_id))
+            .collect();
+
+        if open.is_empty() {
+            return Err(Reject::new(-2011, "Unknown order sent."));
+        }
+        for &(id, list_id) in &open {
+            match list_id {
+                -1 => self.finish(id, OrderStatus::Canceled),
+                list_id => self.cancel_list_orders(list_id),
+            }
+        }
+        Ok(open)
+    }
+
+    pub(super) fn cancel_list(
+        &mut self,
+        symbol: &str,
+        order_list_id: Option<i64>,
+        list_client_order_id: Option<&str>,
+    ) -> Result<i64, Reject> {
+        let list_id = self.find_list(Some(symbol), order_list_id, list_client_order_id)?;
+        if self.lists[&list_id].done {
+            return Err(Reject::new(-2011, "Unknown order list sent."));
+        }
+        self.cancel_list_orders(list_id);
+        Ok(list_id)
+    }
+
+    pub(super) fn find(
+        &self,
+        symbol: &str,
+        order_id: Option<i64>,
+        client_order_id: Option<&str>,
+    ) -> Result<i64, Reject> {
+        let symbol = self.market_symbol(symbol)?;
+        let found = match (order_id, client_order_id) {
+            (Some(id), _) => self.orders.get(&id).filter(|order| order.symbol == symbol),
+            (None, Some(client_order_id)) => {
+                self.orders.values().rev().find(|order| {
+                    order.symbol == symbol && order.client_order_id == client_order_id
+                })
+            }
+            (None, None) => return Err(Reject::new(
+                -1102,
+                "Param 'origClientOrderId' or 'orderId' must be sent, but both were empty/null!",
+            )),
+        };
+
+        match found {
+            Some(order) => Ok(order.order_id),
+            None => Err(Reject::new(-2013, "Order does not exist.")),
+        }
+    }
+
+    pub(super) fn find_list(
+        &self,
+        symbol: Option<&str>,
+        order_list_id: Option<i64>,
+        list_client_order_id: Option<&str>,
+    ) -> Result<i64, Reject> {
+        let symbol = match symbol {
+            Some(symbol) => Some(self.market_symbol(symbol)?),
+            None => None,
+        };
+        let matches = |list: &&PaperList| match &symbol {
+            Some(symbol) => &list.symbol == symbol,
+            None => true,
+        };
+
+        let found = match (order_list_id, list_client_order_id) {
+            (Some(id), _) => self.lists.get(&id).filter(matches),
+            (None, Some(client_id)) => self
+                .lists
+                .values()
+                .rev()
+                .filter(matches)
+                .find(|list| list.list_client_order_id == client_id),
+            (None, None) => {
+                return Err(Reject::new(
+                    -1102,
+                    "Param 'listClientOrderId' or 'orderListId' must be sent, but both were empty/null!",
+                ))
+            }
+        };
+
+        match found {
+            Some(list) => Ok(list.order_list_id),
+            None => Err(Reject::new(-2018, "Order list does not exist.")),
+        }
+    }
+
+    pub(super) fn order_json(&self, id: i64) -> Value {
+        let order = &self.orders[&id];
+        json!({
+            "symbol": order.symbol,
+            "orderId": order.order_id,
+            "orderListId": order.order_list_id,
+            "clientOrderId": order.client_order_id,
+            "price": decimal(order.price),
+            "origQty": decimal(order.orig_qty),
+            "executedQty": decimal(order.executed_qty),
+            "cummulativeQuoteQty": decimal(order.cummulative_quote_qty),
+            "status": order.status,
+            "timeInForce": order.time_in_force,
+            "type": order.order_type,
+            "side": order.side,
+            "stopPrice": decimal(order.stop_price),
+            "icebergQty": decimal(order.iceberg_qty),
+            "time": order.time,
+            "updateTime": order.update_time,
+            "isWorking": order.is_working,
+            "origQuoteOrderQty": decimal(0.0),
+            "selfTradePreventionMode": "NONE",
+        })
+    }
+
+    /// Response of a new order in the requested verbosity.
+    pub(super) fn new_order_json(&self, id: i64, resp_type: OrderRespType) -> Value {
+        let order = &self.orders[&id];
+        let mut value = json!({
+            "symbol": order.symbol,
+            "orderId": order.order_id,
+            "orderListId": order.order_list_id,
+            "clientOrderId": order.client_order_id,
+            "transactTime": order.time,
+        });
+        if let OrderRespType::Ack = resp_type {
+            return value;
+        }
+
+        if let (Value::Object(value), Value::Object(order)) = (&mut value, self.order_json(id)) {
+            for (key, field) in order {
+                if !matches!(
+                    key.as_str(),
+                    "time" | "updateTime" | "isWorking" | "icebergQty"
+                ) {
+                    value.insert(key, field);
+                }
+            }
+        }
+        if let OrderRespType::Full = resp_type {
+            let fills: Vec<_> = self
+                .trades
+                .iter()
+                .filter(|trade| trade.order_id == id)
+                .map(|trade| {
+                    json!({
+                        "price": decimal(trade.price),
+                        "qty": decimal(trade.qty),
+                        "commission": decimal(trade.commission),
+                        "commissionAsset": trade.commission_asset,
+                        "tradeId": trade.id,
+                    })
+                })
+                .collect();
+            value["fills"] = fills.into();
+        }
+        value
+    }
+
+    pub(super) fn list_json(&self, list_id: i64, reports: bool) -> Value {
+        let mut value = self.list_status(list_id);
+        if let Value::Object(value) = &mut value {
+            value.remove("e");
+            value.remove("E");
+            value.insert("transactionTime".into(), value["T"].clone());
+            value.remove("T");
+        }
+
+        let list = &self.lists[&list_id];
+        if reports {
+            let reports: Vec<_> = list.orders.iter().map(|&id| self.order_json(id)).collect();
+            value["orderReports"] = reports.into();
+        }
+        value
+    }
+
+    pub(super) fn open_orders(&self, symbol: Option<&str>) -> Result<Vec<Value>, Reject> {
+        let symbol = match symbol {
+            Some(symbol) => Some(self.market_symbol(symbol)?),
+            None => None,
+        };
+        Ok(self
+            .orders
+            .values()
+            .filter(|order| order.is_open())
+            .filter(|order| match &symbol {
+                Some(symbol) => &order.symbol == symbol,
+                None => true,
+            })
+            .map(|order| self.order_json(order.order_id))
+            .collect())
+    }
+
+    pub(super) fn all_orders(
+        &self,
+        symbol: &str,
+        from_id: Option<i64>,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<Value>, Reject> {
+        let symbol = self.market_symbol(symbol)?;
+        Ok(self
+            .orders
+            .range(from_id.unwrap_or(0)..)
+            .map(|(_, order)| order)
+            .filter(|order| order.symbol == symbol)
+            .filter(|order| in_range(order.time, start_time, end_time))
+            .take(limit)
+            .map(|order| self.order_json(order.order_id))
+            .collect())
+    }
+
+    pub(super) fn all_lists(
+        &self,
+        from_id: Option<i64>,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        limit: usize,
+        open: bool,
+    ) -> Vec<Value> {
+        self.lists
+            .range(from_id.unwrap_or(0)..)
+            .map(|(_, list)| list)
+            .filter(|list| !open || !list.done)
+            .filter(|list| in_range(list.time, start_time, end_time))
+            .take(limit)
+            .map(|list| self.list_json(list.order_list_id, false))
+            .collect()
+    }
+
+    pub(super) fn account_json(&self) -> Value {
+        let balances: Vec<_> = self
+            .balances
+            .iter()
+            .map(|(asset, balance)| {
+                json!({
+                    "asset": asset,
+                    "free": decimal(balance.free),
+                    "locked": decimal(balance.locked),
+                })
+            })
+            .collect();
+
+        json!({
+            "makerCommission": (self.maker_fee * 10_000.0).round() as i64,
+            "takerCommission": (self.taker_fee * 10_000.0).round() as i64,
+            "buyerCommission": 0,
+            "sellerCommission": 0,
+            "commissionRates": {
+                "maker": decimal(self.maker_fee),
+                "taker": decimal(self.taker_fee),
+                "buyer": decimal(0.0),
+                "seller": decimal(0.0),
+            },
+            "canTrade": true,
+            "canWithdraw": false,
+            "canDeposit": false,
+            "brokered": false,
+            "requireSelfTradePrevention": false,
+            "preventSor": false,
+            "updateTime": now(),
+            "accountType": "SPOT",
+            "balances": balances,
+            "permissions": ["SPOT"],
+        })
+    }
+
+    /// Order rate limits of binance, with the orders placed in the current intervals.
+    pub(super) fn order_rate_limit_json(&self) -> Value {
+        let time = now();
+        let count = |interval: i64| {
+            self.orders
+                .values()
+                .filter(|order| order.time > time - interval)
+                .count()
+        };
+        json!([
+            {
+                "rateLimitType": "ORDERS",
+                "interval": "SECOND",
+                "intervalNum": 10,
+                "limit": 100,
+                "count": count(10_000),
+            },
+            {
+                "rateLimitType": "ORDERS",
+                "interval": "DAY",
+                "intervalNum": 1,
+                "limit": 200_000,
+                "count": count(86_400_000),
+            },
+        ])
+    }
+
+    pub(super) fn commission_json(&self, symbol: &str) -> Result<Value, Reject> {
+        let symbol = self.market_symbol(symbol)?;
+        let none = json!({
+            "maker": decimal(0.0),
+            "taker": decimal(0.0),
+            "buyer": decimal(0.0),
+            "seller": decimal(0.0),
+        });
+        Ok(json!({
+            "symbol": symbol,
+            "standardCommission": {
+                "maker": decimal(self.maker_fee),
+                "taker": decimal(self.taker_fee),
+                "buyer": decimal(0.0),
+                "seller": decimal(0.0),
+            },
+            "taxCommission": none,
+            "discount": {
+                "enabledForAccount": false,
+                "enabledForSymbol": false,
+                "discountAsset": "BNB",
+                "discount": decimal(0.0),
+            },
+        }))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn account_trades(
+        &self,
+        symbol: &str,
+        order_id: Option<i64>,
+        from_id: Option<i64>,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<Value>, Reject> {
+        let symbol = self.market_symbol(symbol)?;
+        Ok(self
+            .trades
+            .iter()
+            .filter(|trade| trade.symbol == symbol)
+            .filter(|trade| order_id.is_none() || Some(trade.order_id) == order_id)
+            .filter(|trade| trade.id >= from_id.unwrap_or(0))
+            .filter(|trade| in_range(trade.time, start_time, end_time))
+            .take(limit)
+            .map(|trade| {
+                json!({
+                    "symbol": trade.symbol,
+                    "id": trade.id,
+                    "orderId": trade.order_id,
+                    "orderListId": trade.order_list_id,
+                    "price": decimal(trade.price),
+                    "qty": decimal(trade.qty),
+                    "quoteQty": decimal(trade.price * trade.qty),
+                    "commission": decimal(trade.commission),
+                    "commissionAsset": trade.commission_asset,
+                    "time": trade.time,
+                    "isBuyer": trade.is_buyer,
+                    "isMaker": trade.is_maker,
+                    "isBestMatch": true,
+                })
+            })
+            .collect())
+    }
+
+    /// Best bid and ask of the symbol, or of every symbol.
+    pub(super) fn book_ticker_json(&self, symbol: Option<&str>) -> Result<Value, Reject> {
+        let ticker = |symbol: &str, market: &Market| {
+            let bid = market.bids.first().copied().unwrap_or(PriceLevel(0.0, 0.0));
+            let ask = market.asks.first().copied().unwrap_or(PriceLevel(0.0, 0.0));
+            json!({
+                "symbol": symbol,
+                "bidPrice": decimal(bid.0),
+                "bidQty": decimal(bid.1),
+                "askPrice": decimal(ask.0),
+                "askQty": decimal(ask.1),
+            })
+        };
+        match symbol {
+            Some(symbol) => {
+                let symbol = self.market_symbol(symbol)?;
+                Ok(ticker(&symbol, &self.markets[&symbol]))
+            }
+            None => {
+                let mut symbols: Vec<_> = self.markets.keys().collect();
+                symbols.sort();
+                Ok(symbols
+                    .into_iter()
+                    .map(|symbol| ticker(symbol, &self.markets[symbol]))
+                    .collect())
+            }
+        }
+    }
+
+    pub(super) fn agg_trades(
+        &self,
+        symbol: &str,
+        from_id: Option<i64>,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<Value>, Reject> {
+        let market = self.market(symbol)?;
+        // without a range binance returns the most recent trades
+        let skip = match (from_id, start_time, end_time) {
+            (None, None, None) => market.tape.len().saturating_sub(limit),
+            _ => 0,
+        };
+        Ok(market
+            .tape
+            .iter()
+            .skip(skip)
+            .filter(|trade| trade.id >= from_id.unwrap_or(0))
+            .filter(|trade| in_range(trade.time, start_time, end_time))
+            .take(limit)
+            .map(|trade| {
+                json!({
+                    "a": trade.id,
+                    "p": decimal(trade.price),
+                    "q": decimal(trade.qty),
+                    "f": trade.id,
+                    "l": trade.id,
+                    "T": trade.time,
+                    "m": false,
+                    "M": true,
+                })
+            })
+            .collect())
+    }
+
+    fn market(&self, symbol: &str) -> Result<&Market, Reject> {
+        self.markets
+            .get(&symbol.to_uppercase())
+            .ok_or_else(|| Reject::new(-1121, "Invalid symbol."))
+    }
+
+    fn market_symbol(&self, symbol: &str) -> Result<String, Reject> {
+        let symbol = symbol.to_uppercase();
+        match self.markets.contains_key(&symbol) {
+            true => Ok(symbol),
+            false => Err(Reject::new(-1121, "Invalid symbol.")),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn insert(
+        &mut self,
+        symbol: &str,
+        order_list_id: i64,
+        client_order_id: Option<String>,
+        side: Side,
+        order_type: OrderType,
+        time_in_force: TimeInForce,
+        quantity: f64,
+        price: f64,
+        stop_price: f64,
+    ) -> i64 {
+        let id = self.next_order_id;
+        self.next_order_id += 1;
+
+        let time = now();
+        self.orders.insert(
+            id,
+            PaperOrder {
+                symbol: symbol.to_uppercase(),
+                order_id: id,
+                order_list_id,
+                client_order_id: client_order_id.unwrap_or_else(|| format!("paper-{}", id)),
+                price,
+                orig_qty: quantity,
+                executed_qty: 0.0,
+                cummulative_quote_qty: 0.0,
+                status: OrderStatus::New,
+                time_in_force,
+                order_type,
+                side,
+                stop_price,
+                iceberg_qty: 0.0,
+                time,
+                update_time: time,
+                is_working: !is_stop(order_type),
+                locked: 0.0,
+            },
+        );
+        id
+    }
+
+    fn lock(&mut self, id: i64, asset: &str, amount: f64) {
+        let balance = self.balances.entry(asset.to_string()).or_default();
+        balance.free -= amount;
+        balance.locked += amount;
+        if let Some(order) = self.orders.get_mut(&id) {
+            order.locked += amount;
+        }
+    }
+
+    /// Matches the open orders of the symbol after a market data update.
+    fn on_market(&mut self, symbol: &str, trade: Option<(f64, f64)>) {
+        let ids: Vec<_> = self
+            .orders
+            .values()
+            .filter(|order| order.symbol == symbol && order.is_open())
+            .map(|order| order.order_id)
+            .collect();
+
+        for id in ids {
+            // a leg of an order list may have expired in the meantime
+            let order = &self.orders[&id];
+            if !order.is_open() {
+                continue;
+            }
+
+            if order.is_working {
+                self.rest(id, trade);
+            } else if self.markets[symbol].triggers(order.order_type, order.side, order.stop_price)
+            {
+                if let Some(order) = self.orders.get_mut(&id) {
+                    order.is_working = true;
+                    order.update_time = now();
+                }
+                self.expire_sibling(id);
+                self.execute(id);
+            }
+        }
+    }
+
+    /// Takes liquidity from the book for a working order, then applies its time in force.
+    fn execute(&mut self, id: i64) {
+        let order = &self.orders[&id];
+        let limit = match order.is_market() {
+            true => None,
+            false => Some(order.price),
+        };
+
+        if order.time_in_force == TimeInForce::Fok {
+            let market = &self.markets[&order.symbol];
+            let available: f64 = market
+                .opposite(order.side)
+                .iter()
+                .filter(|level| match limit {
+                    Some(limit) => within(order.side, level.0, limit),
+                    None => true,
+                })
+                .map(|level| level.1)
+                .sum();
+            if available + EPSILON < order.remaining() {
+                self.finish(id, OrderStatus::Expired);
+                return;
+            }
+        }
+
+        self.take(id, limit);
+
+        let order = &self.orders[&id];
+        if order.is_open() && (order.is_market() || order.time_in_force != TimeInForce::Gtc) {
+            self.finish(id, OrderStatus::Expired);
+        }
+    }
+
+    /// Fills the order as taker against the levels of the book within its limit.
+    fn take(&mut self, id: i64, limit: Option<f64>) {
+        loop {
+            let order = &self.orders[&id];
+            let remaining = order.remaining();
+            let side = order.side;
+            let market = &self.markets[&order.symbol];
+            let level = match market.opposite(side).first() {
+                Some(level) => *level,
+                None => break,
+            };
+            let beyond = match limit {
+                Some(limit) => !within(side, level.0, limit),
+                None => false,
+            };
+            if remaining <= EPSILON || beyond {
+                break;
+            }
+
+            let mut qty = remaining.min(level.1);
+            if side == Side::Buy {
+                let budget = order.locked + self.balance(&market.quote).0;
+                qty = qty.min(budget / level.0);
+            }
+            if qty <= EPSILON {
+                break;
+            }
+
+            let symbol = order.symbol.clone();
+            if let Some(market) = self.markets.get_mut(&symbol) {
+                consume(market.opposite_mut(side), qty);
+            }
+            self.fill(id, level.0, qty, false);
+        }
+    }
+
+    /// Fills a resting order at its price when the book crosses it or a trade goes through it.
+    fn rest(&mut self, id: i64, trade: Option<(f64, f64)>) {
+        let order = &self.orders[&id];
+        let (side, price, remaining) = (order.side, order.price, order.remaining());
+        let market = self.markets.get_mut(&order.symbol).expect("known symbol");
+
+        let mut available = 0.0;
+        for level in market.opposite(side) {
+            if !within(side, level.0, price) || available >= remaining {
+                break;
+            }
+            available += level.1;
+        }
+        let mut qty = remaining.min(available);
+        consume(market.opposite_mut(side), qty);
+
+        if let Some((trade_price, trade_qty)) = trade {
+            let through = match side {
+                Side::Buy => trade_price < price,
+                Side::Sell => trade_price > price,
+            };
+            if through {
+                qty = remaining.min(qty + trade_qty);
+            }
+        }
+
+        if qty > EPSILON {
+            self.fill(id, price, qty, true);
+        }
+    }
+
+    fn fill(&mut self, id: i64, price: f64, qty: f64, is_maker: bool) {
+        let time = now();
+        let rate = match is_maker {
+            true => self.maker_fee,
+            false => self.taker_fee,
+        };
+
+        let order = self.orders.get_mut(&id).expect("known order");
+        let market = &self.markets[&order.symbol];
+        let (base, quote) = (market.base.clone(), market.quote.clone());
+        let quote_qty = price * qty;
+
+        let (commission, commission_asset) = match order.side {
+            Side::Buy => {
+                spend(&mut self.balances, &quote, quote_qty, &mut order.locked);
+                let fee = qty * rate;
+                self.balances.entry(base.clone()).or_default().free += qty - fee;
+                (fee, base.clone())
+            }
+            Side::Sell => {
+                spend(&mut self.balances, &base, qty, &mut order.locked);
+                let fee = quote_qty * rate;
+                self.balances.entry(quote.clone()).or_default().free += quote_qty - fee;
+                (fee, quote.clone())
+            }
+        };
+
+        order.executed_qty += qty;
+        order.cummulative_quote_qty += quote_qty;
+        order.update_time = time;
+        order.status = match order.remaining() <= EPSILON {
+            true => OrderStatus::Filled,
+            false => OrderStatus::PartiallyFilled,
+        };
+
+        let trade_id = self.next_trade_id;
+        self.next_trade_id += 1;
+        self.trades.push(PaperTrade {
+            symbol: order.symbol.clone(),
+            id: trade_id,
+            order_id: id,
+            order_list_id: order.order_list_id,
+            price,
+            qty,
+            commission,
+            commission_asset: commission_asset.clone(),
+            time,
+            is_buyer: order.side == Side::Buy,
+            is_maker,
+        });
+
+        if order.status == OrderStatus::Filled {
+            release(&mut self.balances, order, &base, &quote);
+        }
+
+        let fill = Fill {
+            price,
+            qty,
+            commission,
+            commission_asset,
+            trade_id,
+            is_maker,
+        };
+        self.expire_sibling(id);
+        self.report(id, "TRADE", Some(fill));
+        self.place_pending(id);
+        self.position(&[base.as_str(), quote.as_str()]);
+    }
+
+    /// Ends an open order, releasing its remaining funds.
+    fn finish(&mut self, id: i64, status: OrderStatus) {
+        let order = self.orders.get_mut(&id).expect("known order");
+        let market = &self.markets[&order.symbol];
+        let (base, quote) = (market.base.clone(), market.quote.clone());
+
+        order.status = status;
+        order.update_time = now();
+        release(&mut self.balances, order, &base, &quote);
+
+        let exec_type = match status {
+            OrderStatus::Canceled => "CANCELED",
+            _ => "EXPIRED",
+        };
+        self.report(id, exec_type, None);
+        // the pending orders of an oto list end with an unfilled working order
+        for pending in self.pending_of(id) {
+            self.finish(pending, status);
+        }
+        self.position(&[base.as_str(), quote.as_str()]);
+    }
+
+    /// Pending orders of the oto list the order is the working order of.
+    fn pending_of(&self, id: i64) -> Vec<i64> {
+        let list = match self.lists.get(&self.orders[&id].order_list_id) {
+            Some(list) if list.contingency_type == "OTO" && list.orders[0] == id => list,
+            _ => return Vec::new(),
+        };
+        list.orders[1..]
+            .iter()
+            .copied()
+            .filter(|id| self.orders[id].status == OrderStatus::PendingNew)
+            .collect()
+    }
+
+    /// Places the pending orders of an oto list once its working order is filled;
+    /// they expire if the account can't pay for them.
+    fn place_pending(&mut self, id: i64) {
+        if self.orders[&id].status != OrderStatus::Filled {
+            return;
+        }
+        let pending = self.pending_of(id);
+        if pending.is_empty() {
+            return;
+        }
+
+        // an oco pair is paid for once, by whichever leg executes
+        let market = &self.markets[&self.orders[&id].symbol];
+        let mut funds = (String::new(), 0.0);
+        for leg in &pending {
+            let order = &self.orders[leg];
+            let (asset, amount) = match order.side {
+                Side::Sell => (market.base.clone(), order.orig_qty),
+                Side::Buy => (market.quote.clone(), order.orig_qty * order.price),
+            };
+            if amount >= funds.1 {
+                funds = (asset, amount);
+            }
+        }
+        let (asset, amount) = funds;
+        if amount > self.balance(&asset).0 + EPSILON {
+            for leg in pending {
+                self.finish(leg, OrderStatus::Expired);
+            }
+            return;
+        }
+
+        let time = now();
+        for leg in &pending {
+            let order = self.orders.get_mut(leg).expect("known order");
+            order.status = OrderStatus::New;
+            order.update_time = time;
+        }
+        let holder = pending
+            .iter()
+            .copied()
+            .find(|leg| self.orders[leg].is_working)
+            .unwrap_or(pending[0]);
+        self.lock(holder, &asset, amount);
+        for leg in pending {
+            self.report(leg, "NEW", None);
+        }
+    }
+
+    /// Expires the other leg of an order list once one leg fills or triggers.
+    /// Its funds move to the remaining leg.
+    fn expire_sibling(&mut self, id: i64) {
+        let list_id = self.orders[&id].order_list_id;
+        let sibling = match self.lists.get(&list_id) {
+            Some(list) if list.legs().contains(&id) => {
+                list.legs().iter().copied().find(|&other| other != id)
+            }
+            _ => None,
+        };
+
+        if let Some(sibling) = sibling {
+            if !self.orders[&sibling].is_open() {
+                return;
+            }
+            let locked = std::mem::take(&mut self.orders.get_mut(&sibling).expect("known").locked);
+            self.orders.get_mut(&id).expect("known order").locked += locked;
+            self.finish(sibling, OrderStatus::Expired);
+        }
+    }
+
+    fn cancel_list_orders(&mut self, list_id: i64) {
+        let orders = self.lists[&list_id].orders.clone();
+        for id in orders {
+            if !self.orders[&id].is_done() {
+                self.finish(id, OrderStatus::Canceled);
+            }
+        }
+    }
+
+    fn list_status(&self, list_id: i64) -> Value {
+        let list = &self.lists[&list_id];
+        let done = list.orders.iter().all(|id| self.orders[id].is_done());
+        let orders: Vec<_> = list
+            .orders
+            .iter()
+            .map(|id| {
+                let order = &self.orders[id];
+                json!({
+                    "symbol": order.symbol,
+                    "orderId": order.order_id,
+                    "clientOrderId": order.client_order_id,
+                })
+            })
+            .collect();
+
+        json!({
+            "e": "listStatus",
+            "E": now(),
+            "orderListId": list.order_list_id,
+            "contingencyType": list.contingency_type,
+            "listStatusType": if done { "ALL_DONE" } else { "EXEC_STARTED" },
+            "listOrderStatus": if done { "ALL_DONE" } else { "EXECUTING" },
+            "listClientOrderId": list.list_client_order_id,
+            "T": list.time,
+            "symbol": list.symbol,
+            "orders": orders,
+        })
+    }
+
+    /// Sends the execution report of the order, and the list status once its list is done.
+    fn report(&mut self, id: i64, exec_type: &str, fill: Option<Fill>) {
+        let order = &self.orders[&id];
+        let fill = fill.unwrap_or_default();
+        let event = json!({
+            "e": "executionReport",
+            "E": order.update_time,
+            "s": order.symbol,
+            "c": order.client_order_id,
+            "S": order.side,
+            "o": order.order_type,
+            "f": order.time_in_force,
+            "q": decimal(order.orig_qty),
+            "p": decimal(order.price),
+            "P": decimal(order.stop_price),
+            "F": decimal(0.0),
+            "g": order.order_list_id,
+            "C": "",
+            "x": exec_type,
+            "X": order.status,
+            "r": "NONE",
+            "i": order.order_id,
+            "l": decimal(fill.qty),
+            "z": decimal(order.executed_qty),
+            "L": decimal(fill.price),
+            "n": decimal(fill.commission),
+            "N": if fill.trade_id < 0 { Value::Null } else { fill.commission_asset.into() },
+            "T": order.update_time,
+            "t": fill.trade_id,
+            "I": order.order_id,
+            "w": order.is_working && order.is_open(),
+            "m": fill.is_maker,
+            "M": false,
+            "O": order.time,
+            "Z": decimal(order.cummulative_quote_qty),
+            "Y": decimal(fill.price * fill.qty),
+            "Q": decimal(0.0),
+            "V": "NONE",
+        });
+        let list_id = order.order_list_id;
+        self.emit(event);
+
+        let done = match self.lists.get(&list_id) {
+            Some(list) => !list.done && list.orders.iter().all(|id| self.orders[id].is_done()),
+            None => false,
+        };
+        if done {
+            self.lists.get_mut(&list_id).expect("known list").done = true;
+            self.emit(self.list_status(list_id));
+        }
+    }
+
+    /// Sends the balances of the assets.
+    fn position(&mut self, assets: &[&str]) {
+        let balances: Vec<_> = assets
+            .iter()
+            .map(|asset| {
+                let (free, locked) = self.balance(asset);
+                json!({
+                    "a": asset,
+                    "f": decimal(free),
+                    "l": decimal(locked),
+                })
+            })
+            .collect();
+
+        let time = now();
+        self.emit(json!({
+            "e": "outboundAccountPosition",
+            "E": time,
+            "u": time,
+            "B": balances,
+        }));
+    }
+
+    fn emit(&mut self, event: Value) {
+        self.events
+            .retain(|tx| tx.unbounded_send(event.clone()).is_ok());
+    }
+}
+
+impl Market {
+    /// The levels an order of the side takes from.
+    fn opposite(&self, side: Side) -> &Vec<PriceLevel> {
+        match side {
+            Side::Buy => &self.asks,
+            Side::Sell => &self.bids,
+        }
+    }
+
+    fn opposite_mut(&mut self, side: Side) -> &mut Vec<PriceLevel> {
+        match side {
+            Side::Buy => &mut self.asks,
+            Side::Sell => &mut self.bids,
+        }
+    }
+
+    /// Without trades the middle of the book stands in for the last price.
+    fn update_reference(&mut self) {
+        if self.traded {
+            return;
+        }
+        if let (Some(bid), Some(ask)) = (self.bids.first(), self.asks.first()) {
+            self.last_price = Some((bid.0 + ask.0) / 2.0);
+        }
+    }
+
+    fn triggers(&self, order_type: OrderType, side: Side, stop_price: f64) -> bool {
+        let price = match self.last_price {
+            Some(price) => price,
+            None => return false,
+        };
+        match (order_type, side) {
+            (OrderType::StopLoss, Side::Buy) | (OrderType::StopLossLimit, Side::Buy) => {
+                price >= stop_price
+            }
+            (OrderType::StopLoss, Side::Sell) | (OrderType::StopLossLimit, Side::Sell) => {
+                price <= stop_price
+            }
+            (OrderType::TakeProfit, Side::Buy) | (OrderType::TakeProfitLimit, Side::Buy) => {
+                price <= stop_price
+            }
+            (OrderType::TakeProfit, Side::Sell) | (OrderType::TakeProfitLimit, Side::Sell) => {
+                price >= stop_price
+            }
+            _ => false,
+        }
+    }
+
+    fn crosses(&self, side: Side, price: f64) -> bool {
+        match self.opposite(side).first() {
+            Some(level) => within(side, level.0, price),
+            None => false,
+        }
+    }
+
+    /// Quantity an order of the side gets for the quote amount from the book.
+    fn quantity_for(&self, side: Side, mut quote: f64) -> f64 {
+        let mut qty = 0.0;
+        for level in self.opposite(side) {
+            let take = level.1.min(quote / level.0);
+            qty += take;
+            quote -= take * level.0;
+            if quote <= EPSILON {
+                break;
+            }
+        }
+        qty
+    }
+
+    /// Quote amount to buy the quantity from the asks.
+    fn cost(&self, mut qty: f64) -> f64 {
+        let mut cost = 0.0;
+        for level in &self.asks {
+            let take = qty.min(level.1);
+            cost += take * level.0;
+            qty -= take;
+            if qty <= EPSILON {
+                break;
+            }
+        }
+        cost
+    }
+}
+
+struct Fill {
+    price: f64,
+    qty: f64,
+    commission: f64,
+    commission_asset: String,
+    trade_id: i64,
+    is_maker: bool,
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Self {
+            price: 0.0,
+            qty: 0.0,
+            commission: 0.0,
+            commission_asset: String::new(),
+            trade_id: -1,
+            is_maker: false,
+        }
+    }
+}
+
+fn is_stop(order_type: OrderType) -> bool {
+    matches!(
+        order_type,
+        OrderType::StopLoss
+            | OrderType::StopLossLimit
+            | OrderType::TakeProfit
+            | OrderType::TakeProfitLimit
+    )
+}
+
+/// Whether a level at the price is within the limit of an order of the side.
+fn within(side: Side, price: f64, limit: f64) -> bool {
+    match side {
+        Side::Buy => price <= limit,
+        Side::Sell => price >= limit,
+    }
+}
+
+fn in_range(time: i64, start_time: Option<i64>, end_time: Option<i64>) -> bool {
+    time >= start_time.unwrap_or(i64::MIN) && time <= end_time.unwrap_or(i64::MAX)
+}
+
+fn apply_level<F: Fn(f64, f64) -> std::cmp::Ordering>(
+    levels: &mut Vec<PriceLevel>,
+    level: PriceLevel,
+    order: F,
+) {
+    match levels.binary_search_by(|probe| order(probe.0, level.0)) {
+        Ok(index) if level.1 <= 0.0 => {
+            levels.remove(index);
+        }
+        Ok(index) => levels[index].1 = level.1,
+        Err(_) if level.1 <= 0.0 => {}
+        Err(index) => levels.insert(index, level),
+    }
+}
+
+/// Removes the quantity from the best levels.
+fn consume(levels: &mut Vec<PriceLevel>, mut qty: f64) {
+    while qty > EPSILON {
+        let level = match levels.first_mut() {
+            Some(level) => level,
+            None => break,
+        };
+        let take = qty.min(level.1);
+        level.1 -= take;
+        qty -= take;
+        if level.1 <= EPSILON {
+            levels.remove(0);
+        }
+    }
+}
+
+/// Pays the amount from the funds locked for the order first, then from the free balance.
+fn spend(balances: &mut BTreeMap<String, Balance>, asset: &str, amount: f64, locked: &mut f64) {
+    let balance = balances.entry(asset.to_string()).or_default();
+    let from_locked = amount.min(*locked);
+    *locked -= from_locked;
+    balance.locked -= from_locked;
+    balance.free -= amount - from_locked;
+}
+
+fn release(
+    balances: &mut BTreeMap<String, Balance>,
+    order: &mut PaperOrder,
+    base: &str,
+    quote: &str,
+) {
+    let asset = match order.side {
+        Side::Buy => quote,
+        Side::Sell => base,
+    };
+    let balance = balances.entry(asset.to_string()).or_default();
+    balance.locked -= order.locked;
+    balance.free += order.locked;
+    order.locked = 0.0;
+}
+
+fn decimal(value: f64) -> String {
+    format!("{:.8}", value)
+}
+
+fn now() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        engine.add_symbol("BNBUSDT", "BNB", "USDT");
+        engine.set_fees(0.0, 0.0);
+        engine
+    }
+
+    fn order(side: Side, order_type: OrderType, quantity: f64, price: Option<f64>) -> OrderParams {
+        OrderParams {
+            symbol: "BNBUSDT".into(),
+            side,
+            order_type,
+            time_in_force: None,
+            quantity: Some(quantity),
+            quote_order_qty: None,
+            price,
+            stop_price: None,
+            iceberg_qty: None,
+            new_client_order_id: None,
+            new_order_resp_type: None,
+        }
+    }
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn marketable_limit_takes_several_levels() {
+        let mut engine = engine();
+        engine.deposit("USDT", 100.0);
+        let asks = [
+            PriceLevel(10.0, 1.0),
+            PriceLevel(11.0, 1.0),
+            PriceLevel(12.0, 5.0),
+        ];
+        engine.set_book("BNBUSDT", &[], &asks);
+
+        let id = engine
+            .place(&order(Side::Buy, OrderType::Limit, 3.0, Some(11.0)))
+            .unwrap();
+
+        let order = &engine.orders[&id];
+        assert_eq!(order.status, OrderStatus::PartiallyFilled);
+        assert_near(order.executed_qty, 2.0);
+        assert_near(order.cummulative_quote_qty, 21.0);
+        assert_eq!(engine.markets["BNBUSDT"].asks, vec![PriceLevel(12.0, 5.0)]);
+        assert_near(engine.balance("BNB").0, 2.0);
+
+        let (free, locked) = engine.balance("USDT");
+        assert_near(free + locked, 79.0);
+        assert_near(locked, engine.orders[&id].locked);
+    }
+
+    #[test]
+    fn resting_order_fills_on_trades_through_it() {
+        let mut engine = engine();
+        engine.deposit("USDT", 100.0);
+        engine.set_book("BNBUSDT", &[PriceLevel(9.0, 5.0)], &[PriceLevel(12.0, 5.0)]);
+
+        let id = engine
+            .place(&order(Side::Buy, OrderType::Limit, 2.0, Some(10.0)))
+            .unwrap();
+        assert_eq!(engine.orders[&id].status, OrderStatus::New);
+        assert_eq!(engine.balance("USDT"), (80.0, 20.0));
+
+        // a trade at the price does not go through the order
+        engine.trade("BNBUSDT", 10.0, 1.0);
+        assert_near(engine.orders[&id].executed_qty, 0.0);
+
+        engine.trade("BNBUSDT", 9.5, 0.5);
+        assert_eq!(engine.orders[&id].status, OrderStatus::PartiallyFilled);
+        assert_near(engine.orders[&id].executed_qty, 0.5);
+
+        engine.trade("BNBUSDT", 9.0, 5.0);
+        let order = &engine.orders[&id];
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_near(order.cummulative_quote_qty, 20.0);
+        assert!(engine.trades.iter().all(|trade| trade.is_maker));
+        assert_near(engine.balance("BNB").0, 2.0);
+        assert_eq!(engine.balance("USDT"), (80.0, 0.0));
+    }
+
+    #[test]
+    fn fok_expires_without_enough_liquidity() {
+        let mut engine = engine();
+        engine.deposit("USDT", 100.0);
+        let asks = [PriceLevel(10.0, 1.0), PriceLevel(11.0, 1.0)];
+        engine.set_book("BNBUSDT", &[], &asks);
+
+        let mut params = order(Side::Buy, OrderType::Limit, 3.0, Some(11.0));
+        params.time_in_force = Some(TimeInForce::Fok);
+        let id = engine.place(&params).unwrap();
+
+        assert_eq!(engine.orders[&id].status, OrderStatus::Expired);
+        assert_near(engine.orders[&id].executed_qty, 0.0);
+        assert_eq!(engine.markets["BNBUSDT"].asks, asks.to_vec());
+        assert_eq!(engine.balance("USDT"), (100.0, 0.0));
+
+        params.quantity = Some(2.0);
+        let id = engine.place(&params).unwrap();
+        assert_eq!(engine.orders[&id].status, OrderStatus::Filled);
+        assert_near(engine.balance("USDT").0, 79.0);
+    }
+
+    #[test]
+    fn take_caps_buys_at_the_quote_balance() {
+        let mut engine = engine();
+        engine.deposit("USDT", 50.0);
+        engine.set_book("BNBUSDT", &[], &[PriceLevel(10.0, 10.0)]);
+        engine.trade("BNBUSDT", 9.0, 1.0);
+
+        // a stop market buy locks nothing, so it spends the free balance once triggered
+        let mut params = order(Side::Buy, OrderType::StopLoss, 10.0, None);
+        params.stop_price = Some(9.5);
+        let id = engine.place(&params).unwrap();
+        assert!(!engine.orders[&id].is_working);
+
+        engine.trade("BNBUSDT", 10.0, 1.0);
+        let order = &engine.orders[&id];
+        assert_eq!(order.status, OrderStatus::Expired);
+        assert_near(order.executed_qty, 5.0);
+        assert_near(engine.balance("USDT").0, 0.0);
+        assert_near(engine.balance("BNB").0, 5.0);
+        assert_eq!(engine.markets["BNBUSDT"].asks, vec![PriceLevel(10.0, 5.0)]);
+    }
+
+    #[test]
+    fn oco_moves_locked_funds_to_the_triggered_leg() {
+        let mut engine = engine();
+        engine.deposit("BNB", 2.0);
+        engine.trade("BNBUSDT", 10.0, 1.0);
+
+        let list_id = engine
+            .place_oco(&OcoParams {
+                symbol: "BNBUSDT".into(),
+                side: Side::Sell,
+                quantity: 2.0,
+                price: 12.0,
+                stop_price: 9.0,
+                stop_limit_price: Some(8.0),
+                stop_limit_time_in_force: None,
+                list_client_order_id: None,
+                limit_client_order_id: None,
+                stop_client_order_id: None,
+            })
+            .unwrap();
+        let (stop, limit) = (
+            engine.lists[&list_id].orders[0],
+            engine.lists[&list_id].orders[1],
+        );
+        assert_near(engine.orders[&limit].locked, 2.0);
+        assert_eq!(engine.balance("BNB"), (0.0, 2.0));
+
+        // no bids, so the triggered stop limit order rests
+        engine.trade("BNBUSDT", 8.9, 1.0);
+        assert_eq!(engine.orders[&limit].status, OrderStatus::Expired);
+        assert_near(engine.orders[&limit].locked, 0.0);
+        assert_eq!(engine.orders[&stop].status, OrderStatus::New);
+        assert!(engine.orders[&stop].is_working);
+        assert_near(engine.orders[&stop].locked, 2.0);
+        assert_eq!(engine.balance("BNB"), (0.0, 2.0));
+
+        engine.set_book("BNBUSDT", &[PriceLevel(8.5, 5.0)], &[]);
+        assert_eq!(engine.orders[&stop].status, OrderStatus::Filled);
+        assert_eq!(engine.balance("BNB"), (0.0, 0.0));
+        assert_near(engine.balance("USDT").0, 16.0);
+    }
+}
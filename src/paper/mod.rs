@@ -0,0 +1,598 @@
+mod engine;
+mod server;
+
+use crate::bars::Tick;
+use crate::environment::Endpoint;
+use crate::model::{AggTrade, BookTicker, DepthUpdate, OrderBook, PriceLevel, Trade};
+use crate::ws_stream::{ChannelBuf, WebSocketStream};
+use engine::Engine;
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::channel::oneshot;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+/// Simulated exchange for paper trading, served on a local port.
+///
+/// The REST endpoints used by [`AccountClient`](crate::AccountClient), the requests of
+/// [`WsApiClient`](crate::WsApiClient) and the user data stream are answered from simulated
+/// balances, so that a client pointed at the paper exchange runs unchanged; [`MarketDataClient`](crate::MarketDataClient) gets the best
+/// bid and ask and the fed trades. Orders are matched against the book fed from market
+/// data: marketable orders take the levels of the book, resting limit orders fill at their
+/// price once the book or a trade crosses it, and stop orders trigger on the last price.
+/// The pending orders of oto and otoco lists are placed once their working order fills.
+/// Fees are charged in the received asset. Signatures are not checked.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{AccountClient, Channel, Environment, PaperExchange, Side, WebSocketStream};
+/// use serde_json::Value;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let paper = PaperExchange::start().await?;
+///     paper.add_symbol("BNBUSDT", "BNB", "USDT");
+///     paper.deposit("USDT", 10_000.0);
+///     // optional: maker and taker fee; default is 0.001 each.
+///     paper.set_fees(0.00075, 0.00075);
+///
+///     let channel = Channel::BookTicker("BNBUSDT");
+///     let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
+///     let feed = paper.clone();
+///     tokio::spawn(async move { feed.feed(&mut stream).await });
+///
+///     let client = AccountClient::connect("<api-key>", "<secret-key>", &paper)?;
+///     let response = client
+///         .place_market_order("BNBUSDT", Side::Buy, 1.0, true)
+///         .json::<Value>()
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct PaperExchange {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    engine: Arc<Mutex<Engine>>,
+    rest_url: String,
+    stream_url: String,
+    ws_api_url: String,
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl PaperExchange {
+    /// Starts serving on a free local port; the server stops once the last clone is dropped.
+    pub async fn start() -> crate::error::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let engine = Arc::new(Mutex::new(Engine::new()));
+        let (shutdown, stopped) = oneshot::channel();
+
+        tokio::spawn(server::serve(listener, engine.clone(), stopped));
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                engine,
+                rest_url: format!("http://{}", addr),
+                stream_url: format!("ws://{}", addr),
+                ws_api_url: format!("ws://{}/ws-api/v3", addr),
+                _shutdown: shutdown,
+            }),
+        })
+    }
+    /// Adds a tradable symbol with its base and quote asset.
+    pub fn add_symbol(&self, symbol: &str, base: &str, quote: &str) {
+        self.engine().add_symbol(symbol, base, quote);
+    }
+    /// Adds the amount to the free balance of the asset.
+    pub fn deposit(&self, asset: &str, amount: f64) {
+        self.engine().deposit(asset, amount);
+    }
+    /// Sets the maker and taker fee as a fraction, e.g. 0.001 for 0.1%.
+    pub fn set_fees(&self, maker: f64, taker: f64) {
+        self.engine().set_fees(maker, taker);
+    }
+    /// Free and locked balance of the asset.
+    pub fn balance(&self, asset: &str) -> (f64, f64) {
+        self.engine().balance(asset)
+    }
+    /// Synthetic `executionReport`, `listStatus` and `outboundAccountPosition` events,
+    /// as sent by the user data stream.
+    pub fn events(&self) -> UnboundedReceiver<Value> {
+        self.engine().subscribe()
+    }
+    /// Replaces the order book of the symbol.
+    pub fn set_order_book(&self, symbol: &str, book: &OrderBook) {
+        self.engine().set_book(symbol, &book.bids, &book.asks);
+    }
+    /// Applies the changes of the depth stream to the order book.
+    pub fn apply_depth_update(&self, update: &DepthUpdate) {
+        self.engine()
+            .update_book(&update.symbol, &update.bids, &update.asks);
+    }
+    /// Replaces the order book of the symbol with the best bid and ask.
+    pub fn set_book_ticker(&self, ticker: &BookTicker) {
+        let bids = [PriceLevel(ticker.bid_price, ticker.bid_qty)];
+        let asks = [PriceLevel(ticker.ask_price, ticker.ask_qty)];
+        self.engine().set_book(&ticker.symbol, &bids, &asks);
+    }
+    /// Sets the last price of the symbol and fills the resting orders the trade went through.
+    pub fn push_trade<T: Into<Tick>>(&self, symbol: &str, trade: T) {
+        let tick = trade.into();
+        self.engine().trade(symbol, tick.price, tick.qty);
+    }
+    /// Feeds the market data of a combined stream until it ends.
+    ///
+    /// Trades, aggregate trades, book tickers, partial and diff depth are used,
+    /// other channels are skipped.
+    pub async fn feed(&self, stream: &mut WebSocketStream) -> crate::error::Result<()> {
+        while let Some(event) = stream.stream_event::<Value>().await? {
+            let symbol = match event.stream.symbol() {
+                Some(symbol) => symbol.to_uppercase(),
+                None => continue,
+            };
+
+            match event.stream {
+                ChannelBuf::Trade(_) => {
+                    let trade: Trade = serde_json::from_value(event.data)?;
+                    self.push_trade(&symbol, &trade);
+                }
+                ChannelBuf::AggTrade(_) => {
+                    let trade: AggTrade = serde_json::from_value(event.data)?;
+                    self.push_trade(&symbol, &trade);
+                }
+                ChannelBuf::BookTicker(_) => {
+                    self.set_book_ticker(&serde_json::from_value(event.data)?);
+                }
+                ChannelBuf::PartialDepth(..) => {
+                    self.set_order_book(&symbol, &serde_json::from_value(event.data)?);
+                }
+                ChannelBuf::Depth(..) => {
+                    self.apply_depth_update(&serde_json::from_value(event.data)?);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn engine(&self) -> std::sync::MutexGuard<'_, Engine> {
+        self.inner.engine.lock().unwrap()
+    }
+}
+
+impl Endpoint for &PaperExchange {
+    fn rest_url(&self) -> &str {
+        &self.inner.rest_url
+    }
+
+    fn stream_url(&self) -> &str {
+        &self.inner.stream_url
+    }
+
+    fn ws_api_url(&self) -> &str {
+        &self.inner.ws_api_url
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ApiError, CancelReplace, CancelReplaceResult, RateLimit};
+    use crate::model::{RateLimitInterval, RateLimitType};
+    use crate::CancelReplaceMode::{AllowFailure, StopOnFailure};
+    use crate::{AccountClient, OrderType, Side, WsApiClient, ID};
+
+    #[tokio::test]
+    async fn serves_the_ws_api() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 1000.0);
+
+        let client = WsApiClient::connect("<api-key>", "<secret-key>", &paper)
+            .await
+            .unwrap();
+        let order = client
+            .place_limit_order("BNBUSDT", Side::Buy, 300.0, 1.0, true)
+            .json::<Value>()
+            .await
+            .unwrap();
+        assert_eq!(order["status"], "NEW");
+        assert_eq!(order["type"], "LIMIT");
+        assert_eq!(paper.balance("USDT"), (700.0, 300.0));
+
+        let order_id = order["orderId"].as_i64().unwrap();
+        let canceled = client
+            .cancel_order("BNBUSDT", ID::OrderId(order_id))
+            .json::<Value>()
+            .await
+            .unwrap();
+        assert_eq!(canceled["status"], "CANCELED");
+
+        let error = client
+            .get_order("BNBUSDT", ID::OrderId(order_id + 1))
+            .json::<Value>()
+            .await
+            .unwrap_err();
+        assert_eq!(ApiError::from_error(&error).unwrap().code, -2013);
+    }
+
+    #[tokio::test]
+    async fn otoco_places_the_bracket_once_the_entry_fills() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 1000.0);
+        paper.set_fees(0.0, 0.0);
+        paper.push_trade("BNBUSDT", &trade(21.0, 1.0));
+
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let list = client
+            .place_otoco_order_list(
+                "BNBUSDT",
+                OrderType::Limit,
+                Side::Buy,
+                20.0,
+                5.0,
+                Side::Sell,
+                OrderType::LimitMaker,
+                OrderType::StopLossLimit,
+            )
+            .with_pending_above_price(30.0)
+            .with_pending_below_stop_price(15.0)
+            .with_pending_below_price(14.5)
+            .with_list_client_order_id("bracket")
+            .with_pending_above_client_order_id("take-profit")
+            .json::<Value>()
+            .await
+            .unwrap();
+        assert_eq!(list["contingencyType"], "OTO");
+        assert_eq!(list["listClientOrderId"], "bracket");
+        let statuses: Vec<_> = list["orderReports"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|order| order["status"].as_str().unwrap())
+            .collect();
+        assert_eq!(statuses, vec!["NEW", "PENDING_NEW", "PENDING_NEW"]);
+        assert_eq!(paper.balance("USDT"), (900.0, 100.0));
+
+        // the entry fills, the take profit and stop loss are placed as an oco pair
+        paper.push_trade("BNBUSDT", &trade(19.0, 10.0));
+        let open = client.get_open_orders().json::<Vec<Value>>().await.unwrap();
+        let types: Vec<_> = open
+            .iter()
+            .map(|order| order["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, vec!["LIMIT_MAKER", "STOP_LOSS_LIMIT"]);
+        assert_eq!(paper.balance("BNB"), (0.0, 5.0));
+        assert_eq!(paper.balance("USDT"), (900.0, 0.0));
+
+        paper.push_trade("BNBUSDT", &trade(31.0, 10.0));
+        let take_profit = client
+            .get_order("BNBUSDT", ID::ClientOId("take-profit"))
+            .json::<Value>()
+            .await
+            .unwrap();
+        assert_eq!(take_profit["status"], "FILLED");
+        let list = client
+            .get_oco_order(ID::ClientOId("bracket"))
+            .json::<Value>()
+            .await
+            .unwrap();
+        assert_eq!(list["listOrderStatus"], "ALL_DONE");
+        assert_eq!(paper.balance("BNB"), (0.0, 0.0));
+        assert_eq!(paper.balance("USDT"), (1050.0, 0.0));
+    }
+
+    #[tokio::test]
+    async fn canceling_an_oto_list_cancels_the_pending_order() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 1000.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+
+        let list = client
+            .place_oto_order_list(
+                "BNBUSDT",
+                OrderType::Limit,
+                Side::Buy,
+                20.0,
+                5.0,
+                OrderType::Limit,
+                Side::Sell,
+                4.99,
+            )
+            .with_pending_price(30.0)
+            .json::<Value>()
+            .await
+            .unwrap();
+        let list_id = list["orderListId"].as_i64().unwrap();
+
+        let canceled = client
+            .cancel_oco_order("BNBUSDT", ID::OrderId(list_id))
+            .json::<Value>()
+            .await
+            .unwrap();
+        let statuses: Vec<_> = canceled["orderReports"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|order| order["status"].as_str().unwrap())
+            .collect();
+        assert_eq!(statuses, vec!["CANCELED", "CANCELED"]);
+        assert_eq!(canceled["listOrderStatus"], "ALL_DONE");
+        assert_eq!(paper.balance("USDT"), (1000.0, 0.0));
+    }
+
+    #[tokio::test]
+    async fn serves_oco_lists_by_their_above_and_below_leg() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("BNB", 5.0);
+        paper.push_trade("BNBUSDT", &trade(25.0, 1.0));
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+
+        let list = client
+            .place_oco_order_list(
+                "BNBUSDT",
+                Side::Sell,
+                5.0,
+                OrderType::LimitMaker,
+                OrderType::StopLossLimit,
+            )
+            .with_above_price(30.0)
+            .with_below_stop_price(20.0)
+            .with_below_price(19.5)
+            .with_above_client_order_id("above")
+            .json::<Value>()
+            .await
+            .unwrap();
+        assert_eq!(list["contingencyType"], "OCO");
+        let orders: Vec<_> = list["orderReports"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|order| {
+                (
+                    order["type"].as_str().unwrap(),
+                    order["price"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            orders,
+            vec![
+                ("STOP_LOSS_LIMIT", "19.50000000"),
+                ("LIMIT_MAKER", "30.00000000")
+            ]
+        );
+        assert_eq!(list["orderReports"][1]["clientOrderId"], "above");
+        assert_eq!(paper.balance("BNB"), (0.0, 5.0));
+
+        // both legs above the price can't form a bracket
+        let error = client
+            .place_oco_order_list(
+                "BNBUSDT",
+                Side::Sell,
+                1.0,
+                OrderType::LimitMaker,
+                OrderType::LimitMaker,
+            )
+            .with_above_price(30.0)
+            .with_below_price(26.0)
+            .json::<Value>()
+            .await
+            .unwrap_err();
+        assert_eq!(ApiError::from_error(&error).unwrap().code, -1000);
+    }
+
+    #[tokio::test]
+    async fn serves_the_order_rate_limits() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 1000.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+
+        client
+            .place_limit_order("BNBUSDT", Side::Buy, 300.0, 1.0, true)
+            .json::<Value>()
+            .await
+            .unwrap();
+        let limits = client
+            .get_order_rate_limit()
+            .json::<Vec<RateLimit>>()
+            .await
+            .unwrap();
+        let limits: Vec<_> = limits
+            .iter()
+            .map(|limit| {
+                (
+                    limit.rate_limit_type,
+                    limit.interval,
+                    limit.limit,
+                    limit.count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            limits,
+            vec![
+                (
+                    RateLimitType::Orders,
+                    RateLimitInterval::Second,
+                    100,
+                    Some(1)
+                ),
+                (
+                    RateLimitType::Orders,
+                    RateLimitInterval::Day,
+                    200_000,
+                    Some(1)
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_replace_returns_partial_failures() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 100.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let order = client
+            .place_limit_order("BNBUSDT", Side::Buy, 10.0, 1.0, true)
+            .json::<Value>()
+            .await
+            .unwrap();
+        let order_id = order["orderId"].as_i64().unwrap();
+
+        let replaced = client
+            .cancel_replace_order(
+                "BNBUSDT",
+                ID::OrderId(order_id),
+                Side::Buy,
+                11.0,
+                1.0,
+                StopOnFailure,
+            )
+            .outcome()
+            .await
+            .unwrap();
+        assert!(replaced.is_success());
+        assert_eq!(replaced.cancel_response["orderId"], order_id);
+        assert_eq!(replaced.cancel_response["status"], "CANCELED");
+        assert_eq!(replaced.new_order_response["status"], "NEW");
+        assert_eq!(paper.balance("USDT"), (89.0, 11.0));
+        let new_order_id = replaced.new_order_response["orderId"].as_i64().unwrap();
+
+        // the order is canceled already, so the new one is not attempted
+        let stopped = client
+            .cancel_replace_order(
+                "BNBUSDT",
+                ID::OrderId(order_id),
+                Side::Buy,
+                12.0,
+                1.0,
+                StopOnFailure,
+            )
+            .outcome()
+            .await
+            .unwrap();
+        assert_eq!(stopped.cancel_result, CancelReplaceResult::Failure);
+        assert_eq!(stopped.new_order_result, CancelReplaceResult::NotAttempted);
+        assert_eq!(stopped.cancel_error().unwrap().code, -2011);
+        assert!(stopped.new_order_error().is_none());
+        assert!(stopped.new_order_response.is_null());
+        assert_eq!(paper.balance("USDT"), (89.0, 11.0));
+
+        // the cancel succeeds, the new order is refused
+        let allowed = client
+            .cancel_replace_order(
+                "BNBUSDT",
+                ID::OrderId(new_order_id),
+                Side::Buy,
+                200.0,
+                1.0,
+                AllowFailure,
+            )
+            .outcome()
+            .await
+            .unwrap();
+        assert!(!allowed.is_success());
+        assert_eq!(allowed.cancel_result, CancelReplaceResult::Success);
+        assert_eq!(allowed.new_order_result, CancelReplaceResult::Failure);
+        assert!(allowed.cancel_error().is_none());
+        assert_eq!(allowed.new_order_error().unwrap().code, -2010);
+        assert_eq!(paper.balance("USDT"), (100.0, 0.0));
+
+        // as an error, the response carries both outcomes
+        let error = client
+            .cancel_replace_order(
+                "BNBUSDT",
+                ID::OrderId(new_order_id),
+                Side::Buy,
+                12.0,
+                1.0,
+                AllowFailure,
+            )
+            .json::<Value>()
+            .await
+            .unwrap_err();
+        assert_eq!(ApiError::from_error(&error).unwrap().code, -2021);
+        let partial = CancelReplace::from_error(&error).unwrap();
+        assert_eq!(partial.cancel_result, CancelReplaceResult::Failure);
+        assert_eq!(partial.new_order_result, CancelReplaceResult::Success);
+        assert_eq!(paper.balance("USDT"), (88.0, 12.0));
+
+        // other errors stay errors
+        let unreachable = AccountClient::connect("<api-key>", "<secret-key>", "http://127.0.0.1:1");
+        let error = unreachable
+            .unwrap()
+            .cancel_replace_order(
+                "BNBUSDT",
+                ID::OrderId(order_id),
+                Side::Buy,
+                12.0,
+                1.0,
+                AllowFailure,
+            )
+            .outcome()
+            .await
+            .unwrap_err();
+        assert!(CancelReplace::from_error(&error).is_none());
+    }
+
+    #[tokio::test]
+    async fn amends_keep_the_order_in_place() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 100.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let order = client
+            .place_limit_order("BNBUSDT", Side::Buy, 10.0, 3.0, true)
+            .with_new_client_order_id("first")
+            .json::<Value>()
+            .await
+            .unwrap();
+        let order_id = order["orderId"].as_i64().unwrap();
+
+        let amended = client
+            .amend_order_keep_priority("BNBUSDT", ID::ClientOId("first"), 2.0)
+            .with_new_client_order_id("second")
+            .json::<Value>()
+            .await
+            .unwrap();
+        let order = &amended["amendedOrder"];
+        assert_eq!(order["orderId"], order_id);
+        assert_eq!(order["clientOrderId"], "second");
+        assert_eq!(order["origQty"], "2.00000000");
+        assert_eq!(order["status"], "NEW");
+        assert_eq!(paper.balance("USDT"), (80.0, 20.0));
+
+        // the quantity can only go down
+        let error = client
+            .amend_order_keep_priority("BNBUSDT", ID::OrderId(order_id), 5.0)
+            .json::<Value>()
+            .await
+            .unwrap_err();
+        assert_eq!(ApiError::from_error(&error).unwrap().code, -2038);
+        let error = client
+            .amend_order_keep_priority("BNBUSDT", ID::ClientOId("first"), 1.0)
+            .json::<Value>()
+            .await
+            .unwrap_err();
+        assert_eq!(ApiError::from_error(&error).unwrap().code, -2013);
+    }
+
+    fn trade(price: f64, qty: f64) -> Trade {
+        Trade {
+            id: 0,
+            price,
+            qty,
+            time: 0,
+            is_buyer_maker: false,
+        }
+    }
+}
@@ -0,0 +1,615 @@
+use super::engine::{Engine, OcoParams, OrderParams, Reject};
+use crate::param::{CancelReplaceMode, OrderRespType, OrderType};
+use async_tungstenite::tungstenite::Message;
+use futures::{SinkExt, StreamExt};
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+
+/// Default and maximum number of rows returned by the list endpoints.
+const DEFAULT_LIMIT: usize = 500;
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderId {
+    symbol: String,
+    order_id: Option<i64>,
+    orig_client_order_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelReplaceId {
+    symbol: String,
+    cancel_replace_mode: CancelReplaceMode,
+    cancel_order_id: Option<i64>,
+    cancel_orig_client_order_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmendParams {
+    symbol: String,
+    order_id: Option<i64>,
+    orig_client_order_id: Option<String>,
+    new_client_order_id: Option<String>,
+    new_qty: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListId {
+    symbol: Option<String>,
+    order_list_id: Option<i64>,
+    list_client_order_id: Option<String>,
+    orig_client_order_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Query {
+    symbol: Option<String>,
+    order_id: Option<i64>,
+    from_id: Option<i64>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    limit: Option<usize>,
+}
+
+impl Query {
+    fn symbol(&self) -> Result<&str, Reject> {
+        match &self.symbol {
+            Some(symbol) => Ok(symbol),
+            None => Err(Reject::new(
+                -1102,
+                "Mandatory parameter 'symbol' was not sent, was empty/null, or malformed.",
+            )),
+        }
+    }
+
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)
+    }
+}
+
+/// Accepts connections until the shutdown resolves.
+pub(super) async fn serve<F>(listener: TcpListener, engine: Arc<Mutex<Engine>>, shutdown: F)
+where
+    F: std::future::Future + Unpin,
+{
+    let mut shutdown = shutdown;
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(_) => continue,
+            },
+            _ = &mut shutdown => return,
+        };
+
+        let engine = engine.clone();
+        tokio::spawn(async move {
+            match stream_path(&stream).await {
+                Some(path) if path.starts_with("/ws-api/") => serve_ws_api(engine, stream).await,
+                Some(path) => serve_stream(engine, stream, path).await,
+                None => {
+                    let service = service_fn(move |req| handle(engine.clone(), req));
+                    let _ = hyper::server::conn::Http::new()
+                        .http1_only(true)
+                        .serve_connection(stream, service)
+                        .await;
+                }
+            }
+        });
+    }
+}
+
+/// Path of a websocket stream or websocket api request, `None` for REST requests.
+async fn stream_path(stream: &TcpStream) -> Option<String> {
+    let mut buf = [0; 1024];
+    loop {
+        let len = stream.peek(&mut buf).await.ok()?;
+        let head = &buf[..len];
+        match head.windows(2).position(|window| window == b"\r\n") {
+            Some(end) => {
+                let line = String::from_utf8_lossy(&head[..end]);
+                let path = line.split_whitespace().nth(1)?;
+                let websocket = ["/ws/", "/stream", "/ws-api/"]
+                    .iter()
+                    .any(|prefix| path.starts_with(prefix));
+                return match websocket {
+                    true => Some(path.to_string()),
+                    false => None,
+                };
+            }
+            None if len == 0 || len == buf.len() => return None,
+            // the request line is not complete yet
+            None => tokio::time::sleep(Duration::from_millis(1)).await,
+        }
+    }
+}
+
+/// Sends the user data events to a websocket connection until either side closes.
+async fn serve_stream(engine: Arc<Mutex<Engine>>, stream: TcpStream, path: String) {
+    let ws = match async_tungstenite::tokio::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(_) => return,
+    };
+    let listen_key = path.trim_start_matches("/ws/").to_string();
+    let mut events = engine.lock().unwrap().subscribe();
+    let (mut sink, mut source) = ws.split();
+    let mut combined = false;
+
+    loop {
+        let message = tokio::select! {
+            message = source.next() => match message {
+                Some(Ok(Message::Text(text))) => reply(&text, &listen_key, &mut combined),
+                Some(Ok(Message::Ping(payload))) => Some(Message::Pong(payload)),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => None,
+            },
+            event = events.next() => match event {
+                Some(event) if combined => {
+                    Some(Message::Text(json!({"stream": listen_key, "data": event}).to_string()))
+                }
+                Some(event) => Some(Message::Text(event.to_string())),
+                None => break,
+            },
+        };
+
+        if let Some(message) = message {
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// Answers the websocket requests of `WebSocketStream`.
+fn reply(text: &str, listen_key: &str, combined: &mut bool) -> Option<Message> {
+    let request: Value = serde_json::from_str(text).ok()?;
+    let params = &request["params"];
+    let result = match request["method"].as_str()? {
+        "SET_PROPERTY" if params[0] == "combined" => {
+            *combined = params[1].as_bool().unwrap_or_default();
+            Value::Null
+        }
+        "GET_PROPERTY" if params[0] == "combined" => Value::Bool(*combined),
+        "LIST_SUBSCRIPTIONS" => json!([listen_key]),
+        _ => Value::Null,
+    };
+    let reply = json!({"result": result, "id": request["id"]});
+    Some(Message::Text(reply.to_string()))
+}
+
+/// Answers the requests of `WsApiClient` through the REST endpoint of each method.
+async fn serve_ws_api(engine: Arc<Mutex<Engine>>, stream: TcpStream) {
+    let mut ws = match async_tungstenite::tokio::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(_) => return,
+    };
+    let mut api_key = Value::Null;
+
+    while let Some(Ok(message)) = ws.next().await {
+        let request: Value = match message {
+            Message::Text(text) => match serde_json::from_str(&text) {
+                Ok(request) => request,
+                Err(_) => continue,
+            },
+            Message::Ping(payload) => {
+                if ws.send(Message::Pong(payload)).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let params = request["params"].as_object().cloned().unwrap_or_default();
+        let method = request["method"].as_str().unwrap_or_default();
+        if method == "session.logon" {
+            api_key = params["apiKey"].clone();
+        } else if method == "session.logout" {
+            api_key = Value::Null;
+        }
+
+        let (status, value) = match ws_api_endpoint(method) {
+            // signatures are not checked, so any key logs on
+            _ if method.starts_with("session.") => {
+                let now = chrono::Utc::now().timestamp_millis();
+                let session = json!({"apiKey": api_key, "serverTime": now});
+                (StatusCode::OK, session)
+            }
+            Some((method, path)) => {
+                let params: Vec<_> = params
+                    .iter()
+                    .map(|(key, value)| match value {
+                        Value::String(value) => (key.clone(), value.clone()),
+                        value => (key.clone(), value.to_string()),
+                    })
+                    .collect();
+                let params = serde_urlencoded::to_string(&params).unwrap_or_default();
+                respond(&engine, &method, path, &params)
+            }
+            None => not_supported(),
+        };
+
+        let response = match status {
+            StatusCode::OK => json!({"id": request["id"], "status": 200, "result": value}),
+            status => json!({"id": request["id"], "status": status.as_u16(), "error": value}),
+        };
+        if ws.send(Message::Text(response.to_string())).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Method and path of the REST endpoint answering a websocket api method.
+fn ws_api_endpoint(method: &str) -> Option<(Method, &'static str)> {
+    let endpoint = match method {
+        "ping" => (Method::GET, "/api/v3/ping"),
+        "time" => (Method::GET, "/api/v3/time"),
+        "order.test" => (Method::POST, "/api/v3/order/test"),
+        "order.place" => (Method::POST, "/api/v3/order"),
+        "order.status" => (Method::GET, "/api/v3/order"),
+        "order.cancel" => (Method::DELETE, "/api/v3/order"),
+        "openOrders.status" => (Method::GET, "/api/v3/openOrders"),
+        "openOrders.cancelAll" => (Method::DELETE, "/api/v3/openOrders"),
+        "allOrders" => (Method::GET, "/api/v3/allOrders"),
+        "account.status" => (Method::GET, "/api/v3/account"),
+        "account.rateLimits.orders" => (Method::GET, "/api/v3/rateLimit/order"),
+        "myTrades" => (Method::GET, "/api/v3/myTrades"),
+        "userDataStream.start" => (Method::POST, "/api/v3/userDataStream"),
+        "userDataStream.ping" => (Method::PUT, "/api/v3/userDataStream"),
+        "userDataStream.stop" => (Method::DELETE, "/api/v3/userDataStream"),
+        _ => return None,
+    };
+    Some(endpoint)
+}
+
+async fn handle(
+    engine: Arc<Mutex<Engine>>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let query = req.uri().query().unwrap_or_default().to_string();
+    let body = hyper::body::to_bytes(req.into_body())
+        .await
+        .unwrap_or_default();
+    let params = format!("{}&{}", query, String::from_utf8_lossy(&body));
+
+    let (status, value) = respond(&engine, &method, &path, &params);
+    let response = Response::builder()
+        .status(status)
+        .header("content-type", "application/json;charset=UTF-8")
+        .body(Body::from(value.to_string()))
+        .expect("valid response");
+    Ok(response)
+}
+
+/// Status and body of the answer to a request.
+fn respond(
+    engine: &Mutex<Engine>,
+    method: &Method,
+    path: &str,
+    params: &str,
+) -> (StatusCode, Value) {
+    match route(&mut engine.lock().unwrap(), method, path, params) {
+        Ok(value) => (StatusCode::OK, value),
+        Err(Some(reject)) => {
+            let mut value = json!({"code": reject.code, "msg": reject.msg});
+            if let Some(data) = reject.data {
+                value["data"] = data;
+            }
+            // binance answers partial cancel-replace failures with 409
+            match reject.code {
+                -2021 => (StatusCode::CONFLICT, value),
+                _ => (StatusCode::BAD_REQUEST, value),
+            }
+        }
+        Err(None) => not_supported(),
+    }
+}
+
+fn not_supported() -> (StatusCode, Value) {
+    (
+        StatusCode::NOT_FOUND,
+        json!({"code": -1000, "msg": "Not supported by the paper exchange."}),
+    )
+}
+
+/// Dispatches a request; `Err(None)` for unknown endpoints.
+fn route(
+    engine: &mut Engine,
+    method: &Method,
+    path: &str,
+    params: &str,
+) -> Result<Value, Option<Reject>> {
+    let value = match (method.as_str(), path) {
+        ("GET", "/api/v3/ping") => json!({}),
+        ("GET", "/api/v3/time") => json!({"serverTime": chrono::Utc::now().timestamp_millis()}),
+        ("GET", "/api/v3/ticker/bookTicker") => {
+            engine.book_ticker_json(parse::<Query>(params)?.symbol.as_deref())?
+        }
+        ("GET", "/api/v3/aggTrades") => {
+            let params = parse::<Query>(params)?;
+            engine
+                .agg_trades(
+                    params.symbol()?,
+                    params.from_id,
+                    params.start_time,
+                    params.end_time,
+                    params.limit(),
+                )?
+                .into()
+        }
+        ("POST", "/api/v3/order/test") => {
+            engine.test(&parse::<OrderParams>(params)?)?;
+            json!({})
+        }
+        ("POST", "/api/v3/order") => {
+            let params = parse::<OrderParams>(params)?;
+            let id = engine.place(&params)?;
+            let resp_type = match (params.new_order_resp_type, params.order_type) {
+                (Some(resp_type), _) => resp_type,
+                (None, OrderType::Market) | (None, OrderType::Limit) => OrderRespType::Full,
+                (None, _) => OrderRespType::Ack,
+            };
+            engine.new_order_json(id, resp_type)
+        }
+        ("GET", "/api/v3/order") => {
+            let params = parse::<OrderId>(params)?;
+            let id = engine.find(
+                &params.symbol,
+                params.order_id,
+                params.orig_client_order_id.as_deref(),
+            )?;
+            engine.order_json(id)
+        }
+        ("DELETE", "/api/v3/order") => {
+            let params = parse::<OrderId>(params)?;
+            let id = engine.cancel(
+                &params.symbol,
+                params.order_id,
+                params.orig_client_order_id.as_deref(),
+            )?;
+            let mut value = engine.order_json(id);
+            value["origClientOrderId"] = value["clientOrderId"].clone();
+            value
+        }
+        ("POST", "/api/v3/order/cancelReplace") => cancel_replace(engine, params)?,
+        ("PUT", "/api/v3/order/amend/keepPriority") => {
+            let params = parse::<AmendParams>(params)?;
+            let id = engine.amend(
+                &params.symbol,
+                params.order_id,
+                params.orig_client_order_id.as_deref(),
+                params.new_qty,
+                params.new_client_order_id,
+            )?;
+            json!({
+                "transactTime": engine.order_json(id)["updateTime"],
+                "executionId": id,
+                "amendedOrder": engine.order_json(id),
+            })
+        }
+        ("GET", "/api/v3/openOrders") => {
+            let params = parse::<Query>(params)?;
+            engine.open_orders(params.symbol.as_deref())?.into()
+        }
+        ("DELETE", "/api/v3/openOrders") => {
+            let params = parse::<Query>(params)?;
+            let mut canceled = Vec::new();
+            let mut lists = Vec::new();
+            for (id, list_id) in engine.cancel_all(params.symbol()?)? {
+                match list_id {
+                    -1 => canceled.push(engine.order_json(id)),
+                    list_id if !lists.contains(&list_id) => lists.push(list_id),
+                    _ => {}
+                }
+            }
+            for list_id in lists {
+                canceled.push(engine.list_json(list_id, true));
+            }
+            canceled.into()
+        }
+        ("GET", "/api/v3/allOrders") => {
+            let params = parse::<Query>(params)?;
+            engine
+                .all_orders(
+                    params.symbol()?,
+                    params.order_id,
+                    params.start_time,
+                    params.end_time,
+                    params.limit(),
+                )?
+                .into()
+        }
+        ("POST", "/api/v3/order/oco") => {
+            let list_id = engine.place_oco(&parse::<OcoParams>(params)?)?;
+            engine.list_json(list_id, true)
+        }
+        ("POST", "/api/v3/orderList/oco") => {
+            let list_id = engine.place_oco_list(
+                &leg(params, "above", "")?,
+                &leg(params, "below", "")?,
+                parse::<ListId>(params)?.list_client_order_id,
+            )?;
+            engine.list_json(list_id, true)
+        }
+        ("POST", "/api/v3/orderList/oto") => {
+            let list_id = engine.place_oto(
+                &leg(params, "working", "working")?,
+                &[leg(params, "pending", "pending")?],
+                parse::<ListId>(params)?.list_client_order_id,
+            )?;
+            engine.list_json(list_id, true)
+        }
+        ("POST", "/api/v3/orderList/otoco") => {
+            let pending = [
+                leg(params, "pendingAbove", "pending")?,
+                leg(params, "pendingBelow", "pending")?,
+            ];
+            let list_id = engine.place_oto(
+                &leg(params, "working", "working")?,
+                &pending,
+                parse::<ListId>(params)?.list_client_order_id,
+            )?;
+            engine.list_json(list_id, true)
+        }
+        ("DELETE", "/api/v3/orderList") => {
+            let params = parse::<ListId>(params)?;
+            let symbol = params.symbol.as_deref().unwrap_or_default();
+            let list_id = engine.cancel_list(
+                symbol,
+                params.order_list_id,
+                params.list_client_order_id.as_deref(),
+            )?;
+            engine.list_json(list_id, true)
+        }
+        ("GET", "/api/v3/orderList") => {
+            let params = parse::<ListId>(params)?;
+            let list_id = engine.find_list(
+                None,
+                params.order_list_id,
+                params.orig_client_order_id.as_deref(),
+            )?;
+            engine.list_json(list_id, false)
+        }
+        ("GET", "/api/v3/allOrderList") => {
+            let params = parse::<Query>(params)?;
+            let lists = engine.all_lists(
+                params.from_id,
+                params.start_time,
+                params.end_time,
+                params.limit(),
+                false,
+            );
+            lists.into()
+        }
+        ("GET", "/api/v3/openOrderList") => {
+            engine.all_lists(None, None, None, usize::MAX, true).into()
+        }
+        ("GET", "/api/v3/account") => engine.account_json(),
+        ("GET", "/api/v3/rateLimit/order") => engine.order_rate_limit_json(),
+        ("GET", "/api/v3/account/commission") => {
+            engine.commission_json(parse::<Query>(params)?.symbol()?)?
+        }
+        // the paper exchange has no self-trade prevention or smart order routing
+        ("GET", "/api/v3/myPreventedMatches") | ("GET", "/api/v3/myAllocations") => json!([]),
+        ("GET", "/api/v3/myTrades") => {
+            let params = parse::<Query>(params)?;
+            engine
+                .account_trades(
+                    params.symbol()?,
+                    params.order_id,
+                    params.from_id,
+                    params.start_time,
+                    params.end_time,
+                    params.limit(),
+                )?
+                .into()
+        }
+        ("POST", "/api/v3/userDataStream") => json!({"listenKey": "paper".repeat(12)}),
+        ("PUT", "/api/v3/userDataStream") | ("DELETE", "/api/v3/userDataStream") => json!({}),
+        _ => return Err(None),
+    };
+    Ok(value)
+}
+
+/// Cancels an order and places a new one; partial failures are errors carrying both results.
+fn cancel_replace(engine: &mut Engine, params: &str) -> Result<Value, Reject> {
+    let id = parse::<CancelReplaceId>(params)?;
+    let order = parse::<OrderParams>(params)?;
+
+    let canceled = engine.cancel(
+        &id.symbol,
+        id.cancel_order_id,
+        id.cancel_orig_client_order_id.as_deref(),
+    );
+    let (cancel_result, cancel_response) = match canceled {
+        Ok(canceled) => {
+            let mut value = engine.order_json(canceled);
+            value["origClientOrderId"] = value["clientOrderId"].clone();
+            ("SUCCESS", value)
+        }
+        Err(reject) => ("FAILURE", json!({"code": reject.code, "msg": reject.msg})),
+    };
+
+    let (new_order_result, new_order_response) = match cancel_result {
+        "FAILURE" if id.cancel_replace_mode == CancelReplaceMode::StopOnFailure => {
+            ("NOT_ATTEMPTED", Value::Null)
+        }
+        _ => match engine.place(&order) {
+            Ok(placed) => {
+                let resp_type = order.new_order_resp_type.unwrap_or(OrderRespType::Full);
+                ("SUCCESS", engine.new_order_json(placed, resp_type))
+            }
+            Err(reject) => ("FAILURE", json!({"code": reject.code, "msg": reject.msg})),
+        },
+    };
+
+    let data = json!({
+        "cancelResult": cancel_result,
+        "newOrderResult": new_order_result,
+        "cancelResponse": cancel_response,
+        "newOrderResponse": new_order_response,
+    });
+    match (cancel_result, new_order_result) {
+        ("SUCCESS", "SUCCESS") => Ok(data),
+        ("SUCCESS", _) | (_, "SUCCESS") => {
+            Err(Reject::new(-2021, "Order cancel-replace partially failed.").with_data(data))
+        }
+        _ => Err(Reject::new(-2022, "Order cancel-replace failed.").with_data(data)),
+    }
+}
+
+/// Order of an order list, from its parameters with the prefix, e.g. `aboveType` and
+/// `abovePrice`; side and quantity may be shared with the other orders of the prefix.
+fn leg(params: &str, prefix: &str, shared: &str) -> Result<OrderParams, Reject> {
+    let key = |prefix: &str, name: &str| match prefix {
+        "" => name.to_lowercase(),
+        prefix => format!("{}{}", prefix, name),
+    };
+    let names = [
+        (key(prefix, "Type"), "type"),
+        (key(prefix, "Price"), "price"),
+        (key(prefix, "StopPrice"), "stopPrice"),
+        (key(prefix, "TimeInForce"), "timeInForce"),
+        (key(prefix, "ClientOrderId"), "newClientOrderId"),
+        (key(shared, "Side"), "side"),
+        (key(shared, "Quantity"), "quantity"),
+        ("symbol".to_string(), "symbol"),
+    ];
+
+    let pairs = parse::<Vec<(String, String)>>(params)?;
+    let leg: Vec<_> = pairs
+        .iter()
+        .filter_map(|(key, value)| {
+            let (_, name) = names.iter().find(|(param, _)| param == key)?;
+            Some((*name, value.as_str()))
+        })
+        .collect();
+    parse(&serde_urlencoded::to_string(leg).expect("encodable pairs"))
+}
+
+fn parse<T: DeserializeOwned>(params: &str) -> Result<T, Reject> {
+    serde_urlencoded::from_str(params).map_err(|error| {
+        Reject::new(
+            -1102,
+            format!(
+                "Parameter was not sent, was empty/null, or malformed: {}",
+                error
+            ),
+        )
+    })
+}
@@ -1,8 +1,9 @@
+use crate::error::ValidationError;
+use crate::signer::AsyncSigner;
 use chrono::Utc;
-use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use sha2::Sha256;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
 
 #[derive(Copy, Clone, Debug)]
 pub enum ID<'a> {
@@ -10,14 +11,14 @@ pub enum ID<'a> {
     ClientOId(&'a str),
 }
 
-#[derive(Copy, Clone, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Side {
     Buy,
     Sell,
 }
 
-#[derive(Copy, Clone, Serialize)]
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OrderType {
     Limit,
@@ -29,7 +30,7 @@ pub enum OrderType {
     LimitMaker,
 }
 
-#[derive(Copy, Clone, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum TimeInForce {
     Gtc,
@@ -45,8 +46,58 @@ pub enum OrderRespType {
     Full,
 }
 
-#[derive(Copy, Clone, Serialize, Deserialize, Debug)]
+/// What happens when an order would trade against another order of the same account.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SelfTradePreventionMode {
+    None,
+    ExpireTaker,
+    ExpireMaker,
+    ExpireBoth,
+    Decrement,
+}
+
+/// Side of the book a pegged order follows.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PegPriceType {
+    /// Best price on the same side of the book.
+    PrimaryPeg,
+    /// Best price on the opposite side of the book.
+    MarketPeg,
+}
+
+/// What a cancel-replace does when the cancel fails.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelReplaceMode {
+    /// Does not place the new order.
+    StopOnFailure,
+    /// Places the new order anyway.
+    AllowFailure,
+}
+
+/// Status the order must have for a cancel-replace to cancel it.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelRestrictions {
+    OnlyNew,
+    OnlyPartiallyFilled,
+}
+
+/// What a cancel-replace does when the order rate limit is exceeded.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderRateLimitExceededMode {
+    DoNothing,
+    /// Still cancels the order.
+    CancelOnly,
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 pub enum Interval {
+    #[serde(rename = "1s")]
+    OneSecond,
     #[serde(rename = "1m")]
     OneMinute,
     #[serde(rename = "3m")]
@@ -79,6 +130,24 @@ pub enum Interval {
     OneMonth,
 }
 
+/// Window of the rolling window price change statistics.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+pub enum WindowSize {
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TickerType {
+    Full,
+    Mini,
+}
+
 #[derive(Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Parameters<'a> {
@@ -89,15 +158,28 @@ pub struct Parameters<'a> {
     pub end_time: Option<i64>,
     pub interval: Option<Interval>,
     pub side: Option<Side>,
-    #[serde(rename = "type")]
+    // both are sent as `type`; only the one that is set may be serialized
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub order_type: Option<OrderType>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ticker_type: Option<TickerType>,
+    pub window_size: Option<WindowSize>,
+    pub time_zone: Option<&'a str>,
     pub time_in_force: Option<TimeInForce>,
     pub quantity: Option<f64>,
+    pub quote_order_qty: Option<f64>,
     pub price: Option<f64>,
     pub new_client_order_id: Option<&'a str>,
     pub stop_price: Option<f64>,
     pub iceberg_qty: Option<f64>,
     pub new_order_resp_type: Option<OrderRespType>,
+    pub trailing_delta: Option<u32>,
+    pub self_trade_prevention_mode: Option<SelfTradePreventionMode>,
+    pub strategy_id: Option<i64>,
+    pub strategy_type: Option<i64>,
+    pub peg_price_type: Option<PegPriceType>,
+    pub peg_offset_value: Option<u32>,
+    pub peg_offset_type: Option<&'a str>,
     pub order_id: Option<i64>,
     pub orig_client_order_id: Option<&'a str>,
     pub list_client_order_id: Option<&'a str>,
@@ -108,6 +190,56 @@ pub struct Parameters<'a> {
     pub stop_limit_price: Option<f64>,
     pub stop_limit_time_in_force: Option<TimeInForce>,
     pub order_list_id: Option<i64>,
+    pub prevented_match_id: Option<i64>,
+    pub from_prevented_match_id: Option<i64>,
+    pub from_allocation_id: Option<i64>,
+    pub above_type: Option<OrderType>,
+    pub above_client_order_id: Option<&'a str>,
+    pub above_price: Option<f64>,
+    pub above_stop_price: Option<f64>,
+    pub above_iceberg_qty: Option<f64>,
+    pub above_time_in_force: Option<TimeInForce>,
+    pub below_type: Option<OrderType>,
+    pub below_client_order_id: Option<&'a str>,
+    pub below_price: Option<f64>,
+    pub below_stop_price: Option<f64>,
+    pub below_iceberg_qty: Option<f64>,
+    pub below_time_in_force: Option<TimeInForce>,
+    pub working_type: Option<OrderType>,
+    pub working_side: Option<Side>,
+    pub working_client_order_id: Option<&'a str>,
+    pub working_price: Option<f64>,
+    pub working_quantity: Option<f64>,
+    pub working_iceberg_qty: Option<f64>,
+    pub working_time_in_force: Option<TimeInForce>,
+    pub pending_type: Option<OrderType>,
+    pub pending_side: Option<Side>,
+    pub pending_client_order_id: Option<&'a str>,
+    pub pending_price: Option<f64>,
+    pub pending_stop_price: Option<f64>,
+    pub pending_quantity: Option<f64>,
+    pub pending_iceberg_qty: Option<f64>,
+    pub pending_time_in_force: Option<TimeInForce>,
+    pub pending_above_type: Option<OrderType>,
+    pub pending_above_client_order_id: Option<&'a str>,
+    pub pending_above_price: Option<f64>,
+    pub pending_above_stop_price: Option<f64>,
+    pub pending_above_iceberg_qty: Option<f64>,
+    pub pending_above_time_in_force: Option<TimeInForce>,
+    pub pending_below_type: Option<OrderType>,
+    pub pending_below_client_order_id: Option<&'a str>,
+    pub pending_below_price: Option<f64>,
+    pub pending_below_stop_price: Option<f64>,
+    pub pending_below_iceberg_qty: Option<f64>,
+    pub pending_below_time_in_force: Option<TimeInForce>,
+    pub cancel_replace_mode: Option<CancelReplaceMode>,
+    pub cancel_order_id: Option<i64>,
+    pub cancel_orig_client_order_id: Option<&'a str>,
+    pub cancel_new_client_order_id: Option<&'a str>,
+    pub cancel_restrictions: Option<CancelRestrictions>,
+    pub order_rate_limit_exceeded_mode: Option<OrderRateLimitExceededMode>,
+    pub new_qty: Option<f64>,
+    pub countdown_time: Option<u64>,
     pub listen_key: Option<&'a str>,
     pub address: Option<&'a str>,
     pub address_tag: Option<&'a str>,
@@ -120,20 +252,219 @@ pub struct Parameters<'a> {
     pub to_email: Option<&'a str>,
     pub amount: Option<f64>,
     pub recv_window: Option<usize>,
+    pub api_key: Option<&'a str>,
     pub timestamp: Option<i64>,
     pub signature: Option<String>,
 }
 
 impl<'a> Parameters<'a> {
-    pub fn sign<T: Into<String>>(&mut self, secret: T) -> crate::error::Result<&Self> {
+    pub async fn sign<S: AsyncSigner + ?Sized>(
+        &mut self,
+        signer: &S,
+    ) -> crate::error::Result<&Self> {
         self.timestamp = Some(Utc::now().timestamp_millis());
 
         let message = serde_urlencoded::to_string(&self)?;
-        let mut mac = Hmac::<Sha256>::new_from_slice(secret.into().as_bytes())?;
-        mac.update(message.as_bytes());
-        let signature = mac.finalize();
+        self.signature = Some(signer.sign_async(&message).await?);
+        Ok(self)
+    }
 
-        self.signature = Some(hex::encode(signature.into_bytes()));
+    /// Signs the parameters sorted by name, as the websocket api expects.
+    pub async fn sign_sorted<S: AsyncSigner + ?Sized>(
+        &mut self,
+        signer: &S,
+    ) -> crate::error::Result<&Self> {
+        self.timestamp = Some(Utc::now().timestamp_millis());
+
+        let sorted: BTreeMap<_, _> = self.to_map()?.into_iter().collect();
+        let message = serde_urlencoded::to_string(&sorted)?;
+        self.signature = Some(signer.sign_async(&message).await?);
         Ok(self)
     }
+    /// Checks the order options against the combinations binance accepts.
+    pub fn validate(&self) -> crate::error::Result<()> {
+        let invalid = |message: &str| Err(ValidationError::new(message).into());
+        let order_type = self.order_type;
+
+        if self.quote_order_qty.is_some() {
+            if order_type != Some(OrderType::Market) {
+                return invalid("quoteOrderQty is only valid for MARKET orders.");
+            }
+            if self.quantity.is_some() {
+                return invalid("quantity and quoteOrderQty can't be sent together.");
+            }
+        }
+
+        if let Some(trailing_delta) = self.trailing_delta {
+            let is_stop = matches!(
+                order_type,
+                Some(OrderType::StopLoss)
+                    | Some(OrderType::StopLossLimit)
+                    | Some(OrderType::TakeProfit)
+                    | Some(OrderType::TakeProfitLimit)
+            );
+            if !is_stop {
+                return invalid(
+                    "trailingDelta is only valid for stop loss and take profit orders.",
+                );
+            }
+            if trailing_delta == 0 {
+                return invalid("trailingDelta must be greater than 0.");
+            }
+        }
+
+        if let Some(strategy_type) = self.strategy_type {
+            if strategy_type < 1_000_000 {
+                return invalid("strategyType must be at least 1000000.");
+            }
+        }
+
+        if self.peg_price_type.is_some() {
+            let is_limit = matches!(
+                order_type,
+                Some(OrderType::Limit)
+                    | Some(OrderType::LimitMaker)
+                    | Some(OrderType::StopLossLimit)
+                    | Some(OrderType::TakeProfitLimit)
+            );
+            if !is_limit {
+                return invalid("pegPriceType is only valid for limit orders.");
+            }
+            if self.iceberg_qty.is_some() {
+                return invalid("pegPriceType can't be sent with icebergQty.");
+            }
+        }
+        if self.peg_offset_value.is_some() && self.peg_price_type.is_none() {
+            return invalid("pegOffsetValue requires pegPriceType.");
+        }
+        Ok(())
+    }
+    /// Parameters as a json object, leaving out the ones that are not set.
+    pub fn to_map(&self) -> crate::error::Result<Map<String, Value>> {
+        match serde_json::to_value(self)? {
+            Value::Object(map) => Ok(map.into_iter().filter(|(_, v)| !v.is_null()).collect()),
+            _ => Ok(Map::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    fn order(order_type: OrderType) -> Parameters<'static> {
+        Parameters {
+            symbol: Some("BNBUSDT"),
+            side: Some(Side::Buy),
+            order_type: Some(order_type),
+            quantity: Some(1.0),
+            ..Parameters::default()
+        }
+    }
+
+    fn rejection(params: &Parameters) -> String {
+        let error = params.validate().unwrap_err();
+        let error = error.source().unwrap().downcast_ref::<ValidationError>();
+        error.unwrap().to_string()
+    }
+
+    #[test]
+    fn to_map_keeps_order_type() {
+        let params = Parameters {
+            symbol: Some("BNBUSDT"),
+            side: Some(Side::Buy),
+            order_type: Some(OrderType::Limit),
+            price: Some(300.0),
+            quantity: Some(1.0),
+            ..Parameters::default()
+        };
+        let map = params.to_map().unwrap();
+        assert_eq!(map["type"], "LIMIT");
+    }
+
+    #[test]
+    fn to_map_keeps_ticker_type() {
+        let params = Parameters {
+            symbol: Some("BNBUSDT"),
+            ticker_type: Some(TickerType::Mini),
+            ..Parameters::default()
+        };
+        let map = params.to_map().unwrap();
+        assert_eq!(map["type"], "MINI");
+    }
+
+    #[test]
+    fn validate_quote_order_qty() {
+        let mut params = order(OrderType::Market);
+        params.quantity = None;
+        params.quote_order_qty = Some(100.0);
+        assert!(params.validate().is_ok());
+
+        params.quantity = Some(1.0);
+        assert_eq!(
+            rejection(&params),
+            "quantity and quoteOrderQty can't be sent together."
+        );
+
+        let mut params = order(OrderType::Limit);
+        params.quantity = None;
+        params.quote_order_qty = Some(100.0);
+        assert_eq!(
+            rejection(&params),
+            "quoteOrderQty is only valid for MARKET orders."
+        );
+    }
+
+    #[test]
+    fn validate_trailing_delta() {
+        let mut params = order(OrderType::StopLossLimit);
+        params.trailing_delta = Some(100);
+        assert!(params.validate().is_ok());
+
+        params.trailing_delta = Some(0);
+        assert_eq!(rejection(&params), "trailingDelta must be greater than 0.");
+
+        let mut params = order(OrderType::Limit);
+        params.trailing_delta = Some(100);
+        assert_eq!(
+            rejection(&params),
+            "trailingDelta is only valid for stop loss and take profit orders."
+        );
+    }
+
+    #[test]
+    fn validate_strategy_type() {
+        let mut params = order(OrderType::Market);
+        params.strategy_type = Some(1_000_000);
+        assert!(params.validate().is_ok());
+
+        params.strategy_type = Some(999_999);
+        assert_eq!(rejection(&params), "strategyType must be at least 1000000.");
+    }
+
+    #[test]
+    fn validate_peg() {
+        let mut params = order(OrderType::Limit);
+        params.peg_price_type = Some(PegPriceType::PrimaryPeg);
+        params.peg_offset_value = Some(1);
+        assert!(params.validate().is_ok());
+
+        params.iceberg_qty = Some(0.1);
+        assert_eq!(
+            rejection(&params),
+            "pegPriceType can't be sent with icebergQty."
+        );
+
+        let mut params = order(OrderType::Market);
+        params.peg_price_type = Some(PegPriceType::MarketPeg);
+        assert_eq!(
+            rejection(&params),
+            "pegPriceType is only valid for limit orders."
+        );
+
+        let mut params = order(OrderType::Limit);
+        params.peg_offset_value = Some(1);
+        assert_eq!(rejection(&params), "pegOffsetValue requires pegPriceType.");
+    }
 }
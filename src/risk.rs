@@ -0,0 +1,853 @@
+use crate::client::AccountClient;
+use crate::error::RiskError;
+use crate::model::{Account, AccountPosition, BookTicker, ExecutionReport, Order, OrderStatus};
+use crate::param::Side;
+use crate::ws_stream::WebSocketStream;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Orders closed by a response that are remembered at most; the oldest are dropped first,
+/// as their reports come long before that many more orders are answered.
+const MAX_ANSWERED: usize = 1_000;
+
+#[derive(Clone)]
+struct OpenOrder {
+    symbol: String,
+    side: Side,
+    price: f64,
+    /// Quantity not filled yet.
+    qty: f64,
+}
+
+#[derive(Default)]
+struct Limits {
+    max_notional: Option<f64>,
+    max_positions: HashMap<String, f64>,
+    max_open_orders: Option<usize>,
+    price_band: Option<f64>,
+    fat_finger: Option<f64>,
+}
+
+#[derive(Default)]
+struct State {
+    limits: Limits,
+    /// Base and quote asset by symbol.
+    symbols: HashMap<String, (String, String)>,
+    killed: bool,
+    /// Free plus locked balance by asset.
+    positions: HashMap<String, f64>,
+    reference_prices: HashMap<String, f64>,
+    /// Best bid and ask by symbol.
+    books: HashMap<String, (f64, f64)>,
+    open_orders: HashMap<i64, OpenOrder>,
+    /// Orders that passed the checks and are being sent.
+    pending: HashMap<u64, OpenOrder>,
+    next_pending: u64,
+    /// Orders closed by a report while orders were being sent, so that a late response
+    /// does not open them again; dropped once nothing is being sent.
+    reported: HashSet<i64>,
+    /// Orders closed by a response, so that a late report does not open them again;
+    /// dropped at their final report, or once [`MAX_ANSWERED`] newer ones are kept.
+    answered: VecDeque<i64>,
+}
+
+/// Pre-trade risk checks in front of the order placement of an [`AccountClient`].
+///
+/// Orders are checked against the configured limits and refused with a [`RiskError`]
+/// before any request is made. Positions and open orders are seeded by
+/// [`sync`](Self::sync) and kept up to date by the user data stream; prices come from
+/// trade, mark price and bookTicker events or are set directly. Checks that need a price
+/// refuse orders while none is known. Works the same against binance and a
+/// `PaperExchange` of the `paper` feature, and [`check`](Self::check) alone can guard
+/// orders sent any other way.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{AccountClient, Environment, RiskGuard, Side};
+/// use tokio_binance::error::RiskError;
+/// use std::error::Error;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+///     let guard = RiskGuard::new(client)
+///         .with_symbol("BNBUSDT", "BNB", "USDT")
+///         // optional: checks are off unless set.
+///         .with_max_notional(1_000.0)
+///         .with_max_position("BNB", 20.0)
+///         .with_max_open_orders(10)
+///         // optional: 5% around the last trade or mark price.
+///         .with_price_band(0.05)
+///         // optional: 1% through the best bid or ask.
+///         .with_fat_finger(0.01);
+///     guard.sync().await?;
+///     guard.set_reference_price("BNBUSDT", 300.0);
+///
+///     match guard.place_limit_order("BNBUSDT", Side::Buy, 400.0, 1.0).await {
+///         Ok(response) => println!("{}", response),
+///         Err(error) => match error.source().and_then(|s| s.downcast_ref::<RiskError>()) {
+///             Some(RiskError::PriceBand { .. }) => println!("too far from the market"),
+///             _ => return Err(error.into()),
+///         },
+///     }
+///
+///     guard.set_kill_switch(true);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct RiskGuard {
+    client: AccountClient,
+    state: Arc<Mutex<State>>,
+}
+
+impl RiskGuard {
+    pub fn new(client: AccountClient) -> Self {
+        Self {
+            client,
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+    /// Registers the assets of a symbol; orders on unknown symbols are refused.
+    pub fn with_symbol(self, symbol: &str, base: &str, quote: &str) -> Self {
+        self.state.lock().unwrap().symbols.insert(
+            symbol.to_uppercase(),
+            (base.to_uppercase(), quote.to_uppercase()),
+        );
+        self
+    }
+    /// Largest price times quantity of an order, in the quote asset.
+    pub fn with_max_notional(self, max_notional: f64) -> Self {
+        self.state.lock().unwrap().limits.max_notional = Some(max_notional);
+        self
+    }
+    /// Largest holding of the asset once the open orders and the new one fill.
+    pub fn with_max_position(self, asset: &str, max_position: f64) -> Self {
+        self.state
+            .lock()
+            .unwrap()
+            .limits
+            .max_positions
+            .insert(asset.to_uppercase(), max_position);
+        self
+    }
+    /// Most open orders per symbol.
+    pub fn with_max_open_orders(self, max_open_orders: usize) -> Self {
+        self.state.lock().unwrap().limits.max_open_orders = Some(max_open_orders);
+        self
+    }
+    /// Largest distance of a limit price from the last trade or mark price,
+    /// as a fraction of it.
+    pub fn with_price_band(self, price_band: f64) -> Self {
+        self.state.lock().unwrap().limits.price_band = Some(price_band);
+        self
+    }
+    /// Largest distance of a buy above the best ask or a sell below the best bid,
+    /// as a fraction of it.
+    pub fn with_fat_finger(self, fat_finger: f64) -> Self {
+        self.state.lock().unwrap().limits.fat_finger = Some(fat_finger);
+        self
+    }
+    /// Refuses every order while engaged; cancels are not affected.
+    pub fn set_kill_switch(&self, engaged: bool) {
+        self.state.lock().unwrap().killed = engaged;
+    }
+    pub fn is_killed(&self) -> bool {
+        self.state.lock().unwrap().killed
+    }
+    /// Sets the last trade or mark price of the symbol.
+    pub fn set_reference_price(&self, symbol: &str, price: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.reference_prices.insert(symbol.to_uppercase(), price);
+    }
+    pub fn set_book_ticker(&self, ticker: &BookTicker) {
+        let mut state = self.state.lock().unwrap();
+        state.books.insert(
+            ticker.symbol.to_uppercase(),
+            (ticker.bid_price, ticker.ask_price),
+        );
+    }
+    /// Replaces the positions with `get_account` and the open orders with `get_open_orders`.
+    pub async fn sync(&self) -> crate::error::Result<()> {
+        let account = self.client.get_account().json::<Account>().await?;
+        let orders = self.client.get_open_orders().json::<Vec<Order>>().await?;
+
+        let mut state = self.state.lock().unwrap();
+        state.positions = account
+            .balances
+            .iter()
+            .map(|balance| (balance.asset.clone(), balance.free + balance.locked))
+            .collect();
+        state.open_orders = orders
+            .iter()
+            .map(|order| {
+                let open = OpenOrder {
+                    symbol: order.symbol.clone(),
+                    side: order.side,
+                    price: order.price,
+                    qty: order.orig_qty - order.executed_qty,
+                };
+                (order.order_id, open)
+            })
+            .collect();
+        Ok(())
+    }
+    /// Updates the open orders.
+    pub fn apply_report(&self, report: &ExecutionReport) {
+        let mut state = self.state.lock().unwrap();
+        match report.status {
+            OrderStatus::New | OrderStatus::PendingNew | OrderStatus::PartiallyFilled => {
+                if !state.answered.contains(&report.order_id) {
+                    let open = OpenOrder {
+                        symbol: report.symbol.clone(),
+                        side: report.side,
+                        price: report.price,
+                        qty: report.qty - report.cumulative_qty,
+                    };
+                    state.open_orders.insert(report.order_id, open);
+                }
+            }
+            _ => {
+                state.open_orders.remove(&report.order_id);
+                if !state.forget_answered(report.order_id) && !state.pending.is_empty() {
+                    state.reported.insert(report.order_id);
+                }
+            }
+        }
+    }
+    /// Updates the positions.
+    pub fn apply_position(&self, position: &AccountPosition) {
+        let mut state = self.state.lock().unwrap();
+        for balance in &position.balances {
+            let holding = balance.free + balance.locked;
+            state.positions.insert(balance.asset.clone(), holding);
+        }
+    }
+    /// Applies user data, trade, aggTrade, mark price and bookTicker events of a stream
+    /// until it ends.
+    pub async fn process(&self, stream: &mut WebSocketStream) -> crate::error::Result<()> {
+        while let Some(event) = stream.stream_event::<Value>().await? {
+            let data = event.data;
+            match data["e"].as_str() {
+                Some("executionReport") => self.apply_report(&serde_json::from_value(data)?),
+                Some("outboundAccountPosition") => {
+                    self.apply_position(&serde_json::from_value(data)?)
+                }
+                Some("trade") | Some("aggTrade") | Some("markPriceUpdate") => {
+                    let price = data["p"].as_str().and_then(|price| price.parse().ok());
+                    if let (Some(symbol), Some(price)) = (data["s"].as_str(), price) {
+                        self.set_reference_price(symbol, price);
+                    }
+                }
+                // bookTicker events have no type
+                None if data["b"].is_string() && data["a"].is_string() => {
+                    self.set_book_ticker(&serde_json::from_value(data)?)
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+    /// Checks an order without sending it; market orders have no price.
+    pub fn check(
+        &self,
+        symbol: &str,
+        side: Side,
+        price: Option<f64>,
+        quantity: f64,
+    ) -> Result<(), RiskError> {
+        let state = self.state.lock().unwrap();
+        state.check(&symbol.to_uppercase(), side, price, quantity)?;
+        Ok(())
+    }
+    /// Places a limit order that passed the checks.
+    pub async fn place_limit_order(
+        &self,
+        symbol: &str,
+        side: Side,
+        price: f64,
+        quantity: f64,
+    ) -> crate::error::Result<Value> {
+        let pending = self.reserve(symbol, side, Some(price), quantity)?;
+        let response = self
+            .client
+            .place_limit_order(symbol, side, price, quantity, true)
+            .json::<Value>()
+            .await;
+        self.settle(pending, &response);
+        response
+    }
+    /// Places a market order that passed the checks.
+    pub async fn place_market_order(
+        &self,
+        symbol: &str,
+        side: Side,
+        quantity: f64,
+    ) -> crate::error::Result<Value> {
+        let pending = self.reserve(symbol, side, None, quantity)?;
+        let response = self
+            .client
+            .place_market_order(symbol, side, quantity, true)
+            .json::<Value>()
+            .await;
+        self.settle(pending, &response);
+        response
+    }
+
+    /// Checks the order and counts it as open while it is sent.
+    fn reserve(
+        &self,
+        symbol: &str,
+        side: Side,
+        price: Option<f64>,
+        quantity: f64,
+    ) -> Result<u64, RiskError> {
+        let symbol = symbol.to_uppercase();
+        let mut state = self.state.lock().unwrap();
+        let price = state.check(&symbol, side, price, quantity)?;
+
+        let id = state.next_pending;
+        state.next_pending += 1;
+        let open = OpenOrder {
+            symbol,
+            side,
+            price,
+            qty: quantity,
+        };
+        state.pending.insert(id, open);
+        Ok(id)
+    }
+
+    /// Replaces the pending order with the placed one, if it stays open.
+    fn settle(&self, pending: u64, response: &crate::error::Result<Value>) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(open) = state.pending.remove(&pending) {
+            if let Ok(response) = response {
+                state.settle(open, response);
+            }
+        }
+        if state.pending.is_empty() {
+            state.reported.clear();
+        }
+    }
+}
+
+impl State {
+    /// Checks the order; returns the price it is valued at.
+    fn check(
+        &self,
+        symbol: &str,
+        side: Side,
+        price: Option<f64>,
+        quantity: f64,
+    ) -> Result<f64, RiskError> {
+        let limits = &self.limits;
+        if self.killed {
+            return Err(RiskError::KillSwitch);
+        }
+        let (base, quote) = match self.symbols.get(symbol) {
+            Some(assets) => assets,
+            None => return Err(RiskError::UnknownSymbol(symbol.to_string())),
+        };
+        let no_market_data = || RiskError::NoMarketData(symbol.to_string());
+        let reference = self.reference_prices.get(symbol).copied();
+        let book = self.books.get(symbol).copied();
+
+        if let Some(limit) = limits.max_open_orders {
+            let open = self.open_orders().filter(|o| o.symbol == symbol).count();
+            if open >= limit {
+                return Err(RiskError::MaxOpenOrders {
+                    symbol: symbol.to_string(),
+                    open,
+                    limit,
+                });
+            }
+        }
+
+        if let (Some(band), Some(price)) = (limits.price_band, price) {
+            let reference = reference.ok_or_else(no_market_data)?;
+            if (price - reference).abs() > reference * band {
+                return Err(RiskError::PriceBand {
+                    price,
+                    reference,
+                    band,
+                });
+            }
+        }
+
+        if let (Some(limit), Some(price)) = (limits.fat_finger, price) {
+            let (bid, ask) = book.ok_or_else(no_market_data)?;
+            let (best, through) = match side {
+                Side::Buy => (ask, price > ask * (1.0 + limit)),
+                Side::Sell => (bid, price < bid * (1.0 - limit)),
+            };
+            if through {
+                return Err(RiskError::FatFinger { price, best, limit });
+            }
+        }
+
+        // market orders are valued at the last price, or the middle of the book
+        let price = match (price, reference, book) {
+            (Some(price), _, _) | (None, Some(price), _) => price,
+            (None, None, Some((bid, ask))) => (bid + ask) / 2.0,
+            (None, None, None) => match limits.max_notional.is_some() {
+                true => return Err(no_market_data()),
+                false => 0.0,
+            },
+        };
+
+        if let Some(limit) = limits.max_notional {
+            let notional = price * quantity;
+            if notional > limit {
+                return Err(RiskError::MaxNotional { notional, limit });
+            }
+        }
+
+        let (asset, amount) = match side {
+            Side::Buy => (base, quantity),
+            Side::Sell => (quote, price * quantity),
+        };
+        if let Some(&limit) = limits.max_positions.get(asset) {
+            let position = self.positions.get(asset).copied().unwrap_or_default()
+                + self.incoming(asset)
+                + amount;
+            if position > limit {
+                return Err(RiskError::MaxPosition {
+                    asset: asset.clone(),
+                    position,
+                    limit,
+                });
+            }
+        }
+        Ok(price)
+    }
+
+    fn open_orders(&self) -> impl Iterator<Item = &OpenOrder> {
+        self.open_orders.values().chain(self.pending.values())
+    }
+
+    /// Amount of the asset the open orders receive once filled.
+    fn incoming(&self, asset: &str) -> f64 {
+        self.open_orders()
+            .filter_map(|order| {
+                let (base, quote) = self.symbols.get(&order.symbol)?;
+                match order.side {
+                    Side::Buy if base == asset => Some(order.qty),
+                    Side::Sell if quote == asset => Some(order.qty * order.price),
+                    _ => None,
+                }
+            })
+            .sum::<f64>()
+    }
+
+    fn settle(&mut self, open: OpenOrder, response: &Value) {
+        let (order_id, status) = match (response["orderId"].as_i64(), response["status"].as_str()) {
+            (Some(order_id), Some(status)) => (order_id, status),
+            _ => return,
+        };
+        let executed = match response["executedQty"].as_str() {
+            Some(executed) => executed.parse::<f64>().unwrap_or_default(),
+            None => 0.0,
+        };
+        if self.reported.remove(&order_id) {
+            return;
+        }
+        match status {
+            "NEW" | "PARTIALLY_FILLED" => {
+                let open = OpenOrder {
+                    qty: open.qty - executed,
+                    ..open
+                };
+                self.open_orders.entry(order_id).or_insert(open);
+            }
+            _ => {
+                self.open_orders.remove(&order_id);
+                self.answered.push_back(order_id);
+                if self.answered.len() > MAX_ANSWERED {
+                    self.answered.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Drops an order closed by a response; returns whether it was remembered.
+    fn forget_answered(&mut self, order_id: i64) -> bool {
+        match self
+            .answered
+            .iter()
+            .position(|&answered| answered == order_id)
+        {
+            Some(index) => self.answered.remove(index).is_some(),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "paper")]
+    use crate::PaperExchange;
+    use serde_json::json;
+    #[cfg(feature = "paper")]
+    use std::error::Error;
+
+    #[cfg(feature = "paper")]
+    async fn paper() -> (PaperExchange, RiskGuard) {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 1000.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let guard = RiskGuard::new(client).with_symbol("BNBUSDT", "BNB", "USDT");
+        (paper, guard)
+    }
+
+    /// Guard whose client is never used.
+    fn guard() -> RiskGuard {
+        let client = AccountClient::connect("<api-key>", "<secret-key>", "http://127.0.0.1:1");
+        RiskGuard::new(client.unwrap()).with_symbol("BNBUSDT", "BNB", "USDT")
+    }
+
+    #[cfg(feature = "paper")]
+    async fn paper_orders(guard: &RiskGuard) -> usize {
+        let orders = guard.client.get_open_orders().json::<Vec<Order>>().await;
+        orders.unwrap().len()
+    }
+
+    fn report(order_id: i64, status: &str) -> ExecutionReport {
+        let value = json!({
+            "E": 0, "s": "BNBUSDT", "c": "order", "S": "BUY", "o": "LIMIT", "f": "GTC",
+            "q": "1.0", "p": "300.0", "P": "0.0", "g": -1, "x": "NEW", "X": status, "r": "NONE",
+            "i": order_id, "l": "0.0", "z": "0.0", "L": "0.0", "n": "0", "N": null, "T": 0,
+            "t": -1, "w": true, "m": false, "O": 0, "Z": "0.0", "Y": "0.0"
+        });
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn response(order_id: i64, status: &str) -> crate::error::Result<Value> {
+        Ok(json!({"orderId": order_id, "status": status, "executedQty": "0.0"}))
+    }
+
+    fn open(guard: &RiskGuard) -> Vec<i64> {
+        guard
+            .state
+            .lock()
+            .unwrap()
+            .open_orders
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    fn remembered(guard: &RiskGuard) -> usize {
+        let state = guard.state.lock().unwrap();
+        state.reported.len() + state.answered.len()
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn refused_orders_are_not_sent() {
+        let (paper, guard) = paper().await;
+        let guard = guard.with_max_notional(100.0);
+        let refused = guard
+            .place_limit_order("BNBUSDT", Side::Buy, 300.0, 1.0)
+            .await
+            .unwrap_err();
+        let refused = refused.source().unwrap().downcast_ref::<RiskError>();
+        assert_eq!(
+            refused,
+            Some(&RiskError::MaxNotional {
+                notional: 300.0,
+                limit: 100.0
+            })
+        );
+
+        assert_eq!(paper_orders(&guard).await, 0);
+        assert_eq!(paper.balance("USDT"), (1000.0, 0.0));
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn placed_orders_count_as_open() {
+        let (paper, guard) = paper().await;
+        let guard = guard.with_max_open_orders(1);
+        let placed = guard
+            .place_limit_order("BNBUSDT", Side::Buy, 300.0, 1.0)
+            .await
+            .unwrap();
+        assert_eq!(placed["status"], "NEW");
+        assert_eq!(open(&guard), vec![placed["orderId"].as_i64().unwrap()]);
+        assert_eq!(paper_orders(&guard).await, 1);
+
+        let refused = guard
+            .place_limit_order("BNBUSDT", Side::Buy, 290.0, 1.0)
+            .await
+            .unwrap_err();
+        let refused = refused.source().unwrap().downcast_ref::<RiskError>();
+        assert_eq!(
+            refused,
+            Some(&RiskError::MaxOpenOrders {
+                symbol: "BNBUSDT".to_string(),
+                open: 1,
+                limit: 1
+            })
+        );
+        assert_eq!(paper_orders(&guard).await, 1);
+        assert_eq!(paper.balance("USDT"), (700.0, 300.0));
+    }
+
+    #[test]
+    fn late_responses_do_not_open_closed_orders() {
+        let guard = guard();
+        let pending = guard
+            .reserve("BNBUSDT", Side::Buy, Some(300.0), 1.0)
+            .unwrap();
+        guard.apply_report(&report(1, "FILLED"));
+        assert_eq!(remembered(&guard), 1);
+
+        guard.settle(pending, &response(1, "NEW"));
+        assert!(open(&guard).is_empty());
+        assert_eq!(remembered(&guard), 0);
+    }
+
+    #[test]
+    fn late_reports_do_not_open_closed_orders() {
+        let guard = guard();
+        let pending = guard
+            .reserve("BNBUSDT", Side::Buy, Some(300.0), 1.0)
+            .unwrap();
+        guard.settle(pending, &response(1, "FILLED"));
+        assert_eq!(remembered(&guard), 1);
+
+        guard.apply_report(&report(1, "NEW"));
+        assert!(open(&guard).is_empty());
+        guard.apply_report(&report(1, "FILLED"));
+        assert_eq!(remembered(&guard), 0);
+    }
+
+    #[test]
+    fn closed_orders_are_not_remembered_once_nothing_is_sent() {
+        let guard = guard();
+        guard.apply_report(&report(1, "NEW"));
+        assert_eq!(open(&guard), vec![1]);
+        guard.apply_report(&report(1, "CANCELED"));
+        assert!(open(&guard).is_empty());
+        assert_eq!(remembered(&guard), 0);
+
+        // reports while an order is sent are kept until its response
+        let pending = guard
+            .reserve("BNBUSDT", Side::Buy, Some(300.0), 1.0)
+            .unwrap();
+        guard.apply_report(&report(2, "NEW"));
+        guard.apply_report(&report(2, "FILLED"));
+        assert_eq!(remembered(&guard), 1);
+        guard.settle(pending, &response(3, "NEW"));
+        assert_eq!(open(&guard), vec![3]);
+        assert_eq!(remembered(&guard), 0);
+    }
+
+    #[test]
+    fn answered_orders_are_bounded_without_reports() {
+        let guard = guard();
+        for order_id in 0..MAX_ANSWERED as i64 + 10 {
+            let pending = guard
+                .reserve("BNBUSDT", Side::Buy, Some(300.0), 1.0)
+                .unwrap();
+            guard.settle(pending, &response(order_id, "FILLED"));
+        }
+        assert_eq!(remembered(&guard), MAX_ANSWERED);
+
+        // the oldest were dropped, the newest still keep late reports out
+        guard.apply_report(&report(0, "NEW"));
+        guard.apply_report(&report(MAX_ANSWERED as i64, "NEW"));
+        assert_eq!(open(&guard), vec![0]);
+    }
+
+    fn state() -> State {
+        let mut state = State::default();
+        let assets = ("BNB".to_string(), "USDT".to_string());
+        state.symbols.insert("BNBUSDT".to_string(), assets);
+        state
+    }
+
+    #[test]
+    fn check_refuses_orders_while_killed() {
+        let mut state = state();
+        state.killed = true;
+        let refused = state.check("BNBUSDT", Side::Buy, Some(300.0), 1.0);
+        assert_eq!(refused, Err(RiskError::KillSwitch));
+    }
+
+    #[test]
+    fn check_refuses_unknown_symbols() {
+        let refused = state().check("BTCUSDT", Side::Buy, Some(300.0), 1.0);
+        assert_eq!(
+            refused,
+            Err(RiskError::UnknownSymbol("BTCUSDT".to_string()))
+        );
+    }
+
+    #[test]
+    fn check_needs_market_data_for_price_checks() {
+        let no_market_data = Err(RiskError::NoMarketData("BNBUSDT".to_string()));
+        let mut banded = state();
+        banded.limits.price_band = Some(0.05);
+        let refused = banded.check("BNBUSDT", Side::Buy, Some(300.0), 1.0);
+        assert_eq!(refused, no_market_data);
+
+        let mut fat_finger = state();
+        fat_finger.limits.fat_finger = Some(0.01);
+        let refused = fat_finger.check("BNBUSDT", Side::Buy, Some(300.0), 1.0);
+        assert_eq!(refused, no_market_data);
+
+        let mut notional = state();
+        notional.limits.max_notional = Some(1000.0);
+        let refused = notional.check("BNBUSDT", Side::Buy, None, 1.0);
+        assert_eq!(refused, no_market_data);
+        // market orders pass unvalued without a notional limit
+        assert_eq!(state().check("BNBUSDT", Side::Buy, None, 1.0), Ok(0.0));
+    }
+
+    #[test]
+    fn check_limits_the_notional() {
+        let mut state = state();
+        state.limits.max_notional = Some(1000.0);
+        assert_eq!(
+            state.check("BNBUSDT", Side::Buy, Some(300.0), 3.0),
+            Ok(300.0)
+        );
+        assert_eq!(
+            state.check("BNBUSDT", Side::Sell, Some(300.0), 4.0),
+            Err(RiskError::MaxNotional {
+                notional: 1200.0,
+                limit: 1000.0
+            })
+        );
+    }
+
+    #[test]
+    fn check_values_market_orders_at_the_last_price_then_the_book() {
+        let mut state = state();
+        state.limits.max_notional = Some(1000.0);
+        state.books.insert("BNBUSDT".to_string(), (299.0, 301.0));
+        assert_eq!(state.check("BNBUSDT", Side::Buy, None, 1.0), Ok(300.0));
+
+        state.reference_prices.insert("BNBUSDT".to_string(), 250.0);
+        assert_eq!(state.check("BNBUSDT", Side::Buy, None, 4.0), Ok(250.0));
+        assert_eq!(
+            state.check("BNBUSDT", Side::Buy, None, 5.0),
+            Err(RiskError::MaxNotional {
+                notional: 1250.0,
+                limit: 1000.0
+            })
+        );
+    }
+
+    #[test]
+    fn check_limits_the_position_with_the_open_orders() {
+        let mut state = state();
+        state.limits.max_positions.insert("BNB".to_string(), 10.0);
+        state
+            .limits
+            .max_positions
+            .insert("USDT".to_string(), 1000.0);
+        state.positions.insert("BNB".to_string(), 5.0);
+        let order = OpenOrder {
+            symbol: "BNBUSDT".to_string(),
+            side: Side::Buy,
+            price: 300.0,
+            qty: 3.0,
+        };
+        state.open_orders.insert(1, order);
+
+        assert_eq!(
+            state.check("BNBUSDT", Side::Buy, Some(300.0), 2.0),
+            Ok(300.0)
+        );
+        assert_eq!(
+            state.check("BNBUSDT", Side::Buy, Some(300.0), 3.0),
+            Err(RiskError::MaxPosition {
+                asset: "BNB".to_string(),
+                position: 11.0,
+                limit: 10.0
+            })
+        );
+        // sells receive the quote asset
+        assert_eq!(
+            state.check("BNBUSDT", Side::Sell, Some(300.0), 4.0),
+            Err(RiskError::MaxPosition {
+                asset: "USDT".to_string(),
+                position: 1200.0,
+                limit: 1000.0
+            })
+        );
+    }
+
+    #[test]
+    fn check_limits_the_open_orders_including_pending_ones() {
+        let mut state = state();
+        state.limits.max_open_orders = Some(2);
+        let order = OpenOrder {
+            symbol: "BNBUSDT".to_string(),
+            side: Side::Buy,
+            price: 300.0,
+            qty: 1.0,
+        };
+        state.open_orders.insert(1, order.clone());
+        assert!(state.check("BNBUSDT", Side::Buy, Some(300.0), 1.0).is_ok());
+
+        state.pending.insert(0, order);
+        assert_eq!(
+            state.check("BNBUSDT", Side::Buy, Some(300.0), 1.0),
+            Err(RiskError::MaxOpenOrders {
+                symbol: "BNBUSDT".to_string(),
+                open: 2,
+                limit: 2
+            })
+        );
+    }
+
+    #[test]
+    fn check_keeps_limit_prices_in_the_band() {
+        let mut state = state();
+        state.limits.price_band = Some(0.05);
+        state.reference_prices.insert("BNBUSDT".to_string(), 300.0);
+        assert!(state.check("BNBUSDT", Side::Buy, Some(314.0), 1.0).is_ok());
+        assert!(state.check("BNBUSDT", Side::Sell, Some(286.0), 1.0).is_ok());
+        assert_eq!(
+            state.check("BNBUSDT", Side::Sell, Some(284.0), 1.0),
+            Err(RiskError::PriceBand {
+                price: 284.0,
+                reference: 300.0,
+                band: 0.05
+            })
+        );
+    }
+
+    #[test]
+    fn check_refuses_prices_through_the_book() {
+        let mut state = state();
+        state.limits.fat_finger = Some(0.01);
+        state.books.insert("BNBUSDT".to_string(), (200.0, 300.0));
+        // far from the opposite side is fine
+        assert!(state.check("BNBUSDT", Side::Buy, Some(150.0), 1.0).is_ok());
+        assert!(state.check("BNBUSDT", Side::Buy, Some(302.0), 1.0).is_ok());
+        assert_eq!(
+            state.check("BNBUSDT", Side::Buy, Some(304.0), 1.0),
+            Err(RiskError::FatFinger {
+                price: 304.0,
+                best: 300.0,
+                limit: 0.01
+            })
+        );
+        assert_eq!(
+            state.check("BNBUSDT", Side::Sell, Some(197.0), 1.0),
+            Err(RiskError::FatFinger {
+                price: 197.0,
+                best: 200.0,
+                limit: 0.01
+            })
+        );
+    }
+}
@@ -0,0 +1,808 @@
+use crate::client::AccountClient;
+use crate::error::ClientError;
+use crate::model::{AggTrade, ApiError, Kline, KlineEvent, StreamEvent, Trade};
+use crate::param::{Side, ID};
+use crate::ws_stream::{ChannelBuf, WebSocketStream};
+use chrono::Utc;
+use futures::future::Either;
+use futures::{stream, Stream, StreamExt};
+use serde_json::Value;
+use std::error::Error;
+
+/// Quantities below this are treated as zero.
+const EPSILON: f64 = 1e-9;
+
+/// Market data passed to a [`Strategy`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarketEvent {
+    Kline(Kline),
+    Trade(Trade),
+    AggTrade(AggTrade),
+}
+
+impl MarketEvent {
+    /// Close time of a kline or time of a trade.
+    pub fn time(&self) -> i64 {
+        match self {
+            Self::Kline(kline) => kline.close_time,
+            Self::Trade(trade) => trade.time,
+            Self::AggTrade(trade) => trade.time,
+        }
+    }
+    /// Close price of a kline or price of a trade.
+    pub fn price(&self) -> f64 {
+        match self {
+            Self::Kline(kline) => kline.close,
+            Self::Trade(trade) => trade.price,
+            Self::AggTrade(trade) => trade.price,
+        }
+    }
+}
+
+impl From<Kline> for MarketEvent {
+    fn from(kline: Kline) -> Self {
+        Self::Kline(kline)
+    }
+}
+
+impl From<Trade> for MarketEvent {
+    fn from(trade: Trade) -> Self {
+        Self::Trade(trade)
+    }
+}
+
+impl From<AggTrade> for MarketEvent {
+    fn from(trade: AggTrade) -> Self {
+        Self::AggTrade(trade)
+    }
+}
+
+/// Fill of an order placed by a strategy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderFill {
+    pub client_order_id: String,
+    pub time: i64,
+    pub side: Side,
+    pub price: f64,
+    pub qty: f64,
+    pub fee: f64,
+    pub fee_asset: String,
+    pub is_maker: bool,
+    /// Profit of a sell against the average buy price, after fees; zero for buys.
+    pub realized_pnl: f64,
+}
+
+/// Order or cancel of a strategy refused by the exchange.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderRejection {
+    /// Id of the refused order, or of the order a refused cancel was for.
+    pub client_order_id: String,
+    /// Binance error code, e.g. -2010 for an insufficient balance; zero if none was sent.
+    pub code: i64,
+    pub message: String,
+}
+
+/// Limit order of a strategy that is not completely filled yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpenOrder {
+    pub client_order_id: String,
+    pub side: Side,
+    pub price: f64,
+    /// Remaining quantity.
+    pub qty: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum OrderRequest {
+    Market(String, Side, f64),
+    Limit(OpenOrder),
+    Cancel(String),
+}
+
+/// Trading logic shared by [`Backtest`](crate::Backtest) and [`LiveRunner`].
+///
+/// Orders are placed through the [`Context`] and sent once the callback returns.
+/// # Example
+///
+/// ```
+/// use tokio_binance::{Context, MarketEvent, Strategy};
+///
+/// /// Buys when the close crosses above the average of the last closes, sells when below.
+/// struct Crossover {
+///     closes: Vec<f64>,
+/// }
+///
+/// impl Strategy for Crossover {
+///     fn on_event(&mut self, ctx: &mut Context, event: &MarketEvent) {
+///         self.closes.push(event.price());
+///         let window = &self.closes[self.closes.len().saturating_sub(20)..];
+///         let average = window.iter().sum::<f64>() / window.len() as f64;
+///
+///         if event.price() > average && ctx.position() == 0.0 {
+///             ctx.buy(ctx.cash() / event.price());
+///         } else if event.price() < average && ctx.position() > 0.0 {
+///             ctx.sell(ctx.position());
+///         }
+///     }
+/// }
+/// ```
+pub trait Strategy {
+    /// Called for every closed kline or trade.
+    fn on_event(&mut self, ctx: &mut Context, event: &MarketEvent);
+    /// Called for every fill of an order of the strategy.
+    fn on_fill(&mut self, _ctx: &mut Context, _fill: &OrderFill) {}
+    /// Called for every order or cancel of the strategy the exchange refuses;
+    /// a refused order is no longer open.
+    fn on_reject(&mut self, _ctx: &mut Context, _rejection: &OrderRejection) {}
+}
+
+/// Account state of a strategy and the orders it places.
+#[derive(Clone, Debug)]
+pub struct Context {
+    symbol: String,
+    base: String,
+    quote: String,
+    time: i64,
+    price: f64,
+    position: f64,
+    cash: f64,
+    avg_price: f64,
+    realized_pnl: f64,
+    fees: f64,
+    open_orders: Vec<OpenOrder>,
+    requests: Vec<OrderRequest>,
+    prefix: String,
+    next_id: u64,
+}
+
+impl Context {
+    pub(crate) fn new(symbol: &str, base: &str, quote: &str, position: f64, cash: f64) -> Self {
+        Self {
+            symbol: symbol.to_uppercase(),
+            base: base.to_uppercase(),
+            quote: quote.to_uppercase(),
+            time: 0,
+            price: 0.0,
+            position,
+            cash,
+            avg_price: 0.0,
+            realized_pnl: 0.0,
+            fees: 0.0,
+            open_orders: Vec::new(),
+            requests: Vec::new(),
+            prefix: format!("strategy{}-", Utc::now().timestamp_millis()),
+            next_id: 1,
+        }
+    }
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+    /// Time of the current event.
+    pub fn time(&self) -> i64 {
+        self.time
+    }
+    /// Price of the current event.
+    pub fn price(&self) -> f64 {
+        self.price
+    }
+    /// Held quantity of the base asset.
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+    /// Held quantity of the quote asset.
+    pub fn cash(&self) -> f64 {
+        self.cash
+    }
+    /// Cash plus the position at the current price.
+    pub fn equity(&self) -> f64 {
+        self.cash + self.position * self.price
+    }
+    /// Average buy price of the position, fees included.
+    pub fn avg_price(&self) -> f64 {
+        self.avg_price
+    }
+    /// Realized profit of all sells so far.
+    pub fn realized_pnl(&self) -> f64 {
+        self.realized_pnl
+    }
+    /// Fees paid so far, in the quote asset.
+    pub fn fees(&self) -> f64 {
+        self.fees
+    }
+    pub fn open_orders(&self) -> &[OpenOrder] {
+        &self.open_orders
+    }
+    /// Buys at market; returns the client order id.
+    pub fn buy(&mut self, qty: f64) -> String {
+        self.market(Side::Buy, qty)
+    }
+    /// Sells at market; returns the client order id.
+    pub fn sell(&mut self, qty: f64) -> String {
+        self.market(Side::Sell, qty)
+    }
+    /// Places a limit buy; returns the client order id.
+    pub fn buy_limit(&mut self, price: f64, qty: f64) -> String {
+        self.limit(Side::Buy, price, qty)
+    }
+    /// Places a limit sell; returns the client order id.
+    pub fn sell_limit(&mut self, price: f64, qty: f64) -> String {
+        self.limit(Side::Sell, price, qty)
+    }
+    /// Cancels the open order.
+    pub fn cancel(&mut self, client_order_id: &str) {
+        if self.remove_order(client_order_id) {
+            self.requests
+                .push(OrderRequest::Cancel(client_order_id.to_string()));
+        }
+    }
+    /// Cancels all open orders.
+    pub fn cancel_all(&mut self) {
+        for order in std::mem::take(&mut self.open_orders) {
+            self.requests
+                .push(OrderRequest::Cancel(order.client_order_id));
+        }
+    }
+
+    fn market(&mut self, side: Side, qty: f64) -> String {
+        let client_order_id = self.next_client_order_id();
+        self.requests
+            .push(OrderRequest::Market(client_order_id.clone(), side, qty));
+        client_order_id
+    }
+
+    fn limit(&mut self, side: Side, price: f64, qty: f64) -> String {
+        let order = OpenOrder {
+            client_order_id: self.next_client_order_id(),
+            side,
+            price,
+            qty,
+        };
+        self.open_orders.push(order.clone());
+        self.requests.push(OrderRequest::Limit(order.clone()));
+        order.client_order_id
+    }
+
+    fn next_client_order_id(&mut self) -> String {
+        let id = format!("{}{}", self.prefix, self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    pub(crate) fn is_own(&self, client_order_id: &str) -> bool {
+        client_order_id.starts_with(&self.prefix)
+    }
+
+    pub(crate) fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub(crate) fn quote(&self) -> &str {
+        &self.quote
+    }
+
+    pub(crate) fn set_market(&mut self, event: &MarketEvent) {
+        self.time = event.time();
+        self.price = event.price();
+    }
+
+    pub(crate) fn take_requests(&mut self) -> Vec<OrderRequest> {
+        std::mem::take(&mut self.requests)
+    }
+
+    pub(crate) fn remove_order(&mut self, client_order_id: &str) -> bool {
+        let len = self.open_orders.len();
+        self.open_orders
+            .retain(|order| order.client_order_id != client_order_id);
+        self.open_orders.len() < len
+    }
+
+    /// Applies the fill to the position, cash and open orders.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn fill(
+        &mut self,
+        client_order_id: &str,
+        time: i64,
+        side: Side,
+        price: f64,
+        qty: f64,
+        fee: f64,
+        fee_asset: &str,
+        is_maker: bool,
+    ) -> OrderFill {
+        let (base_fee, quote_fee) = match fee_asset {
+            asset if asset == self.base => (fee, 0.0),
+            asset if asset == self.quote => (0.0, fee),
+            _ => (0.0, 0.0),
+        };
+        let fee_value = base_fee * price + quote_fee;
+
+        let mut realized_pnl = 0.0;
+        match side {
+            Side::Buy => {
+                let received = qty - base_fee;
+                let cost = self.avg_price * self.position.max(0.0) + price * qty + quote_fee;
+                self.position += received;
+                self.cash -= price * qty + quote_fee;
+                if self.position > EPSILON {
+                    self.avg_price = cost / self.position;
+                }
+            }
+            Side::Sell => {
+                realized_pnl = (price - self.avg_price) * qty - fee_value;
+                self.position -= qty + base_fee;
+                self.cash += price * qty - quote_fee;
+                if self.position <= EPSILON {
+                    self.avg_price = 0.0;
+                }
+            }
+        }
+        self.realized_pnl += realized_pnl;
+        self.fees += fee_value;
+
+        if let Some(order) = self
+            .open_orders
+            .iter_mut()
+            .find(|order| order.client_order_id == client_order_id)
+        {
+            order.qty -= qty;
+            if order.qty <= EPSILON {
+                self.remove_order(client_order_id);
+            }
+        }
+
+        OrderFill {
+            client_order_id: client_order_id.to_string(),
+            time,
+            side,
+            price,
+            qty,
+            fee,
+            fee_asset: fee_asset.to_string(),
+            is_maker,
+            realized_pnl,
+        }
+    }
+}
+
+/// Runs a [`Strategy`] against the exchange.
+///
+/// Market data is read from a kline, trade or aggTrade stream, klines are passed once
+/// closed as in a backtest. Fills are read from the user data stream. Orders go through
+/// the [`AccountClient`], which may also point at a `PaperExchange` of the `paper` feature.
+/// Orders the exchange refuses are passed to [`Strategy::on_reject`] and the other orders
+/// are still sent; only connection and stream errors end the run.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{AccountClient, Channel, Environment, Interval, LiveRunner, WebSocketStream};
+/// use tokio_binance::{Context, MarketEvent, Strategy, UserDataClient};
+/// use serde_json::Value;
+///
+/// struct Hold;
+///
+/// impl Strategy for Hold {
+///     fn on_event(&mut self, ctx: &mut Context, event: &MarketEvent) {
+///         if ctx.position() == 0.0 {
+///             ctx.buy(0.1);
+///         }
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+///     let user_data = UserDataClient::connect("<api-key>", Environment::BinanceUs)?;
+///     let value = user_data.start_stream().json::<Value>().await?;
+///     let listen_key = value["listenKey"].as_str().unwrap();
+///
+///     let channel = Channel::Kline("BNBUSDT", Interval::OneMinute);
+///     let mut market = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
+///     let channel = Channel::UserData(listen_key);
+///     let mut user = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
+///
+///     let runner = LiveRunner::new(client, "BNBUSDT", "BNB", "USDT");
+///     let ctx = runner.run(&mut Hold, &mut market, &mut user).await?;
+///     println!("realized {}", ctx.realized_pnl());
+///     Ok(())
+/// }
+/// ```
+pub struct LiveRunner {
+    client: AccountClient,
+    symbol: String,
+    base: String,
+    quote: String,
+}
+
+impl LiveRunner {
+    pub fn new<S: Into<String>>(client: AccountClient, symbol: S, base: S, quote: S) -> Self {
+        Self {
+            client,
+            symbol: symbol.into().to_uppercase(),
+            base: base.into().to_uppercase(),
+            quote: quote.into().to_uppercase(),
+        }
+    }
+    /// Runs the strategy until the market stream ends; returns the final context.
+    ///
+    /// The position and cash start from the free balances of the account.
+    pub async fn run<S: Strategy>(
+        &self,
+        strategy: &mut S,
+        market: &mut WebSocketStream,
+        user: &mut WebSocketStream,
+    ) -> crate::error::Result<Context> {
+        let account = self.client.get_account().json::<Value>().await?;
+        let free = |asset: &str| {
+            let balances = account["balances"].as_array();
+            let balance = balances
+                .and_then(|balances| balances.iter().find(|balance| balance["asset"] == asset));
+            match balance {
+                Some(balance) => balance["free"]
+                    .as_str()
+                    .and_then(|free| free.parse().ok())
+                    .unwrap_or_default(),
+                None => 0.0,
+            }
+        };
+
+        let mut ctx = Context::new(
+            &self.symbol,
+            &self.base,
+            &self.quote,
+            free(&self.base),
+            free(&self.quote),
+        );
+
+        // the streams are merged rather than raced, so no read is dropped halfway
+        let market = events(market).map(Either::Left);
+        let user = events(user).map(Either::Right);
+        let events = stream::select(market, user);
+        futures::pin_mut!(events);
+
+        while let Some(event) = events.next().await {
+            match event {
+                Either::Left(event) => {
+                    let event = match event? {
+                        Some(event) => event,
+                        None => return Ok(ctx),
+                    };
+                    if let Some(event) = self.market_event(event)? {
+                        ctx.set_market(&event);
+                        strategy.on_event(&mut ctx, &event);
+                        self.submit(strategy, &mut ctx).await?;
+                    }
+                }
+                Either::Right(event) => {
+                    let event = match event? {
+                        Some(event) => event,
+                        None => return Ok(ctx),
+                    };
+                    if let Some(fill) = self.execution(&mut ctx, &event.data) {
+                        strategy.on_fill(&mut ctx, &fill);
+                        self.submit(strategy, &mut ctx).await?;
+                    }
+                }
+            }
+        }
+        Ok(ctx)
+    }
+
+    fn market_event(&self, event: StreamEvent<Value>) -> crate::error::Result<Option<MarketEvent>> {
+        match event.stream.symbol() {
+            Some(symbol) if symbol.eq_ignore_ascii_case(&self.symbol) => {}
+            _ => return Ok(None),
+        }
+
+        let event = match event.stream {
+            ChannelBuf::Kline(..) | ChannelBuf::KlineUtc8(..) => {
+                let event: KlineEvent = serde_json::from_value(event.data)?;
+                match event.kline.is_closed {
+                    true => Some(MarketEvent::Kline(event.kline)),
+                    false => None,
+                }
+            }
+            ChannelBuf::Trade(_) => Some(MarketEvent::Trade(serde_json::from_value(event.data)?)),
+            ChannelBuf::AggTrade(_) => {
+                Some(MarketEvent::AggTrade(serde_json::from_value(event.data)?))
+            }
+            _ => None,
+        };
+        Ok(event)
+    }
+
+    /// Applies an execution report of an order of the strategy.
+    fn execution(&self, ctx: &mut Context, report: &Value) -> Option<OrderFill> {
+        if report["e"] != "executionReport" || report["s"] != self.symbol.as_str() {
+            return None;
+        }
+
+        // cancels carry the id of the canceled order in "C"
+        let client_order_id = match report["C"].as_str() {
+            Some(id) if !id.is_empty() => id,
+            _ => report["c"].as_str()?,
+        };
+        if !ctx.is_own(client_order_id) {
+            return None;
+        }
+
+        let decimal = |key: &str| -> f64 {
+            report[key]
+                .as_str()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default()
+        };
+
+        match report["x"].as_str()? {
+            "TRADE" => {
+                let side = match report["S"].as_str()? {
+                    "BUY" => Side::Buy,
+                    _ => Side::Sell,
+                };
+                let fill = ctx.fill(
+                    client_order_id,
+                    report["T"].as_i64().unwrap_or_default(),
+                    side,
+                    decimal("L"),
+                    decimal("l"),
+                    decimal("n"),
+                    report["N"].as_str().unwrap_or_default(),
+                    report["m"].as_bool().unwrap_or_default(),
+                );
+                if report["X"] == "FILLED" {
+                    ctx.remove_order(client_order_id);
+                }
+                Some(fill)
+            }
+            "CANCELED" | "EXPIRED" | "REJECTED" | "TRADE_PREVENTION" => {
+                ctx.remove_order(client_order_id);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Sends the orders placed by the strategy, including those placed on a rejection.
+    async fn submit<S: Strategy>(
+        &self,
+        strategy: &mut S,
+        ctx: &mut Context,
+    ) -> crate::error::Result<()> {
+        let mut requests = ctx.take_requests();
+        while !requests.is_empty() {
+            for request in requests {
+                let (client_order_id, result) = match &request {
+                    OrderRequest::Market(client_order_id, side, qty) => {
+                        let result = self
+                            .client
+                            .place_market_order(&self.symbol, *side, *qty, true)
+                            .with_new_client_order_id(client_order_id)
+                            .text()
+                            .await;
+                        (client_order_id, result)
+                    }
+                    OrderRequest::Limit(order) => {
+                        let result = self
+                            .client
+                            .place_limit_order(
+                                &self.symbol,
+                                order.side,
+                                order.price,
+                                order.qty,
+                                true,
+                            )
+                            .with_new_client_order_id(&order.client_order_id)
+                            .text()
+                            .await;
+                        (&order.client_order_id, result)
+                    }
+                    OrderRequest::Cancel(client_order_id) => {
+                        let result = self
+                            .client
+                            .cancel_order(&self.symbol, ID::ClientOId(client_order_id))
+                            .text()
+                            .await;
+                        (client_order_id, result)
+                    }
+                };
+
+                let error = match result {
+                    Ok(_) => continue,
+                    Err(error) => error,
+                };
+                ctx.remove_order(client_order_id);
+                let rejection = match rejection(client_order_id, &error) {
+                    Some(rejection) => rejection,
+                    None => return Err(error),
+                };
+                strategy.on_reject(ctx, &rejection);
+            }
+            requests = ctx.take_requests();
+        }
+        Ok(())
+    }
+}
+
+/// The refusal of a request the exchange answered; none for connection errors.
+fn rejection(client_order_id: &str, error: &crate::error::Error) -> Option<OrderRejection> {
+    let client_error = error.source()?.downcast_ref::<ClientError>()?;
+    let (code, message) = match ApiError::from_error(error) {
+        Some(error) => (error.code, error.msg),
+        None => (0, client_error.to_string()),
+    };
+    Some(OrderRejection {
+        client_order_id: client_order_id.to_string(),
+        code,
+        message,
+    })
+}
+
+/// Results of reading the stream event by event; each read runs to completion once started.
+fn events(
+    stream: &mut WebSocketStream,
+) -> impl Stream<Item = crate::error::Result<Option<StreamEvent<Value>>>> + '_ {
+    stream::unfold(stream, |stream| async move {
+        let event = stream.stream_event::<Value>().await;
+        Some((event, stream))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{next_request, reply, send, serve_ws, MockServer, WsServer};
+    use crate::ws_stream::Channel;
+    #[cfg(feature = "paper")]
+    use crate::PaperExchange;
+    use async_tungstenite::tungstenite::Message;
+    use futures::SinkExt;
+    use hyper::StatusCode;
+    use serde_json::json;
+
+    /// Records rejections and places a smaller order for the first one.
+    #[derive(Default)]
+    struct Retry {
+        rejections: Vec<OrderRejection>,
+        retried: Option<String>,
+    }
+
+    impl Strategy for Retry {
+        fn on_event(&mut self, _ctx: &mut Context, _event: &MarketEvent) {}
+
+        fn on_reject(&mut self, ctx: &mut Context, rejection: &OrderRejection) {
+            self.rejections.push(rejection.clone());
+            if self.retried.is_none() {
+                self.retried = Some(ctx.buy_limit(10.0, 0.5));
+            }
+        }
+    }
+
+    #[cfg(feature = "paper")]
+    #[tokio::test]
+    async fn rejected_orders_are_reported_and_the_others_sent() {
+        let paper = PaperExchange::start().await.unwrap();
+        paper.add_symbol("BNBUSDT", "BNB", "USDT");
+        paper.deposit("USDT", 100.0);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", &paper).unwrap();
+        let runner = LiveRunner::new(client, "BNBUSDT", "BNB", "USDT");
+
+        let mut ctx = Context::new("BNBUSDT", "BNB", "USDT", 0.0, 100.0);
+        let first = ctx.buy_limit(10.0, 5.0);
+        let refused = ctx.buy_limit(10.0, 100.0);
+        let last = ctx.buy_limit(10.0, 4.0);
+        let mut strategy = Retry::default();
+        runner.submit(&mut strategy, &mut ctx).await.unwrap();
+
+        assert_eq!(strategy.rejections.len(), 1);
+        assert_eq!(strategy.rejections[0].client_order_id, refused);
+        assert_eq!(strategy.rejections[0].code, -2010);
+
+        // the order placed on the rejection is sent as well
+        let retried = strategy.retried.unwrap();
+        let open: Vec<_> = ctx
+            .open_orders()
+            .iter()
+            .map(|order| order.client_order_id.clone())
+            .collect();
+        assert_eq!(open, vec![first, last, retried]);
+        assert_eq!(paper.balance("USDT"), (5.0, 95.0));
+    }
+
+    #[tokio::test]
+    async fn connection_errors_end_the_submit() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let client = AccountClient::connect("<api-key>", "<secret-key>", url).unwrap();
+        let runner = LiveRunner::new(client, "BNBUSDT", "BNB", "USDT");
+
+        let mut ctx = Context::new("BNBUSDT", "BNB", "USDT", 0.0, 100.0);
+        let order = ctx.buy_limit(10.0, 5.0);
+        ctx.buy_limit(10.0, 4.0);
+        let mut strategy = Retry::default();
+        assert!(runner.submit(&mut strategy, &mut ctx).await.is_err());
+
+        assert!(strategy.rejections.is_empty());
+        assert!(ctx
+            .open_orders()
+            .iter()
+            .all(|open| open.client_order_id != order));
+    }
+
+    /// Records the market events.
+    #[derive(Default)]
+    struct Record {
+        events: Vec<MarketEvent>,
+    }
+
+    impl Strategy for Record {
+        fn on_event(&mut self, _ctx: &mut Context, event: &MarketEvent) {
+            self.events.push(event.clone());
+        }
+    }
+
+    /// Answers the `SET_PROPERTY` of `connect`.
+    async fn combined(ws: &mut WsServer) {
+        let request = next_request(ws).await.unwrap();
+        send(ws, json!({"result": null, "id": request["id"]})).await;
+    }
+
+    #[tokio::test]
+    async fn events_of_both_streams_are_read_in_turns() {
+        let server = MockServer::start().await;
+        server.route("/api/v3/account", |_| {
+            reply(StatusCode::OK, r#"{"balances":[]}"#)
+        });
+        let client = AccountClient::connect("<api-key>", "<secret-key>", server.url()).unwrap();
+        let runner = LiveRunner::new(client, "BNBUSDT", "BNB", "USDT");
+
+        // trades come in between reports of another symbol, then the market closes
+        let market = serve_ws(|mut ws| async move {
+            combined(&mut ws).await;
+            for id in 0..20 {
+                let trade = json!({
+                    "e": "trade", "s": "BNBUSDT", "t": id, "p": "10.0", "q": "1.0",
+                    "T": id, "m": false
+                });
+                send(&mut ws, json!({"stream": "bnbusdt@trade", "data": trade})).await;
+                tokio::task::yield_now().await;
+            }
+            let _ = ws.close(None).await;
+        })
+        .await;
+        let listen_key = "a".repeat(60);
+        let key = listen_key.clone();
+        let user = serve_ws(move |mut ws| {
+            let key = key.clone();
+            async move {
+                combined(&mut ws).await;
+                let report = json!({"e": "executionReport", "s": "BTCUSDT"});
+                let event = json!({"stream": key, "data": report}).to_string();
+                while ws.send(Message::Text(event.clone())).await.is_ok() {
+                    tokio::task::yield_now().await;
+                }
+            }
+        })
+        .await;
+        let trades = Channel::Trade("BNBUSDT");
+        let mut market = WebSocketStream::connect(trades, market.as_str())
+            .await
+            .unwrap();
+        let channel = Channel::UserData(&listen_key);
+        let mut user = WebSocketStream::connect(channel, user.as_str())
+            .await
+            .unwrap();
+
+        let mut strategy = Record::default();
+        let result = runner.run(&mut strategy, &mut market, &mut user).await;
+        assert!(result.is_err());
+        let ids: Vec<_> = strategy
+            .events
+            .iter()
+            .map(|event| match event {
+                MarketEvent::Trade(trade) => trade.id,
+                _ => -1,
+            })
+            .collect();
+        assert_eq!(ids, (0..20).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,378 @@
+use crate::environment::Endpoint;
+use crate::error::WsCloseError;
+use crate::ws_stream::{Channel, ChannelBuf, WebSocketStream};
+use async_tungstenite::tungstenite::{protocol::frame::coding::CloseCode, Message};
+use core::pin::Pin;
+use futures::{
+    future,
+    stream::StreamExt,
+    task::{Context, Poll},
+    Stream,
+};
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use tokio::time::{self, Duration, Instant};
+
+/// Maximum number of streams binance allows on a single connection.
+pub const MAX_STREAMS_PER_CONNECTION: usize = 1024;
+
+/// Binance allows 5 incoming messages per second on a connection, pings and pongs included;
+/// requests are spaced a little wider to leave room for those.
+const REQUEST_INTERVAL: Duration = Duration::from_millis(250);
+
+struct Shard {
+    stream: WebSocketStream,
+    channels: HashSet<ChannelBuf>,
+    last_request: Instant,
+}
+
+impl Shard {
+    async fn throttle(&mut self) {
+        time::sleep_until(self.last_request + REQUEST_INTERVAL).await;
+        self.last_request = Instant::now();
+    }
+}
+
+/// Spreads channels aka streams over as many connections as needed
+/// and merges them back into a single stream of messages.
+///
+/// Subscribe and unsubscribe requests are throttled per connection.
+/// Binance also limits new connections to 300 per 5 minutes per IP.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{Channel, Environment, StreamPool};
+/// use serde_json::Value;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let symbols = ["BNBUSDT", "BTCUSDT", "ETHUSDT"];
+///     let channels: Vec<_> = symbols.iter().map(|symbol| Channel::Trade(symbol)).collect();
+///
+///     let mut pool = StreamPool::new(Environment::BinanceUs);
+///     pool.subscribe(&channels).await?;
+///
+///     while let Some(value) = pool.json::<Value>().await? {
+///         println!("{}", value["stream"]);
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct StreamPool {
+    url: String,
+    max_streams: usize,
+    shards: Vec<Shard>,
+    /// Shard polled first, rotated so that a busy connection does not starve the others.
+    next: usize,
+}
+
+impl StreamPool {
+    /// Creates an empty pool; connections are opened on subscribe.
+    pub fn new<U: Endpoint>(url: U) -> Self {
+        Self {
+            url: url.stream_url().to_string(),
+            max_streams: MAX_STREAMS_PER_CONNECTION,
+            shards: Vec::new(),
+            next: 0,
+        }
+    }
+    /// Limit the number of streams per connection; can't be above 1024.
+    pub fn with_max_streams(mut self, max_streams: usize) -> Self {
+        self.max_streams = max_streams.clamp(1, MAX_STREAMS_PER_CONNECTION);
+        self
+    }
+    /// Number of open connections.
+    pub fn connections(&self) -> usize {
+        self.shards.len()
+    }
+    /// All subscribed channels aka streams.
+    pub fn channels(&self) -> impl Iterator<Item = &ChannelBuf> {
+        self.shards.iter().flat_map(|shard| shard.channels.iter())
+    }
+    /// Subscribe to one or more channels aka streams, opening connections as needed.
+    ///
+    /// Channels that are already subscribed are skipped.
+    pub async fn subscribe(&mut self, channels: &[Channel<'_>]) -> crate::error::Result<()> {
+        let mut seen = HashSet::new();
+        let mut pending: Vec<_> = channels
+            .iter()
+            .filter(|channel| {
+                let channel = ChannelBuf::from(**channel);
+                !self.contains(&channel) && seen.insert(channel)
+            })
+            .copied()
+            .collect();
+
+        for shard in &mut self.shards {
+            let room = self.max_streams.saturating_sub(shard.channels.len());
+            if room == 0 || pending.is_empty() {
+                continue;
+            }
+
+            let batch: Vec<_> = pending.drain(..room.min(pending.len())).collect();
+            shard.throttle().await;
+            shard.stream.subscribe(&batch).await?;
+            shard
+                .channels
+                .extend(batch.iter().map(|channel| ChannelBuf::from(*channel)));
+        }
+
+        while !pending.is_empty() {
+            let batch: Vec<_> = pending
+                .drain(..self.max_streams.min(pending.len()))
+                .collect();
+
+            let stream = WebSocketStream::connect(batch[0], self.url.as_str()).await?;
+            let mut shard = Shard {
+                stream,
+                channels: std::iter::once(batch[0].into()).collect(),
+                last_request: Instant::now(),
+            };
+
+            if batch.len() > 1 {
+                shard.throttle().await;
+                shard.stream.subscribe(&batch[1..]).await?;
+                shard
+                    .channels
+                    .extend(batch[1..].iter().map(|channel| ChannelBuf::from(*channel)));
+            }
+            self.shards.push(shard);
+        }
+        Ok(())
+    }
+    /// Unsubscribe from one or more channels aka streams.
+    ///
+    /// Connections left without any channel are closed, also when another connection
+    /// fails to unsubscribe.
+    pub async fn unsubscribe(&mut self, channels: &[Channel<'_>]) -> crate::error::Result<()> {
+        let unsubscribed = self.unsubscribe_shards(channels).await;
+
+        let (empty, shards): (Vec<_>, Vec<_>) = self
+            .shards
+            .drain(..)
+            .partition(|shard| shard.channels.is_empty());
+        self.shards = shards;
+        unsubscribed?;
+
+        for mut shard in empty {
+            shard.stream.close(None).await?;
+        }
+        Ok(())
+    }
+    /// Helper method for getting messages as text; same as [`WebSocketStream::text`].
+    pub async fn text(&mut self) -> crate::error::Result<Option<String>> {
+        match future::poll_fn(|cx| self.poll_shards(cx)).await {
+            Some(result) => {
+                let (index, msg) = result?;
+                self.shards[index].stream.message_text(msg).await
+            }
+            None => Ok(None),
+        }
+    }
+    /// Helper method for getting messages as a serde deserializable.
+    pub async fn json<J: DeserializeOwned>(&mut self) -> crate::error::Result<Option<J>> {
+        match self.text().await? {
+            Some(text) => Ok(Some(serde_json::from_str(&text)?)),
+            None => Ok(None),
+        }
+    }
+    /// Close all connections.
+    pub async fn close(&mut self) -> crate::error::Result<()> {
+        for mut shard in self.shards.drain(..) {
+            shard.stream.close(None).await?;
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe_shards(&mut self, channels: &[Channel<'_>]) -> crate::error::Result<()> {
+        for shard in &mut self.shards {
+            let batch: Vec<_> = channels
+                .iter()
+                .filter(|channel| shard.channels.contains(&ChannelBuf::from(**channel)))
+                .copied()
+                .collect();
+
+            if batch.is_empty() {
+                continue;
+            }
+
+            shard.throttle().await;
+            shard.stream.unsubscribe(&batch).await?;
+            for channel in batch {
+                shard.channels.remove(&channel.into());
+            }
+        }
+        Ok(())
+    }
+
+    fn contains(&self, channel: &ChannelBuf) -> bool {
+        self.shards
+            .iter()
+            .any(|shard| shard.channels.contains(channel))
+    }
+
+    /// Polls every connection once, returning the first message along with its shard.
+    ///
+    /// A connection that ends is dropped together with its channels and reported as an error.
+    fn poll_shards(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Option<crate::error::Result<(usize, Message)>>> {
+        if self.shards.is_empty() {
+            return Poll::Ready(None);
+        }
+
+        let len = self.shards.len();
+        for offset in 0..len {
+            let index = (self.next + offset) % len;
+
+            match self.shards[index].stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(msg)) => {
+                    self.next = (index + 1) % len;
+                    return Poll::Ready(Some(msg.map(|msg| (index, msg))));
+                }
+                Poll::Ready(None) => {
+                    let shard = self.shards.remove(index);
+                    self.next = 0;
+                    let reason = format!(
+                        "Connection ended; dropped {} channels",
+                        shard.channels.len()
+                    );
+                    let error = WsCloseError::new(CloseCode::Abnormal, reason);
+                    return Poll::Ready(Some(Err(error.into())));
+                }
+                Poll::Pending => {}
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Stream for StreamPool {
+    type Item = crate::error::Result<Message>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        match self.poll_shards(cx) {
+            Poll::Ready(Some(result)) => Poll::Ready(Some(result.map(|(_, msg)| msg))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{next_request, send, serve_ws};
+    use serde_json::json;
+
+    const SYMBOLS: [&str; 6] = [
+        "BNBUSDT", "BTCUSDT", "ETHUSDT", "LTCUSDT", "XRPUSDT", "ADAUSDT",
+    ];
+
+    fn channels(symbols: &[&'static str]) -> Vec<Channel<'static>> {
+        symbols
+            .iter()
+            .map(|symbol| Channel::Trade(symbol))
+            .collect()
+    }
+
+    /// Serves stream connections that acknowledge every request but unsubscribing from
+    /// the trades of `refused`; returns the url.
+    async fn server(refused: &'static str) -> String {
+        serve_ws(move |mut ws| async move {
+            while let Some(request) = next_request(&mut ws).await {
+                let channel = Channel::Trade(refused).to_string();
+                let reply = match request["params"].as_array() {
+                    Some(params)
+                        if request["method"] == "UNSUBSCRIBE"
+                            && params.contains(&channel.into()) =>
+                    {
+                        let error = json!({"code": 2, "msg": "Invalid request"});
+                        json!({"error": error, "id": request["id"]})
+                    }
+                    _ => json!({"result": null, "id": request["id"]}),
+                };
+                send(&mut ws, reply).await;
+            }
+        })
+        .await
+    }
+
+    /// Subscribed channels per connection.
+    fn shards(pool: &StreamPool) -> Vec<usize> {
+        pool.shards
+            .iter()
+            .map(|shard| shard.channels.len())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn channels_fill_connections_up_to_the_limit() {
+        let url = server("").await;
+        let mut pool = StreamPool::new(url).with_max_streams(2);
+
+        pool.subscribe(&channels(&SYMBOLS[..5])).await.unwrap();
+        assert_eq!(shards(&pool), vec![2, 2, 1]);
+
+        // already subscribed channels are skipped, the last connection is filled first
+        pool.subscribe(&channels(&SYMBOLS[3..])).await.unwrap();
+        assert_eq!(shards(&pool), vec![2, 2, 2]);
+        assert_eq!(pool.connections(), 3);
+
+        let mut subscribed: Vec<_> = pool.channels().cloned().collect();
+        let mut expected: Vec<_> = channels(&SYMBOLS)
+            .into_iter()
+            .map(ChannelBuf::from)
+            .collect();
+        subscribed.sort_by_key(|channel| channel.to_string());
+        expected.sort_by_key(|channel| channel.to_string());
+        assert_eq!(subscribed, expected);
+    }
+
+    #[tokio::test]
+    async fn freed_room_is_used_before_opening_connections() {
+        let url = server("").await;
+        let mut pool = StreamPool::new(url).with_max_streams(2);
+        pool.subscribe(&channels(&SYMBOLS[..4])).await.unwrap();
+
+        pool.unsubscribe(&channels(&SYMBOLS[..1])).await.unwrap();
+        assert_eq!(shards(&pool), vec![1, 2]);
+
+        pool.subscribe(&channels(&SYMBOLS[4..])).await.unwrap();
+        assert_eq!(shards(&pool), vec![2, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn empty_connections_are_closed() {
+        let url = server("").await;
+        let mut pool = StreamPool::new(url).with_max_streams(2);
+        pool.subscribe(&channels(&SYMBOLS[..3])).await.unwrap();
+
+        pool.unsubscribe(&channels(&SYMBOLS[..2])).await.unwrap();
+        assert_eq!(shards(&pool), vec![1]);
+        pool.unsubscribe(&channels(&SYMBOLS[2..3])).await.unwrap();
+        assert_eq!(pool.connections(), 0);
+    }
+
+    #[test]
+    fn max_streams_stays_within_the_binance_limit() {
+        let pool = StreamPool::new(crate::Environment::BinanceUs);
+        assert_eq!(pool.with_max_streams(0).max_streams, 1);
+        let pool = StreamPool::new(crate::Environment::BinanceUs);
+        assert_eq!(pool.with_max_streams(5000).max_streams, 1024);
+    }
+
+    #[tokio::test]
+    async fn empty_connections_are_closed_when_another_fails() {
+        let url = server(SYMBOLS[2]).await;
+        let mut pool = StreamPool::new(url).with_max_streams(2);
+        pool.subscribe(&channels(&SYMBOLS[..4])).await.unwrap();
+
+        let error = pool.unsubscribe(&channels(&SYMBOLS[..3])).await;
+        assert!(error.is_err());
+        // the first connection is empty, the second kept the refused channel
+        assert_eq!(shards(&pool), vec![2]);
+        assert!(pool.contains(&Channel::Trade(SYMBOLS[2]).into()));
+    }
+}
@@ -16,11 +16,32 @@ pub trait StopClientOrderId {}
 pub trait LimitIcebergQty {}
 pub trait StopIcebergQty {}
 pub trait StopLimitPrice {}
+pub trait CancelNewClientOrderId {}
+pub trait CancelRestrictions {}
+pub trait OrderRateLimitExceededMode {}
 pub trait RecvWindow {}
+pub trait WindowSize {}
+pub trait TickerType {}
+pub trait TimeZone {}
 
 pub trait LimitMaker {}
 pub trait LimitOrderStopPrice {}
 pub trait MarketOrderStopPrice {}
+pub trait QuoteOrderQty {}
+pub trait TrailingDelta {}
+pub trait SelfTradePreventionMode {}
+pub trait Strategy {}
+pub trait PegPriceType {}
+pub trait FromPreventedMatchId {}
+pub trait FromAllocationId {}
+
+pub trait AboveLeg {}
+pub trait BelowLeg {}
+pub trait WorkingLeg {}
+pub trait PendingLeg {}
+pub trait PendingAboveLeg {}
+pub trait PendingBelowLeg {}
+pub trait PendingQuantity {}
 
 pub trait AddressTag {}
 pub trait Name {}
@@ -55,6 +76,16 @@ impl Limit for KlinesParams {}
 impl StartTime for KlinesParams {}
 impl EndTime for KlinesParams {}
 
+pub struct UiKlinesParams;
+impl Limit for UiKlinesParams {}
+impl StartTime for UiKlinesParams {}
+impl EndTime for UiKlinesParams {}
+impl TimeZone for UiKlinesParams {}
+
+pub struct RollingWindowTickerParams;
+impl WindowSize for RollingWindowTickerParams {}
+impl TickerType for RollingWindowTickerParams {}
+
 pub struct TwentyfourHourTickerPriceParams;
 impl Symbol for TwentyfourHourTickerPriceParams {}
 
@@ -65,6 +96,10 @@ pub struct OrderBookTickerParams;
 impl Symbol for OrderBookTickerParams {}
 
 pub struct LimitOrderParams;
+impl TrailingDelta for LimitOrderParams {}
+impl SelfTradePreventionMode for LimitOrderParams {}
+impl Strategy for LimitOrderParams {}
+impl PegPriceType for LimitOrderParams {}
 impl TimeInForce for LimitOrderParams {}
 impl LimitMaker for LimitOrderParams {}
 impl LimitOrderStopPrice for LimitOrderParams {}
@@ -74,11 +109,18 @@ impl NewOrderRespType for LimitOrderParams {}
 impl RecvWindow for LimitOrderParams {}
 
 pub struct LimitMakerOrderParams;
+impl SelfTradePreventionMode for LimitMakerOrderParams {}
+impl Strategy for LimitMakerOrderParams {}
+impl PegPriceType for LimitMakerOrderParams {}
 impl NewClientOrderId for LimitMakerOrderParams {}
 impl NewOrderRespType for LimitMakerOrderParams {}
 impl RecvWindow for LimitMakerOrderParams {}
 
 pub struct MarketOrderParams;
+impl QuoteOrderQty for MarketOrderParams {}
+impl TrailingDelta for MarketOrderParams {}
+impl SelfTradePreventionMode for MarketOrderParams {}
+impl Strategy for MarketOrderParams {}
 impl MarketOrderStopPrice for MarketOrderParams {}
 impl NewClientOrderId for MarketOrderParams {}
 impl NewOrderRespType for MarketOrderParams {}
@@ -91,6 +133,25 @@ pub struct CancelOrderParams;
 impl NewClientOrderId for CancelOrderParams {}
 impl RecvWindow for CancelOrderParams {}
 
+pub struct CancelReplaceParams;
+impl TrailingDelta for CancelReplaceParams {}
+impl SelfTradePreventionMode for CancelReplaceParams {}
+impl Strategy for CancelReplaceParams {}
+impl PegPriceType for CancelReplaceParams {}
+impl TimeInForce for CancelReplaceParams {}
+impl LimitOrderStopPrice for CancelReplaceParams {}
+impl NewClientOrderId for CancelReplaceParams {}
+impl CancelNewClientOrderId for CancelReplaceParams {}
+impl IcebergQty for CancelReplaceParams {}
+impl NewOrderRespType for CancelReplaceParams {}
+impl CancelRestrictions for CancelReplaceParams {}
+impl OrderRateLimitExceededMode for CancelReplaceParams {}
+impl RecvWindow for CancelReplaceParams {}
+
+pub struct AmendOrderParams;
+impl NewClientOrderId for AmendOrderParams {}
+impl RecvWindow for AmendOrderParams {}
+
 pub struct OpenOrderParams;
 impl Symbol for OpenOrderParams {}
 impl RecvWindow for OpenOrderParams {}
@@ -103,6 +164,7 @@ impl Limit for AllOrdersParams {}
 impl RecvWindow for AllOrdersParams {}
 
 pub struct OcoParams;
+impl SelfTradePreventionMode for OcoParams {}
 impl ListClientOrderId for OcoParams {}
 impl LimitClientOrderId for OcoParams {}
 impl LimitIcebergQty for OcoParams {}
@@ -112,6 +174,32 @@ impl StopLimitPrice for OcoParams {}
 impl NewOrderRespType for OcoParams {}
 impl RecvWindow for OcoParams {}
 
+pub struct OcoOrderListParams;
+impl SelfTradePreventionMode for OcoOrderListParams {}
+impl ListClientOrderId for OcoOrderListParams {}
+impl AboveLeg for OcoOrderListParams {}
+impl BelowLeg for OcoOrderListParams {}
+impl NewOrderRespType for OcoOrderListParams {}
+impl RecvWindow for OcoOrderListParams {}
+
+pub struct OtoOrderListParams;
+impl SelfTradePreventionMode for OtoOrderListParams {}
+impl ListClientOrderId for OtoOrderListParams {}
+impl WorkingLeg for OtoOrderListParams {}
+impl PendingLeg for OtoOrderListParams {}
+impl NewOrderRespType for OtoOrderListParams {}
+impl RecvWindow for OtoOrderListParams {}
+
+pub struct OtocoOrderListParams;
+impl SelfTradePreventionMode for OtocoOrderListParams {}
+impl ListClientOrderId for OtocoOrderListParams {}
+impl WorkingLeg for OtocoOrderListParams {}
+impl PendingAboveLeg for OtocoOrderListParams {}
+impl PendingBelowLeg for OtocoOrderListParams {}
+impl PendingQuantity for OtocoOrderListParams {}
+impl NewOrderRespType for OtocoOrderListParams {}
+impl RecvWindow for OtocoOrderListParams {}
+
 pub struct CancelOcoParams;
 impl NewClientOrderId for CancelOcoParams {}
 impl RecvWindow for CancelOcoParams {}
@@ -133,12 +221,35 @@ pub struct AccountParams;
 impl RecvWindow for AccountParams {}
 
 pub struct AccountTradesParams;
+impl OrderId for AccountTradesParams {}
 impl Limit for AccountTradesParams {}
 impl FromId for AccountTradesParams {}
 impl StartTime for AccountTradesParams {}
 impl EndTime for AccountTradesParams {}
 impl RecvWindow for AccountTradesParams {}
 
+pub struct CommissionParams;
+impl RecvWindow for CommissionParams {}
+
+pub struct OrderRateLimitParams;
+impl RecvWindow for OrderRateLimitParams {}
+
+pub struct PreventedMatchesParams;
+impl FromPreventedMatchId for PreventedMatchesParams {}
+impl Limit for PreventedMatchesParams {}
+impl RecvWindow for PreventedMatchesParams {}
+
+pub struct PreventedMatchParams;
+impl RecvWindow for PreventedMatchParams {}
+
+pub struct AllocationsParams;
+impl OrderId for AllocationsParams {}
+impl FromAllocationId for AllocationsParams {}
+impl StartTime for AllocationsParams {}
+impl EndTime for AllocationsParams {}
+impl Limit for AllocationsParams {}
+impl RecvWindow for AllocationsParams {}
+
 pub struct StartStreamParams;
 pub struct KeepAliveStreamParams;
 pub struct CloseStreamParams;
@@ -216,3 +327,6 @@ impl RecvWindow for AssetDividendParams {}
 
 pub struct CancelAllOrdersParams;
 impl RecvWindow for CancelAllOrdersParams {}
+
+pub struct CountdownCancelAllParams;
+impl RecvWindow for CountdownCancelAllParams {}
@@ -0,0 +1,439 @@
+use crate::client::{AccountClient, UsdFuturesClient};
+use crate::error::ClientError;
+use crate::model::ApiError;
+use log::warn;
+use std::collections::HashMap;
+use std::error::Error as _;
+use std::sync::{Arc, Mutex};
+use tokio::time::{self, Duration, Instant};
+
+struct Signal {
+    timeout: Duration,
+    last_seen: Instant,
+}
+
+#[derive(Default)]
+struct State {
+    signals: HashMap<String, Signal>,
+    /// Name of the signal that lapsed.
+    tripped: Option<String>,
+}
+
+/// Dead-man's switch canceling the open orders of some symbols once a health signal lapses.
+///
+/// Each signal, e.g. the user data stream, REST calls or a heartbeat of the application,
+/// has to be fed within its timeout. When one is not, `cancel_all_orders` is sent for
+/// every symbol until it succeeds or is refused, and the watchdog stays tripped until
+/// [`reset`](Self::reset).
+///
+/// Spot binance has no auto-cancel countdown, so orders there are only canceled while this
+/// process can still reach binance. With a [`UsdFuturesClient`] the watchdog guards the
+/// USDⓈ-M futures orders instead and sets a countdown as well, which the exchange runs out
+/// by itself if the process dies.
+/// # Example
+///
+/// ```no_run
+/// use tokio_binance::{AccountClient, Channel, Environment, UserDataClient, Watchdog};
+/// use tokio_binance::WebSocketStream;
+/// use serde_json::Value;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = AccountClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs)?;
+///     let user_data = UserDataClient::connect("<api-key>", Environment::BinanceUs)?;
+///     let value = user_data.start_stream().json::<Value>().await?;
+///     let channel = Channel::UserData(value["listenKey"].as_str().unwrap());
+///     let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
+///
+///     let watchdog = Watchdog::new(client.clone(), &["BNBUSDT", "BTCUSDT"])
+///         .with_signal("user-data", Duration::from_secs(90))
+///         .with_signal("rest", Duration::from_secs(30))
+///         .with_signal("heartbeat", Duration::from_secs(10));
+///
+///     let switch = watchdog.clone();
+///     tokio::spawn(async move { switch.run(Duration::from_secs(1)).await });
+///
+///     loop {
+///         tokio::select! {
+///             event = stream.json::<Value>() => match event? {
+///                 Some(_) => watchdog.feed("user-data"),
+///                 None => break,
+///             },
+///             _ = tokio::time::sleep(Duration::from_secs(5)) => {
+///                 let response = client.get_open_orders().json::<Value>().await;
+///                 watchdog.observe("rest", &response);
+///                 watchdog.feed("heartbeat");
+///             }
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Watchdog {
+    client: AccountClient,
+    symbols: Arc<Vec<String>>,
+    countdown: Option<(UsdFuturesClient, Duration)>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Watchdog {
+    pub fn new(client: AccountClient, symbols: &[&str]) -> Self {
+        Self {
+            client,
+            symbols: Arc::new(symbols.iter().map(|symbol| symbol.to_string()).collect()),
+            countdown: None,
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+    /// Adds a signal that has to be fed within the timeout, counting from now.
+    pub fn with_signal(self, name: &str, timeout: Duration) -> Self {
+        self.state.lock().unwrap().signals.insert(
+            name.to_string(),
+            Signal {
+                timeout,
+                last_seen: Instant::now(),
+            },
+        );
+        self
+    }
+    /// Sends `countdown_cancel_all` of the futures client for every symbol at each check
+    /// while healthy, and cancels the open orders with it instead of the spot client once
+    /// tripped.
+    pub fn with_countdown(mut self, client: UsdFuturesClient, countdown: Duration) -> Self {
+        self.countdown = Some((client, countdown));
+        self
+    }
+    /// Marks the signal as alive.
+    pub fn feed(&self, name: &str) {
+        if let Some(signal) = self.state.lock().unwrap().signals.get_mut(name) {
+            signal.last_seen = Instant::now();
+        }
+    }
+    /// Feeds the signal if the request succeeded.
+    pub fn observe<T>(&self, name: &str, result: &crate::error::Result<T>) {
+        if result.is_ok() {
+            self.feed(name);
+        }
+    }
+    /// Name of the signal that lapsed, if any.
+    pub fn tripped(&self) -> Option<String> {
+        self.state.lock().unwrap().tripped.clone()
+    }
+    /// Re-arms a tripped watchdog, to be [`run`](Self::run) again; every signal counts from now.
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        for signal in state.signals.values_mut() {
+            signal.last_seen = now;
+        }
+        state.tripped = None;
+    }
+    /// Checks the signals at every interval until one lapses, then cancels the open orders
+    /// of the symbols; returns the name of the lapsed signal.
+    ///
+    /// With a countdown the orders are canceled on the USDⓈ-M futures api. A refresh of the
+    /// countdown that fails on the network, a rate limit or the recvWindow is logged and lets
+    /// the countdown run down; any other refusal, e.g. of the api key or a ban of the IP, is
+    /// returned at once since the countdown can't be armed.
+    ///
+    /// Cancels that fail on the network, a rate limit or the recvWindow are sent again at the
+    /// next interval; any other refusal, a ban of the IP included, is returned once the
+    /// remaining symbols are done.
+    pub async fn run(&self, check: Duration) -> crate::error::Result<String> {
+        let mut interval = time::interval(check);
+        let name = loop {
+            interval.tick().await;
+            if let Some(name) = self.check() {
+                break name;
+            }
+            if let Some((futures, countdown)) = &self.countdown {
+                for symbol in self.symbols.iter() {
+                    let response = futures
+                        .countdown_cancel_all(symbol, *countdown)
+                        .text()
+                        .await;
+                    match response {
+                        Ok(_) => {}
+                        // a lost refresh runs the countdown down, as intended
+                        Err(error) if retryable(&error) => {
+                            warn!("countdown of {} not refreshed: {}", symbol, error)
+                        }
+                        Err(error) => return Err(error),
+                    }
+                }
+            }
+        };
+
+        let mut refused = None;
+        let mut pending: Vec<_> = self.symbols.iter().collect();
+        while !pending.is_empty() {
+            let mut failed = Vec::new();
+            for symbol in pending {
+                let response = match &self.countdown {
+                    Some((futures, _)) => futures.cancel_all_orders(symbol).text().await,
+                    None => self.client.cancel_all_orders(symbol).text().await,
+                };
+                match response {
+                    Ok(_) => {}
+                    // no open orders
+                    Err(error) if ApiError::has_code(&error, -2011) => {}
+                    Err(error) if retryable(&error) => failed.push(symbol),
+                    Err(error) => {
+                        refused.get_or_insert(error);
+                    }
+                }
+            }
+            pending = failed;
+            if !pending.is_empty() {
+                interval.tick().await;
+            }
+        }
+        match refused {
+            Some(error) => Err(error),
+            None => Ok(name),
+        }
+    }
+
+    /// Trips on the first lapsed signal; returns its name.
+    fn check(&self) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        if state.tripped.is_none() {
+            let now = Instant::now();
+            state.tripped = state
+                .signals
+                .iter()
+                .find(|(_, signal)| now.duration_since(signal.last_seen) > signal.timeout)
+                .map(|(name, _)| name.clone());
+        }
+        state.tripped.clone()
+    }
+}
+
+/// Whether a failed cancel may succeed when sent again.
+fn retryable(error: &crate::error::Error) -> bool {
+    match error
+        .source()
+        .and_then(|source| source.downcast_ref::<ClientError>())
+    {
+        // a ban lasts from minutes to days, resending only makes it longer
+        Some(client_error) if client_error.is_banned() => false,
+        // timestamp outside of the recvWindow
+        Some(client_error) => client_error.is_rate_limited() || ApiError::has_code(error, -1021),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{reply, MockServer, Received};
+    use hyper::{Body, Response, StatusCode};
+    use std::collections::HashMap;
+
+    const REFUSED: &str =
+        r#"{"code":-2015,"msg":"Invalid API-key, IP, or permissions for action."}"#;
+
+    /// Answers cancel-all of the spot and futures api, failing the first `failures` cancels
+    /// of BNBUSDT; BTCUSDT has no open orders, ETHUSDT is refused for the api key
+    /// and XRPUSDT for a ban of the IP.
+    fn cancel_all(failures: usize) -> impl FnMut(&Received) -> Response<Body> {
+        let mut sent = HashMap::new();
+        move |req| {
+            let symbol = req.param("symbol").unwrap_or_default().to_string();
+            let sent = sent
+                .entry(symbol.clone())
+                .and_modify(|n| *n += 1)
+                .or_insert(1);
+            match symbol.as_str() {
+                "BTCUSDT" => reply(
+                    StatusCode::BAD_REQUEST,
+                    r#"{"code":-2011,"msg":"Unknown order sent."}"#,
+                ),
+                "ETHUSDT" => reply(StatusCode::UNAUTHORIZED, REFUSED),
+                "XRPUSDT" => reply(
+                    StatusCode::IM_A_TEAPOT,
+                    r#"{"code":-1003,"msg":"Way too many requests; IP banned."}"#,
+                ),
+                _ if *sent <= failures => reply(
+                    StatusCode::BAD_REQUEST,
+                    r#"{"code":-1021,"msg":"Timestamp for this request is outside of the recvWindow."}"#,
+                ),
+                _ => reply(StatusCode::OK, "[]"),
+            }
+        }
+    }
+
+    /// Serves [`cancel_all`] and the countdown, which is refused for the `<revoked-key>`.
+    async fn exchange(
+        api_key: &str,
+        failures: usize,
+    ) -> (AccountClient, UsdFuturesClient, MockServer) {
+        let server = MockServer::start().await;
+        server.route("/api/v3/openOrders", cancel_all(failures));
+        server.route("/fapi/v1/allOpenOrders", cancel_all(failures));
+        server.route("/fapi/v1/countdownCancelAll", |req| {
+            match req.headers["X-MBX-APIKEY"] == "<revoked-key>" {
+                true => reply(StatusCode::UNAUTHORIZED, REFUSED),
+                false => reply(StatusCode::OK, "{}"),
+            }
+        });
+
+        let client = AccountClient::connect(api_key, "<secret-key>", server.url()).unwrap();
+        let futures = UsdFuturesClient::connect(api_key, "<secret-key>", server.url()).unwrap();
+        (client, futures, server)
+    }
+
+    /// Symbols of the requests sent to the path.
+    fn sent(server: &MockServer, path: &str) -> Vec<String> {
+        server
+            .requests()
+            .into_iter()
+            .filter(|req| req.path == path)
+            .filter_map(|req| Some(req.param("symbol")?.to_string()))
+            .collect()
+    }
+
+    // the idle pool of the client runs timers too, so paused time can jump while a
+    // request is in flight; times are only checked before the orders are canceled.
+
+    #[tokio::test]
+    async fn trips_on_the_first_lapsed_signal() {
+        time::pause();
+        let (client, _, server) = exchange("<api-key>", 0).await;
+        let watchdog = Watchdog::new(client, &["BNBUSDT", "BTCUSDT"])
+            .with_signal("rest", Duration::from_secs(10))
+            .with_signal("heartbeat", Duration::from_secs(30));
+
+        let switch = watchdog.clone();
+        let handle = tokio::spawn(async move { switch.run(Duration::from_secs(1)).await });
+
+        time::sleep(Duration::from_millis(5500)).await;
+        watchdog.feed("rest");
+        time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(watchdog.tripped(), None);
+        assert!(server.requests().is_empty());
+
+        time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(watchdog.tripped(), Some("rest".to_string()));
+
+        assert_eq!(handle.await.unwrap().unwrap(), "rest");
+        assert_eq!(
+            sent(&server, "/api/v3/openOrders"),
+            vec!["BNBUSDT", "BTCUSDT"]
+        );
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_cancels_are_sent_again() {
+        time::pause();
+        let (client, _, server) = exchange("<api-key>", 2).await;
+        let watchdog = Watchdog::new(client, &["BNBUSDT", "BTCUSDT"])
+            .with_signal("rest", Duration::from_secs(10));
+
+        assert_eq!(watchdog.run(Duration::from_secs(1)).await.unwrap(), "rest");
+        // no open orders counts as canceled, failures are sent again
+        assert_eq!(
+            sent(&server, "/api/v3/openOrders"),
+            vec!["BNBUSDT", "BTCUSDT", "BNBUSDT", "BNBUSDT"]
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_cancels_are_returned() {
+        time::pause();
+        let (client, _, server) = exchange("<api-key>", 1).await;
+        let watchdog = Watchdog::new(client, &["ETHUSDT", "BNBUSDT"])
+            .with_signal("rest", Duration::from_secs(10));
+
+        let error = watchdog.run(Duration::from_secs(1)).await.unwrap_err();
+        assert!(ApiError::has_code(&error, -2015));
+        // the refusal is not sent again, the other symbols are still canceled
+        assert_eq!(
+            sent(&server, "/api/v3/openOrders"),
+            vec!["ETHUSDT", "BNBUSDT", "BNBUSDT"]
+        );
+        assert_eq!(watchdog.tripped(), Some("rest".to_string()));
+    }
+
+    #[tokio::test]
+    async fn bans_are_not_sent_again() {
+        time::pause();
+        let (client, _, server) = exchange("<api-key>", 1).await;
+        let watchdog = Watchdog::new(client, &["XRPUSDT", "BNBUSDT"])
+            .with_signal("rest", Duration::from_secs(10));
+
+        let error = watchdog.run(Duration::from_secs(1)).await.unwrap_err();
+        assert!(ApiError::has_code(&error, -1003));
+        assert_eq!(
+            sent(&server, "/api/v3/openOrders"),
+            vec!["XRPUSDT", "BNBUSDT", "BNBUSDT"]
+        );
+    }
+
+    #[tokio::test]
+    async fn countdown_cancels_on_futures() {
+        time::pause();
+        let (client, futures, server) = exchange("<api-key>", 1).await;
+        let watchdog = Watchdog::new(client, &["BNBUSDT", "BTCUSDT"])
+            .with_signal("rest", Duration::from_secs(10))
+            .with_countdown(futures, Duration::from_secs(60));
+
+        let switch = watchdog.clone();
+        let handle = tokio::spawn(async move { switch.run(Duration::from_secs(1)).await });
+
+        time::sleep(Duration::from_millis(2500)).await;
+        assert_eq!(watchdog.tripped(), None);
+
+        assert_eq!(handle.await.unwrap().unwrap(), "rest");
+        // one refresh per symbol at each healthy check
+        let countdowns = sent(&server, "/fapi/v1/countdownCancelAll").len();
+        assert!(countdowns >= 2 && countdowns.is_multiple_of(2));
+        assert_eq!(
+            sent(&server, "/fapi/v1/allOpenOrders"),
+            vec!["BNBUSDT", "BTCUSDT", "BNBUSDT"]
+        );
+        assert!(sent(&server, "/api/v3/openOrders").is_empty());
+    }
+
+    #[tokio::test]
+    async fn reset_rearms_the_signals() {
+        time::pause();
+        let (client, _, server) = exchange("<api-key>", 0).await;
+        let watchdog =
+            Watchdog::new(client, &["BNBUSDT"]).with_signal("rest", Duration::from_secs(10));
+
+        assert_eq!(watchdog.run(Duration::from_secs(1)).await.unwrap(), "rest");
+        // stays tripped until reset
+        time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(watchdog.tripped(), Some("rest".to_string()));
+        assert_eq!(watchdog.check(), Some("rest".to_string()));
+
+        watchdog.reset();
+        assert_eq!(watchdog.tripped(), None);
+        time::sleep(Duration::from_secs(9)).await;
+        assert_eq!(watchdog.check(), None);
+
+        time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(watchdog.run(Duration::from_secs(1)).await.unwrap(), "rest");
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn refused_countdowns_are_returned() {
+        time::pause();
+        let (client, futures, server) = exchange("<revoked-key>", 0).await;
+        let watchdog = Watchdog::new(client, &["BNBUSDT", "BTCUSDT"])
+            .with_signal("rest", Duration::from_secs(10))
+            .with_countdown(futures, Duration::from_secs(60));
+
+        let error = watchdog.run(Duration::from_secs(1)).await.unwrap_err();
+        assert!(ApiError::has_code(&error, -2015));
+        // returned on the first refresh, before anything lapsed
+        assert_eq!(watchdog.tripped(), None);
+        assert_eq!(sent(&server, "/fapi/v1/countdownCancelAll").len(), 1);
+        assert!(sent(&server, "/fapi/v1/allOpenOrders").is_empty());
+    }
+}
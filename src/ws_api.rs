@@ -0,0 +1,843 @@
+use crate::builder::ParamBuilder;
+use crate::environment::Endpoint;
+use crate::error::{ClientError, WsCloseError};
+use crate::param::{OrderType, Parameters, Side, TimeInForce, ID};
+use crate::signer::{AsyncSigner, HmacSigner};
+use crate::types::*;
+use crate::ws_stream::WebSocketStream;
+use async_tungstenite::tungstenite::{protocol::frame::coding::CloseCode, Message};
+use chrono::Utc;
+use futures::channel::{mpsc, oneshot};
+use futures::{SinkExt, StreamExt};
+use log::warn;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+type Responder = oneshot::Sender<Value>;
+
+/// Time to wait for a response unless set with [`WsApiClient::with_timeout`].
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+enum Command {
+    Send(Request),
+    /// Drops the responder of a request that timed out.
+    Forget(u64),
+}
+
+struct Request {
+    id: u64,
+    method: &'static str,
+    params: Map<String, Value>,
+    responder: Responder,
+}
+
+#[derive(Serialize)]
+struct RequestMessage<'a> {
+    id: u64,
+    method: &'a str,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    params: &'a Map<String, Value>,
+}
+
+/// Method of the websocket api a [`ParamBuilder`] is sent to.
+pub struct WsApiRequest<'b> {
+    method: &'static str,
+    client: &'b WsApiClient,
+}
+
+/// Client for the websocket api; requests and responses share one persistent connection.
+#[derive(Clone)]
+pub struct WsApiClient {
+    api_key: String,
+    signer: Arc<dyn AsyncSigner>,
+    sender: mpsc::UnboundedSender<Command>,
+    session: Arc<AtomicBool>,
+    next_id: Arc<AtomicU64>,
+    timeout: Duration,
+}
+
+impl WsApiClient {
+    /// Connects to the websocket api.
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tokio_binance::{WsApiClient, Environment};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let client = WsApiClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn connect<A, S, U>(api_key: A, secret_key: S, url: U) -> crate::error::Result<Self>
+    where
+        A: Into<String>,
+        S: Into<String>,
+        U: Endpoint,
+    {
+        Self::connect_with_signer(api_key, HmacSigner::new(secret_key.into())?, url).await
+    }
+    /// Connects to the websocket api, signing requests with the provided signer.
+    /// # Example
+    ///
+    /// ```no_run
+    /// use tokio_binance::{WsApiClient, Ed25519Signer, Environment};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let signer = Ed25519Signer::from_pem(&std::fs::read_to_string("private_key.pem")?)?;
+    ///     let client = WsApiClient::connect_with_signer("<api-key>", signer, Environment::BinanceUs).await?;
+    ///     // Ed25519 keys can authenticate the connection once instead of signing every request.
+    ///     client.logon().await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn connect_with_signer<A, S, U>(
+        api_key: A,
+        signer: S,
+        url: U,
+    ) -> crate::error::Result<Self>
+    where
+        A: Into<String>,
+        S: AsyncSigner + 'static,
+        U: Endpoint,
+    {
+        let stream = WebSocketStream::connect_url(url.ws_api_url().to_string()).await?;
+        let (sender, receiver) = mpsc::unbounded();
+        tokio::spawn(run(stream, receiver));
+
+        Ok(Self {
+            api_key: api_key.into(),
+            signer: Arc::new(signer),
+            sender,
+            session: Arc::new(AtomicBool::new(false)),
+            next_id: Arc::new(AtomicU64::new(0)),
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+    /// Time to wait for each response; default is 10 seconds.
+    ///
+    /// A request that times out fails with a `-1007` [`ClientError`]; like on the REST api,
+    /// whether an order was placed is unknown then.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    /// Authenticate the connection; only supported for Ed25519 keys.
+    ///
+    /// Signed requests sent afterwards carry neither the api key nor a signature.
+    pub async fn logon(&self) -> crate::error::Result<Value> {
+        let mut params = Parameters {
+            api_key: Some(&self.api_key),
+            ..Parameters::default()
+        };
+        params.sign_sorted(self.signer.as_ref()).await?;
+
+        let result = self.request("session.logon", params.to_map()?).await?;
+        self.session.store(true, Ordering::SeqCst);
+        Ok(result)
+    }
+    /// Forget the api key the connection was authenticated with.
+    pub async fn logout(&self) -> crate::error::Result<Value> {
+        let result = self.request("session.logout", Map::new()).await?;
+        self.session.store(false, Ordering::SeqCst);
+        Ok(result)
+    }
+    /// Query the authentication status of the connection.
+    pub async fn get_session_status(&self) -> crate::error::Result<Value> {
+        self.request("session.status", Map::new()).await
+    }
+    /// Place a new limit order.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{WsApiClient, Environment};
+    /// use tokio_binance::{Side::Sell, TimeInForce::Fok, OrderRespType::Full};
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = WsApiClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs).await?;
+    /// let response = client
+    ///     // false will send as test, true will send as a real order.
+    ///     .place_limit_order("BNBUSDT", Sell, 20.00, 5.00, false)
+    ///     // optional: lifetime of order; default is Gtc.
+    ///     .with_time_in_force(Fok)
+    ///     // optional: unique id; auto generated by default.
+    ///     .with_new_client_order_id("<uuid>")
+    ///     // optional: output verbosity; default is Ack.
+    ///     .with_new_order_resp_type(Full)
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn place_limit_order<'a>(
+        &self,
+        symbol: &'a str,
+        side: Side,
+        price: f64,
+        quantity: f64,
+        execute: bool,
+    ) -> ParamBuilder<'a, '_, LimitOrderParams, WsApiRequest<'_>> {
+        let method = if execute { "order.place" } else { "order.test" };
+
+        self.signed(
+            method,
+            Parameters {
+                symbol: Some(symbol),
+                side: Some(side),
+                order_type: Some(OrderType::Limit),
+                price: Some(price),
+                quantity: Some(quantity),
+                time_in_force: Some(TimeInForce::Gtc),
+                ..Parameters::default()
+            },
+        )
+    }
+    /// Place a new market order.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{WsApiClient, Environment};
+    /// use tokio_binance::Side::Buy;
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = WsApiClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs).await?;
+    /// let response = client
+    ///     // false will send as test, true will send as a real order.
+    ///     .place_market_order("BNBUSDT", Buy, 5.00, false)
+    ///     // optional: converts Market to Stop-Loss; triggers when price hits below 21.00.
+    ///     .with_stop_loss(21.00)
+    ///     //
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn place_market_order<'a>(
+        &self,
+        symbol: &'a str,
+        side: Side,
+        quantity: f64,
+        execute: bool,
+    ) -> ParamBuilder<'a, '_, MarketOrderParams, WsApiRequest<'_>> {
+        let method = if execute { "order.place" } else { "order.test" };
+
+        self.signed(
+            method,
+            Parameters {
+                symbol: Some(symbol),
+                side: Some(side),
+                order_type: Some(OrderType::Market),
+                quantity: Some(quantity),
+                ..Parameters::default()
+            },
+        )
+    }
+    /// Get order.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{WsApiClient, Environment};
+    /// use tokio_binance::ID;
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = WsApiClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs).await?;
+    /// let response = client
+    ///     .get_order("BNBUSDT", ID::ClientOId("<uuid>"))
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_order<'a>(
+        &self,
+        symbol: &'a str,
+        id: ID<'a>,
+    ) -> ParamBuilder<'a, '_, OrderStatusParams, WsApiRequest<'_>> {
+        self.signed("order.status", order_params(symbol, id))
+    }
+    /// Cancel order.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{WsApiClient, Environment};
+    /// use tokio_binance::ID;
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = WsApiClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs).await?;
+    /// let response = client
+    ///     .cancel_order("BNBUSDT", ID::OrderId(1230494))
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cancel_order<'a>(
+        &self,
+        symbol: &'a str,
+        id: ID<'a>,
+    ) -> ParamBuilder<'a, '_, CancelOrderParams, WsApiRequest<'_>> {
+        self.signed("order.cancel", order_params(symbol, id))
+    }
+    /// Get open orders.
+    pub fn get_open_orders(&self) -> ParamBuilder<'_, '_, OpenOrderParams, WsApiRequest<'_>> {
+        self.signed("openOrders.status", Parameters::default())
+    }
+    /// Cancel all open orders on a symbol.
+    pub fn cancel_all_orders<'a>(
+        &self,
+        symbol: &'a str,
+    ) -> ParamBuilder<'a, '_, CancelAllOrdersParams, WsApiRequest<'_>> {
+        self.signed(
+            "openOrders.cancelAll",
+            Parameters {
+                symbol: Some(symbol),
+                ..Parameters::default()
+            },
+        )
+    }
+    /// Get all orders.
+    pub fn get_all_orders<'a>(
+        &self,
+        symbol: &'a str,
+    ) -> ParamBuilder<'a, '_, AllOrdersParams, WsApiRequest<'_>> {
+        self.signed(
+            "allOrders",
+            Parameters {
+                symbol: Some(symbol),
+                ..Parameters::default()
+            },
+        )
+    }
+    /// Get current account information.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{WsApiClient, Environment};
+    /// use serde_json::Value;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = WsApiClient::connect("<api-key>", "<secret-key>", Environment::BinanceUs).await?;
+    /// let response = client
+    ///     .get_account()
+    ///     // optional: processing time for request; default is 5000, can't be above 60000.
+    ///     .with_recv_window(8000)
+    ///     //
+    ///     .json::<Value>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_account(&self) -> ParamBuilder<'_, '_, AccountParams, WsApiRequest<'_>> {
+        self.signed("account.status", Parameters::default())
+    }
+    /// Get trades for a specific account and symbol.
+    pub fn get_account_trades<'a>(
+        &self,
+        symbol: &'a str,
+    ) -> ParamBuilder<'a, '_, AccountTradesParams, WsApiRequest<'_>> {
+        self.signed(
+            "myTrades",
+            Parameters {
+                symbol: Some(symbol),
+                ..Parameters::default()
+            },
+        )
+    }
+    /// Start a new user data stream.
+    pub fn start_stream(&self) -> ParamBuilder<'_, '_, StartStreamParams, WsApiRequest<'_>> {
+        ParamBuilder::new(
+            Parameters::default(),
+            WsApiRequest {
+                method: "userDataStream.start",
+                client: self,
+            },
+            Some(&self.api_key),
+            None,
+        )
+    }
+    /// Keep alive a user data stream.
+    pub fn keep_alive<'a>(
+        &self,
+        listen_key: &'a str,
+    ) -> ParamBuilder<'a, '_, KeepAliveStreamParams, WsApiRequest<'_>> {
+        ParamBuilder::new(
+            Parameters {
+                listen_key: Some(listen_key),
+                ..Parameters::default()
+            },
+            WsApiRequest {
+                method: "userDataStream.ping",
+                client: self,
+            },
+            Some(&self.api_key),
+            None,
+        )
+    }
+    /// Close out a user data stream.
+    pub fn close_stream<'a>(
+        &self,
+        listen_key: &'a str,
+    ) -> ParamBuilder<'a, '_, CloseStreamParams, WsApiRequest<'_>> {
+        ParamBuilder::new(
+            Parameters {
+                listen_key: Some(listen_key),
+                ..Parameters::default()
+            },
+            WsApiRequest {
+                method: "userDataStream.stop",
+                client: self,
+            },
+            Some(&self.api_key),
+            None,
+        )
+    }
+
+    fn signed<'a, T>(
+        &self,
+        method: &'static str,
+        params: Parameters<'a>,
+    ) -> ParamBuilder<'a, '_, T, WsApiRequest<'_>> {
+        ParamBuilder::new(
+            params,
+            WsApiRequest {
+                method,
+                client: self,
+            },
+            Some(&self.api_key),
+            Some(self.signer.as_ref()),
+        )
+    }
+
+    async fn request(
+        &self,
+        method: &'static str,
+        params: Map<String, Value>,
+    ) -> crate::error::Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (responder, response) = oneshot::channel();
+        let request = Request {
+            id,
+            method,
+            params,
+            responder,
+        };
+
+        self.sender
+            .unbounded_send(Command::Send(request))
+            .map_err(|_| connection_closed())?;
+        let mut response = match time::timeout(self.timeout, response).await {
+            Ok(response) => response.map_err(|_| connection_closed())?,
+            Err(_) => {
+                let _ = self.sender.unbounded_send(Command::Forget(id));
+                return Err(timed_out());
+            }
+        };
+
+        let status = response["status"].as_u64().unwrap_or_default() as u16;
+        if response.get("error").is_some() {
+            let reason = StatusCode::from_u16(status)
+                .ok()
+                .and_then(|status| status.canonical_reason())
+                .unwrap_or("UNKNOWN");
+            let data = &response["error"]["data"];
+            let retry_after = data["retryAfter"]
+                .as_u64()
+                .zip(data["serverTime"].as_u64())
+                .map(|(retry_after, now)| Duration::from_millis(retry_after.saturating_sub(now)));
+            let message = response["error"].to_string();
+            let err = ClientError::new(status, reason, &message).with_retry_after(retry_after);
+            Err(err.into())
+        } else {
+            Ok(response["result"].take())
+        }
+    }
+}
+
+impl<'a, 'b, T> ParamBuilder<'a, 'b, T, WsApiRequest<'b>> {
+    pub async fn text(self) -> crate::error::Result<String> {
+        let text = serde_json::to_string(&self.response().await?)?;
+        Ok(text)
+    }
+
+    pub async fn json<J: DeserializeOwned>(self) -> crate::error::Result<J> {
+        let json = serde_json::from_value(self.response().await?)?;
+        Ok(json)
+    }
+
+    async fn response(mut self) -> crate::error::Result<Value> {
+        let WsApiRequest { method, client } = self.builder;
+        self.params.validate()?;
+        let session = client.session.load(Ordering::SeqCst);
+
+        match self.signer {
+            Some(_) if session => {
+                self.params.timestamp = Some(Utc::now().timestamp_millis());
+            }
+            Some(signer) => {
+                self.params.api_key = self.api_key;
+                self.params.sign_sorted(signer).await?;
+            }
+            None => self.params.api_key = self.api_key,
+        }
+
+        client.request(method, self.params.to_map()?).await
+    }
+}
+
+fn order_params<'a>(symbol: &'a str, id: ID<'a>) -> Parameters<'a> {
+    let order_id = if let ID::OrderId(id) = id {
+        Some(id)
+    } else {
+        None
+    };
+
+    let orig_client_order_id = if let ID::ClientOId(id) = id {
+        Some(id)
+    } else {
+        None
+    };
+
+    Parameters {
+        symbol: Some(symbol),
+        order_id,
+        orig_client_order_id,
+        ..Parameters::default()
+    }
+}
+
+fn connection_closed() -> crate::error::Error {
+    WsCloseError::new(CloseCode::Abnormal, "Websocket api connection closed").into()
+}
+
+/// Error of a request without a response in time; the execution status is unknown.
+fn timed_out() -> crate::error::Error {
+    let status = StatusCode::GATEWAY_TIMEOUT;
+    let message = r#"{"code":-1007,"msg":"Timeout waiting for response from backend server. Send status unknown; execution status unknown."}"#;
+    let reason = status.canonical_reason().unwrap_or("UNKNOWN");
+    ClientError::new(status.as_u16(), reason, message).into()
+}
+
+async fn run(stream: WebSocketStream, mut commands: mpsc::UnboundedReceiver<Command>) {
+    let mut pending: HashMap<u64, Responder> = HashMap::new();
+    // only whole messages are taken off the stream, so a command never cancels a read
+    // partway and pings are answered from the loop
+    let (mut sink, mut messages) = stream.split();
+
+    loop {
+        tokio::select! {
+            command = commands.next() => match command {
+                Some(Command::Send(Request { id, method, params, responder })) => {
+                    let message = RequestMessage { id, method, params: &params };
+                    let message = match serde_json::to_string(&message) {
+                        Ok(message) => message,
+                        Err(e) => {
+                            warn!("{}", e);
+                            continue;
+                        }
+                    };
+
+                    if let Err(e) = sink.send(Message::Text(message)).await {
+                        warn!("{}", e);
+                        return;
+                    }
+                    pending.insert(id, responder);
+                }
+                Some(Command::Forget(id)) => {
+                    pending.remove(&id);
+                }
+                None => {
+                    let _ = sink.close().await;
+                    return;
+                }
+            },
+            message = messages.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let value: Value = match serde_json::from_str(&text) {
+                        Ok(value) => value,
+                        Err(e) => {
+                            warn!("{}", e);
+                            return;
+                        }
+                    };
+                    let responder = value["id"].as_u64().and_then(|id| pending.remove(&id));
+                    if let Some(responder) = responder {
+                        let _ = responder.send(value);
+                    }
+                }
+                Some(Ok(Message::Ping(payload))) => {
+                    if let Err(e) = sink.send(Message::Pong(payload)).await {
+                        warn!("{}", e);
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(frame))) => {
+                    if let Some(frame) = frame {
+                        warn!("{}", WsCloseError::new(frame.code, frame.reason));
+                    }
+                    return;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    warn!("{}", e);
+                    return;
+                }
+                None => return,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{next_request, send, serve_ws};
+    use crate::model::ApiError;
+    use serde_json::json;
+    use std::error::Error as _;
+    use std::sync::Mutex;
+
+    async fn client(url: String) -> WsApiClient {
+        WsApiClient::connect("<api-key>", "<secret-key>", url)
+            .await
+            .unwrap()
+    }
+
+    /// Sorted names of the parameters of a request.
+    fn names(request: &Value) -> Vec<String> {
+        let params = request["params"].as_object().cloned().unwrap_or_default();
+        let mut names: Vec<_> = params.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn sorted(names: &[&str]) -> Vec<String> {
+        let mut names: Vec<_> = names.iter().map(|name| name.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn requests_after_logon_only_carry_a_timestamp() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        let url = serve_ws(move |mut ws| {
+            let log = log.clone();
+            async move {
+                while let Some(request) = next_request(&mut ws).await {
+                    send(
+                        &mut ws,
+                        json!({"id": request["id"], "status": 200, "result": {}}),
+                    )
+                    .await;
+                    log.lock().unwrap().push(request);
+                }
+            }
+        })
+        .await;
+        let client = client(url).await;
+
+        let order = || client.place_limit_order("BNBUSDT", Side::Buy, 300.0, 1.0, false);
+        order().json::<Value>().await.unwrap();
+        client.logon().await.unwrap();
+        order().json::<Value>().await.unwrap();
+        client.start_stream().json::<Value>().await.unwrap();
+        client.logout().await.unwrap();
+        order().json::<Value>().await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        let methods: Vec<_> = requests.iter().map(|request| &request["method"]).collect();
+        assert_eq!(
+            methods,
+            vec![
+                "order.test",
+                "session.logon",
+                "order.test",
+                "userDataStream.start",
+                "session.logout",
+                "order.test"
+            ]
+        );
+        let ids: Vec<_> = requests
+            .iter()
+            .map(|request| request["id"].clone())
+            .collect();
+        assert_eq!(ids, (0..6).map(Value::from).collect::<Vec<_>>());
+
+        let order = ["price", "quantity", "side", "symbol", "timeInForce", "type"];
+        let signed = sorted(&[&order[..], &["apiKey", "signature", "timestamp"]].concat());
+        assert_eq!(names(&requests[0]), signed);
+        assert_eq!(requests[0]["params"]["apiKey"], "<api-key>");
+        assert_eq!(
+            names(&requests[1]),
+            vec!["apiKey", "signature", "timestamp"]
+        );
+        assert_eq!(
+            names(&requests[2]),
+            sorted(&[&order[..], &["timestamp"]].concat())
+        );
+        // unsigned requests carry the api key only
+        assert_eq!(names(&requests[3]), vec!["apiKey"]);
+        assert!(requests[4].get("params").is_none());
+        assert_eq!(names(&requests[5]), signed);
+    }
+
+    #[tokio::test]
+    async fn errors_map_to_client_errors() {
+        let url = serve_ws(|mut ws| async move {
+            let request = next_request(&mut ws).await.unwrap();
+            let error = json!({
+                "code": -1003,
+                "msg": "Too much request weight used; current limit is 6000 request weight per 1 MINUTE.",
+                "data": {"serverTime": 1_000, "retryAfter": 3_500},
+            });
+            send(&mut ws, json!({"id": request["id"], "status": 429, "error": error})).await;
+
+            let request = next_request(&mut ws).await.unwrap();
+            let error = json!({"code": -2013, "msg": "Order does not exist."});
+            send(&mut ws, json!({"id": request["id"], "status": 400, "error": error})).await;
+            while next_request(&mut ws).await.is_some() {}
+        })
+        .await;
+        let client = client(url).await;
+
+        let error = client.get_account().json::<Value>().await.unwrap_err();
+        let client_error = error
+            .source()
+            .and_then(|source| source.downcast_ref::<ClientError>())
+            .unwrap();
+        assert_eq!(client_error.code(), 429);
+        assert!(client_error.is_rate_limited());
+        assert_eq!(
+            client_error.retry_after(),
+            Some(Duration::from_millis(2_500))
+        );
+        assert_eq!(ApiError::from_error(&error).unwrap().code, -1003);
+
+        let error = client
+            .get_order("BNBUSDT", ID::OrderId(1))
+            .json::<Value>()
+            .await
+            .unwrap_err();
+        let client_error = error
+            .source()
+            .and_then(|source| source.downcast_ref::<ClientError>())
+            .unwrap();
+        assert_eq!(client_error.code(), 400);
+        assert_eq!(client_error.retry_after(), None);
+        let refusal = ApiError::from_error(&error).unwrap();
+        assert_eq!(
+            (refusal.code, refusal.msg.as_str()),
+            (-2013, "Order does not exist.")
+        );
+    }
+
+    #[tokio::test]
+    async fn dropped_connections_fail_the_pending_requests() {
+        let url = serve_ws(|mut ws| async move {
+            next_request(&mut ws).await.unwrap();
+            next_request(&mut ws).await.unwrap();
+            // both requests are read, neither is answered
+            drop(ws);
+        })
+        .await;
+        let client = client(url).await;
+
+        let (first, second) = tokio::join!(
+            client.get_account().json::<Value>(),
+            client.get_open_orders().json::<Value>(),
+        );
+        for error in [first.unwrap_err(), second.unwrap_err()] {
+            let error = error
+                .source()
+                .and_then(|source| source.downcast_ref::<WsCloseError>());
+            assert!(error.is_some());
+        }
+        // and so do the requests sent afterwards
+        let error = client.get_account().json::<Value>().await.unwrap_err();
+        assert!(error.source().unwrap().is::<WsCloseError>());
+    }
+
+    #[tokio::test]
+    async fn pings_are_answered_while_requests_are_sent() {
+        let url = serve_ws(|mut ws| async move {
+            let first = next_request(&mut ws).await.unwrap();
+            ws.send(Message::Ping(b"ping".to_vec())).await.unwrap();
+            let mut pong = None;
+            let mut second = None;
+            while pong.is_none() || second.is_none() {
+                match ws.next().await.unwrap().unwrap() {
+                    Message::Pong(payload) => pong = Some(payload),
+                    Message::Text(text) => second = serde_json::from_str::<Value>(&text).ok(),
+                    _ => {}
+                }
+            }
+            assert_eq!(pong.unwrap(), b"ping");
+            for (request, result) in [(first, 1), (second.unwrap(), 2)] {
+                let response = json!({"id": request["id"], "status": 200, "result": result});
+                send(&mut ws, response).await;
+            }
+            while next_request(&mut ws).await.is_some() {}
+        })
+        .await;
+        let client = client(url).await;
+
+        let (first, second) = tokio::join!(
+            client.get_account().json::<Value>(),
+            client.get_open_orders().json::<Value>(),
+        );
+        assert_eq!((first.unwrap(), second.unwrap()), (json!(1), json!(2)));
+    }
+
+    #[tokio::test]
+    async fn requests_time_out() {
+        let url = serve_ws(|mut ws| async move {
+            let ignored = next_request(&mut ws).await.unwrap();
+            let request = next_request(&mut ws).await.unwrap();
+            // the answer to the request that timed out comes too late
+            send(
+                &mut ws,
+                json!({"id": ignored["id"], "status": 200, "result": 1}),
+            )
+            .await;
+            send(
+                &mut ws,
+                json!({"id": request["id"], "status": 200, "result": 2}),
+            )
+            .await;
+            while next_request(&mut ws).await.is_some() {}
+        })
+        .await;
+        let client = client(url).await.with_timeout(Duration::from_millis(50));
+
+        let error = client.get_account().json::<Value>().await.unwrap_err();
+        let client_error = error
+            .source()
+            .and_then(|source| source.downcast_ref::<ClientError>())
+            .unwrap();
+        assert_eq!(client_error.code(), 504);
+        assert_eq!(ApiError::from_error(&error).unwrap().code, -1007);
+
+        let result = client.get_account().json::<Value>().await.unwrap();
+        assert_eq!(result, 2);
+    }
+}
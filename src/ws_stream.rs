@@ -18,23 +18,32 @@ use futures::{
 use tokio::net::TcpStream;
 use tokio_native_tls::TlsStream;
 
-use crate::error::{Error, Kind, WsCloseError};
-use crate::param::Interval;
-use serde::de::DeserializeOwned;
-use serde::Serialize;
+use crate::environment::Endpoint;
+use crate::error::{Error, Kind, ParseChannelError, WsCloseError, WsRequestError};
+use crate::model::StreamEvent;
+use crate::param::{Interval, WindowSize};
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
+use std::collections::VecDeque;
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Channel<'c> {
     AggTrade(&'c str),
     Depth(&'c str, Speed),
     Trade(&'c str),
     Kline(&'c str, Interval),
+    /// Klines with intervals aligned to UTC+8
+    KlineUtc8(&'c str, Interval),
     MiniTicker(&'c str),
     AllMiniTickers,
     Ticker(&'c str),
     AllTickers,
+    RollingTicker(&'c str, WindowSize),
+    AllRollingTickers(WindowSize),
+    AvgPrice(&'c str),
     BookTicker(&'c str),
     AllBookTickers,
     PartialDepth(&'c str, Level, Speed),
@@ -55,32 +64,43 @@ impl<'c> fmt::Display for Channel<'c> {
                     symbol.to_lowercase() + "@kline_" + interval.as_str().unwrap()
                 )
             }
+            Self::KlineUtc8(symbol, interval) => {
+                let interval = serde_json::to_value(interval).unwrap();
+                write!(
+                    f,
+                    "{}",
+                    symbol.to_lowercase() + "@kline_" + interval.as_str().unwrap() + "@+08:00"
+                )
+            }
             Self::MiniTicker(symbol) => write!(f, "{}", symbol.to_lowercase() + "@miniTicker"),
             Self::AllMiniTickers => write!(f, "!miniTicker@arr"),
             Self::Ticker(symbol) => write!(f, "{}", symbol.to_lowercase() + "@ticker"),
             Self::AllTickers => write!(f, "!ticker@arr"),
+            Self::RollingTicker(symbol, window) => {
+                let window = serde_json::to_value(window).unwrap();
+                write!(
+                    f,
+                    "{}",
+                    symbol.to_lowercase() + "@ticker_" + window.as_str().unwrap()
+                )
+            }
+            Self::AllRollingTickers(window) => {
+                let window = serde_json::to_value(window).unwrap();
+                write!(f, "!ticker_{}@arr", window.as_str().unwrap())
+            }
+            Self::AvgPrice(symbol) => write!(f, "{}", symbol.to_lowercase() + "@avgPrice"),
             Self::BookTicker(symbol) => write!(f, "{}", symbol.to_lowercase() + "@bookTicker"),
             Self::AllBookTickers => write!(f, "!bookTicker"),
             Self::PartialDepth(symbol, level, speed) => {
                 let level = serde_json::to_value(level).unwrap();
-                let speed = serde_json::to_value(speed).unwrap();
                 write!(
                     f,
                     "{}",
-                    symbol.to_lowercase()
-                        + "@depth"
-                        + level.as_str().unwrap()
-                        + "@"
-                        + speed.as_str().unwrap()
+                    symbol.to_lowercase() + "@depth" + level.as_str().unwrap() + speed.suffix()
                 )
             }
             Self::Depth(symbol, speed) => {
-                let speed = serde_json::to_value(speed).unwrap();
-                write!(
-                    f,
-                    "{}",
-                    symbol.to_lowercase() + "@depth@" + speed.as_str().unwrap()
-                )
+                write!(f, "{}", symbol.to_lowercase() + "@depth" + speed.suffix())
             }
             Self::UserData(listen_key) => write!(f, "{}", listen_key),
         }
@@ -105,7 +125,241 @@ impl<'c> PartialEq<Value> for Channel<'c> {
     }
 }
 
-#[derive(Copy, Clone, Serialize)]
+/// Owned version of [`Channel`] that can be stored, sent across tasks
+/// and parsed back from a stream name.
+///
+/// Symbols are kept in uppercase, so channels compare equal regardless of the case they were created with.
+/// # Example
+///
+/// ```
+/// use tokio_binance::{Channel, ChannelBuf, Interval};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let channel: ChannelBuf = "bnbusdt@kline_1m".parse()?;
+/// assert_eq!(channel, ChannelBuf::Kline("BNBUSDT".into(), Interval::OneMinute));
+/// assert_eq!(channel, ChannelBuf::from(Channel::Kline("bnbusdt", Interval::OneMinute)));
+/// assert_eq!(channel.to_string(), "bnbusdt@kline_1m");
+///
+/// let channel: ChannelBuf = "bnbusdt@kline_1s@+08:00".parse()?;
+/// assert_eq!(channel, ChannelBuf::KlineUtc8("BNBUSDT".into(), Interval::OneSecond));
+/// assert_eq!(channel.to_string(), "bnbusdt@kline_1s@+08:00");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ChannelBuf {
+    AggTrade(String),
+    Depth(String, Speed),
+    Trade(String),
+    Kline(String, Interval),
+    /// Klines with intervals aligned to UTC+8
+    KlineUtc8(String, Interval),
+    MiniTicker(String),
+    AllMiniTickers,
+    Ticker(String),
+    AllTickers,
+    RollingTicker(String, WindowSize),
+    AllRollingTickers(WindowSize),
+    AvgPrice(String),
+    BookTicker(String),
+    AllBookTickers,
+    PartialDepth(String, Level, Speed),
+    /// The only channel that takes a listen-key instead of a symbol
+    UserData(String),
+}
+
+impl ChannelBuf {
+    /// Borrows the channel, e.g. to subscribe to it.
+    pub fn as_channel(&self) -> Channel<'_> {
+        match self {
+            Self::AggTrade(symbol) => Channel::AggTrade(symbol),
+            Self::Depth(symbol, speed) => Channel::Depth(symbol, *speed),
+            Self::Trade(symbol) => Channel::Trade(symbol),
+            Self::Kline(symbol, interval) => Channel::Kline(symbol, *interval),
+            Self::KlineUtc8(symbol, interval) => Channel::KlineUtc8(symbol, *interval),
+            Self::MiniTicker(symbol) => Channel::MiniTicker(symbol),
+            Self::AllMiniTickers => Channel::AllMiniTickers,
+            Self::Ticker(symbol) => Channel::Ticker(symbol),
+            Self::AllTickers => Channel::AllTickers,
+            Self::RollingTicker(symbol, window) => Channel::RollingTicker(symbol, *window),
+            Self::AllRollingTickers(window) => Channel::AllRollingTickers(*window),
+            Self::AvgPrice(symbol) => Channel::AvgPrice(symbol),
+            Self::BookTicker(symbol) => Channel::BookTicker(symbol),
+            Self::AllBookTickers => Channel::AllBookTickers,
+            Self::PartialDepth(symbol, level, speed) => {
+                Channel::PartialDepth(symbol, *level, *speed)
+            }
+            Self::UserData(listen_key) => Channel::UserData(listen_key),
+        }
+    }
+    /// Symbol of the channel; none for the channels of all symbols and user data.
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            Self::AggTrade(symbol)
+            | Self::Depth(symbol, _)
+            | Self::Trade(symbol)
+            | Self::Kline(symbol, _)
+            | Self::KlineUtc8(symbol, _)
+            | Self::MiniTicker(symbol)
+            | Self::Ticker(symbol)
+            | Self::RollingTicker(symbol, _)
+            | Self::AvgPrice(symbol)
+            | Self::BookTicker(symbol)
+            | Self::PartialDepth(symbol, ..) => Some(symbol),
+            Self::AllMiniTickers
+            | Self::AllTickers
+            | Self::AllRollingTickers(_)
+            | Self::AllBookTickers
+            | Self::UserData(_) => None,
+        }
+    }
+}
+
+impl<'c> From<Channel<'c>> for ChannelBuf {
+    fn from(channel: Channel<'c>) -> Self {
+        match channel {
+            Channel::AggTrade(symbol) => Self::AggTrade(symbol.to_uppercase()),
+            Channel::Depth(symbol, speed) => Self::Depth(symbol.to_uppercase(), speed),
+            Channel::Trade(symbol) => Self::Trade(symbol.to_uppercase()),
+            Channel::Kline(symbol, interval) => Self::Kline(symbol.to_uppercase(), interval),
+            Channel::KlineUtc8(symbol, interval) => {
+                Self::KlineUtc8(symbol.to_uppercase(), interval)
+            }
+            Channel::MiniTicker(symbol) => Self::MiniTicker(symbol.to_uppercase()),
+            Channel::AllMiniTickers => Self::AllMiniTickers,
+            Channel::Ticker(symbol) => Self::Ticker(symbol.to_uppercase()),
+            Channel::AllTickers => Self::AllTickers,
+            Channel::RollingTicker(symbol, window) => {
+                Self::RollingTicker(symbol.to_uppercase(), window)
+            }
+            Channel::AllRollingTickers(window) => Self::AllRollingTickers(window),
+            Channel::AvgPrice(symbol) => Self::AvgPrice(symbol.to_uppercase()),
+            Channel::BookTicker(symbol) => Self::BookTicker(symbol.to_uppercase()),
+            Channel::AllBookTickers => Self::AllBookTickers,
+            Channel::PartialDepth(symbol, level, speed) => {
+                Self::PartialDepth(symbol.to_uppercase(), level, speed)
+            }
+            Channel::UserData(listen_key) => Self::UserData(listen_key.into()),
+        }
+    }
+}
+
+impl fmt::Display for ChannelBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_channel().fmt(f)
+    }
+}
+
+impl FromStr for ChannelBuf {
+    type Err = ParseChannelError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let channel = match name {
+            "!miniTicker@arr" => Some(Self::AllMiniTickers),
+            "!ticker@arr" => Some(Self::AllTickers),
+            "!bookTicker" => Some(Self::AllBookTickers),
+            _ if name.starts_with('!') => name
+                .strip_prefix("!ticker_")
+                .and_then(|window| window.strip_suffix("@arr"))
+                .and_then(from_name)
+                .map(Self::AllRollingTickers),
+            _ => match name.split_once('@') {
+                Some((symbol, stream)) if !symbol.is_empty() => {
+                    parse_symbol_channel(symbol.to_uppercase(), stream)
+                }
+                Some(_) => None,
+                None if is_listen_key(name) => Some(Self::UserData(name.into())),
+                None => None,
+            },
+        };
+
+        channel.ok_or_else(|| ParseChannelError::new(name))
+    }
+}
+
+fn parse_symbol_channel(symbol: String, stream: &str) -> Option<ChannelBuf> {
+    let channel = match stream {
+        "aggTrade" => ChannelBuf::AggTrade(symbol),
+        "trade" => ChannelBuf::Trade(symbol),
+        "miniTicker" => ChannelBuf::MiniTicker(symbol),
+        "ticker" => ChannelBuf::Ticker(symbol),
+        "bookTicker" => ChannelBuf::BookTicker(symbol),
+        "avgPrice" => ChannelBuf::AvgPrice(symbol),
+        _ => {
+            if let Some(kline) = stream.strip_prefix("kline_") {
+                match kline.split_once('@') {
+                    Some((interval, "+08:00")) => {
+                        ChannelBuf::KlineUtc8(symbol, from_name(interval)?)
+                    }
+                    Some(_) => return None,
+                    None => ChannelBuf::Kline(symbol, from_name(kline)?),
+                }
+            } else if let Some(window) = stream.strip_prefix("ticker_") {
+                ChannelBuf::RollingTicker(symbol, from_name(window)?)
+            } else if let Some(depth) = stream.strip_prefix("depth") {
+                let (level, speed) = match depth.split_once('@') {
+                    Some((level, "100ms")) => (level, Speed::HundredMillis),
+                    // binance defaults to 1000ms, which is left out of the name
+                    Some(_) => return None,
+                    None => (depth, Speed::ThousandMillis),
+                };
+
+                if level.is_empty() {
+                    ChannelBuf::Depth(symbol, speed)
+                } else {
+                    ChannelBuf::PartialDepth(symbol, from_name(level)?, speed)
+                }
+            } else {
+                return None;
+            }
+        }
+    };
+    Some(channel)
+}
+
+/// Listen keys are 60 alphanumeric characters; shorter names are rather a symbol
+/// that is missing its stream.
+fn is_listen_key(name: &str) -> bool {
+    name.len() >= 32 && name.bytes().all(|byte| byte.is_ascii_alphanumeric())
+}
+
+/// Parses a serde renamed enum like [`Interval`] from its name.
+fn from_name<T: DeserializeOwned>(name: &str) -> Option<T> {
+    serde_json::from_value(Value::String(name.into())).ok()
+}
+
+impl Serialize for ChannelBuf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ChannelBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(de::Error::custom)
+    }
+}
+
+impl PartialEq<&str> for ChannelBuf {
+    fn eq(&self, other: &&str) -> bool {
+        &self.to_string() == other
+    }
+}
+
+impl PartialEq<String> for ChannelBuf {
+    fn eq(&self, other: &String) -> bool {
+        &self.to_string() == other
+    }
+}
+
+impl PartialEq<Value> for ChannelBuf {
+    fn eq(&self, other: &Value) -> bool {
+        &self.to_string() == other
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Level {
     #[serde(rename = "5")]
     Five,
@@ -115,14 +369,25 @@ pub enum Level {
     Twenty,
 }
 
-#[derive(Copy, Clone, Serialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Speed {
     #[serde(rename = "100ms")]
     HundredMillis,
+    /// The default speed; stream names leave it out.
     #[serde(rename = "1000ms")]
     ThousandMillis,
 }
 
+impl Speed {
+    /// Suffix of the stream name, e.g. `@100ms`.
+    fn suffix(&self) -> &'static str {
+        match self {
+            Self::HundredMillis => "@100ms",
+            Self::ThousandMillis => "",
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct SubscribeMessage<'a> {
     method: &'a str,
@@ -130,6 +395,20 @@ struct SubscribeMessage<'a> {
     id: u64,
 }
 
+#[derive(Deserialize)]
+struct Reply {
+    id: u64,
+    #[serde(default)]
+    result: Value,
+    error: Option<ReplyError>,
+}
+
+#[derive(Deserialize)]
+struct ReplyError {
+    code: i64,
+    msg: String,
+}
+
 type InnerStream = (
     WsStream<StreamSwitcher<TokioAdapter<TcpStream>, TokioAdapter<TlsStream<TcpStream>>>>,
     Response,
@@ -139,6 +418,14 @@ type InnerStream = (
 pub struct WebSocketStream {
     inner: InnerStream,
     id: u64,
+    /// Whether replies to subscribe and property requests are kept out of the data stream.
+    filter_replies: bool,
+    /// Messages read while waiting for a reply.
+    buffer: VecDeque<Message>,
+    /// Id of the request being waited on; requests take the stream mutably, so there is at
+    /// most one, and replies to requests that were dropped are discarded.
+    awaiting: Option<u64>,
+    reply: Option<Reply>,
 }
 
 impl WebSocketStream {
@@ -146,44 +433,47 @@ impl WebSocketStream {
     /// # Example
     ///
     /// ```no_run
-    /// use tokio_binance::{WebSocketStream, BINANCE_US_WSS_URL, Channel};
+    /// use tokio_binance::{WebSocketStream, Environment, Channel};
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
     ///     let channel = Channel::Ticker("BNBUSDT");
-    ///     let mut stream = WebSocketStream::connect(channel, BINANCE_US_WSS_URL).await?;
+    ///     let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
     ///     Ok(())
     /// }
     /// ```
-    pub async fn connect<U: Into<String>>(
-        channel: Channel<'_>,
-        url: U,
-    ) -> crate::error::Result<Self> {
-        let url = url.into() + "/ws/" + &channel.to_string();
-
-        let inner = connect_async(url).await?;
-        let mut stream = Self { inner, id: 0 };
+    pub async fn connect<U: Endpoint>(channel: Channel<'_>, url: U) -> crate::error::Result<Self> {
+        let url = url.stream_url().to_string() + "/ws/" + &channel.to_string();
+        let mut stream = Self::connect_url(url).await?;
+        stream.filter_replies = true;
 
-        let message = SubscribeMessage {
-            method: "SET_PROPERTY",
-            params: &["combined".into(), true.into()],
-            id: stream.id,
-        };
-        let message = serde_json::to_string(&message)?;
-        stream.send(Message::Text(message)).await?;
-        stream.id += 1;
+        stream
+            .request("SET_PROPERTY", &["combined".into(), true.into()])
+            .await?;
 
         Ok(stream)
     }
+    /// Connects to the url as is, without subscribing to any channel.
+    pub(crate) async fn connect_url(url: String) -> crate::error::Result<Self> {
+        let inner = connect_async(url).await?;
+        Ok(Self {
+            inner,
+            id: 0,
+            filter_replies: false,
+            buffer: VecDeque::new(),
+            awaiting: None,
+            reply: None,
+        })
+    }
     /// Helper method for getting messages as text.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WebSocketStream, BINANCE_US_WSS_URL, Channel};
+    /// # use tokio_binance::{WebSocketStream, Environment, Channel};
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// # let channel = Channel::Ticker("BNBUSDT");
-    /// # let mut stream = WebSocketStream::connect(channel, BINANCE_US_WSS_URL).await?;
+    /// # let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
     /// while let Some(text) = stream.text().await? {
     ///     println!("{}", text);
     /// }
@@ -192,32 +482,7 @@ impl WebSocketStream {
     /// ```
     pub async fn text(&mut self) -> crate::error::Result<Option<String>> {
         match self.try_next().await? {
-            Some(msg) => match msg {
-                Message::Text(text) => Ok(Some(text)),
-                Message::Ping(ref value) => {
-                    self.send(Message::Pong(value.clone())).await?;
-                    let ping = serde_json::json!({
-                        "ping": msg.into_text()?,
-                    });
-                    Ok(Some(serde_json::to_string(&ping)?))
-                }
-                Message::Pong(ref value) => {
-                    self.send(Message::Ping(value.clone())).await?;
-                    let pong = serde_json::json!({
-                        "pong": msg.into_text()?,
-                    });
-                    Ok(Some(serde_json::to_string(&pong)?))
-                }
-                Message::Binary(_) => Ok(Some(msg.into_text()?)),
-                Message::Close(Some(frame)) => {
-                    Err(WsCloseError::new(frame.code, frame.reason).into())
-                }
-                Message::Close(None) => Err(WsCloseError::new(
-                    CloseCode::Abnormal,
-                    "Close message with no frame received",
-                )
-                .into()),
-            },
+            Some(msg) => self.message_text(msg).await,
             None => Ok(None),
         }
     }
@@ -225,13 +490,13 @@ impl WebSocketStream {
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WebSocketStream, BINANCE_US_WSS_URL, Channel};
+    /// # use tokio_binance::{WebSocketStream, Environment, Channel};
     /// use serde_json::Value;
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// # let channel = Channel::Ticker("BNBUSDT");
-    /// # let mut stream = WebSocketStream::connect(channel, BINANCE_US_WSS_URL).await?;
+    /// # let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
     /// while let Some(value) = stream.json::<Value>().await? {
     ///     // filter the messages before accessing a field.
     ///     if channel == value["stream"] {
@@ -247,17 +512,31 @@ impl WebSocketStream {
             None => Ok(None),
         }
     }
+    /// Waits for the next event of a combined stream, skipping pings, pongs and replies.
+    pub(crate) async fn stream_event<T: DeserializeOwned>(
+        &mut self,
+    ) -> crate::error::Result<Option<StreamEvent<T>>> {
+        while let Some(text) = self.text().await? {
+            let value: Value = serde_json::from_str(&text)?;
+            if value.get("stream").is_some() {
+                return Ok(Some(serde_json::from_value(value)?));
+            }
+        }
+        Ok(None)
+    }
     /// Subscribe to one or more channels aka streams.
+    ///
+    /// Resolves once binance acknowledges the request.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WebSocketStream, BINANCE_US_WSS_URL};
+    /// # use tokio_binance::{WebSocketStream, Environment};
     /// use tokio_binance::{Channel, Interval};
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// # let channel = Channel::Ticker("BNBUSDT");
-    /// # let mut stream = WebSocketStream::connect(channel, BINANCE_US_WSS_URL).await?;
+    /// # let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
     /// stream.subscribe(&[
     ///     Channel::AggTrade("BNBUSDT"),
     ///     Channel::Ticker("BTCUSDT"),
@@ -271,16 +550,18 @@ impl WebSocketStream {
         self.send_msg("SUBSCRIBE", channels).await
     }
     /// Unsubscribe from one or more channels aka streams.
+    ///
+    /// Resolves once binance acknowledges the request.
     /// # Example
     ///
     /// ```no_run
-    /// # use tokio_binance::{WebSocketStream, BINANCE_US_WSS_URL};
+    /// # use tokio_binance::{WebSocketStream, Environment};
     /// use tokio_binance::{Channel, Interval};
     ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// # let channel = Channel::Ticker("BNBUSDT");
-    /// # let mut stream = WebSocketStream::connect(channel, BINANCE_US_WSS_URL).await?;
+    /// # let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
     /// stream.unsubscribe(&[
     ///     Channel::AggTrade("BNBUSDT"),
     ///     Channel::Kline("BNBUSDT", Interval::OneMinute)
@@ -292,6 +573,41 @@ impl WebSocketStream {
     pub async fn unsubscribe(&mut self, channels: &[Channel<'_>]) -> crate::error::Result<()> {
         self.send_msg("UNSUBSCRIBE", channels).await
     }
+    /// List the channels aka streams the connection is subscribed to.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{WebSocketStream, Environment, Channel};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let channel = Channel::Ticker("BNBUSDT");
+    /// # let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
+    /// let subscriptions = stream.list_subscriptions().await?;
+    /// assert!(subscriptions.iter().any(|name| channel == *name));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_subscriptions(&mut self) -> crate::error::Result<Vec<String>> {
+        let result = self.request("LIST_SUBSCRIPTIONS", &[]).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+    /// Get the value of a connection property, e.g. `combined`.
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tokio_binance::{WebSocketStream, Environment, Channel};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let channel = Channel::Ticker("BNBUSDT");
+    /// # let mut stream = WebSocketStream::connect(channel, Environment::BinanceUs).await?;
+    /// let combined = stream.get_property("combined").await?;
+    /// assert_eq!(combined, true);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_property(&mut self, property: &str) -> crate::error::Result<Value> {
+        self.request("GET_PROPERTY", &[property.into()]).await
+    }
     /// Returns a shared reference to the inner stream.
     pub fn get_ref(&self) -> &InnerStream {
         &self.inner
@@ -316,15 +632,91 @@ impl WebSocketStream {
             .map(|channel| Value::String(channel.to_string()))
             .collect();
 
-        let message = SubscribeMessage {
-            method,
-            params: &params,
-            id: self.id,
-        };
+        self.request(method, &params).await?;
+        Ok(())
+    }
+
+    async fn request(&mut self, method: &str, params: &[Value]) -> crate::error::Result<Value> {
+        let id = self.id;
+        let message = SubscribeMessage { method, params, id };
         let message = serde_json::to_string(&message)?;
+        // a previous request that was dropped is not waited on anymore
+        self.awaiting = Some(id);
+        self.reply = None;
         self.send(Message::Text(message)).await?;
         self.id += 1;
-        Ok(())
+
+        loop {
+            if let Some(reply) = self.reply.take() {
+                self.awaiting = None;
+                return match reply.error {
+                    Some(error) => Err(WsRequestError::new(id, error.code, error.msg).into()),
+                    None => Ok(reply.result),
+                };
+            }
+
+            match self.inner.0.try_next().await? {
+                Some(message) => match self.reply(&message) {
+                    Some(reply) => self.keep_reply(reply),
+                    None => self.buffer.push_back(message),
+                },
+                None => {
+                    return Err(WsCloseError::new(
+                        CloseCode::Abnormal,
+                        "Stream ended before the reply was received",
+                    )
+                    .into())
+                }
+            }
+        }
+    }
+
+    /// Converts a message to text, answering pings and pongs along the way.
+    pub(crate) async fn message_text(
+        &mut self,
+        msg: Message,
+    ) -> crate::error::Result<Option<String>> {
+        match msg {
+            Message::Text(text) => Ok(Some(text)),
+            Message::Ping(ref value) => {
+                self.send(Message::Pong(value.clone())).await?;
+                let ping = serde_json::json!({
+                    "ping": msg.into_text()?,
+                });
+                Ok(Some(serde_json::to_string(&ping)?))
+            }
+            Message::Pong(ref value) => {
+                self.send(Message::Ping(value.clone())).await?;
+                let pong = serde_json::json!({
+                    "pong": msg.into_text()?,
+                });
+                Ok(Some(serde_json::to_string(&pong)?))
+            }
+            Message::Binary(_) => Ok(Some(msg.into_text()?)),
+            Message::Close(Some(frame)) => Err(WsCloseError::new(frame.code, frame.reason).into()),
+            Message::Close(None) => Err(WsCloseError::new(
+                CloseCode::Abnormal,
+                "Close message with no frame received",
+            )
+            .into()),
+        }
+    }
+
+    /// Keeps the reply to the request being waited on; others are discarded.
+    fn keep_reply(&mut self, reply: Reply) {
+        if self.awaiting == Some(reply.id) {
+            self.reply = Some(reply);
+        }
+    }
+
+    /// Parses the message if it is a reply to a request rather than stream data.
+    fn reply(&self, message: &Message) -> Option<Reply> {
+        match message {
+            Message::Text(text) if self.filter_replies && !text.starts_with("{\"stream\"") => {
+                serde_json::from_str(text).ok()
+            }
+            _ => None,
+        }
     }
 }
 
@@ -332,10 +724,22 @@ impl Stream for WebSocketStream {
     type Item = crate::error::Result<Message>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        match self.inner.0.try_poll_next_unpin(cx) {
-            Poll::Ready(Some(val)) => Poll::Ready(Some(Ok(val?))),
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Pending => Poll::Pending,
+        if let Some(message) = self.buffer.pop_front() {
+            return Poll::Ready(Some(Ok(message)));
+        }
+
+        loop {
+            match self.inner.0.try_poll_next_unpin(cx) {
+                Poll::Ready(Some(val)) => {
+                    let message = val?;
+                    match self.reply(&message) {
+                        Some(reply) => self.keep_reply(reply),
+                        None => return Poll::Ready(Some(Ok(message))),
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
         }
     }
 }
@@ -373,3 +777,270 @@ impl Sink<Message> for WebSocketStream {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{next_request, send, serve_ws, WsServer};
+    use futures::{Future, StreamExt};
+    use serde_json::json;
+    use std::error::Error as _;
+    use std::sync::Arc;
+    use tokio::time::{timeout, Duration};
+
+    /// Serves connections that get the `SET_PROPERTY` of `connect` answered, then the script.
+    /// Returns the url.
+    async fn serve<F, Fut>(script: F) -> String
+    where
+        F: Fn(WsServer) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let script = Arc::new(script);
+        serve_ws(move |mut ws| {
+            let script = script.clone();
+            async move {
+                let request = next_request(&mut ws).await.unwrap();
+                send(&mut ws, json!({"result": null, "id": request["id"]})).await;
+                script(ws).await;
+            }
+        })
+        .await
+    }
+
+    fn trade(id: i64) -> Value {
+        json!({"stream": "bnbusdt@trade", "data": {"e": "trade", "t": id}})
+    }
+
+    async fn connect(url: String) -> WebSocketStream {
+        WebSocketStream::connect(Channel::Trade("BNBUSDT"), url)
+            .await
+            .unwrap()
+    }
+
+    /// Trade ids of the next events of the stream.
+    async fn trades(stream: &mut WebSocketStream, count: usize) -> Vec<i64> {
+        let mut ids = Vec::new();
+        for _ in 0..count {
+            let value = stream.json::<Value>().await.unwrap().unwrap();
+            ids.push(value["data"]["t"].as_i64().unwrap());
+        }
+        ids
+    }
+
+    #[tokio::test]
+    async fn replies_are_matched_to_their_request() {
+        let url = serve(|mut ws| async move {
+            // the first request is given up on before it is answered
+            let dropped = next_request(&mut ws).await.unwrap();
+            let request = next_request(&mut ws).await.unwrap();
+            assert_eq!(request["method"], "LIST_SUBSCRIPTIONS");
+
+            send(
+                &mut ws,
+                json!({"result": ["stale"], "id": request["id"].as_u64().unwrap() + 1}),
+            )
+            .await;
+            send(&mut ws, json!({"result": true, "id": dropped["id"]})).await;
+            send(
+                &mut ws,
+                json!({"result": ["bnbusdt@trade"], "id": request["id"]}),
+            )
+            .await;
+            send(&mut ws, json!({"result": false, "id": dropped["id"]})).await;
+            send(&mut ws, trade(1)).await;
+            while let Some(Ok(_)) = ws.next().await {}
+        })
+        .await;
+        let mut stream = connect(url).await;
+
+        let property = stream.get_property("combined");
+        assert!(timeout(Duration::from_millis(50), property).await.is_err());
+        let subscriptions = stream.list_subscriptions().await.unwrap();
+        assert_eq!(subscriptions, vec!["bnbusdt@trade"]);
+
+        // late replies are neither kept nor streamed
+        assert_eq!(trades(&mut stream, 1).await, vec![1]);
+        assert!(stream.awaiting.is_none());
+        assert!(stream.reply.is_none());
+    }
+
+    #[tokio::test]
+    async fn error_replies_fail_the_request() {
+        let url = serve(|mut ws| async move {
+            let request = next_request(&mut ws).await.unwrap();
+            let error = json!({"code": 2, "msg": "Invalid request: unknown variable"});
+            send(&mut ws, json!({"error": error, "id": request["id"]})).await;
+            while let Some(Ok(_)) = ws.next().await {}
+        })
+        .await;
+        let mut stream = connect(url).await;
+
+        let error = stream.get_property("unknown").await.unwrap_err();
+        let error = error
+            .source()
+            .and_then(|source| source.downcast_ref::<WsRequestError>())
+            .unwrap();
+        // the id after the SET_PROPERTY of connect
+        assert_eq!(error.id(), 1);
+        assert_eq!(error.code(), 2);
+    }
+
+    #[tokio::test]
+    async fn data_read_while_waiting_is_kept_in_order() {
+        let url = serve(|mut ws| async move {
+            let request = next_request(&mut ws).await.unwrap();
+            assert_eq!(request["method"], "SUBSCRIBE");
+            assert_eq!(request["params"], json!(["bnbusdt@aggTrade"]));
+
+            send(&mut ws, trade(1)).await;
+            send(&mut ws, trade(2)).await;
+            send(&mut ws, json!({"result": null, "id": request["id"]})).await;
+            send(&mut ws, trade(3)).await;
+            while let Some(Ok(_)) = ws.next().await {}
+        })
+        .await;
+        let mut stream = connect(url).await;
+
+        stream
+            .subscribe(&[Channel::AggTrade("BNBUSDT")])
+            .await
+            .unwrap();
+        assert_eq!(stream.buffer.len(), 2);
+        assert_eq!(trades(&mut stream, 3).await, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn replies_are_kept_out_of_the_data() {
+        let url = serve(|mut ws| async move {
+            send(&mut ws, trade(1)).await;
+            send(&mut ws, json!({"result": null, "id": 7})).await;
+            let error = json!({"code": 2, "msg": "Invalid request"});
+            send(&mut ws, json!({"error": error, "id": 8})).await;
+            send(&mut ws, trade(2)).await;
+            while let Some(Ok(_)) = ws.next().await {}
+        })
+        .await;
+        let mut stream = connect(url).await;
+
+        assert_eq!(trades(&mut stream, 2).await, vec![1, 2]);
+        assert!(stream.reply.is_none());
+    }
+
+    const LISTEN_KEY: &str = "pqrsT4fNKmrYmLdrSgGhZNQXDLHw1PFwz2sjQwNcUxCHzwtZykS8aX9Jq3Ad";
+
+    /// Every channel with its stream name.
+    fn channels() -> Vec<(ChannelBuf, &'static str)> {
+        let symbol = || "BNBUSDT".to_string();
+        vec![
+            (ChannelBuf::AggTrade(symbol()), "bnbusdt@aggTrade"),
+            (
+                ChannelBuf::Depth(symbol(), Speed::ThousandMillis),
+                "bnbusdt@depth",
+            ),
+            (
+                ChannelBuf::Depth(symbol(), Speed::HundredMillis),
+                "bnbusdt@depth@100ms",
+            ),
+            (ChannelBuf::Trade(symbol()), "bnbusdt@trade"),
+            (
+                ChannelBuf::Kline(symbol(), Interval::OneSecond),
+                "bnbusdt@kline_1s",
+            ),
+            (
+                ChannelBuf::Kline(symbol(), Interval::OneMonth),
+                "bnbusdt@kline_1M",
+            ),
+            (
+                ChannelBuf::KlineUtc8(symbol(), Interval::OneHour),
+                "bnbusdt@kline_1h@+08:00",
+            ),
+            (ChannelBuf::MiniTicker(symbol()), "bnbusdt@miniTicker"),
+            (ChannelBuf::AllMiniTickers, "!miniTicker@arr"),
+            (ChannelBuf::Ticker(symbol()), "bnbusdt@ticker"),
+            (ChannelBuf::AllTickers, "!ticker@arr"),
+            (
+                ChannelBuf::RollingTicker(symbol(), WindowSize::FourHours),
+                "bnbusdt@ticker_4h",
+            ),
+            (
+                ChannelBuf::AllRollingTickers(WindowSize::OneDay),
+                "!ticker_1d@arr",
+            ),
+            (ChannelBuf::AvgPrice(symbol()), "bnbusdt@avgPrice"),
+            (ChannelBuf::BookTicker(symbol()), "bnbusdt@bookTicker"),
+            (ChannelBuf::AllBookTickers, "!bookTicker"),
+            (
+                ChannelBuf::PartialDepth(symbol(), Level::Five, Speed::ThousandMillis),
+                "bnbusdt@depth5",
+            ),
+            (
+                ChannelBuf::PartialDepth(symbol(), Level::Twenty, Speed::HundredMillis),
+                "bnbusdt@depth20@100ms",
+            ),
+            (ChannelBuf::UserData(LISTEN_KEY.into()), LISTEN_KEY),
+        ]
+    }
+
+    #[test]
+    fn channels_round_trip_through_their_name() {
+        for (channel, name) in channels() {
+            assert_eq!(channel.to_string(), name);
+            assert_eq!(channel.as_channel().to_string(), name);
+            assert_eq!(name.parse::<ChannelBuf>().unwrap(), channel, "{}", name);
+            assert_eq!(ChannelBuf::from(channel.as_channel()), channel);
+        }
+        // symbols are kept in uppercase
+        let channel: ChannelBuf = "BNBUSDT@trade".parse().unwrap();
+        assert_eq!(channel.to_string(), "bnbusdt@trade");
+        assert_eq!(channel.symbol(), Some("BNBUSDT"));
+    }
+
+    #[test]
+    fn channels_round_trip_through_serde() {
+        for (channel, name) in channels() {
+            let value = serde_json::to_value(&channel).unwrap();
+            assert_eq!(value, Value::String(name.into()));
+            assert_eq!(
+                serde_json::from_value::<ChannelBuf>(value).unwrap(),
+                channel
+            );
+        }
+
+        let event = json!({"stream": "bnbusdt@depth@100ms", "data": {}});
+        let event: StreamEvent<Value> = serde_json::from_value(event).unwrap();
+        assert_eq!(
+            event.stream,
+            ChannelBuf::Depth("BNBUSDT".into(), Speed::HundredMillis)
+        );
+        let event = json!({"stream": "bnbusdt", "data": {}});
+        assert!(serde_json::from_value::<StreamEvent<Value>>(event).is_err());
+    }
+
+    #[test]
+    fn unknown_names_are_refused() {
+        let names = [
+            "",
+            // a symbol without its stream, not a listen key
+            "bnbusdt",
+            "listen-key-with-dashes-is-no-listen-key-at-all-000000000000000",
+            "@trade",
+            "bnbusdt@",
+            "bnbusdt@trades",
+            "bnbusdt@kline_2m",
+            "bnbusdt@kline_1m@+09:00",
+            "bnbusdt@ticker_2h",
+            "bnbusdt@depth7",
+            "bnbusdt@depth@10ms",
+            // the default speed is left out of the name
+            "bnbusdt@depth@1000ms",
+            "bnbusdt@depth5@1000ms",
+            "!ticker",
+            "!ticker_2h@arr",
+            "!bookTicker@arr",
+        ];
+        for name in names.iter() {
+            let error = name.parse::<ChannelBuf>().unwrap_err();
+            assert_eq!(error.to_string(), format!("Unknown channel: {}", name));
+        }
+    }
+}